@@ -1,5 +1,3 @@
-use std::sync::OnceLock;
-
 // ==================================================================================
 //  GLOBAL UI CONFIGURATION
 //  This file contains all the design tokens, dimensions, colors, and assets settings
@@ -25,13 +23,30 @@ pub const INPUT_H: f32 = 32.0;
 pub const BTN_Y: f32 = 96.0;
 pub const BTN_H: f32 = 30.0;
 pub const BTN_W: f32 = 80.0;
+pub const BTN_GAP: f32 = 8.0; // Space between Run and Cancel
+pub const TITLE_ICON_SIZE: f32 = 24.0;
+pub const INPUT_TEXT_LEADING: f32 = 35.0; // Room for the search icon before the text
+pub const INPUT_TEXT_TRAILING: f32 = 30.0; // Room for the chevron after the text
+pub const INPUT_TEXT_PAD_Y: f32 = 8.0;
+pub const CHEVRON_INSET: f32 = 20.0; // Chevron centre, from the input's trailing edge
 
 // ----------------------------------------------------------------------------------
 //  DROPDOWN CONFIGURATION
 // ----------------------------------------------------------------------------------
 pub const ITEM_H: f32 = 26.0; // Height of each history item row
-pub const DROPDOWN_MAX_ITEMS: usize = 5;
+pub const ITEM_H_DETAILED: f32 = 42.0; // Entry rows with a second line (detailed_rows)
+pub const HEADER_H: f32 = 22.0; // Height of a section header row
+pub const DROPDOWN_MAX_H: f32 = 220.0; // Taller lists scroll
 pub const DROPDOWN_GAP: f32 = 5.0;
+pub const DROPDOWN_TEXT_PAD: f32 = 10.0;
+pub const DROPDOWN_SCROLLBAR_W: f32 = 8.0;
+pub const DROPDOWN_DETAIL_RATIO: f32 = 0.45; // Share of a row given to its description
+pub const DROPDOWN_GLYPH_W: f32 = 22.0; // Source glyph before each entry
+pub const GLYPH_FONT: &str = "Segoe MDL2 Assets";
+pub const GLYPH_SIZE: f32 = 12.0;
+pub const HEADER_FONT_SIZE: f32 = 11.0;
+pub const DETAIL_FONT_SIZE: f32 = 11.0;
+pub const FITTED_DETAIL_CACHE_LIMIT: usize = 256; // Shortened detail lines kept between paints
 
 // ----------------------------------------------------------------------------------
 //  TOOLTIP CONFIGURATION
@@ -44,6 +59,8 @@ pub const TOOLTIP_GAP: f32 = 4.0; // Gap between input and tooltip
 pub const TOOLTIP_ICON_SIZE: f32 = 25.0;
 pub const TOOLTIP_PADDING: f32 = 15.0;
 pub const TOOLTIP_CORNER_RADIUS: f32 = 8.0;
+pub const TOOLTIP_TRI_X: f32 = 20.0; // Triangle offset from the leading edge
+pub const TOOLTIP_TEXT_TRAILING: f32 = 25.0;
 
 // ----------------------------------------------------------------------------------
 //  DIALOG WINDOW CONFIGURATION
@@ -57,11 +74,10 @@ pub const DIALOG_BTN_H: f32 = 36.0;
 // ----------------------------------------------------------------------------------
 //  TYPOGRAPHY & FONTS
 // ----------------------------------------------------------------------------------
-// Font Families
-pub const FONT_DISPLAY: windows::core::PCWSTR = windows::core::w!("Segoe UI Variable Display");
-pub const FONT_TEXT: windows::core::PCWSTR = windows::core::w!("Segoe UI Variable Text");
-pub const FONT_SMALL: windows::core::PCWSTR = windows::core::w!("Segoe UI Variable Small");
-pub const FONT_STD: windows::core::PCWSTR = windows::core::w!("Segoe UI");
+// Font Families (defaults; themes can override the Direct2D ones)
+pub const FONT_DISPLAY: &str = "Segoe UI Variable Display";
+pub const FONT_TEXT: &str = "Segoe UI Variable Text";
+pub const FONT_SMALL: &str = "Segoe UI Variable Small";
 
 // Font Sizes
 pub const FONT_SZ_TITLE: f32 = 13.0;
@@ -71,7 +87,6 @@ pub const FONT_SZ_TOOLTIP: f32 = 11.5;
 pub const FONT_SZ_TOOLTIP_BOLD: f32 = 12.5;
 pub const FONT_SZ_DIALOG_MSG: f32 = 18.0;
 pub const FONT_SZ_DIALOG_BTN: f32 = 15.0;
-pub const FONT_SZ_INPUT: i32 = 20;
 
 // ----------------------------------------------------------------------------------
 //  ANIMATIONS
@@ -82,8 +97,30 @@ pub const ANIM_DROPDOWN_DURATION_MS: u128 = 200;
 pub const ANIM_TOOLTIP_DURATION_MS: u128 = 300;
 pub const ANIM_TIMER_MS: u32 = 1; // High-frequency timer for maximum smoothness (limited by monitor refresh)
 
+// ----------------------------------------------------------------------------------
+//  CONFIG FILES
+// ----------------------------------------------------------------------------------
+pub const CONFIG_RELOAD_DEBOUNCE_MS: u64 = 150; // Editors often write a file several times per save
+
+// ----------------------------------------------------------------------------------
+//  FILE INDEX
+// ----------------------------------------------------------------------------------
+pub const FILE_SEARCH_PREFIX: &str = "f "; // Input that searches the file index instead
+pub const FILE_SEARCH_LIMIT: usize = 20; // Best matches a file search returns
+pub const FILE_INDEX_MAX_ENTRIES: usize = 200_000; // Walk stops here so a huge root cannot eat memory
+pub const FILE_INDEX_DEBOUNCE_MS: u64 = 2000; // Wait after a change before rescanning, so a burst scans once
+pub const FILE_INDEX_POLL_MS: u32 = 500; // How often the indexer checks for a stop request
+
+// ----------------------------------------------------------------------------------
+//  HIGH CONTRAST
+// ----------------------------------------------------------------------------------
+pub const HC_MIN_TEXT_CONTRAST: f32 = 4.5; // WCAG AA for body text
+pub const HC_MIN_UI_CONTRAST: f32 = 3.0; // WCAG AA for borders and icons
+pub const HC_FOCUS_STROKE: f32 = 2.0; // Input focus ring width while high contrast is on
+
 // ----------------------------------------------------------------------------------
 //  COLORS (THEME)
+//  Defaults for the built-in Fluent theme. See `data/theme.rs` and `res/themes`.
 // ----------------------------------------------------------------------------------
 // General
 pub const COLOR_ACCENT_OPACITY: f32 = 0.9;
@@ -120,6 +157,16 @@ pub const COLOR_INPUT_BG_DARK: f32 = 0.1;
 pub const COLOR_INPUT_BG_LIGHT: f32 = 0.9;
 pub const COLOR_INPUT_BG_OPACITY: f32 = 0.15;
 
+// Command line highlighting, 0xAABBGGRR like the acrylic tints
+pub const COLOR_SYNTAX_PROGRAM_DARK: u32 = 0xFFAADCDC;
+pub const COLOR_SYNTAX_STRING_DARK: u32 = 0xFF7891CE;
+pub const COLOR_SYNTAX_VARIABLE_DARK: u32 = 0xFFC086C5;
+pub const COLOR_SYNTAX_URL_DARK: u32 = 0xFFFFC14F;
+pub const COLOR_SYNTAX_PROGRAM_LIGHT: u32 = 0xFF265E79;
+pub const COLOR_SYNTAX_STRING_LIGHT: u32 = 0xFF1515A3;
+pub const COLOR_SYNTAX_VARIABLE_LIGHT: u32 = 0xFFDB00AF;
+pub const COLOR_SYNTAX_URL_LIGHT: u32 = 0xFFBF6600;
+
 pub const ACRYLIC_TINT_DARK: u32 = 0x00202020;
 pub const ACRYLIC_TINT_LIGHT: u32 = 0x00F3F3F3;
 
@@ -130,28 +177,36 @@ pub const COLOR_ACCENT_DEFAULT_B: f32 = 0.83;
 // ----------------------------------------------------------------------------------
 //  MISC
 // ----------------------------------------------------------------------------------
-pub const EDIT_ID: u32 = 101;
-
-// Static string caching (Performance)
-pub static STR_TITLE: OnceLock<Vec<u16>> = OnceLock::new();
-pub static STR_RUN: OnceLock<Vec<u16>> = OnceLock::new();
-pub static STR_CANCEL: OnceLock<Vec<u16>> = OnceLock::new();
-
-pub fn get_str_title() -> &'static [u16] {
-    STR_TITLE
-        .get_or_init(|| "Swift Run".encode_utf16().collect())
-        .as_slice()
-}
-pub fn get_str_run() -> &'static [u16] {
-    STR_RUN
-        .get_or_init(|| "Run \u{21B5}".encode_utf16().collect())
-        .as_slice()
-}
-pub fn get_str_cancel() -> &'static [u16] {
-    STR_CANCEL
-        .get_or_init(|| "Cancel".encode_utf16().collect())
-        .as_slice()
-}
+pub const UNDO_LIMIT: usize = 100; // Undo steps kept for the input
+pub const SUGGESTION_LIMIT: usize = 50; // Dropdown entries kept after merging all providers
+pub const SUGGEST_HISTORY_BUDGET_MS: u64 = 40; // How long each provider may take per keystroke
+pub const SUGGEST_COMPLETION_BUDGET_MS: u64 = 150; // Lists folders, so it gets longer
+pub const SUGGEST_ALIAS_BUDGET_MS: u64 = 40;
+pub const SUGGEST_CALCULATOR_BUDGET_MS: u64 = 40;
+pub const SUGGEST_APPS_BUDGET_MS: u64 = 40;
+pub const SUGGEST_FILES_BUDGET_MS: u64 = 100;
+pub const SUGGEST_REPOS_BUDGET_MS: u64 = 40;
+pub const SUGGEST_WORKSPACES_BUDGET_MS: u64 = 40;
+pub const SUGGEST_TERMINAL_BUDGET_MS: u64 = 40;
+pub const SUGGEST_SSH_BUDGET_MS: u64 = 40;
+pub const SUGGEST_BOOKMARKS_BUDGET_MS: u64 = 40;
+pub const SUGGEST_SETTINGS_BUDGET_MS: u64 = 40;
+pub const SECTION_HISTORY_LIMIT: usize = 6; // Entries shown per dropdown section
+pub const SECTION_COMMANDS_LIMIT: usize = 8;
+pub const SECTION_ALIASES_LIMIT: usize = 3;
+pub const SECTION_APPS_LIMIT: usize = 4;
+pub const SECTION_CALCULATOR_LIMIT: usize = 1;
+pub const SECTION_FILES_LIMIT: usize = 8;
+pub const SECTION_REPOS_LIMIT: usize = 4;
+pub const SECTION_WORKSPACES_LIMIT: usize = 6;
+pub const SECTION_TERMINAL_LIMIT: usize = 6;
+pub const SECTION_SSH_LIMIT: usize = 6;
+pub const SECTION_BOOKMARKS_LIMIT: usize = 4;
+pub const SECTION_SETTINGS_LIMIT: usize = 3;
+pub const COMPLETION_PATH_LIMIT: usize = 100; // Folder entries offered when completing a path
+pub const FRECENCY_DECAY: f32 = 0.9; // Weight of each older history line when ranking arguments
+pub const DIAGNOSTICS_COMMAND: &str = ":diagnostics"; // Typed into the input instead of a command
+pub const EXPANSION_PREVIEW_PREFIX: &str = "  → "; // Between the input and its expanded ghost
 
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub enum HoverId {
@@ -0,0 +1,64 @@
+#![allow(static_mut_refs)]
+
+use std::sync::Arc;
+
+use crate::data::ini::*;
+
+pub const ALIASES_FILE: &str = "aliases.ini";
+
+pub static mut ALIASES: Option<Arc<Vec<Alias>>> = None;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Alias {
+    pub name: String,
+    pub target: String,
+}
+
+/// Aliases live either at the top of `aliases.ini` or under an `[aliases]` header:
+/// `gh = https://github.com` or `proj = %USERPROFILE%\Projects`.
+pub fn parse_aliases(text: &str) -> Result<Vec<Alias>, ConfigError> {
+    let doc = parse_ini(ALIASES_FILE, text)?;
+    let mut aliases: Vec<Alias> = Vec::new();
+
+    for section in &doc.sections {
+        if !section.name.is_empty() && !section.name.eq_ignore_ascii_case("aliases") {
+            return Err(ConfigError::new(
+                ALIASES_FILE,
+                section.line,
+                format!("unknown section [{}]", section.name),
+            ));
+        }
+        for entry in &section.entries {
+            if entry.key.chars().any(char::is_whitespace) {
+                return Err(ConfigError::new(
+                    ALIASES_FILE,
+                    entry.line,
+                    format!("alias '{}' must be a single word", entry.key),
+                ));
+            }
+            if entry.value.is_empty() {
+                return Err(ConfigError::new(
+                    ALIASES_FILE,
+                    entry.line,
+                    format!("alias '{}' has no target", entry.key),
+                ));
+            }
+            let name = entry.key.to_lowercase();
+            aliases.retain(|a| a.name != name);
+            aliases.push(Alias {
+                name,
+                target: entry.value.clone(),
+            });
+        }
+    }
+
+    Ok(aliases)
+}
+
+pub fn lookup_alias(name: &str) -> Option<String> {
+    let name = name.to_lowercase();
+    unsafe { ALIASES.as_ref() }?
+        .iter()
+        .find(|a| a.name == name)
+        .map(|a| a.target.clone())
+}
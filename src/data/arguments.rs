@@ -0,0 +1,150 @@
+use crate::config::FRECENCY_DECAY;
+use crate::data::command_line::{CommandLine, program_key};
+use crate::data::matcher::completion_suffix;
+
+// ==================================================================================
+//  ARGUMENTS
+//  Suggestions once the program is typed: the argument lists used with it before,
+//  then completions of the word under way from the words used in the same position.
+//  Both are ranked by frecency: every history line adds `FRECENCY_DECAY^age`, age
+//  being its position in the newest-first history.
+// ==================================================================================
+
+/// Whole command lines continuing `input`, best first, or `None` while the program
+/// itself is still being typed.
+pub fn argument_suggestions(
+    input: &str,
+    history: &[String],
+    ignore_accents: bool,
+) -> Option<Vec<String>> {
+    let typed = CommandLine::parse(input)?;
+    if typed.program.range.end == input.len() {
+        return None;
+    }
+    let key = program_key(&typed.program.value);
+    let arguments_start = typed.arguments_start(input);
+    let typed_arguments = &input[arguments_start..];
+
+    // The word being typed, or a new one after trailing whitespace.
+    let (word_index, word_start) = match typed.arguments.last() {
+        Some(last) if last.range.end == input.len() => {
+            (typed.arguments.len() - 1, last.range.start)
+        }
+        _ => (typed.arguments.len(), input.len()),
+    };
+    let word = &input[word_start..];
+
+    let mut lists: Vec<(&str, f32)> = Vec::new();
+    let mut words: Vec<(&str, f32)> = Vec::new();
+    let mut weight = 1.0;
+    for line in history {
+        if let Some(parsed) = CommandLine::parse(line)
+            && program_key(&parsed.program.value) == key
+        {
+            let list = line[parsed.arguments_start(line)..].trim_end();
+            if extends(list, typed_arguments, ignore_accents) {
+                add(&mut lists, list, weight);
+            }
+            if let Some(argument) = parsed.arguments.get(word_index) {
+                let argument = &line[argument.range.clone()];
+                if extends(argument, word, ignore_accents) {
+                    add(&mut words, argument, weight);
+                }
+            }
+        }
+        weight *= FRECENCY_DECAY;
+    }
+
+    let mut suggestions: Vec<String> = Vec::new();
+    let ranked_lists = ranked(lists).map(|list| format!("{}{}", &input[..arguments_start], list));
+    let ranked_words = ranked(words).map(|word| format!("{}{}", &input[..word_start], word));
+    for suggestion in ranked_lists.chain(ranked_words) {
+        if !suggestions.contains(&suggestion) {
+            suggestions.push(suggestion);
+        }
+    }
+    Some(suggestions)
+}
+
+/// `candidate` starts with `typed` and adds something to it.
+fn extends(candidate: &str, typed: &str, ignore_accents: bool) -> bool {
+    completion_suffix(candidate, typed, ignore_accents).is_some_and(|suffix| !suffix.is_empty())
+}
+
+fn add<'a>(scores: &mut Vec<(&'a str, f32)>, text: &'a str, weight: f32) {
+    match scores.iter_mut().find(|(seen, _)| *seen == text) {
+        Some((_, score)) => *score += weight,
+        None => scores.push((text, weight)),
+    }
+}
+
+/// Highest score first; ties keep the more recent first use.
+fn ranked(mut scores: Vec<(&str, f32)>) -> impl Iterator<Item = &str> {
+    scores.sort_by(|a, b| b.1.total_cmp(&a.1));
+    scores.into_iter().map(|(text, _)| text)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history() -> Vec<String> {
+        [
+            "git commit -m fix",
+            "code C:\\src\\app",
+            "git checkout main",
+            "git commit -m wip",
+            "Code.exe C:\\src\\app",
+            "git status",
+            "notepad todo.txt",
+            "git checkout dev",
+        ]
+        .iter()
+        .map(|s| s.to_string())
+        .collect()
+    }
+
+    fn suggest(input: &str) -> Option<Vec<String>> {
+        argument_suggestions(input, &history(), false)
+    }
+
+    #[test]
+    fn waits_for_the_program() {
+        assert_eq!(suggest("gi"), None);
+        assert_eq!(suggest("git"), None);
+        assert_eq!(suggest(""), None);
+    }
+
+    #[test]
+    fn offers_argument_lists_by_frecency() {
+        assert_eq!(suggest("code ").unwrap(), ["code C:\\src\\app"]);
+        let s = suggest("git ").unwrap();
+        assert_eq!(
+            &s[..5],
+            [
+                "git commit -m fix",
+                "git checkout main",
+                "git commit -m wip",
+                "git status",
+                "git checkout dev"
+            ]
+        );
+        // `git status` is already a whole list, so only two first words follow.
+        assert_eq!(&s[5..], ["git commit", "git checkout"]);
+    }
+
+    #[test]
+    fn completes_the_current_word() {
+        assert_eq!(
+            suggest("git ch").unwrap(),
+            ["git checkout main", "git checkout dev", "git checkout"]
+        );
+        assert_eq!(suggest("GIT checkout m").unwrap(), ["GIT checkout main"]);
+        assert_eq!(
+            suggest("git commit -m ").unwrap(),
+            ["git commit -m fix", "git commit -m wip"]
+        );
+        assert_eq!(suggest("notepad todo.txt").unwrap(), Vec::<String>::new());
+        assert_eq!(suggest("unknown ").unwrap(), Vec::<String>::new());
+    }
+}
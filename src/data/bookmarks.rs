@@ -0,0 +1,381 @@
+use std::collections::{BTreeSet, HashMap};
+
+use crate::data::json::{Json, JsonError, parse_json};
+use crate::data::sqlite::{Database, SqliteError, Value};
+
+// ==================================================================================
+//  BOOKMARKS
+//  Browser bookmarks with the folders they are filed in. Chromium browsers (Chrome,
+//  Edge, Brave) keep them in a `Bookmarks` JSON file per profile; Firefox keeps
+//  them in `places.sqlite`, read through `sqlite.rs`. Only addresses a browser can
+//  be started with are kept: bookmarklets and Firefox's saved searches are left
+//  out, as are Firefox's tags, which are stored as bookmarks too.
+// ==================================================================================
+
+/// Folder names are joined with this into a path.
+const FOLDER_SEPARATOR: &str = " / ";
+const OPENABLE_SCHEMES: &[&str] = &["http://", "https://", "ftp://", "file://"];
+/// Folders nested deeper than this are taken to be a damaged parent chain.
+const MAX_FOLDER_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Bookmark {
+    pub title: String,
+    pub url: String,
+    /// "Bookmarks bar / Work"; empty at the top level.
+    pub folder: String,
+    /// "Chrome", or "Chrome (Work)" when the browser has several profiles.
+    pub browser: String,
+}
+
+impl Bookmark {
+    /// "Edge: Bookmarks bar / Work".
+    pub fn location(&self) -> String {
+        if self.folder.is_empty() {
+            self.browser.clone()
+        } else {
+            format!("{}: {}", self.browser, self.folder)
+        }
+    }
+}
+
+fn is_openable(url: &str) -> bool {
+    OPENABLE_SCHEMES.iter().any(|scheme| {
+        url.get(..scheme.len())
+            .is_some_and(|s| s.eq_ignore_ascii_case(scheme))
+    })
+}
+
+// ==================================================================================
+//  CHROMIUM
+// ==================================================================================
+
+/// Bookmarks in a Chromium `Bookmarks` file, in the order the browser shows them.
+pub fn parse_chromium_bookmarks(text: &str, browser: &str) -> Result<Vec<Bookmark>, JsonError> {
+    let root = parse_json(text)?;
+    let mut bookmarks = Vec::new();
+    // `bookmark_bar`, `other` and `synced`, each a folder named in the browser's
+    // language.
+    for (_, folder) in root.get("roots").map_or(&[][..], Json::entries) {
+        if folder.get("children").is_some() {
+            let name = folder
+                .get("name")
+                .and_then(Json::as_str)
+                .unwrap_or_default();
+            collect_chromium(folder, name, browser, &mut bookmarks);
+        }
+    }
+    Ok(bookmarks)
+}
+
+fn collect_chromium(folder: &Json, path: &str, browser: &str, bookmarks: &mut Vec<Bookmark>) {
+    for node in folder.get("children").map_or(&[][..], Json::items) {
+        let name = node.get("name").and_then(Json::as_str).unwrap_or_default();
+        match node.get("type").and_then(Json::as_str) {
+            Some("url") => {
+                let url = node.get("url").and_then(Json::as_str).unwrap_or_default();
+                if is_openable(url) {
+                    bookmarks.push(Bookmark {
+                        title: if name.is_empty() { url } else { name }.to_string(),
+                        url: url.to_string(),
+                        folder: path.to_string(),
+                        browser: browser.to_string(),
+                    });
+                }
+            }
+            Some("folder") => {
+                let path = if path.is_empty() {
+                    name.to_string()
+                } else {
+                    format!("{}{}{}", path, FOLDER_SEPARATOR, name)
+                };
+                collect_chromium(node, &path, browser, bookmarks);
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Profile folder and display name of each profile in Chromium's `Local State`.
+pub fn chromium_profile_names(text: &str) -> Vec<(String, String)> {
+    let Ok(root) = parse_json(text) else {
+        return Vec::new();
+    };
+    root.path(&["profile", "info_cache"])
+        .map_or(&[][..], Json::entries)
+        .iter()
+        .filter_map(|(dir, info)| {
+            let name = info.get("name").and_then(Json::as_str)?;
+            Some((dir.clone(), name.to_string()))
+        })
+        .collect()
+}
+
+// ==================================================================================
+//  FIREFOX
+// ==================================================================================
+
+/// Firefox's built-in folders by GUID, with the names its English UI gives them;
+/// the titles stored for them are internal ("toolbar", "unfiled").
+const FIREFOX_ROOTS: &[(&str, &str)] = &[
+    ("menu________", "Bookmarks Menu"),
+    ("toolbar_____", "Bookmarks Toolbar"),
+    ("unfiled_____", "Other Bookmarks"),
+    ("mobile______", "Mobile Bookmarks"),
+];
+const FIREFOX_ROOT: &str = "root________";
+const FIREFOX_TAGS: &str = "tags________";
+const TYPE_BOOKMARK: i64 = 1;
+const TYPE_FOLDER: i64 = 2;
+
+struct Folder {
+    parent: i64,
+    title: String,
+    guid: String,
+}
+
+/// Bookmarks in Firefox's `places.sqlite` and its `-wal` file, grouped by folder and
+/// in their order within it.
+pub fn read_places(
+    main: &[u8],
+    wal: Option<&[u8]>,
+    browser: &str,
+) -> Result<Vec<Bookmark>, SqliteError> {
+    let db = Database::open(main, wal)?;
+    let rows = db.read_table(
+        "moz_bookmarks",
+        &["id", "type", "fk", "parent", "position", "title", "guid"],
+    )?;
+    // Only the places bookmarks point at; the rest of `moz_places` is history. Its
+    // `id` is the rowid.
+    let fks: BTreeSet<i64> = rows
+        .iter()
+        .filter(|row| row[1].as_i64() == Some(TYPE_BOOKMARK))
+        .filter_map(|row| row[2].as_i64())
+        .collect();
+    let places: HashMap<i64, (String, String)> = db
+        .read_table_rows("moz_places", &["id", "url", "title"], &fks)?
+        .into_iter()
+        .filter_map(|row| {
+            let url = row[1].as_str()?.to_string();
+            Some((row[0].as_i64()?, (url, text(&row[2]))))
+        })
+        .collect();
+
+    let mut folders: HashMap<i64, Folder> = HashMap::new();
+    for row in &rows {
+        if let (Some(id), Some(TYPE_FOLDER)) = (row[0].as_i64(), row[1].as_i64()) {
+            let folder = Folder {
+                parent: row[3].as_i64().unwrap_or(0),
+                title: text(&row[5]),
+                guid: text(&row[6]),
+            };
+            folders.insert(id, folder);
+        }
+    }
+
+    let mut found: Vec<(String, i64, Bookmark)> = Vec::new();
+    for row in &rows {
+        if row[1].as_i64() != Some(TYPE_BOOKMARK) {
+            continue;
+        }
+        let Some((url, place_title)) = row[2].as_i64().and_then(|fk| places.get(&fk)) else {
+            continue;
+        };
+        let Some(folder) = folder_path(&folders, row[3].as_i64().unwrap_or(0)) else {
+            continue;
+        };
+        if !is_openable(url) {
+            continue;
+        }
+        let title = [text(&row[5]), place_title.clone(), url.clone()]
+            .into_iter()
+            .find(|t| !t.is_empty())
+            .unwrap_or_default();
+        let position = row[4].as_i64().unwrap_or(0);
+        let bookmark = Bookmark {
+            title,
+            url: url.clone(),
+            folder: folder.clone(),
+            browser: browser.to_string(),
+        };
+        found.push((folder, position, bookmark));
+    }
+    found.sort_by(|a, b| (&a.0, a.1).cmp(&(&b.0, b.1)));
+    Ok(found.into_iter().map(|(_, _, bookmark)| bookmark).collect())
+}
+
+/// Path of folder `id`, or `None` under the tags folder or when its parent chain is
+/// broken.
+fn folder_path(folders: &HashMap<i64, Folder>, mut id: i64) -> Option<String> {
+    let mut names = Vec::new();
+    for _ in 0..MAX_FOLDER_DEPTH {
+        let folder = folders.get(&id)?;
+        match folder.guid.as_str() {
+            FIREFOX_ROOT => {
+                names.reverse();
+                return Some(names.join(FOLDER_SEPARATOR));
+            }
+            FIREFOX_TAGS => return None,
+            guid => {
+                let root = FIREFOX_ROOTS.iter().find(|(g, _)| *g == guid);
+                names.push(root.map_or(folder.title.as_str(), |(_, name)| name));
+            }
+        }
+        id = folder.parent;
+    }
+    None
+}
+
+fn text(value: &Value) -> String {
+    value.as_str().unwrap_or_default().to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CHROMIUM: &str = include_str!("../../tests/fixtures/Bookmarks");
+    const LOCAL_STATE: &str = include_str!("../../tests/fixtures/Local State");
+    const PLACES: &[u8] = include_bytes!("../../tests/fixtures/places.sqlite");
+    const WAL_MAIN: &[u8] = include_bytes!("../../tests/fixtures/wal.sqlite");
+    const WAL: &[u8] = include_bytes!("../../tests/fixtures/wal.sqlite-wal");
+
+    fn summary(bookmarks: &[Bookmark]) -> Vec<(&str, &str, &str)> {
+        bookmarks
+            .iter()
+            .map(|b| (b.title.as_str(), b.url.as_str(), b.folder.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn chromium_bookmarks_keep_their_folders() {
+        let bookmarks = parse_chromium_bookmarks(CHROMIUM, "Edge").unwrap();
+        assert_eq!(
+            summary(&bookmarks),
+            [
+                (
+                    "Rust — docs",
+                    "https://doc.rust-lang.org/std/",
+                    "Bookmarks bar"
+                ),
+                (
+                    "https://crates.io/",
+                    "https://crates.io/",
+                    "Bookmarks bar / Work / Rust"
+                ),
+                ("Intranet", "HTTP://intranet/", "Other bookmarks"),
+            ]
+        );
+        assert_eq!(bookmarks[1].location(), "Edge: Bookmarks bar / Work / Rust");
+        let top = Bookmark {
+            folder: String::new(),
+            ..bookmarks[0].clone()
+        };
+        assert_eq!(top.location(), "Edge");
+    }
+
+    #[test]
+    fn chromium_damage_is_an_error_or_nothing() {
+        assert!(parse_chromium_bookmarks(&CHROMIUM[..CHROMIUM.len() / 2], "Edge").is_err());
+        assert!(parse_chromium_bookmarks("{}", "Edge").unwrap().is_empty());
+        let odd = r#"{"roots": {"x": 1, "y": {"children": 3}}}"#;
+        assert!(parse_chromium_bookmarks(odd, "Edge").unwrap().is_empty());
+    }
+
+    #[test]
+    fn chromium_profiles_need_a_name() {
+        assert_eq!(
+            chromium_profile_names(LOCAL_STATE),
+            [
+                ("Default".to_string(), "Person 1".to_string()),
+                ("Profile 2".to_string(), "Work".to_string()),
+            ]
+        );
+        assert!(chromium_profile_names("garbage").is_empty());
+    }
+
+    #[test]
+    fn firefox_bookmarks_skip_tags_searches_and_scripts() {
+        let bookmarks = read_places(PLACES, None, "Firefox").unwrap();
+        let long = format!("https://example.com/{}", "x".repeat(3000));
+        assert_eq!(
+            summary(&bookmarks),
+            [
+                ("GitHub home", "https://github.com/", "Bookmarks Toolbar"),
+                (
+                    "https://docs.example.com/",
+                    "https://docs.example.com/",
+                    "Bookmarks Toolbar / Dev"
+                ),
+                (
+                    "Rust Programming Language",
+                    "https://www.rust-lang.org/",
+                    "Bookmarks Toolbar / Dev / Rust"
+                ),
+                ("Very long", long.as_str(), "Other Bookmarks"),
+            ]
+        );
+        assert_eq!(bookmarks[0].browser, "Firefox");
+        // An empty log is the same as none.
+        assert_eq!(
+            read_places(PLACES, Some(&[]), "Firefox").unwrap(),
+            bookmarks
+        );
+    }
+
+    #[test]
+    fn firefox_log_adds_committed_changes() {
+        let without = read_places(WAL_MAIN, None, "Firefox").unwrap();
+        assert_eq!(without.len(), 4);
+        assert_eq!(without[0].title, "GitHub home");
+        let with = read_places(WAL_MAIN, Some(WAL), "Firefox").unwrap();
+        assert_eq!(with.len(), 5);
+        assert_eq!(with[0].title, "GitHub (renamed)");
+        assert!(
+            with.iter()
+                .any(|b| b.url == "https://wal.example.com/" && b.title == "From the log")
+        );
+    }
+
+    #[test]
+    fn firefox_log_damage_drops_the_transaction() {
+        let without = read_places(WAL_MAIN, None, "Firefox").unwrap();
+        // Header, then frames of a header and one page each.
+        assert_eq!(WAL.len(), 32 + 4 * (24 + 1024));
+        let mut bad_checksum = WAL.to_vec();
+        bad_checksum[32 + 24 + 100] ^= 0xFF;
+        assert_eq!(
+            read_places(WAL_MAIN, Some(&bad_checksum), "Firefox").unwrap(),
+            without
+        );
+        let mut other_salts = WAL.to_vec();
+        other_salts[32 + 8] ^= 1;
+        assert_eq!(
+            read_places(WAL_MAIN, Some(&other_salts), "Firefox").unwrap(),
+            without
+        );
+        // A torn last frame loses only its own commit, the rename.
+        let torn = read_places(WAL_MAIN, Some(&WAL[..WAL.len() - 10]), "Firefox").unwrap();
+        assert_eq!(torn.len(), 5);
+        assert_eq!(torn[0].title, "GitHub home");
+    }
+
+    #[test]
+    fn firefox_damage_is_an_error() {
+        assert!(read_places(&[], None, "Firefox").is_err());
+        assert!(read_places(&PLACES[..PLACES.len() / 2], None, "Firefox").is_err());
+        let other = include_bytes!("../../tests/fixtures/altered.sqlite");
+        let err = read_places(other, None, "Firefox").unwrap_err();
+        assert_eq!(err.to_string(), "page 1: no table 'moz_bookmarks'");
+    }
+
+    #[test]
+    fn only_browsable_addresses_open() {
+        assert!(is_openable("HTTPS://example.com"));
+        assert!(is_openable("file:///C:/notes.txt"));
+        assert!(!is_openable("javascript:void(0)"));
+        assert!(!is_openable("place:tag=work"));
+        assert!(!is_openable("http"));
+    }
+}
@@ -0,0 +1,139 @@
+// ==================================================================================
+//  CALCULATOR
+//  Arithmetic typed into the input: `+ - * / %` (remainder), `^` (power, right
+//  associative), parentheses, unary minus and decimals with `.`. An optional leading
+//  `=` is allowed. Anything else, division by zero included, is not a calculation.
+// ==================================================================================
+
+/// Marks the input as a calculation; the calculator hotkey types it.
+pub const CALCULATION_PREFIX: &str = "=";
+
+/// Value of `input` when it is a calculation with at least one operator.
+pub fn evaluate(input: &str) -> Option<f64> {
+    let expression = input.trim();
+    let expression = expression
+        .strip_prefix(CALCULATION_PREFIX)
+        .unwrap_or(expression);
+    let mut parser = Parser {
+        bytes: expression.as_bytes(),
+        at: 0,
+        operators: 0,
+    };
+    let value = parser.sum()?;
+    parser.skip_spaces();
+    let complete = parser.at == parser.bytes.len();
+    (complete && parser.operators > 0 && value.is_finite()).then_some(value)
+}
+
+/// Shortest plain form: integers without a point, other values rounded to ten
+/// decimals so `0.1 + 0.2` shows `0.3`.
+pub fn format_number(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+    let text = format!("{:.10}", value);
+    let text = text.trim_end_matches('0').trim_end_matches('.');
+    if text == "-0" {
+        "0".to_string()
+    } else {
+        text.to_string()
+    }
+}
+
+struct Parser<'a> {
+    bytes: &'a [u8],
+    at: usize,
+    operators: usize,
+}
+
+impl Parser<'_> {
+    fn skip_spaces(&mut self) {
+        while self.bytes.get(self.at).is_some_and(u8::is_ascii_whitespace) {
+            self.at += 1;
+        }
+    }
+
+    /// The next non-space byte if it is one of `ops`, consumed.
+    fn operator(&mut self, ops: &[u8]) -> Option<u8> {
+        self.skip_spaces();
+        let op = *self.bytes.get(self.at)?;
+        if ops.contains(&op) {
+            self.at += 1;
+            self.operators += 1;
+            Some(op)
+        } else {
+            None
+        }
+    }
+
+    fn sum(&mut self) -> Option<f64> {
+        let mut value = self.product()?;
+        while let Some(op) = self.operator(b"+-") {
+            let rhs = self.product()?;
+            value = if op == b'+' { value + rhs } else { value - rhs };
+        }
+        Some(value)
+    }
+
+    fn product(&mut self) -> Option<f64> {
+        let mut value = self.unary()?;
+        while let Some(op) = self.operator(b"*/%") {
+            let rhs = self.unary()?;
+            if op != b'*' && rhs == 0.0 {
+                return None;
+            }
+            value = match op {
+                b'*' => value * rhs,
+                b'/' => value / rhs,
+                _ => value % rhs,
+            };
+        }
+        Some(value)
+    }
+
+    /// A leading minus binds looser than `^`, so `-2^2` is -4. It is not counted as
+    /// an operator: `-5` alone is no calculation.
+    fn unary(&mut self) -> Option<f64> {
+        self.skip_spaces();
+        if self.bytes.get(self.at) == Some(&b'-') {
+            self.at += 1;
+            return Some(-self.unary()?);
+        }
+        self.power()
+    }
+
+    fn power(&mut self) -> Option<f64> {
+        let base = self.atom()?;
+        if self.operator(b"^").is_some() {
+            let exponent = self.unary()?;
+            return Some(base.powf(exponent));
+        }
+        Some(base)
+    }
+
+    fn atom(&mut self) -> Option<f64> {
+        self.skip_spaces();
+        if self.bytes.get(self.at) == Some(&b'(') {
+            self.at += 1;
+            let value = self.sum()?;
+            self.skip_spaces();
+            if self.bytes.get(self.at) != Some(&b')') {
+                return None;
+            }
+            self.at += 1;
+            return Some(value);
+        }
+        let start = self.at;
+        while self
+            .bytes
+            .get(self.at)
+            .is_some_and(|b| b.is_ascii_digit() || *b == b'.')
+        {
+            self.at += 1;
+        }
+        std::str::from_utf8(&self.bytes[start..self.at])
+            .ok()?
+            .parse()
+            .ok()
+    }
+}
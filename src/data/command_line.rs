@@ -0,0 +1,182 @@
+use std::ops::Range;
+
+// ==================================================================================
+//  COMMAND LINE
+//  Splits a command line into words with the Windows argument rules: whitespace
+//  separates words outside double quotes, quotes are dropped from the value, and
+//  backslashes only escape a following quote (`\"`, `\\"`), so `C:\dir\` stays as
+//  typed. An unclosed quote runs to the end.
+// ==================================================================================
+
+/// One word: where it is in the text (quotes included) and what it means.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Token {
+    pub range: Range<usize>,
+    pub value: String,
+}
+
+/// A command line split into its program and arguments.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandLine {
+    pub program: Token,
+    pub arguments: Vec<Token>,
+}
+
+impl CommandLine {
+    /// `None` for a blank line.
+    pub fn parse(text: &str) -> Option<CommandLine> {
+        let mut tokens = tokenize(text).into_iter();
+        Some(CommandLine {
+            program: tokens.next()?,
+            arguments: tokens.collect(),
+        })
+    }
+
+    /// Byte offset where the arguments start in `text`: the first word after the
+    /// program, or the end of the line.
+    pub fn arguments_start(&self, text: &str) -> usize {
+        self.arguments
+            .first()
+            .map_or(text.len(), |argument| argument.range.start)
+    }
+}
+
+pub fn tokenize(text: &str) -> Vec<Token> {
+    let mut tokens = Vec::new();
+    let mut current: Option<Token> = None;
+    let mut in_quotes = false;
+    let mut backslashes = 0;
+
+    for (i, c) in text.char_indices() {
+        if c.is_whitespace() && !in_quotes {
+            if let Some(mut token) = current.take() {
+                push_backslashes(&mut token.value, backslashes);
+                tokens.push(token);
+            }
+            backslashes = 0;
+            continue;
+        }
+
+        let token = current.get_or_insert_with(|| Token {
+            range: i..i,
+            value: String::new(),
+        });
+        token.range.end = i + c.len_utf8();
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                push_backslashes(&mut token.value, backslashes / 2);
+                if backslashes.is_multiple_of(2) {
+                    in_quotes = !in_quotes;
+                } else {
+                    token.value.push('"');
+                }
+                backslashes = 0;
+            }
+            _ => {
+                push_backslashes(&mut token.value, backslashes);
+                backslashes = 0;
+                token.value.push(c);
+            }
+        }
+    }
+    if let Some(mut token) = current {
+        push_backslashes(&mut token.value, backslashes);
+        tokens.push(token);
+    }
+    tokens
+}
+
+/// Name arguments are remembered under: the program's file name without its
+/// extension, lowercase, so `code`, `Code.exe` and `C:\VS Code\code.exe` share them.
+pub fn program_key(program: &str) -> String {
+    let name = program.rsplit(['\\', '/']).next().unwrap_or(program);
+    let stem = match name.rsplit_once('.') {
+        Some((stem, _)) if !stem.is_empty() => stem,
+        _ => name,
+    };
+    stem.to_lowercase()
+}
+
+/// `value` in double quotes, read back unchanged by `tokenize`: backslashes before a
+/// quote are doubled, so `C:\` becomes `"C:\\"` rather than ending in `\"`.
+pub fn quote(value: &str) -> String {
+    let mut quoted = String::from('"');
+    let mut backslashes = 0;
+    for c in value.chars() {
+        if c == '"' {
+            push_backslashes(&mut quoted, backslashes + 1);
+        }
+        backslashes = if c == '\\' { backslashes + 1 } else { 0 };
+        quoted.push(c);
+    }
+    push_backslashes(&mut quoted, backslashes);
+    quoted.push('"');
+    quoted
+}
+
+fn push_backslashes(value: &mut String, count: usize) {
+    value.extend(std::iter::repeat_n('\\', count));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn values(text: &str) -> Vec<String> {
+        tokenize(text).into_iter().map(|t| t.value).collect()
+    }
+
+    #[test]
+    fn splits_like_windows() {
+        assert_eq!(values("  git  commit -m x "), ["git", "commit", "-m", "x"]);
+        assert_eq!(
+            values(r#""C:\Program Files\app.exe" "a b" c"#),
+            [r"C:\Program Files\app.exe", "a b", "c"]
+        );
+        assert_eq!(
+            values(r#"echo "say \"hi\"" C:\dir\ a\\"b c""#),
+            ["echo", r#"say "hi""#, r"C:\dir\", r"a\b c"]
+        );
+        assert_eq!(values(r#"x "unclosed quote"#), ["x", "unclosed quote"]);
+        assert_eq!(values(r#"a"b c"d"#), ["ab cd"]);
+        assert_eq!(tokenize(r#"run "a b""#)[1].range, 4..9);
+    }
+
+    #[test]
+    fn decomposes_lines() {
+        let line = r#""C:\VS Code\Code.exe" --new-window ."#;
+        let parsed = CommandLine::parse(line).unwrap();
+        assert_eq!(program_key(&parsed.program.value), "code");
+        assert_eq!(&line[parsed.arguments_start(line)..], "--new-window .");
+        assert_eq!(CommandLine::parse("   "), None);
+        assert_eq!(
+            CommandLine::parse("calc").unwrap().arguments_start("calc"),
+            4
+        );
+    }
+
+    #[test]
+    fn quoted_values_read_back_unchanged() {
+        assert_eq!(quote(r"C:\"), r#""C:\\""#);
+        assert_eq!(quote(r"C:\src\x"), r#""C:\src\x""#);
+        assert_eq!(quote(r#"say "hi""#), r#""say \"hi\"""#);
+        for value in [
+            r"C:\",
+            r"a\\",
+            r#"a\"b"#,
+            "two words",
+            "",
+            r"\\server\share\",
+        ] {
+            assert_eq!(values(&quote(value)), [value]);
+        }
+    }
+
+    #[test]
+    fn program_keys() {
+        assert_eq!(program_key("code"), "code");
+        assert_eq!(program_key(".hidden"), ".hidden");
+        assert_eq!(program_key("a/b/Tool.CMD"), "tool");
+    }
+}
@@ -0,0 +1,342 @@
+use crate::config::COMPLETION_PATH_LIMIT;
+use crate::data::command_line::tokenize;
+use crate::data::completion_spec::{ArgKind, Command};
+use crate::data::matcher::completion_suffix;
+
+// ==================================================================================
+//  COMPLETION
+//  Walks the typed words through a completion spec: subcommands narrow the command,
+//  flags that take a value consume the next word, anything else is a positional
+//  argument. The word under the caret is then completed from whatever may come
+//  there. Folders are listed through `DirectoryLister`, so the walk runs without
+//  Windows.
+// ==================================================================================
+
+/// A whole command line continuing the input, and what the completed word means.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Completion {
+    pub text: String,
+    pub description: String,
+}
+
+pub trait DirectoryLister {
+    /// Names in `dir` (as typed, `%VAR%`s unexpanded) and whether each is a folder.
+    fn list(&self, dir: &str) -> Vec<(String, bool)>;
+}
+
+/// Completions for the last word of `input`, whose first word is the program `spec`
+/// describes. Subcommands come first, then flags or argument values.
+pub fn complete(
+    spec: &Command,
+    input: &str,
+    lister: &impl DirectoryLister,
+    ignore_accents: bool,
+) -> Vec<Completion> {
+    let tokens = tokenize(input);
+    // Still typing the program.
+    if tokens
+        .first()
+        .is_none_or(|program| program.range.end == input.len())
+    {
+        return Vec::new();
+    }
+    let (done, word_start) = match tokens.split_last() {
+        Some((last, rest)) if last.range.end == input.len() => (&rest[1..], last.range.start),
+        _ => (&tokens[1..], input.len()),
+    };
+
+    let mut command = spec;
+    let mut pending: Option<&ArgKind> = None;
+    let mut positional = 0;
+    for token in done {
+        if pending.take().is_some() {
+            continue;
+        }
+        let value = token.value.as_str();
+        if value.len() > 1 && value.starts_with('-') {
+            if !value.contains('=') {
+                pending = command.flag(value).and_then(|flag| flag.value.as_ref());
+            }
+            continue;
+        }
+        if positional == 0
+            && let Some(sub) = command.subcommand(value)
+        {
+            command = sub;
+            continue;
+        }
+        positional += 1;
+    }
+
+    let head = &input[..word_start];
+    let word = &input[word_start..];
+    let matches = |candidate: &str| completion_suffix(candidate, word, ignore_accents).is_some();
+    let mut completions = Vec::new();
+
+    if let Some(kind) = pending {
+        complete_value(kind, head, word, lister, ignore_accents, &mut completions);
+    } else if word.starts_with('-') {
+        match word.split_once('=') {
+            Some((name, value)) => {
+                if let Some(kind) = command.flag(name).and_then(|flag| flag.value.as_ref()) {
+                    let head = format!("{}{}=", head, name);
+                    complete_value(kind, &head, value, lister, ignore_accents, &mut completions);
+                }
+            }
+            None => {
+                for flag in command.flags.iter().filter(|f| matches(&f.name)) {
+                    completions.push(Completion {
+                        text: format!("{}{}", head, flag.name),
+                        description: flag.description.clone(),
+                    });
+                }
+            }
+        }
+    } else {
+        if positional == 0 {
+            for sub in command.subcommands.iter().filter(|c| matches(&c.name)) {
+                completions.push(Completion {
+                    text: format!("{}{}", head, sub.name),
+                    description: sub.description.clone(),
+                });
+            }
+        }
+        if let Some(arg) = command.arg(positional) {
+            complete_value(
+                &arg.kind,
+                head,
+                word,
+                lister,
+                ignore_accents,
+                &mut completions,
+            );
+        }
+    }
+
+    // Nothing to add once the word is complete.
+    completions.retain(|c| c.text != input);
+    completions
+}
+
+fn complete_value(
+    kind: &ArgKind,
+    head: &str,
+    word: &str,
+    lister: &impl DirectoryLister,
+    ignore_accents: bool,
+    completions: &mut Vec<Completion>,
+) {
+    match kind {
+        ArgKind::Text => {}
+        ArgKind::Choice(choices) => {
+            for choice in choices {
+                if completion_suffix(choice, word, ignore_accents).is_some() {
+                    completions.push(Completion {
+                        text: format!("{}{}", head, choice),
+                        description: String::new(),
+                    });
+                }
+            }
+        }
+        ArgKind::File | ArgKind::Dir | ArgKind::Path => {
+            let folders_only = *kind == ArgKind::Dir;
+            for path in complete_path(word, lister, folders_only, ignore_accents) {
+                completions.push(Completion {
+                    text: format!("{}{}", head, path),
+                    description: String::new(),
+                });
+            }
+        }
+    }
+}
+
+/// Entries of the folder typed so far whose names start with the rest of `word`.
+/// Nothing is listed before a separator is typed, and network paths never are.
+fn complete_path(
+    word: &str,
+    lister: &impl DirectoryLister,
+    folders_only: bool,
+    ignore_accents: bool,
+) -> Vec<String> {
+    let path = word.replace('"', "");
+    let Some(split) = path.rfind(['\\', '/']) else {
+        return Vec::new();
+    };
+    if path.starts_with("\\\\") {
+        return Vec::new();
+    }
+    let (dir, name) = path.split_at(split + 1);
+    let separator = &dir[split..];
+
+    let mut entries: Vec<(String, bool)> = lister
+        .list(dir)
+        .into_iter()
+        .filter(|(entry, is_dir)| {
+            (*is_dir || !folders_only) && completion_suffix(entry, name, ignore_accents).is_some()
+        })
+        .collect();
+    entries.sort_by_key(|(entry, is_dir)| (!is_dir, entry.to_lowercase()));
+    entries.truncate(COMPLETION_PATH_LIMIT);
+
+    entries
+        .into_iter()
+        .map(|(entry, is_dir)| {
+            let full = format!("{}{}", dir, entry);
+            // A folder's quote stays open so typing can go on inside it; `\"` would
+            // escape the quote anyway.
+            match (full.contains(char::is_whitespace), is_dir) {
+                (true, true) => format!("\"{}{}", full, separator),
+                (true, false) => format!("\"{}\"", full),
+                (false, true) => format!("{}{}", full, separator),
+                (false, false) => full,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::completion_spec::{bundled_spec, parse_spec};
+
+    struct FakeLister;
+
+    impl DirectoryLister for FakeLister {
+        fn list(&self, dir: &str) -> Vec<(String, bool)> {
+            let entries: &[(&str, bool)] = match dir {
+                "C:\\" => &[
+                    ("Users", true),
+                    ("Program Files", true),
+                    ("pagefile.sys", false),
+                    ("Windows", true),
+                ],
+                "src/" => &[("main.rs", false), ("ui", true), ("my file.txt", false)],
+                _ => &[],
+            };
+            entries
+                .iter()
+                .map(|(name, dir)| (name.to_string(), *dir))
+                .collect()
+        }
+    }
+
+    const SPEC: &str = "
+[tool]
+description = A tool
+args = <path>...
+-v, --verbose = Talk more
+--mode = <fast|slow> How to run
+-C = <dir> Run in a folder
+
+[tool remote add]
+args = <text> <text>
+
+[tool remote]
+description = Manage remotes
+
+[tool run]
+args = <debug|release> <file>
+";
+
+    fn spec() -> Command {
+        parse_spec("tool.ini", SPEC).unwrap()
+    }
+
+    fn texts(input: &str) -> Vec<String> {
+        complete(&spec(), input, &FakeLister, false)
+            .into_iter()
+            .map(|c| c.text)
+            .collect()
+    }
+
+    #[test]
+    fn walks_subcommands_and_flags() {
+        assert!(texts("tool").is_empty());
+        assert_eq!(texts("tool "), ["tool remote", "tool run"]);
+        assert_eq!(texts("tool re"), ["tool remote"]);
+        assert_eq!(texts("tool remote "), ["tool remote add"]);
+        assert!(texts("tool remote add ").is_empty());
+        assert_eq!(
+            texts("tool -"),
+            ["tool -v", "tool --verbose", "tool --mode", "tool -C"]
+        );
+        assert_eq!(texts("tool --m"), ["tool --mode"]);
+        assert_eq!(
+            complete(&spec(), "tool --v", &FakeLister, false),
+            [Completion {
+                text: "tool --verbose".into(),
+                description: "Talk more".into()
+            }]
+        );
+        // A word that is already complete offers nothing.
+        assert!(texts("tool remote").is_empty());
+    }
+
+    #[test]
+    fn completes_flag_values_and_arguments() {
+        assert_eq!(
+            texts("tool --mode "),
+            ["tool --mode fast", "tool --mode slow"]
+        );
+        assert_eq!(texts("tool --mode=s"), ["tool --mode=slow"]);
+        // The flag's value is consumed, so subcommands are still offered.
+        assert_eq!(
+            texts("tool --mode fast r"),
+            ["tool --mode fast remote", "tool --mode fast run"]
+        );
+        assert_eq!(texts("tool run "), ["tool run debug", "tool run release"]);
+        assert_eq!(
+            texts("tool run debug src/ma"),
+            ["tool run debug src/main.rs"]
+        );
+    }
+
+    #[test]
+    fn completes_paths() {
+        assert_eq!(
+            texts("tool C:\\"),
+            [
+                "tool \"C:\\Program Files\\",
+                "tool C:\\Users\\",
+                "tool C:\\Windows\\",
+                "tool C:\\pagefile.sys"
+            ]
+        );
+        assert_eq!(texts("tool C:\\w"), ["tool C:\\Windows\\"]);
+        // A `<dir>` value lists folders only.
+        assert_eq!(
+            texts("tool -C C:\\"),
+            [
+                "tool -C \"C:\\Program Files\\",
+                "tool -C C:\\Users\\",
+                "tool -C C:\\Windows\\"
+            ]
+        );
+        assert_eq!(texts("tool src/my"), ["tool \"src/my file.txt\""]);
+        assert!(texts("tool sr").is_empty());
+        assert!(texts("tool \\\\server\\").is_empty());
+    }
+
+    #[test]
+    fn bundled_specs_complete() {
+        let git = parse_spec("git", bundled_spec("git").unwrap()).unwrap();
+        let out = complete(&git, "git ch", &FakeLister, false);
+        assert_eq!(out[0].text, "git checkout");
+        assert_eq!(out[0].description, "Switch branches or restore files");
+        let winget = parse_spec("winget", bundled_spec("winget").unwrap()).unwrap();
+        let texts = |input| -> Vec<String> {
+            complete(&winget, input, &FakeLister, false)
+                .into_iter()
+                .map(|c| c.text)
+                .collect()
+        };
+        assert_eq!(texts("winget in"), ["winget install"]);
+        assert_eq!(
+            texts("winget install --scope "),
+            [
+                "winget install --scope user",
+                "winget install --scope machine"
+            ]
+        );
+    }
+}
@@ -0,0 +1,332 @@
+#![allow(static_mut_refs)]
+
+use std::collections::HashMap;
+use std::fs;
+
+use crate::data::ini::*;
+use crate::data::settings::get_config_dir;
+
+// ==================================================================================
+//  COMPLETION SPECS
+//  What a program accepts, one INI file per program under `completions/`. Each
+//  section names a command path (`[git]`, `[git remote add]`); inside it:
+//    description = Shown next to the subcommand in the dropdown
+//    args        = <file> <dir> <path> <text> or <a|b|c>; a trailing `...` repeats
+//    -m, --message = <text> A flag, its value kind if it takes one, and description
+//  A user file replaces the bundled spec of the same name.
+// ==================================================================================
+
+pub const COMPLETIONS_DIR: &str = "completions";
+
+pub const BUNDLED_SPECS: &[(&str, &str)] = &[
+    ("git", include_str!("../../res/completions/git.ini")),
+    ("winget", include_str!("../../res/completions/winget.ini")),
+    ("code", include_str!("../../res/completions/code.ini")),
+    ("wt", include_str!("../../res/completions/wt.ini")),
+    (
+        "powershell",
+        include_str!("../../res/completions/powershell.ini"),
+    ),
+    ("pwsh", include_str!("../../res/completions/powershell.ini")),
+];
+
+// Specs by program key, parsed on first use; `None` for programs without one.
+pub static mut COMPLETION_SPECS: Option<HashMap<String, Option<Command>>> = None;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ArgKind {
+    /// Free text; nothing to complete.
+    Text,
+    File,
+    Dir,
+    /// A file or a folder.
+    Path,
+    Choice(Vec<String>),
+}
+
+impl ArgKind {
+    /// Parses `<kind>`, brackets included.
+    pub fn parse(spec: &str) -> Option<ArgKind> {
+        let inner = spec.strip_prefix('<')?.strip_suffix('>')?;
+        Some(match inner {
+            "text" => ArgKind::Text,
+            "file" => ArgKind::File,
+            "dir" => ArgKind::Dir,
+            "path" => ArgKind::Path,
+            _ if inner.contains('|') => {
+                let choices: Vec<String> = inner.split('|').map(str::to_string).collect();
+                if choices.iter().any(|c| c.is_empty()) {
+                    return None;
+                }
+                ArgKind::Choice(choices)
+            }
+            _ => return None,
+        })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Arg {
+    pub kind: ArgKind,
+    pub repeats: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Flag {
+    pub name: String,
+    pub description: String,
+    pub value: Option<ArgKind>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Command {
+    pub name: String,
+    pub description: String,
+    pub subcommands: Vec<Command>,
+    pub flags: Vec<Flag>,
+    pub args: Vec<Arg>,
+}
+
+impl Command {
+    /// Exact match first, then ignoring case (PowerShell and winget do).
+    pub fn subcommand(&self, name: &str) -> Option<&Command> {
+        self.subcommands
+            .iter()
+            .find(|c| c.name == name)
+            .or_else(|| {
+                self.subcommands
+                    .iter()
+                    .find(|c| c.name.eq_ignore_ascii_case(name))
+            })
+    }
+
+    /// Same rule as `subcommand`, so `-m` and `-M` stay apart.
+    pub fn flag(&self, name: &str) -> Option<&Flag> {
+        self.flags.iter().find(|f| f.name == name).or_else(|| {
+            self.flags
+                .iter()
+                .find(|f| f.name.eq_ignore_ascii_case(name))
+        })
+    }
+
+    /// The positional argument at `index`; the last one covers the rest if it repeats.
+    pub fn arg(&self, index: usize) -> Option<&Arg> {
+        self.args
+            .get(index)
+            .or_else(|| self.args.last().filter(|arg| arg.repeats))
+    }
+
+    fn path_mut(&mut self, path: &[&str]) -> &mut Command {
+        let Some((first, rest)) = path.split_first() else {
+            return self;
+        };
+        let index = match self.subcommands.iter().position(|c| c.name == *first) {
+            Some(index) => index,
+            None => {
+                self.subcommands.push(Command {
+                    name: first.to_string(),
+                    ..Default::default()
+                });
+                self.subcommands.len() - 1
+            }
+        };
+        self.subcommands[index].path_mut(rest)
+    }
+}
+
+pub fn parse_spec(file: &str, text: &str) -> Result<Command, ConfigError> {
+    let doc = parse_ini(file, text)?;
+    let mut root: Option<Command> = None;
+
+    for section in &doc.sections {
+        if section.name.is_empty() {
+            let line = section.entries.first().map_or(0, |e| e.line);
+            return Err(ConfigError::new(file, line, "expected a [command] section"));
+        }
+        let path: Vec<&str> = section.name.split_whitespace().collect();
+        let root = root.get_or_insert_with(|| Command {
+            name: path[0].to_string(),
+            ..Default::default()
+        });
+        if path[0] != root.name {
+            return Err(ConfigError::new(
+                file,
+                section.line,
+                format!(
+                    "section '{}' does not start with '{}'",
+                    section.name, root.name
+                ),
+            ));
+        }
+        let command = root.path_mut(&path[1..]);
+
+        for entry in &section.entries {
+            match entry.key.as_str() {
+                "description" => command.description = entry.value.clone(),
+                "args" => command.args = parse_args(file, entry)?,
+                key if key.starts_with('-') => {
+                    let (value, description) = match entry.value.strip_prefix('<') {
+                        Some(_) => {
+                            let end = entry.value.find('>').map_or(entry.value.len(), |i| i + 1);
+                            let kind = ArgKind::parse(&entry.value[..end])
+                                .ok_or_else(|| bad_kind(file, entry, &entry.value[..end]))?;
+                            (Some(kind), entry.value[end..].trim())
+                        }
+                        None => (None, entry.value.as_str()),
+                    };
+                    for name in key.split(',').map(str::trim) {
+                        command.flags.push(Flag {
+                            name: name.to_string(),
+                            description: description.to_string(),
+                            value: value.clone(),
+                        });
+                    }
+                }
+                _ => {
+                    return Err(ConfigError::new(
+                        file,
+                        entry.line,
+                        format!("unknown key '{}'", entry.key),
+                    ));
+                }
+            }
+        }
+    }
+
+    root.ok_or_else(|| ConfigError::new(file, 0, "no [command] section"))
+}
+
+fn parse_args(file: &str, entry: &IniEntry) -> Result<Vec<Arg>, ConfigError> {
+    entry
+        .value
+        .split_whitespace()
+        .map(|word| {
+            let (spec, repeats) = match word.strip_suffix("...") {
+                Some(spec) => (spec, true),
+                None => (word, false),
+            };
+            ArgKind::parse(spec)
+                .map(|kind| Arg { kind, repeats })
+                .ok_or_else(|| bad_kind(file, entry, word))
+        })
+        .collect()
+}
+
+fn bad_kind(file: &str, entry: &IniEntry, kind: &str) -> ConfigError {
+    ConfigError::new(
+        file,
+        entry.line,
+        format!(
+            "invalid argument kind '{}' (expected <file>, <dir>, <path>, <text> or <a|b>)",
+            kind
+        ),
+    )
+}
+
+pub fn bundled_spec(key: &str) -> Option<&'static str> {
+    BUNDLED_SPECS
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(key))
+        .map(|(_, text)| *text)
+}
+
+/// Spec for a program key (see `command_line::program_key`). A user file that does
+/// not parse falls back to the bundled spec.
+pub fn completion_spec(key: &str) -> Option<Command> {
+    unsafe {
+        COMPLETION_SPECS
+            .get_or_insert_with(HashMap::new)
+            .entry(key.to_string())
+            .or_insert_with(|| load_spec(key))
+            .clone()
+    }
+}
+
+/// Forgets parsed specs so edited files are read again.
+pub fn clear_completion_specs() {
+    unsafe { COMPLETION_SPECS = None };
+}
+
+fn load_spec(key: &str) -> Option<Command> {
+    // Keys become file names, so they must not leave the completions folder.
+    if key.is_empty()
+        || !key
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+    {
+        return None;
+    }
+    let relative = format!("{}/{}.ini", COMPLETIONS_DIR, key);
+    let user = get_config_dir()
+        .and_then(|dir| fs::read_to_string(dir.join(&relative)).ok())
+        .and_then(|text| parse_spec(&relative, &text).ok());
+    user.or_else(|| parse_spec(&relative, bundled_spec(key)?).ok())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SPEC: &str = "
+[tool]
+description = A tool
+args = <path>...
+-v, --verbose = Talk more
+--mode = <fast|slow> How to run
+
+[tool remote add]
+description = Add a remote
+args = <text> <text>
+
+[tool remote]
+description = Manage remotes
+
+[tool run]
+args = <debug|release> <file>
+";
+
+    #[test]
+    fn parses_commands_flags_and_args() {
+        let spec = parse_spec("tool.ini", SPEC).unwrap();
+        assert_eq!(spec.name, "tool");
+        assert_eq!(spec.subcommands.len(), 2);
+        let remote = spec.subcommand("REMOTE").unwrap();
+        assert_eq!(remote.description, "Manage remotes");
+        assert_eq!(remote.subcommand("add").unwrap().args.len(), 2);
+        assert_eq!(spec.flag("--verbose").unwrap().description, "Talk more");
+        assert_eq!(spec.flag("-v").unwrap().description, "Talk more");
+        let mode = spec.flag("--mode").unwrap();
+        assert_eq!(
+            mode.value,
+            Some(ArgKind::Choice(vec!["fast".into(), "slow".into()]))
+        );
+        assert_eq!(mode.description, "How to run");
+        // A repeating last argument covers every later position.
+        assert!(spec.arg(5).unwrap().repeats);
+    }
+
+    #[test]
+    fn rejects_malformed_specs() {
+        assert!(parse_spec("x.ini", "").is_err());
+        assert!(parse_spec("x.ini", "key = 1").is_err());
+        assert!(parse_spec("x.ini", "[a]\nfoo = bar").is_err());
+        assert!(parse_spec("x.ini", "[a]\n[b c]").is_err());
+        assert!(parse_spec("x.ini", "[a]\n-x = <a|> y").is_err());
+        let err = parse_spec("x.ini", "[a]\n\nargs = <files>").unwrap_err();
+        assert_eq!(err.line, 3);
+    }
+
+    #[test]
+    fn bundled_specs_parse() {
+        for (name, text) in BUNDLED_SPECS {
+            let spec = parse_spec(name, text).unwrap_or_else(|e| panic!("{}: {}", name, e));
+            assert!(
+                !spec.subcommands.is_empty() || !spec.flags.is_empty(),
+                "{}",
+                name
+            );
+        }
+        assert!(bundled_spec("git").is_some());
+        assert!(bundled_spec("nonexistent").is_none());
+    }
+}
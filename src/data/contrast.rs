@@ -0,0 +1,226 @@
+#![allow(static_mut_refs)]
+
+use crate::config::*;
+use crate::data::theme::*;
+
+// ==================================================================================
+//  HIGH CONTRAST
+//  While Windows high contrast is on, the palette comes from the system colours
+//  instead of the theme file. Every text/background pair is checked against the
+//  WCAG contrast ratio and falls back to black or white when the system pair is
+//  not readable enough.
+// ==================================================================================
+
+/// Set by the UI layer while high contrast is on; takes precedence over the user theme.
+pub static mut HIGH_CONTRAST_THEME: Option<Theme> = None;
+
+/// The `GetSysColor` entries SwiftRun paints with.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct SystemColors {
+    pub window: Color,
+    pub window_text: Color,
+    pub button_face: Color,
+    pub highlight: Color,
+    pub highlight_text: Color,
+    pub gray_text: Color,
+}
+
+/// WCAG 2 relative luminance of an sRGB colour (alpha is ignored).
+pub fn relative_luminance(c: Color) -> f32 {
+    let linear = |v: f32| {
+        if v <= 0.03928 {
+            v / 12.92
+        } else {
+            ((v + 0.055) / 1.055).powf(2.4)
+        }
+    };
+    0.2126 * linear(c.r) + 0.7152 * linear(c.g) + 0.0722 * linear(c.b)
+}
+
+/// WCAG 2 contrast ratio, from 1.0 (identical) to 21.0 (black on white).
+pub fn contrast_ratio(a: Color, b: Color) -> f32 {
+    let (la, lb) = (relative_luminance(a), relative_luminance(b));
+    let (hi, lo) = if la > lb { (la, lb) } else { (lb, la) };
+    (hi + 0.05) / (lo + 0.05)
+}
+
+/// `preferred` if it reaches `min_ratio` on `background`, otherwise black or white,
+/// whichever contrasts more.
+pub fn readable_on(background: Color, preferred: Color, min_ratio: f32) -> Color {
+    if contrast_ratio(preferred, background) >= min_ratio {
+        return preferred;
+    }
+    let (black, white) = (Color::gray(0.0), Color::gray(1.0));
+    if contrast_ratio(black, background) >= contrast_ratio(white, background) {
+        black
+    } else {
+        white
+    }
+}
+
+/// Derives a fully opaque theme from the system colours. Fonts and radii still come
+/// from `base`; colours, accent and backdrop do not.
+pub fn high_contrast_theme(base: &Theme, sys: &SystemColors) -> Theme {
+    let opaque = |c: Color| c.with_alpha(1.0);
+    let window = opaque(sys.window);
+    let highlight = opaque(sys.highlight);
+
+    let text = readable_on(window, opaque(sys.window_text), HC_MIN_TEXT_CONTRAST);
+    // Buttons share the window text colour, so fall back to the window colour if
+    // the button face does not carry it.
+    let button = if contrast_ratio(text, opaque(sys.button_face)) >= HC_MIN_TEXT_CONTRAST {
+        opaque(sys.button_face)
+    } else {
+        window
+    };
+    // Gray text is often too faint in custom high-contrast schemes.
+    let text_secondary = if contrast_ratio(opaque(sys.gray_text), window) >= HC_MIN_TEXT_CONTRAST {
+        opaque(sys.gray_text)
+    } else {
+        text
+    };
+    let highlight_text = readable_on(highlight, opaque(sys.highlight_text), HC_MIN_TEXT_CONTRAST);
+
+    let palette = Palette {
+        background: window,
+        text,
+        text_secondary,
+        button,
+        button_hover: highlight,
+        border: readable_on(window, text, HC_MIN_UI_CONTRAST),
+        input_background: window,
+        backdrop_tint: window,
+        hover_text: highlight_text,
+        accent_text: highlight_text,
+        // Only the system colours are legible, so the command line is not coloured.
+        syntax_program: text,
+        syntax_string: text,
+        syntax_variable: text,
+        syntax_url: text,
+    };
+
+    Theme {
+        name: "High Contrast".to_string(),
+        dark: palette,
+        light: palette,
+        accent: AccentSource::Custom(highlight),
+        destructive: highlight,
+        warning: readable_on(window, highlight, HC_MIN_UI_CONTRAST),
+        fonts: base.fonts.clone(),
+        corner_radius: base.corner_radius,
+        tooltip_corner_radius: base.tooltip_corner_radius,
+        backdrop: Backdrop::Solid,
+        high_contrast: true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hex(value: &str) -> Color {
+        Color::parse_hex(value).unwrap()
+    }
+
+    fn system_colors(colors: [&str; 6]) -> SystemColors {
+        SystemColors {
+            window: hex(colors[0]),
+            window_text: hex(colors[1]),
+            button_face: hex(colors[2]),
+            highlight: hex(colors[3]),
+            highlight_text: hex(colors[4]),
+            gray_text: hex(colors[5]),
+        }
+    }
+
+    /// Every pair the UI draws must be readable, whatever the system colours are.
+    fn assert_readable(sys: SystemColors) {
+        let theme = high_contrast_theme(&Theme::default(), &sys);
+        assert!(theme.high_contrast);
+        assert_eq!(theme.backdrop, Backdrop::Solid);
+        assert_eq!(theme.dark, theme.light);
+        let p = theme.palette(true);
+        for (fg, bg) in [
+            (p.text, p.background),
+            (p.text, p.button),
+            (p.text_secondary, p.background),
+            (p.hover_text, p.button_hover),
+        ] {
+            assert!(
+                contrast_ratio(fg, bg) >= HC_MIN_TEXT_CONTRAST,
+                "{:?} on {:?}",
+                fg,
+                bg
+            );
+        }
+        let AccentSource::Custom(accent) = theme.accent else {
+            panic!("accent should come from the highlight colour");
+        };
+        assert!(contrast_ratio(p.accent_text, accent) >= HC_MIN_TEXT_CONTRAST);
+        assert!(contrast_ratio(p.border, p.background) >= HC_MIN_UI_CONTRAST);
+        for c in [p.background, p.text, p.button, p.border, p.input_background] {
+            assert_eq!(c.a, 1.0);
+        }
+    }
+
+    #[test]
+    fn contrast_ratio_extremes() {
+        let (black, white) = (Color::gray(0.0), Color::gray(1.0));
+        assert!((contrast_ratio(black, white) - 21.0).abs() < 0.01);
+        assert!((contrast_ratio(white, black) - 21.0).abs() < 0.01);
+        assert!((contrast_ratio(white, white) - 1.0).abs() < 0.001);
+        // Alpha does not count.
+        assert_eq!(
+            contrast_ratio(black.with_alpha(0.1), white),
+            contrast_ratio(black, white)
+        );
+    }
+
+    #[test]
+    fn readable_on_keeps_readable_colours() {
+        let white = Color::gray(1.0);
+        let navy = hex("#000080");
+        assert_eq!(readable_on(white, navy, 4.5), navy);
+        assert_eq!(readable_on(white, hex("#EEEEEE"), 4.5), Color::gray(0.0));
+        assert_eq!(readable_on(hex("#101010"), hex("#202020"), 4.5), white);
+    }
+
+    #[test]
+    fn high_contrast_black() {
+        assert_readable(system_colors([
+            "#000000", "#FFFFFF", "#000000", "#1AEBFF", "#000000", "#3FF23F",
+        ]));
+    }
+
+    #[test]
+    fn high_contrast_white() {
+        assert_readable(system_colors([
+            "#FFFFFF", "#000000", "#FFFFFF", "#37006E", "#FFFFFF", "#600000",
+        ]));
+    }
+
+    #[test]
+    fn unreadable_custom_scheme_falls_back() {
+        let sys = system_colors([
+            "#808080", "#888888", "#000000", "#777777", "#7A7A7A", "#818181",
+        ]);
+        assert_readable(sys);
+        let theme = high_contrast_theme(&Theme::default(), &sys);
+        // The button face cannot carry the text, so buttons use the window colour.
+        assert_eq!(theme.dark.button, theme.dark.background);
+        assert_eq!(theme.dark.text_secondary, theme.dark.text);
+    }
+
+    #[test]
+    fn fonts_come_from_the_base_theme() {
+        let mut base = Theme::default();
+        base.fonts.label_size = 17.0;
+        base.corner_radius = 3.0;
+        let sys = system_colors([
+            "#000000", "#FFFFFF", "#000000", "#1AEBFF", "#000000", "#3FF23F",
+        ]);
+        let theme = high_contrast_theme(&base, &sys);
+        assert_eq!(theme.fonts, base.fonts);
+        assert_eq!(theme.corner_radius, 3.0);
+    }
+}
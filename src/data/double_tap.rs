@@ -0,0 +1,264 @@
+// ==================================================================================
+//  DOUBLE TAP
+//  Detects a modifier tapped twice in quick succession (Ctrl, Ctrl), which
+//  `RegisterHotKey` cannot express. Fed with every key event from the low-level
+//  keyboard hook; pure so it can be driven with made-up timestamps.
+// ==================================================================================
+
+// Virtual-key codes; the low-level hook reports the left/right variants.
+const VK_SHIFT: u32 = 0x10;
+const VK_CONTROL: u32 = 0x11;
+const VK_MENU: u32 = 0x12;
+const VK_LSHIFT: u32 = 0xA0;
+const VK_RSHIFT: u32 = 0xA1;
+const VK_LCONTROL: u32 = 0xA2;
+const VK_RCONTROL: u32 = 0xA3;
+const VK_LMENU: u32 = 0xA4;
+const VK_RMENU: u32 = 0xA5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TapKey {
+    Ctrl,
+    Alt,
+    Shift,
+}
+
+impl TapKey {
+    pub fn parse(value: &str) -> Option<TapKey> {
+        match value.to_ascii_lowercase().as_str() {
+            "ctrl" | "control" => Some(TapKey::Ctrl),
+            "alt" => Some(TapKey::Alt),
+            "shift" => Some(TapKey::Shift),
+            _ => None,
+        }
+    }
+
+    /// Left and right keys count as the same key.
+    pub fn matches(self, vk: u32) -> bool {
+        match self {
+            TapKey::Ctrl => matches!(vk, VK_CONTROL | VK_LCONTROL | VK_RCONTROL),
+            TapKey::Alt => matches!(vk, VK_MENU | VK_LMENU | VK_RMENU),
+            TapKey::Shift => matches!(vk, VK_SHIFT | VK_LSHIFT | VK_RSHIFT),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyEvent {
+    pub vk: u32,
+    pub down: bool,
+    /// Milliseconds from a clock that may wrap, like `KBDLLHOOKSTRUCT::time`.
+    pub time: u32,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TapState {
+    Idle,
+    FirstDown { at: u32 },
+    FirstUp { at: u32 },
+    SecondDown { at: u32 },
+}
+
+/// Fires when `key` is pressed and released twice, with no other key in between,
+/// each press and the gap between them no longer than `interval_ms`. Auto-repeat
+/// key-downs while the key is held are ignored.
+#[derive(Debug, Clone)]
+pub struct DoubleTapDetector {
+    pub key: TapKey,
+    pub interval_ms: u32,
+    state: TapState,
+}
+
+impl DoubleTapDetector {
+    pub fn new(key: TapKey, interval_ms: u32) -> Self {
+        DoubleTapDetector {
+            key,
+            interval_ms,
+            state: TapState::Idle,
+        }
+    }
+
+    /// Feeds one key event; returns true on the key-up that completes a double tap.
+    pub fn feed(&mut self, event: KeyEvent) -> bool {
+        if !self.key.matches(event.vk) {
+            // Any other key pressed in between (Ctrl+C, say) cancels the sequence.
+            // Releases are ignored, they may belong to keys pressed before it.
+            if event.down {
+                self.state = TapState::Idle;
+            }
+            return false;
+        }
+
+        let within = |at: u32| event.time.wrapping_sub(at) <= self.interval_ms;
+        let (next, fired) = match (self.state, event.down) {
+            (TapState::Idle, true) => (TapState::FirstDown { at: event.time }, false),
+            (TapState::FirstUp { at }, true) if within(at) => {
+                (TapState::SecondDown { at: event.time }, false)
+            }
+            // Too slow for a second tap, but it can still start a new sequence.
+            (TapState::FirstUp { .. }, true) => (TapState::FirstDown { at: event.time }, false),
+            // Auto-repeat while held.
+            (held @ (TapState::FirstDown { .. } | TapState::SecondDown { .. }), true) => {
+                (held, false)
+            }
+            (TapState::FirstDown { at }, false) if within(at) => {
+                (TapState::FirstUp { at: event.time }, false)
+            }
+            (TapState::SecondDown { at }, false) if within(at) => (TapState::Idle, true),
+            // Held too long to be a tap, or a stray release.
+            (_, false) => (TapState::Idle, false),
+        };
+        self.state = next;
+        fired
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::settings::parse_settings;
+
+    const VK_C: u32 = 0x43;
+
+    /// Feeds `(vk, down, time)` events and returns which of them fired.
+    fn feed(detector: &mut DoubleTapDetector, events: &[(u32, bool, u32)]) -> Vec<bool> {
+        events
+            .iter()
+            .map(|&(vk, down, time)| detector.feed(KeyEvent { vk, down, time }))
+            .collect()
+    }
+
+    #[test]
+    fn fires_on_the_second_release() {
+        let mut detector = DoubleTapDetector::new(TapKey::Ctrl, 300);
+        let events = [
+            (VK_LCONTROL, true, 0),
+            (VK_LCONTROL, false, 50),
+            (VK_RCONTROL, true, 150),
+            (VK_RCONTROL, false, 200),
+        ];
+        assert_eq!(feed(&mut detector, &events), [false, false, false, true]);
+        // A third tap starts over rather than firing again.
+        let events = [(VK_LCONTROL, true, 250), (VK_LCONTROL, false, 260)];
+        assert_eq!(feed(&mut detector, &events), [false, false]);
+    }
+
+    #[test]
+    fn ignores_auto_repeat() {
+        let mut detector = DoubleTapDetector::new(TapKey::Ctrl, 300);
+        let events = [
+            (VK_LCONTROL, true, 0),
+            (VK_LCONTROL, true, 30),
+            (VK_LCONTROL, true, 60),
+            (VK_LCONTROL, false, 90),
+            (VK_LCONTROL, true, 150),
+            (VK_LCONTROL, true, 180),
+            (VK_LCONTROL, false, 200),
+        ];
+        assert_eq!(feed(&mut detector, &events).last(), Some(&true));
+    }
+
+    #[test]
+    fn another_key_press_cancels() {
+        let mut detector = DoubleTapDetector::new(TapKey::Ctrl, 300);
+        let between = [
+            (VK_LCONTROL, true, 0),
+            (VK_LCONTROL, false, 50),
+            (VK_C, true, 60),
+            (VK_C, false, 70),
+            (VK_LCONTROL, true, 100),
+            (VK_LCONTROL, false, 150),
+        ];
+        assert!(!feed(&mut detector, &between).contains(&true));
+
+        let mut detector = DoubleTapDetector::new(TapKey::Ctrl, 300);
+        let chord = [
+            (VK_LCONTROL, true, 0),
+            (VK_LCONTROL, false, 50),
+            (VK_LCONTROL, true, 100),
+            (VK_C, true, 120),
+            (VK_LCONTROL, false, 150),
+        ];
+        assert!(!feed(&mut detector, &chord).contains(&true));
+    }
+
+    #[test]
+    fn another_key_release_does_not_cancel() {
+        let mut detector = DoubleTapDetector::new(TapKey::Ctrl, 300);
+        let events = [
+            (VK_LCONTROL, true, 0),
+            (VK_C, false, 10),
+            (VK_LCONTROL, false, 50),
+            (VK_LCONTROL, true, 100),
+            (VK_LCONTROL, false, 150),
+        ];
+        assert_eq!(feed(&mut detector, &events).last(), Some(&true));
+    }
+
+    #[test]
+    fn slow_second_tap_starts_a_new_sequence() {
+        let mut detector = DoubleTapDetector::new(TapKey::Ctrl, 300);
+        let events = [
+            (VK_LCONTROL, true, 0),
+            (VK_LCONTROL, false, 50),
+            (VK_LCONTROL, true, 500),
+            (VK_LCONTROL, false, 550),
+            (VK_LCONTROL, true, 600),
+            (VK_LCONTROL, false, 650),
+        ];
+        assert_eq!(
+            feed(&mut detector, &events),
+            [false, false, false, false, false, true]
+        );
+    }
+
+    #[test]
+    fn long_press_is_not_a_tap() {
+        let mut detector = DoubleTapDetector::new(TapKey::Ctrl, 300);
+        let events = [
+            (VK_LCONTROL, true, 0),
+            (VK_LCONTROL, false, 400),
+            (VK_LCONTROL, true, 450),
+            (VK_LCONTROL, false, 500),
+        ];
+        assert!(!feed(&mut detector, &events).contains(&true));
+    }
+
+    #[test]
+    fn survives_a_wrapping_clock() {
+        let mut detector = DoubleTapDetector::new(TapKey::Alt, 300);
+        let events = [
+            (VK_LMENU, true, u32::MAX - 60),
+            (VK_LMENU, false, u32::MAX - 10),
+            (VK_RMENU, true, 40),
+            (VK_RMENU, false, 90),
+        ];
+        assert_eq!(feed(&mut detector, &events).last(), Some(&true));
+    }
+
+    #[test]
+    fn keys_parse_and_match_both_sides() {
+        assert_eq!(TapKey::parse("Control"), Some(TapKey::Ctrl));
+        assert_eq!(TapKey::parse("win"), None);
+        assert!(TapKey::Shift.matches(VK_RSHIFT));
+        assert!(TapKey::Shift.matches(VK_SHIFT));
+        assert!(!TapKey::Shift.matches(VK_LCONTROL));
+    }
+
+    #[test]
+    fn settings_section() {
+        let settings = parse_settings("[double_tap]\nkey = Ctrl\ninterval_ms = 250\n").unwrap();
+        assert_eq!(settings.double_tap, Some(TapKey::Ctrl));
+        assert_eq!(settings.double_tap_ms, 250);
+        // The default hotkey stays.
+        assert_eq!(settings.hotkeys.len(), 1);
+        assert!(parse_settings("[double_tap]\nkey = win\n").is_err());
+        assert!(parse_settings("[double_tap]\ninterval_ms = 50\n").is_err());
+        assert_eq!(
+            parse_settings("[double_tap]\nkey = none\n")
+                .unwrap()
+                .double_tap,
+            None
+        );
+    }
+}
@@ -0,0 +1,255 @@
+use crate::data::syntax::words;
+
+// ==================================================================================
+//  HISTORY EXPANSION
+//  Bash-style event designators, replaced before the command is resolved:
+//    !!        the previous command        !n / !-n   command n / n commands back
+//    !prefix   last command starting so    !?text[?]  last command containing text
+//    !$        last word of the previous   ^old^new[^] previous, old replaced by new
+//  History is newest first, like `HISTORY`; `!1` is the oldest entry. A `!` starts
+//  a designator only at the start of a word and outside double quotes, so paths
+//  (`C:\Games\Fun!git`), URLs (`#!/page`) and quoted text keep theirs; `\!` at the
+//  start of a word is a literal `!`.
+// ==================================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ExpansionError {
+    /// The designator, such as `!git`, matched no command.
+    EventNotFound(String),
+    /// `^old^new` where the previous command does not contain `old`.
+    SubstitutionFailed(String),
+}
+
+/// `input` with every designator replaced and `\!` escapes undone, or `None` when it
+/// has neither.
+pub fn expand_history(input: &str, history: &[String]) -> Result<Option<String>, ExpansionError> {
+    if let Some(rest) = input.strip_prefix('^') {
+        return quick_substitution(rest, history).map(Some);
+    }
+
+    let mut output = String::with_capacity(input.len());
+    let mut expanded = false;
+    let (mut quoted, mut word_start) = (false, true);
+    let mut rest = input;
+    while let Some(c) = rest.chars().next() {
+        if c == '"' {
+            quoted = !quoted;
+        }
+        if word_start && !quoted {
+            if let Some(after) = rest.strip_prefix("\\!") {
+                output.push('!');
+                (rest, word_start, expanded) = (after, false, true);
+                continue;
+            }
+            if let Some(designator) = rest.strip_prefix('!')
+                && let Some((command, used)) = event(designator, history)?
+            {
+                output.push_str(&command);
+                (rest, word_start, expanded) = (&designator[used..], false, true);
+                continue;
+            }
+        }
+        output.push(c);
+        word_start = c.is_whitespace();
+        rest = &rest[c.len_utf8()..];
+    }
+    Ok(expanded.then_some(output))
+}
+
+/// The command for the designator after a `!` and how many bytes it spans, or `None`
+/// when the `!` is literal (followed by a space, `=`, `(` or nothing).
+fn event(designator: &str, history: &[String]) -> Result<Option<(String, usize)>, ExpansionError> {
+    let not_found = |text: &str| ExpansionError::EventNotFound(format!("!{}", text));
+    let Some(first) = designator.chars().next() else {
+        return Ok(None);
+    };
+    if first.is_whitespace() || matches!(first, '=' | '(') {
+        return Ok(None);
+    }
+
+    let found = match first {
+        '!' => history.first().map(|c| (c.clone(), 1)),
+        '$' => history
+            .first()
+            .and_then(|c| words(c).pop().map(|w| c[w].to_string()))
+            .map(|word| (word, 1)),
+        '?' => {
+            let body = &designator[1..];
+            let (text, used) = match body.find('?') {
+                Some(end) => (&body[..end], end + 2),
+                None => (body, body.len() + 1),
+            };
+            if text.is_empty() {
+                return Err(not_found(&designator[..used]));
+            }
+            history
+                .iter()
+                .find(|c| c.contains(text))
+                .map(|c| (c.clone(), used))
+        }
+        _ => {
+            let len = word_len(designator);
+            let word = &designator[..len];
+            let number = word.strip_prefix('-').unwrap_or(word);
+            if !number.is_empty() && number.bytes().all(|b| b.is_ascii_digit()) {
+                let n: usize = number.parse().unwrap_or(0);
+                let index = if word.starts_with('-') {
+                    n.checked_sub(1)
+                } else {
+                    history.len().checked_sub(n)
+                };
+                index
+                    .filter(|_| n > 0)
+                    .and_then(|i| history.get(i))
+                    .map(|c| (c.clone(), len))
+            } else {
+                history
+                    .iter()
+                    .find(|c| c.starts_with(word))
+                    .map(|c| (c.clone(), len))
+            }
+        }
+    };
+    found
+        .map(Some)
+        .ok_or_else(|| not_found(&designator[..word_len(designator)]))
+}
+
+/// Length of the designator up to the next space.
+fn word_len(designator: &str) -> usize {
+    designator
+        .find(char::is_whitespace)
+        .unwrap_or(designator.len())
+}
+
+/// `^old^new^rest`: the previous command with the first `old` replaced by `new`.
+fn quick_substitution(spec: &str, history: &[String]) -> Result<String, ExpansionError> {
+    let previous = history
+        .first()
+        .ok_or_else(|| ExpansionError::EventNotFound("!!".to_string()))?;
+    let mut parts = spec.splitn(3, '^');
+    let old = parts.next().unwrap_or_default();
+    let new = parts.next().unwrap_or_default();
+    let rest = parts.next().unwrap_or_default();
+    if old.is_empty() || !previous.contains(old) {
+        return Err(ExpansionError::SubstitutionFailed(old.to_string()));
+    }
+    Ok(format!("{}{}", previous.replacen(old, new, 1), rest))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Newest first.
+    fn history() -> Vec<String> {
+        [
+            "git push origin main",
+            "notepad C:\\notes.txt",
+            "git status",
+            "ping example.com",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    fn expand(input: &str) -> Option<String> {
+        expand_history(input, &history()).unwrap()
+    }
+
+    fn not_found(input: &str, history: &[String]) -> ExpansionError {
+        expand_history(input, history).unwrap_err()
+    }
+
+    #[test]
+    fn previous_and_numbered_events() {
+        assert_eq!(expand("!!").as_deref(), Some("git push origin main"));
+        assert_eq!(
+            expand("sudo !!").as_deref(),
+            Some("sudo git push origin main")
+        );
+        assert_eq!(expand("!1").as_deref(), Some("ping example.com"));
+        assert_eq!(expand("!4").as_deref(), Some("git push origin main"));
+        assert_eq!(expand("!-2").as_deref(), Some("notepad C:\\notes.txt"));
+    }
+
+    #[test]
+    fn prefix_and_search_events() {
+        assert_eq!(expand("!git").as_deref(), Some("git push origin main"));
+        assert_eq!(
+            expand("!note -x").as_deref(),
+            Some("notepad C:\\notes.txt -x")
+        );
+        assert_eq!(expand("!?status").as_deref(), Some("git status"));
+        assert_eq!(
+            expand("!?stat? --short").as_deref(),
+            Some("git status --short")
+        );
+        assert_eq!(expand("code !$").as_deref(), Some("code main"));
+    }
+
+    #[test]
+    fn quick_substitution() {
+        assert_eq!(expand("^main^dev").as_deref(), Some("git push origin dev"));
+        assert_eq!(
+            expand("^main^dev^ -f").as_deref(),
+            Some("git push origin dev -f")
+        );
+        assert_eq!(
+            not_found("^zzz^y", &history()),
+            ExpansionError::SubstitutionFailed("zzz".to_string())
+        );
+    }
+
+    #[test]
+    fn text_without_designators_is_untouched() {
+        assert_eq!(expand("calc"), None);
+        assert_eq!(expand("echo hi !"), None);
+        assert_eq!(expand("echo ! x != y"), None);
+        assert_eq!(expand("https://x.com/#!/page"), None);
+        assert_eq!(expand("chrome https://x.com/#!/a"), None);
+    }
+
+    #[test]
+    fn quoted_and_mid_word_marks_are_literal() {
+        assert_eq!(expand("notepad \"C:\\todo!.txt\""), None);
+        assert_eq!(expand("echo \"!git\" x!!"), None);
+        assert_eq!(expand("C:\\Games\\Fun!git\\run.exe"), None);
+        assert_eq!(expand("echo wow!!"), None);
+        assert_eq!(
+            expand("echo \"a b\" !git").as_deref(),
+            Some("echo \"a b\" git push origin main")
+        );
+    }
+
+    #[test]
+    fn backslash_escapes_a_designator() {
+        assert_eq!(expand("echo \\!git").as_deref(), Some("echo !git"));
+        assert_eq!(
+            expand("\\!! !!").as_deref(),
+            Some("!! git push origin main")
+        );
+        // Only where a designator could start; paths keep their backslashes.
+        assert_eq!(expand("C:\\dir\\!x.txt"), None);
+        assert_eq!(expand("echo \"\\!git\""), None);
+    }
+
+    #[test]
+    fn missing_events_are_errors() {
+        let history = history();
+        for (input, designator) in [
+            ("!nope", "!nope"),
+            ("!9 x", "!9"),
+            ("!0", "!0"),
+            ("!?zzz", "!?zzz"),
+        ] {
+            assert_eq!(
+                not_found(input, &history),
+                ExpansionError::EventNotFound(designator.to_string())
+            );
+        }
+        let none = ExpansionError::EventNotFound("!!".to_string());
+        assert_eq!(not_found("!!", &[]), none);
+        assert_eq!(not_found("^a^b", &[]), none);
+    }
+}
@@ -0,0 +1,536 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::Path;
+use std::time::UNIX_EPOCH;
+
+use crate::config::*;
+use crate::data::matcher::fold;
+
+// ==================================================================================
+//  FILE INDEX
+//  File and folder names under the roots configured in `[files]`, for `f <query>`.
+//  Every folder keeps its modification time, which changes whenever an entry is
+//  added, removed or renamed in it; a rescan lists only folders whose time moved and
+//  reuses the rest, so rescans cost a `stat` per folder. Saved as `file_index.txt`:
+//  a header with the settings it was built for, then each folder followed by the
+//  names in it, so no path is stored twice.
+// ==================================================================================
+
+pub const FILE_INDEX_FILE: &str = "file_index.txt";
+const FORMAT_HEADER: &str = "swiftrun-file-index 1";
+
+const WORD_START_BONUS: i32 = 8;
+const CONSECUTIVE_BONUS: i32 = 4;
+const PREFIX_BONUS: i32 = 20;
+const SUBSTRING_BONUS: i32 = 10;
+/// Word starts tried after the first occurrence, so a long name costs a few passes.
+const MAX_WORD_STARTS: usize = 4;
+
+/// The `[files]` settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileIndexConfig {
+    /// Folders to index; empty turns the indexer off.
+    pub roots: Vec<String>,
+    /// Globs a file name must match, if any are given. Folders are always walked.
+    pub include: Vec<String>,
+    /// Globs for file and folder names to skip; a skipped folder is not walked.
+    pub exclude: Vec<String>,
+    /// Folder levels below each root that are walked.
+    pub depth: usize,
+    /// Rescan this often even without change notifications; 0 for never.
+    pub rescan_minutes: u32,
+}
+
+impl Default for FileIndexConfig {
+    fn default() -> Self {
+        FileIndexConfig {
+            roots: Vec::new(),
+            include: Vec::new(),
+            exclude: vec![".git".to_string(), "node_modules".to_string()],
+            depth: 6,
+            rescan_minutes: 30,
+        }
+    }
+}
+
+impl FileIndexConfig {
+    pub fn is_enabled(&self) -> bool {
+        !self.roots.is_empty()
+    }
+
+    /// What the saved index must have been built with to be reused.
+    fn fingerprint(&self) -> String {
+        format!(
+            "{}|{}|{}|{}",
+            self.roots.join(";"),
+            self.include.join(";"),
+            self.exclude.join(";"),
+            self.depth
+        )
+    }
+
+    fn excludes(&self, name: &str) -> bool {
+        self.exclude.iter().any(|glob| glob_match(glob, name))
+    }
+
+    fn includes(&self, name: &str) -> bool {
+        self.include.is_empty() || self.include.iter().any(|glob| glob_match(glob, name))
+    }
+}
+
+/// `;`-separated list from settings, trimmed, without empty items.
+pub fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(';')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Case-insensitive match of a whole name against `*` (any run) and `?` (one
+/// character).
+pub fn glob_match(glob: &str, name: &str) -> bool {
+    let glob: Vec<char> = glob.to_lowercase().chars().collect();
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let (mut g, mut n) = (0, 0);
+    // Where the last `*` was and how much of `name` it has taken so far.
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        if g < glob.len() && (glob[g] == '?' || glob[g] == name[n]) {
+            g += 1;
+            n += 1;
+        } else if g < glob.len() && glob[g] == '*' {
+            star = Some((g, n));
+            g += 1;
+        } else if let Some((star_g, star_n)) = star {
+            g = star_g + 1;
+            n = star_n + 1;
+            star = Some((star_g, star_n + 1));
+        } else {
+            return false;
+        }
+    }
+    glob[g..].iter().all(|&c| c == '*')
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+struct Folder {
+    path: String,
+    /// Nanoseconds since the Unix epoch.
+    modified: u64,
+    files: Vec<String>,
+    folders: Vec<String>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct FileIndex {
+    folders: Vec<Folder>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FileHit {
+    pub path: String,
+    pub name: String,
+    /// The folder it is in.
+    pub folder: String,
+    pub is_folder: bool,
+    pub score: i32,
+}
+
+impl FileIndex {
+    pub fn build(config: &FileIndexConfig) -> FileIndex {
+        FileIndex::default().refresh(config)
+    }
+
+    /// Walks the roots again, listing only folders that changed since this index.
+    pub fn refresh(&self, config: &FileIndexConfig) -> FileIndex {
+        let previous: HashMap<&str, &Folder> = self
+            .folders
+            .iter()
+            .map(|folder| (folder.path.as_str(), folder))
+            .collect();
+        let mut walk = Walk {
+            config,
+            previous,
+            folders: Vec::new(),
+            entries: 0,
+        };
+        for root in &config.roots {
+            walk.visit(Path::new(root), 0);
+        }
+        FileIndex {
+            folders: walk.folders,
+        }
+    }
+
+    /// Best `limit` names that contain the letters of `query` in order, favouring
+    /// ones where they start words or run together. Gives up when `cancelled`.
+    pub fn search(
+        &self,
+        query: &str,
+        ignore_accents: bool,
+        limit: usize,
+        cancelled: impl Fn() -> bool,
+    ) -> Vec<FileHit> {
+        let query: Vec<char> = fold(query.trim(), ignore_accents).chars().collect();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut hits = Vec::new();
+        for folder in &self.folders {
+            if cancelled() {
+                return Vec::new();
+            }
+            let names = folder
+                .files
+                .iter()
+                .map(|name| (name, false))
+                .chain(folder.folders.iter().map(|name| (name, true)));
+            for (name, is_folder) in names {
+                if let Some(score) = fuzzy_score(name, &query, ignore_accents) {
+                    hits.push(FileHit {
+                        path: Path::new(&folder.path)
+                            .join(name)
+                            .to_string_lossy()
+                            .to_string(),
+                        name: name.clone(),
+                        folder: folder.path.clone(),
+                        is_folder,
+                        score,
+                    });
+                }
+            }
+        }
+        hits.sort_by(|a, b| {
+            b.score
+                .cmp(&a.score)
+                .then(a.path.len().cmp(&b.path.len()))
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        hits.truncate(limit);
+        hits
+    }
+
+    /// Reads a saved index; `None` if it is damaged or was built with other settings.
+    pub fn parse(text: &str, config: &FileIndexConfig) -> Option<FileIndex> {
+        let mut lines = text.lines();
+        if lines.next()? != FORMAT_HEADER || lines.next()? != config.fingerprint() {
+            return None;
+        }
+        let mut folders: Vec<Folder> = Vec::new();
+        for line in lines {
+            let (kind, rest) = line.split_once('\t')?;
+            match kind {
+                "D" => {
+                    let (modified, path) = rest.split_once('\t')?;
+                    folders.push(Folder {
+                        path: path.to_string(),
+                        modified: modified.parse().ok()?,
+                        ..Folder::default()
+                    });
+                }
+                "F" => folders.last_mut()?.files.push(rest.to_string()),
+                "S" => folders.last_mut()?.folders.push(rest.to_string()),
+                _ => return None,
+            }
+        }
+        Some(FileIndex { folders })
+    }
+
+    pub fn to_text(&self, config: &FileIndexConfig) -> String {
+        let mut text = format!("{}\n{}\n", FORMAT_HEADER, config.fingerprint());
+        for folder in &self.folders {
+            text.push_str(&format!("D\t{}\t{}\n", folder.modified, folder.path));
+            for name in &folder.files {
+                text.push_str(&format!("F\t{}\n", name));
+            }
+            for name in &folder.folders {
+                text.push_str(&format!("S\t{}\n", name));
+            }
+        }
+        text
+    }
+}
+
+struct Walk<'a> {
+    config: &'a FileIndexConfig,
+    previous: HashMap<&'a str, &'a Folder>,
+    folders: Vec<Folder>,
+    entries: usize,
+}
+
+impl Walk<'_> {
+    fn visit(&mut self, dir: &Path, depth: usize) {
+        if self.entries >= FILE_INDEX_MAX_ENTRIES {
+            return;
+        }
+        let Some(modified) = modified_nanos(dir) else {
+            return;
+        };
+        let path = dir.to_string_lossy().to_string();
+        let (files, folders) = match self.previous.get(path.as_str()) {
+            Some(old) if old.modified == modified => (old.files.clone(), old.folders.clone()),
+            _ => list(dir, self.config),
+        };
+        self.entries += files.len() + folders.len();
+        let walk_into = if depth < self.config.depth {
+            folders.clone()
+        } else {
+            Vec::new()
+        };
+        self.folders.push(Folder {
+            path,
+            modified,
+            files,
+            folders,
+        });
+        for name in walk_into {
+            self.visit(&dir.join(name), depth + 1);
+        }
+    }
+}
+
+fn modified_nanos(path: &Path) -> Option<u64> {
+    let modified = fs::metadata(path).ok()?.modified().ok()?;
+    let nanos = modified.duration_since(UNIX_EPOCH).ok()?.as_nanos();
+    Some(nanos as u64)
+}
+
+/// File and folder names in `dir` that the settings keep, sorted. Links to folders
+/// count as files, so a link cycle cannot make the walk loop.
+fn list(dir: &Path, config: &FileIndexConfig) -> (Vec<String>, Vec<String>) {
+    let (mut files, mut folders) = (Vec::new(), Vec::new());
+    let Ok(entries) = fs::read_dir(dir) else {
+        return (files, folders);
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if config.excludes(&name) {
+            continue;
+        }
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            folders.push(name);
+        } else if config.includes(&name) {
+            files.push(name);
+        }
+    }
+    files.sort();
+    folders.sort();
+    (files, folders)
+}
+
+fn is_word_separator(c: char) -> bool {
+    matches!(c, ' ' | '-' | '_' | '.' | '(' | '[')
+}
+
+/// How well `name` matches the folded `query`, or `None` if it lacks one of its
+/// letters in order.
+fn fuzzy_score(name: &str, query: &[char], ignore_accents: bool) -> Option<i32> {
+    let name: Vec<char> = fold(name, ignore_accents).chars().collect();
+    let mut starts = (0..name.len()).filter(|&i| name[i] == query[0]);
+    // If the letters are not there from the first occurrence, they are nowhere.
+    let first = starts.next()?;
+    let mut best = match_from(&name, query, first)?;
+    // Matching from the first occurrence only would miss `report` in
+    // `quarterly report` starting at the word, so a few word starts are tried too.
+    for start in starts
+        .filter(|&i| is_word_separator(name[i - 1]))
+        .take(MAX_WORD_STARTS)
+    {
+        if let Some(score) = match_from(&name, query, start) {
+            best = best.max(score);
+        }
+    }
+    let bonus = if name.starts_with(query) {
+        PREFIX_BONUS
+    } else if name.windows(query.len()).any(|window| window == query) {
+        SUBSTRING_BONUS
+    } else {
+        0
+    };
+    // Among equal matches, shorter names are closer to what was typed.
+    Some(best + bonus - (name.len() / 8) as i32)
+}
+
+/// Score of matching `query` letter by letter in `name` from `start` onwards.
+fn match_from(name: &[char], query: &[char], start: usize) -> Option<i32> {
+    let mut score = 0;
+    let mut matched = 0;
+    let mut previous: Option<usize> = None;
+    for (i, &c) in name.iter().enumerate().skip(start) {
+        if matched == query.len() {
+            break;
+        }
+        if c != query[matched] {
+            continue;
+        }
+        score += 1;
+        if i == 0 || is_word_separator(name[i - 1]) {
+            score += WORD_START_BONUS;
+        }
+        if previous.is_some_and(|p| p + 1 == i) {
+            score += CONSECUTIVE_BONUS;
+        }
+        previous = Some(i);
+        matched += 1;
+    }
+    (matched == query.len()).then_some(score)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::temp_dir::TempDir;
+    use std::path::PathBuf;
+
+    /// Documents, sources and the folders excluded by default.
+    fn temp_tree(name: &str) -> TempDir {
+        let tree = TempDir::new(&format!("file_index_{}", name));
+        for file in [
+            "docs/Quarterly Report.pdf",
+            "docs/reports/annual_report.docx",
+            "docs/reports/2024/q1.xlsx",
+            "src/main.rs",
+            "src/deep/a/b/c/buried.txt",
+            ".git/objects/abc",
+            "node_modules/x/index.js",
+            "readme.md",
+        ] {
+            tree.write(file, "x");
+        }
+        tree
+    }
+
+    fn config(tree: &TempDir) -> FileIndexConfig {
+        FileIndexConfig {
+            roots: vec![tree.root()],
+            ..FileIndexConfig::default()
+        }
+    }
+
+    fn names(index: &FileIndex, query: &str) -> Vec<String> {
+        index
+            .search(query, false, 50, || false)
+            .into_iter()
+            .map(|hit| hit.name)
+            .collect()
+    }
+
+    #[test]
+    fn globs_match_whole_names() {
+        assert!(glob_match("*.PDF", "a.pdf"));
+        assert!(glob_match("q?.xlsx", "q1.xlsx"));
+        assert!(!glob_match("q?.xlsx", "q10.xlsx"));
+        assert!(glob_match("*report*", "Annual_Report.docx"));
+        assert!(glob_match("a*b*c", "aXbYbZc"));
+        assert!(!glob_match("a*b*c", "aXbYbZ"));
+        assert!(glob_match("*", ""));
+        assert!(glob_match(".git", ".GIT"));
+        assert!(!glob_match(".git", ".github"));
+        assert_eq!(split_list(" a; ;b ;"), ["a", "b"]);
+    }
+
+    #[test]
+    fn fuzzy_scores_favour_word_starts_and_runs() {
+        let query: Vec<char> = "report".chars().collect();
+        let score = |name| fuzzy_score(name, &query, false);
+        assert!(score("report.txt") > score("my report.txt"));
+        assert!(score("my report.txt") > score("rxexpxoxrxt"));
+        // From the first `r`, in `quarterly`, the letters scatter; the word start wins.
+        let name: Vec<char> = "quarterly report".chars().collect();
+        let scattered = match_from(&name, &query, 3).unwrap();
+        let at_word = match_from(&name, &query, 10).unwrap();
+        assert!(at_word > scattered);
+        assert_eq!(
+            score("quarterly report"),
+            Some(at_word + SUBSTRING_BONUS - 2)
+        );
+        assert_eq!(score("trope"), None);
+        // A long name of repeated letters stays cheap and still matches.
+        let long = "r".repeat(10_000) + "eport";
+        assert!(score(&long).is_some());
+    }
+
+    #[test]
+    fn builds_and_searches_a_tree() {
+        let tree = temp_tree("search");
+        let index = FileIndex::build(&config(&tree));
+        let hits = index.search("report", false, 10, || false);
+        let found: Vec<&str> = hits.iter().map(|h| h.name.as_str()).collect();
+        assert_eq!(
+            found,
+            ["reports", "Quarterly Report.pdf", "annual_report.docx"]
+        );
+        let pdf = &hits[1];
+        assert_eq!(
+            PathBuf::from(&pdf.path),
+            tree.join("docs/Quarterly Report.pdf")
+        );
+        assert_eq!(PathBuf::from(&pdf.folder), tree.join("docs"));
+        assert!(hits[0].is_folder && !pdf.is_folder);
+
+        assert_eq!(names(&index, "qrp"), ["Quarterly Report.pdf"]);
+        // `.git` and `node_modules` are excluded by default.
+        assert!(names(&index, "index.js").is_empty());
+        assert!(names(&index, "abc").is_empty());
+        assert!(names(&index, "  ").is_empty());
+        assert!(index.search("report", false, 10, || true).is_empty());
+        assert_eq!(index.search("r", false, 2, || false).len(), 2);
+    }
+
+    #[test]
+    fn include_exclude_and_depth() {
+        let tree = temp_tree("rules");
+        let mut filtered = config(&tree);
+        filtered.include = vec!["*.pdf".into(), "*.docx".into()];
+        filtered.exclude = vec!["reports".into()];
+        let index = FileIndex::build(&filtered);
+        assert_eq!(names(&index, "rep"), ["Quarterly Report.pdf"]);
+        // Include globs only name files; folders still show.
+        assert_eq!(names(&index, "deep"), ["deep"]);
+
+        let mut shallow = config(&tree);
+        shallow.depth = 2;
+        let index = FileIndex::build(&shallow);
+        // root/src/deep is listed at depth 2, but its folders are not walked.
+        assert!(names(&index, "a").contains(&"a".to_string()));
+        assert!(names(&index, "buried").is_empty());
+        let deep = FileIndex::build(&config(&tree));
+        assert_eq!(names(&deep, "buried"), ["buried.txt"]);
+    }
+
+    #[test]
+    fn refresh_picks_up_changes() {
+        let tree = temp_tree("refresh");
+        let config = config(&tree);
+        let index = FileIndex::build(&config);
+        assert_eq!(index.refresh(&config), index);
+
+        std::thread::sleep(std::time::Duration::from_millis(20));
+        tree.write("src/deep/a/new_notes.txt", "x");
+        fs::remove_file(tree.join("readme.md")).unwrap();
+        let fresh = index.refresh(&config);
+        assert_ne!(fresh, index);
+        assert_eq!(names(&fresh, "newnotes"), ["new_notes.txt"]);
+        assert!(names(&fresh, "readme").is_empty());
+        assert_eq!(fresh, FileIndex::build(&config));
+    }
+
+    #[test]
+    fn saved_index_round_trips_for_its_settings_only() {
+        let tree = temp_tree("save");
+        let config = config(&tree);
+        let index = FileIndex::build(&config);
+        let text = index.to_text(&config);
+        assert_eq!(FileIndex::parse(&text, &config), Some(index));
+        let mut other = config.clone();
+        other.depth = 1;
+        assert_eq!(FileIndex::parse(&text, &other), None);
+        assert_eq!(FileIndex::parse("garbage", &config), None);
+        assert_eq!(
+            FileIndex::parse(&format!("{}X\tfoo\n", text), &config),
+            None
+        );
+    }
+}
@@ -0,0 +1,298 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::data::command_line::quote;
+use crate::data::locale::interpolate;
+
+// ==================================================================================
+//  GIT REPOSITORIES
+//  Working trees under the roots configured in `[git]`. A folder is one when it
+//  holds `.git`: a folder for a normal clone, or a file with `gitdir: <path>` for a
+//  linked worktree or submodule. The branch comes from `HEAD` in that git folder.
+//  The walk does not look inside a working tree, so vendored clones and submodules
+//  do not crowd the list.
+// ==================================================================================
+
+const SKIPPED_FOLDERS: &[&str] = &["node_modules", "$RECYCLE.BIN", "System Volume Information"];
+
+/// The `[git]` settings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct GitReposConfig {
+    /// Folders to search; empty turns repository suggestions off.
+    pub roots: Vec<String>,
+    /// Folder levels below each root that are searched.
+    pub depth: usize,
+    /// `editor`, `terminal`, `explorer`, or a command with `{path}` in it.
+    pub open: String,
+}
+
+impl Default for GitReposConfig {
+    fn default() -> Self {
+        GitReposConfig {
+            roots: Vec::new(),
+            depth: 3,
+            open: "editor".to_string(),
+        }
+    }
+}
+
+/// Whether `open` is a known action or a command template.
+pub fn is_valid_open_action(open: &str) -> bool {
+    matches!(
+        open.to_ascii_lowercase().as_str(),
+        "editor" | "terminal" | "explorer"
+    ) || open.contains("{path}")
+}
+
+/// The command that opens `path` with `open`. A quoted `"{path}"` gets the path
+/// quoted so that a trailing backslash, as in `C:\`, does not escape the quote.
+pub fn open_command(open: &str, path: &str) -> String {
+    let template = match open.to_ascii_lowercase().as_str() {
+        "editor" => "code \"{path}\"",
+        "terminal" => "wt -d \"{path}\"",
+        "explorer" => "explorer \"{path}\"",
+        _ => open,
+    };
+    template
+        .replace("\"{path}\"", &quote(path))
+        .replace("{path}", path)
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Head {
+    Branch(String),
+    /// Abbreviated commit id.
+    Detached(String),
+    Unknown,
+}
+
+impl Head {
+    /// "main", or "detached at 1a2b3c4" from `detached`, the `git_detached` text.
+    pub fn describe(&self, detached: &str) -> String {
+        match self {
+            Head::Branch(name) => name.clone(),
+            Head::Detached(commit) => interpolate(detached, &[("commit", commit)]),
+            Head::Unknown => String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Repo {
+    pub name: String,
+    pub path: String,
+    pub head: Head,
+}
+
+/// Contents of a `HEAD` file: `ref: refs/heads/<branch>` or a commit id.
+pub fn parse_head(text: &str) -> Head {
+    let text = text.trim();
+    if let Some(target) = text.strip_prefix("ref:") {
+        let target = target.trim();
+        let name = target.strip_prefix("refs/heads/").unwrap_or(target);
+        return if name.is_empty() {
+            Head::Unknown
+        } else {
+            Head::Branch(name.to_string())
+        };
+    }
+    // SHA-1 ids are 40 digits, SHA-256 ones 64.
+    if matches!(text.len(), 40 | 64) && text.chars().all(|c| c.is_ascii_hexdigit()) {
+        return Head::Detached(text[..7].to_string());
+    }
+    Head::Unknown
+}
+
+/// The git folder of the working tree at `dir`, if it is one.
+pub fn git_dir(dir: &Path) -> Option<PathBuf> {
+    let dot_git = dir.join(".git");
+    let meta = fs::metadata(&dot_git).ok()?;
+    if meta.is_dir() {
+        return Some(dot_git);
+    }
+    // `gitdir:` paths may be relative to the working tree.
+    let text = fs::read_to_string(&dot_git).ok()?;
+    let target = text.lines().find_map(|line| line.strip_prefix("gitdir:"))?;
+    Some(dir.join(target.trim()))
+}
+
+/// The working tree at `dir` with its branch, if it is one.
+pub fn read_repo(dir: &Path) -> Option<Repo> {
+    let git = git_dir(dir)?;
+    let head = fs::read_to_string(git.join("HEAD"))
+        .map(|text| parse_head(&text))
+        .unwrap_or(Head::Unknown);
+    let name = dir.file_name()?.to_string_lossy().to_string();
+    Some(Repo {
+        name,
+        path: dir.to_string_lossy().to_string(),
+        head,
+    })
+}
+
+/// Working trees at or below `roots`, at most `depth` levels down, sorted by name.
+pub fn discover(roots: &[String], depth: usize) -> Vec<Repo> {
+    let mut repos = Vec::new();
+    for root in roots {
+        collect(Path::new(root), depth, &mut repos);
+    }
+    repos.sort_by(|a, b| {
+        a.name
+            .to_lowercase()
+            .cmp(&b.name.to_lowercase())
+            .then_with(|| a.path.cmp(&b.path))
+    });
+    repos.dedup_by(|a, b| a.path == b.path);
+    repos
+}
+
+fn collect(dir: &Path, depth: usize, repos: &mut Vec<Repo>) {
+    if let Some(repo) = read_repo(dir) {
+        repos.push(repo);
+        return;
+    }
+    if depth == 0 {
+        return;
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        // Hidden folders are tool state, not projects.
+        if name.starts_with('.')
+            || SKIPPED_FOLDERS
+                .iter()
+                .any(|s| s.eq_ignore_ascii_case(&name))
+        {
+            continue;
+        }
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            collect(&entry.path(), depth - 1, repos);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::temp_dir::TempDir;
+
+    const SHA: &str = "0123456789abcdef0123456789abcdef01234567";
+
+    /// Repositories at several depths, ones that are skipped, and a linked worktree.
+    fn temp_repos(name: &str) -> TempDir {
+        let repos = TempDir::new(&format!("git_repos_{}", name));
+        let repo = |dir: &str, head: &str| repos.write(&format!("{}/.git/HEAD", dir), head);
+        repo("work/swift-run", "ref: refs/heads/main\n");
+        repo("work/api_server", &format!("{}\n", SHA));
+        repo("work/swift-run/vendor/inner", "ref: refs/heads/dev\n");
+        repo("deep/a/b/c/too-deep", "ref: refs/heads/main\n");
+        repo(".hidden/secret", "ref: refs/heads/main\n");
+        repo("node_modules/pkg", "ref: refs/heads/main\n");
+        // A linked worktree: `.git` is a file naming the main repository's folder for it.
+        repos.write(
+            "work/swift-run/.git/worktrees/feature/HEAD",
+            "ref: refs/heads/feature/login\n",
+        );
+        repos.write(
+            "work/feature-tree/.git",
+            "gitdir: ../swift-run/.git/worktrees/feature\n",
+        );
+        repos.write("work/broken/.git", "nonsense\n");
+        repos.create_dir("work/no-head/.git");
+        repos
+    }
+
+    #[test]
+    fn heads_name_a_branch_or_a_commit() {
+        let branch = |name: &str| Head::Branch(name.to_string());
+        assert_eq!(parse_head("ref: refs/heads/main\n"), branch("main"));
+        assert_eq!(parse_head("ref:refs/heads/feature/x"), branch("feature/x"));
+        assert_eq!(
+            parse_head("ref: refs/remotes/origin/main"),
+            branch("refs/remotes/origin/main")
+        );
+        assert_eq!(parse_head(SHA), Head::Detached("0123456".into()));
+        assert_eq!(
+            parse_head(&"a".repeat(64)),
+            Head::Detached("aaaaaaa".into())
+        );
+        assert_eq!(parse_head(&SHA[..39]), Head::Unknown);
+        assert_eq!(parse_head("ref: "), Head::Unknown);
+        assert_eq!(parse_head(""), Head::Unknown);
+        assert_eq!(
+            Head::Detached("0123456".into()).describe("detached at {commit}"),
+            "detached at 0123456"
+        );
+        assert_eq!(branch("main").describe("{commit}"), "main");
+        assert_eq!(Head::Unknown.describe("{commit}"), "");
+    }
+
+    #[test]
+    fn git_dir_follows_folders_and_gitdir_files() {
+        let repos = temp_repos("git_dir");
+        let work = repos.join("work");
+        assert_eq!(
+            git_dir(&work.join("swift-run")),
+            Some(work.join("swift-run").join(".git"))
+        );
+        assert_eq!(
+            git_dir(&work.join("feature-tree")),
+            Some(
+                work.join("feature-tree")
+                    .join("../swift-run/.git/worktrees/feature")
+            )
+        );
+        assert_eq!(git_dir(&work.join("broken")), None);
+        assert_eq!(git_dir(&work), None);
+        let linked = read_repo(&work.join("feature-tree")).unwrap();
+        assert_eq!(linked.head, Head::Branch("feature/login".into()));
+    }
+
+    #[test]
+    fn discover_respects_depth_and_skips() {
+        let repos = temp_repos("discover");
+        let roots = vec![repos.root()];
+        let found = discover(&roots, 3);
+        let heads: Vec<(&str, &Head)> = found.iter().map(|r| (r.name.as_str(), &r.head)).collect();
+        // Nested, hidden and node_modules repositories are not listed.
+        assert_eq!(
+            heads,
+            [
+                ("api_server", &Head::Detached("0123456".into())),
+                ("feature-tree", &Head::Branch("feature/login".into())),
+                ("no-head", &Head::Unknown),
+                ("swift-run", &Head::Branch("main".into())),
+            ]
+        );
+        assert_eq!(PathBuf::from(&found[3].path), repos.join("work/swift-run"));
+        assert!(discover(&roots, 5).iter().any(|r| r.name == "too-deep"));
+        assert!(discover(&roots, 1).is_empty());
+        // A root that is a repository itself, and a root given twice.
+        let single = repos.join("work/swift-run").to_string_lossy().to_string();
+        assert_eq!(discover(&[single], 0).len(), 1);
+        assert_eq!(discover(&[roots[0].clone(), roots[0].clone()], 3).len(), 4);
+        assert!(discover(&["/does/not/exist".into()], 3).is_empty());
+    }
+
+    #[test]
+    fn open_commands_quote_the_path() {
+        assert_eq!(open_command("editor", r"C:\src\x"), r#"code "C:\src\x""#);
+        assert_eq!(open_command("Terminal", r"C:\src\x"), r#"wt -d "C:\src\x""#);
+        assert_eq!(
+            open_command("explorer", r"C:\src\x"),
+            r#"explorer "C:\src\x""#
+        );
+        // A drive root keeps its backslash instead of escaping the quote.
+        assert_eq!(open_command("terminal", r"C:\"), r#"wt -d "C:\\""#);
+        assert_eq!(
+            open_command(r#"code -n "{path}""#, r"D:\"),
+            r#"code -n "D:\\""#
+        );
+        assert_eq!(open_command("idea64 {path}", r"C:\x"), r"idea64 C:\x");
+        assert!(is_valid_open_action("EDITOR"));
+        assert!(is_valid_open_action("x {path}"));
+        assert!(!is_valid_open_action("vim"));
+    }
+}
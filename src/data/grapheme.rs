@@ -0,0 +1,265 @@
+// ==================================================================================
+//  GRAPHEMES
+//  User-perceived characters, so the caret never lands between a letter and its
+//  accent or inside an emoji sequence. Follows the extended grapheme cluster rules
+//  of UAX #29 with compact approximations of the property tables: combining marks,
+//  ZWJ emoji sequences, flags, Hangul syllables and CR LF.
+// ==================================================================================
+
+const ZWJ: char = '\u{200D}';
+
+/// Byte offset where the grapheme starting at `start` ends.
+pub fn next_boundary(text: &str, start: usize) -> usize {
+    let mut chars = text[start..].char_indices();
+    let Some((_, first)) = chars.next() else {
+        return text.len();
+    };
+    let mut prev = first;
+    let mut regional = is_regional_indicator(first) as usize;
+    let mut end = start + first.len_utf8();
+    for (offset, c) in chars {
+        if !joins(prev, c, regional) {
+            break;
+        }
+        if is_regional_indicator(c) {
+            regional += 1;
+        }
+        prev = c;
+        end = start + offset + c.len_utf8();
+    }
+    end
+}
+
+/// Byte offset of the grapheme boundary before `at`.
+pub fn prev_boundary(text: &str, at: usize) -> usize {
+    let mut last = 0;
+    for (start, _) in graphemes(text) {
+        if start >= at {
+            break;
+        }
+        last = start;
+    }
+    last
+}
+
+/// Largest grapheme boundary at or before `at`, for offsets coming from elsewhere.
+pub fn floor_boundary(text: &str, at: usize) -> usize {
+    if at >= text.len() {
+        return text.len();
+    }
+    let mut last = 0;
+    for (start, _) in graphemes(text) {
+        if start > at {
+            break;
+        }
+        last = start;
+    }
+    last
+}
+
+/// Smallest grapheme boundary at or after `at`.
+pub fn ceil_boundary(text: &str, at: usize) -> usize {
+    let floor = floor_boundary(text, at);
+    if floor == at.min(text.len()) {
+        floor
+    } else {
+        next_boundary(text, floor)
+    }
+}
+
+/// `(byte offset, grapheme)` pairs.
+pub fn graphemes(text: &str) -> impl Iterator<Item = (usize, &str)> {
+    let mut at = 0;
+    std::iter::from_fn(move || {
+        if at >= text.len() {
+            return None;
+        }
+        let start = at;
+        at = next_boundary(text, start);
+        Some((start, &text[start..at]))
+    })
+}
+
+fn joins(prev: char, next: char, regional: usize) -> bool {
+    if prev == '\r' && next == '\n' {
+        return true;
+    }
+    if is_control(prev) || is_control(next) {
+        return false;
+    }
+    if is_extend(next) || next == ZWJ {
+        return true;
+    }
+    if prev == ZWJ && is_pictographic(next) {
+        return true;
+    }
+    if is_regional_indicator(prev) && is_regional_indicator(next) {
+        // Flags are pairs; a third indicator starts the next flag.
+        return regional % 2 == 1;
+    }
+    hangul_joins(prev, next)
+}
+
+fn is_control(c: char) -> bool {
+    matches!(c, '\0'..='\u{1F}' | '\u{7F}'..='\u{9F}' | '\u{2028}' | '\u{2029}')
+}
+
+/// Combining marks, spacing marks, variation selectors, emoji modifiers and tags.
+fn is_extend(c: char) -> bool {
+    matches!(c as u32,
+        0x0300..=0x036F
+        | 0x0483..=0x0489
+        | 0x0591..=0x05BD
+        | 0x05BF
+        | 0x05C1..=0x05C2
+        | 0x05C4..=0x05C5
+        | 0x05C7
+        | 0x0610..=0x061A
+        | 0x064B..=0x065F
+        | 0x0670
+        | 0x06D6..=0x06DC
+        | 0x06DF..=0x06E4
+        | 0x06E7..=0x06E8
+        | 0x06EA..=0x06ED
+        | 0x0711
+        | 0x0730..=0x074A
+        | 0x07EB..=0x07F3
+        | 0x0816..=0x082D
+        | 0x0859..=0x085B
+        | 0x08D3..=0x08E1
+        | 0x08E3..=0x0903
+        | 0x093A..=0x093C
+        | 0x093E..=0x094F
+        | 0x0951..=0x0957
+        | 0x0962..=0x0963
+        | 0x0981..=0x0983
+        | 0x09BC
+        | 0x09BE..=0x09CD
+        | 0x0A01..=0x0A03
+        | 0x0A3C..=0x0A51
+        | 0x0E31
+        | 0x0E34..=0x0E3A
+        | 0x0E47..=0x0E4E
+        | 0x0EB1
+        | 0x0EB4..=0x0EBC
+        | 0x0EC8..=0x0ECD
+        | 0x1AB0..=0x1AFF
+        | 0x1DC0..=0x1DFF
+        | 0x200C
+        | 0x20D0..=0x20FF
+        | 0x302A..=0x302F
+        | 0x3099..=0x309A
+        | 0xFE00..=0xFE0F
+        | 0xFE20..=0xFE2F
+        | 0x1F3FB..=0x1F3FF
+        | 0xE0020..=0xE007F
+        | 0xE0100..=0xE01EF
+    )
+}
+
+fn is_pictographic(c: char) -> bool {
+    matches!(c as u32,
+        0x00A9
+        | 0x00AE
+        | 0x203C
+        | 0x2049
+        | 0x2122
+        | 0x2139
+        | 0x2194..=0x21AA
+        | 0x231A..=0x23FF
+        | 0x24C2
+        | 0x25AA..=0x27BF
+        | 0x2934..=0x2935
+        | 0x2B05..=0x2B55
+        | 0x3030
+        | 0x303D
+        | 0x3297
+        | 0x3299
+        | 0x1F000..=0x1FAFF
+    )
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    matches!(c as u32, 0x1F1E6..=0x1F1FF)
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Jamo {
+    L,
+    V,
+    T,
+    Lv,
+    Lvt,
+}
+
+fn jamo(c: char) -> Option<Jamo> {
+    let c = c as u32;
+    match c {
+        0x1100..=0x115F | 0xA960..=0xA97C => Some(Jamo::L),
+        0x1160..=0x11A7 | 0xD7B0..=0xD7C6 => Some(Jamo::V),
+        0x11A8..=0x11FF | 0xD7CB..=0xD7FB => Some(Jamo::T),
+        0xAC00..=0xD7A3 if (c - 0xAC00).is_multiple_of(28) => Some(Jamo::Lv),
+        0xAC00..=0xD7A3 => Some(Jamo::Lvt),
+        _ => None,
+    }
+}
+
+fn hangul_joins(prev: char, next: char) -> bool {
+    let (Some(prev), Some(next)) = (jamo(prev), jamo(next)) else {
+        return false;
+    };
+    matches!(
+        (prev, next),
+        (Jamo::L, Jamo::L | Jamo::V | Jamo::Lv | Jamo::Lvt)
+            | (Jamo::Lv | Jamo::V, Jamo::V | Jamo::T)
+            | (Jamo::Lvt | Jamo::T, Jamo::T)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn clusters(text: &str) -> Vec<&str> {
+        graphemes(text).map(|(_, g)| g).collect()
+    }
+
+    #[test]
+    fn splits_into_clusters() {
+        assert_eq!(clusters("abc"), ["a", "b", "c"]);
+        assert_eq!(clusters(""), Vec::<&str>::new());
+        assert_eq!(clusters("e\u{301}x"), ["e\u{301}", "x"]);
+        assert_eq!(clusters("\r\nx"), ["\r\n", "x"]);
+        assert_eq!(clusters("❤\u{FE0F}x"), ["❤\u{FE0F}", "x"]);
+        assert_eq!(clusters("नमस्ते"), ["न", "म", "स्", "ते"]);
+    }
+
+    #[test]
+    fn keeps_emoji_sequences_whole() {
+        assert_eq!(clusters("👍🏽!"), ["👍🏽", "!"]);
+        assert_eq!(
+            clusters("👨\u{200D}👩\u{200D}👧a"),
+            ["👨\u{200D}👩\u{200D}👧", "a"]
+        );
+        assert_eq!(clusters("🇩🇪🇫🇷🇮"), ["🇩🇪", "🇫🇷", "🇮"]);
+    }
+
+    #[test]
+    fn keeps_hangul_syllables_whole() {
+        assert_eq!(
+            clusters("\u{1100}\u{1161}\u{11A8}가"),
+            ["\u{1100}\u{1161}\u{11A8}", "가"]
+        );
+    }
+
+    #[test]
+    fn boundaries_around_a_combining_mark() {
+        let text = "ae\u{301}b";
+        assert_eq!(next_boundary(text, 1), 4);
+        assert_eq!(prev_boundary(text, text.len()), 4);
+        assert_eq!(prev_boundary(text, 4), 1);
+        assert_eq!(floor_boundary(text, 2), 1);
+        assert_eq!(ceil_boundary(text, 2), 4);
+        assert_eq!(ceil_boundary(text, 4), 4);
+    }
+}
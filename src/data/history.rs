@@ -5,35 +5,63 @@
 use std::fs::{self, OpenOptions};
 use std::io::{BufRead, BufReader, Write};
 use std::path::PathBuf;
-use windows::Win32::Foundation::*;
-use windows::Win32::UI::Controls::EM_SETSEL;
-use windows::Win32::UI::WindowsAndMessaging::*;
-use windows::core::*;
+use std::sync::Arc;
 
-pub static mut HISTORY: Option<Vec<String>> = None;
-pub static mut FILTERED_HISTORY: Option<Vec<String>> = None;
+use crate::data::history_search::HistorySearch;
+use crate::data::sections::Groups;
+use crate::data::settings::*;
+use crate::data::suggest::{Orchestrator, Source, Suggestion};
+use crate::data::usage::{load_usage, save_usage};
+use crate::ui::resources::INPUT;
+
+// Newest first. Providers keep the `Arc` they were given, so a new command copies
+// the list only while a query is still reading it.
+pub static mut HISTORY: Option<Arc<Vec<String>>> = None;
+// What the dropdown shows: the current query's suggestions, or the whole history
+// when browsing it from the chevron. `None` cycles through the plain history.
+pub static mut SUGGESTIONS: Option<Groups> = None;
 pub static mut PREDICTION: String = String::new();
 pub static mut HISTORY_INDEX: isize = -1;
-pub static mut IS_CYCLING: bool = false;
+// Runs the suggestion providers; its generation tells current results from stale ones.
+pub static mut SUGGESTER: Option<Orchestrator> = None;
+// Active Ctrl+R search; the input shows its current match.
+pub static mut HISTORY_SEARCH: Option<HistorySearch> = None;
 
-pub fn get_history_path() -> Option<PathBuf> {
-    if let Ok(local_app_data) = std::env::var("LOCALAPPDATA") {
-        let mut path = PathBuf::from(local_app_data);
-        path.push("SwiftRun");
-        if !path.exists() {
-            let _ = fs::create_dir(&path);
-        }
-        path.push("history.txt");
-        Some(path)
-    } else {
-        None
+/// Drops the results of queries still running, e.g. once a suggestion was taken.
+pub unsafe fn cancel_suggestions() {
+    if let Some(suggester) = SUGGESTER.as_ref() {
+        suggester.cancel();
     }
 }
 
+/// `HISTORY`, or nothing before it is loaded.
+pub unsafe fn history_lines() -> &'static [String] {
+    HISTORY.as_deref().map_or(&[], |history| history.as_slice())
+}
+
+/// The whole history as dropdown entries, newest first.
+pub unsafe fn history_groups() -> Groups {
+    let items = history_lines()
+        .iter()
+        .map(|line| Suggestion {
+            text: line.clone(),
+            name: String::new(),
+            detail: String::new(),
+            source: Source::History,
+            score: 0.0,
+        })
+        .collect();
+    Groups::flat(items)
+}
+
+pub fn get_history_path() -> Option<PathBuf> {
+    get_config_dir().map(|dir| dir.join("history.txt"))
+}
+
 pub fn load_history() {
     unsafe {
-        HISTORY = Some(Vec::new());
-        FILTERED_HISTORY = None;
+        HISTORY = Some(Arc::default());
+        SUGGESTIONS = None;
         PREDICTION = String::new();
         if let Some(path) = get_history_path() {
             if let Ok(file) = fs::File::open(path) {
@@ -46,21 +74,24 @@ pub fn load_history() {
                         }
                     }
                 }
-                HISTORY = Some(items);
+                HISTORY = Some(Arc::new(items));
             }
         }
     }
+    load_usage();
 }
 
 pub fn save_history(cmd: &str) {
     unsafe {
         if let Some(history) = HISTORY.as_mut() {
+            let history = Arc::make_mut(history);
             if let Some(pos) = history.iter().position(|x| x == cmd) {
                 history.remove(pos);
             }
             history.insert(0, cmd.to_string());
-            if history.len() > 50 {
-                history.truncate(50);
+            let limit = settings().history_limit;
+            if history.len() > limit {
+                history.truncate(limit);
             }
 
             if let Some(path) = get_history_path() {
@@ -75,15 +106,18 @@ pub fn save_history(cmd: &str) {
                     }
                 }
             }
+            save_usage(cmd, history);
         }
     }
 }
 
-pub unsafe fn cycle_history(delta: isize, h_edit: HWND) {
-    let history_to_use = if let Some(filtered) = FILTERED_HISTORY.as_ref() {
-        filtered
+/// Steps through the dropdown entries (or the history), replacing the input as one
+/// undoable edit.
+pub unsafe fn cycle_history(delta: isize) {
+    let history_to_use: Vec<&str> = if let Some(groups) = SUGGESTIONS.as_ref() {
+        groups.items.iter().map(|item| item.text.as_str()).collect()
     } else if let Some(history) = HISTORY.as_ref() {
-        history
+        history.iter().map(String::as_str).collect()
     } else {
         return;
     };
@@ -108,21 +142,12 @@ pub unsafe fn cycle_history(delta: isize, h_edit: HWND) {
     } else {
         let real_index = HISTORY_INDEX;
         if real_index >= 0 && real_index < history_len {
-            history_to_use[real_index as usize].clone()
+            history_to_use[real_index as usize].to_string()
         } else {
             String::new()
         }
     };
 
-    IS_CYCLING = true;
     PREDICTION = String::new();
-    let text_u16: Vec<u16> = text_to_set
-        .encode_utf16()
-        .chain(std::iter::once(0))
-        .collect();
-    let _ = SetWindowTextW(h_edit, PCWSTR(text_u16.as_ptr()));
-
-    // Also select all
-    SendMessageW(h_edit, EM_SETSEL, Some(WPARAM(0)), Some(LPARAM(-1)));
-    IS_CYCLING = false;
+    INPUT.set_text(&text_to_set);
 }
@@ -0,0 +1,205 @@
+use crate::data::matcher;
+
+// ==================================================================================
+//  HISTORY SEARCH
+//  Reverse incremental search (Ctrl+R), as in bash and PowerShell: every typed
+//  character narrows to the most recent entry containing the query, and searching
+//  again steps to older matches. Entries are newest first, like `HISTORY`.
+// ==================================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HistorySearch {
+    query: String,
+    /// Index of the entry shown, if anything has matched yet.
+    found: Option<usize>,
+    /// The last step found nothing; `found` still shows the previous match.
+    failed: bool,
+    /// Input before the search started, restored on cancel.
+    original: String,
+    ignore_accents: bool,
+}
+
+impl HistorySearch {
+    pub fn new(original: &str, ignore_accents: bool) -> Self {
+        HistorySearch {
+            query: String::new(),
+            found: None,
+            failed: false,
+            original: original.to_string(),
+            ignore_accents,
+        }
+    }
+
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+
+    pub fn failed(&self) -> bool {
+        self.failed
+    }
+
+    pub fn original(&self) -> &str {
+        &self.original
+    }
+
+    /// The entry to show in the input, or the original text before anything matched.
+    pub fn current<'a>(&'a self, history: &'a [String]) -> &'a str {
+        self.found
+            .and_then(|i| history.get(i))
+            .map_or(&self.original, |entry| entry.as_str())
+    }
+
+    /// Byte range of the query inside `current`, for selecting it.
+    pub fn match_range(&self, history: &[String]) -> Option<std::ops::Range<usize>> {
+        let entry = history.get(self.found?)?;
+        matcher::find(entry, &self.query, self.ignore_accents)
+    }
+
+    /// Adds typed text to the query. The current match is kept when it still matches,
+    /// otherwise the search moves on to older entries.
+    pub fn push_str(&mut self, text: &str, history: &[String]) {
+        self.query.push_str(text);
+        let from = self.found.unwrap_or(0);
+        self.search_from(from, history);
+    }
+
+    /// Removes the last character of the query and searches again from the newest
+    /// entry.
+    pub fn pop(&mut self, history: &[String]) {
+        self.query.pop();
+        if self.query.is_empty() {
+            self.found = None;
+            self.failed = false;
+        } else {
+            self.search_from(0, history);
+        }
+    }
+
+    /// Steps to the next older match (Ctrl+R again).
+    pub fn older(&mut self, history: &[String]) {
+        if self.query.is_empty() {
+            return;
+        }
+        let from = self.found.map_or(0, |i| i + 1);
+        self.search_from(from, history);
+    }
+
+    /// Text to put in the input when the search is accepted.
+    pub fn accept(self, history: &[String]) -> String {
+        self.current(history).to_string()
+    }
+
+    /// Text to put back when the search is cancelled.
+    pub fn cancel(self) -> String {
+        self.original
+    }
+
+    fn search_from(&mut self, from: usize, history: &[String]) {
+        let hit = history
+            .iter()
+            .enumerate()
+            .skip(from)
+            .find(|(_, entry)| matcher::contains(entry, &self.query, self.ignore_accents));
+        match hit {
+            Some((index, _)) => {
+                self.found = Some(index);
+                self.failed = false;
+            }
+            None => self.failed = true,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Newest first, as the history is stored.
+    fn history() -> Vec<String> {
+        [
+            "git push",
+            "notepad",
+            "git status",
+            "ping 1.1.1.1",
+            "git commit -m x",
+        ]
+        .map(String::from)
+        .to_vec()
+    }
+
+    #[test]
+    fn typing_narrows_to_the_newest_match() {
+        let history = history();
+        let mut search = HistorySearch::new("draft", false);
+        assert_eq!(search.current(&history), "draft");
+        assert_eq!(search.match_range(&history), None);
+        search.push_str("g", &history);
+        assert_eq!(search.current(&history), "git push");
+        search.push_str("it", &history);
+        assert_eq!(search.current(&history), "git push");
+        assert_eq!(search.match_range(&history), Some(0..3));
+    }
+
+    #[test]
+    fn older_steps_back_and_fails_at_the_end() {
+        let history = history();
+        let mut search = HistorySearch::new("", false);
+        search.push_str("git", &history);
+        search.older(&history);
+        assert_eq!(search.current(&history), "git status");
+        // The current entry no longer matches, so the search moves on to older ones.
+        search.push_str(" c", &history);
+        assert_eq!(search.current(&history), "git commit -m x");
+        assert!(!search.failed());
+        search.older(&history);
+        assert!(search.failed());
+        assert_eq!(search.current(&history), "git commit -m x");
+    }
+
+    #[test]
+    fn backspace_recovers_from_a_failed_search() {
+        let history = history();
+        let mut search = HistorySearch::new("draft", false);
+        search.push_str("git c", &history);
+        search.push_str("zz", &history);
+        assert!(search.failed());
+        search.pop(&history);
+        search.pop(&history);
+        assert!(!search.failed());
+        assert_eq!(search.query(), "git c");
+        assert_eq!(search.current(&history), "git commit -m x");
+        search.pop(&history);
+        // A shorter query starts again at the newest entry.
+        assert_eq!(search.current(&history), "git push");
+        assert_eq!(search.clone().accept(&history), "git push");
+        assert_eq!(search.cancel(), "draft");
+    }
+
+    #[test]
+    fn empty_and_unmatched_queries() {
+        let history = history();
+        let mut search = HistorySearch::new("", false);
+        search.older(&history);
+        assert_eq!(search.current(&history), "");
+        search.push_str("xyz", &history);
+        assert!(search.failed());
+        assert_eq!(search.clone().accept(&history), "");
+        for _ in 0..3 {
+            search.pop(&history);
+        }
+        assert!(!search.failed());
+        assert!(search.query().is_empty());
+    }
+
+    #[test]
+    fn matching_ignores_case_and_optionally_accents() {
+        let history = ["Straße 1".to_string(), "go café".to_string()];
+        let mut search = HistorySearch::new("", false);
+        search.push_str("SS", &history);
+        assert_eq!(search.match_range(&history), Some(4..6));
+        let mut search = HistorySearch::new("", true);
+        search.push_str("cafe", &history);
+        assert_eq!(search.current(&history), "go café");
+        assert_eq!(search.match_range(&history), Some(3..8));
+    }
+}
@@ -0,0 +1,683 @@
+use std::fmt;
+
+// ==================================================================================
+//  HOTKEYS
+//  Global hotkeys are written as chords such as `Win+R` or `Ctrl+Alt+Space` and bound
+//  to an action in the `[hotkeys]` section of settings.ini. The parser produces the
+//  modifier flags and virtual-key code `RegisterHotKey` expects.
+// ==================================================================================
+
+// Same values as the Win32 `MOD_*` flags.
+pub const MOD_ALT: u32 = 0x0001;
+pub const MOD_CONTROL: u32 = 0x0002;
+pub const MOD_SHIFT: u32 = 0x0004;
+pub const MOD_WIN: u32 = 0x0008;
+
+const MODIFIERS: &[(&str, u32)] = &[
+    ("ctrl", MOD_CONTROL),
+    ("control", MOD_CONTROL),
+    ("alt", MOD_ALT),
+    ("shift", MOD_SHIFT),
+    ("win", MOD_WIN),
+    ("windows", MOD_WIN),
+];
+
+// Named keys besides letters, digits and F1-F24; the first name is the canonical one.
+const KEYS: &[(&str, u32)] = &[
+    ("Space", 0x20),
+    ("Enter", 0x0D),
+    ("Return", 0x0D),
+    ("Tab", 0x09),
+    ("Esc", 0x1B),
+    ("Escape", 0x1B),
+    ("Backspace", 0x08),
+    ("Insert", 0x2D),
+    ("Delete", 0x2E),
+    ("Del", 0x2E),
+    ("Home", 0x24),
+    ("End", 0x23),
+    ("PageUp", 0x21),
+    ("PageDown", 0x22),
+    ("Left", 0x25),
+    ("Up", 0x26),
+    ("Right", 0x27),
+    ("Down", 0x28),
+    ("Pause", 0x13),
+    ("PrintScreen", 0x2C),
+    ("Semicolon", 0xBA),
+    ("Plus", 0xBB),
+    ("Comma", 0xBC),
+    ("Minus", 0xBD),
+    ("Period", 0xBE),
+    ("Slash", 0xBF),
+    ("Backquote", 0xC0),
+    ("BracketLeft", 0xDB),
+    ("Backslash", 0xDC),
+    ("BracketRight", 0xDD),
+    ("Quote", 0xDE),
+    ("Num0", 0x60),
+    ("Num1", 0x61),
+    ("Num2", 0x62),
+    ("Num3", 0x63),
+    ("Num4", 0x64),
+    ("Num5", 0x65),
+    ("Num6", 0x66),
+    ("Num7", 0x67),
+    ("Num8", 0x68),
+    ("Num9", 0x69),
+];
+
+const VK_F1: u32 = 0x70;
+const VK_F24: u32 = 0x87;
+const VK_L: u32 = 0x4C;
+const VK_DELETE: u32 = 0x2E;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Hotkey {
+    pub modifiers: u32,
+    pub vk: u32,
+}
+
+impl Hotkey {
+    /// Parses a global hotkey: a chord that Windows lets `RegisterHotKey` take.
+    pub fn parse(text: &str) -> Result<Hotkey, String> {
+        let hotkey = Hotkey::parse_chord(text)?;
+        let is_function_key = (VK_F1..=VK_F24).contains(&hotkey.vk);
+        if hotkey.modifiers & !MOD_SHIFT == 0 && !is_function_key {
+            // Would swallow ordinary typing everywhere.
+            return Err(format!("{} needs Ctrl, Alt or Win", hotkey));
+        }
+        if hotkey.is_reserved() {
+            return Err(format!("{} is reserved by Windows", hotkey));
+        }
+        Ok(hotkey)
+    }
+
+    /// Parses any chord, including bare keys like `Enter`; used for in-window bindings.
+    pub fn parse_chord(text: &str) -> Result<Hotkey, String> {
+        let text = text.trim();
+        if text.is_empty() {
+            return Err("hotkey is empty".to_string());
+        }
+
+        let mut modifiers = 0;
+        let mut vk = None;
+        for part in text.split('+').map(str::trim) {
+            if part.is_empty() {
+                return Err(format!(
+                    "'{}' has an empty key name; use Plus for '+'",
+                    text
+                ));
+            }
+            if let Some(&(_, flag)) = MODIFIERS
+                .iter()
+                .find(|(name, _)| name.eq_ignore_ascii_case(part))
+            {
+                if modifiers & flag != 0 {
+                    return Err(format!("'{}' repeats {}", text, part));
+                }
+                modifiers |= flag;
+                continue;
+            }
+            let Some(code) = key_code(part) else {
+                return Err(format!("'{}' is not a key name", part));
+            };
+            if vk.is_some() {
+                return Err(format!("'{}' has more than one non-modifier key", text));
+            }
+            vk = Some(code);
+        }
+
+        let Some(vk) = vk else {
+            return Err(format!("'{}' has no key besides modifiers", text));
+        };
+        Ok(Hotkey { modifiers, vk })
+    }
+
+    /// Chords Windows never hands to applications.
+    fn is_reserved(&self) -> bool {
+        (self.modifiers == MOD_WIN && self.vk == VK_L)
+            || (self.modifiers == MOD_CONTROL | MOD_ALT && self.vk == VK_DELETE)
+    }
+}
+
+impl fmt::Display for Hotkey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (flag, name) in [
+            (MOD_CONTROL, "Ctrl"),
+            (MOD_ALT, "Alt"),
+            (MOD_SHIFT, "Shift"),
+            (MOD_WIN, "Win"),
+        ] {
+            if self.modifiers & flag != 0 {
+                write!(f, "{}+", name)?;
+            }
+        }
+        write!(f, "{}", key_name(self.vk))
+    }
+}
+
+fn key_code(name: &str) -> Option<u32> {
+    let upper = name.to_ascii_uppercase();
+    let bytes = upper.as_bytes();
+    if bytes.len() == 1 && bytes[0].is_ascii_alphanumeric() {
+        return Some(bytes[0] as u32);
+    }
+    if let Some(n) = upper.strip_prefix('F').and_then(|n| n.parse::<u32>().ok())
+        && (1..=24).contains(&n)
+    {
+        return Some(VK_F1 + n - 1);
+    }
+    KEYS.iter()
+        .find(|(key, _)| key.eq_ignore_ascii_case(name))
+        .map(|(_, code)| *code)
+}
+
+fn key_name(vk: u32) -> String {
+    match vk {
+        0x30..=0x39 | 0x41..=0x5A => (vk as u8 as char).to_string(),
+        VK_F1..=VK_F24 => format!("F{}", vk - VK_F1 + 1),
+        _ => KEYS
+            .iter()
+            .find(|(_, code)| *code == vk)
+            .map_or_else(|| format!("0x{:02X}", vk), |(key, _)| key.to_string()),
+    }
+}
+
+/// What a global hotkey does.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HotkeyAction {
+    /// Show the window with the last command, or hide it when it is already open.
+    Toggle,
+    /// Show the window with an empty input.
+    Show,
+    /// Show the window with the clipboard text as input.
+    Clipboard,
+    /// Show the window with `=` typed, ready for a calculation.
+    Calculator,
+}
+
+impl HotkeyAction {
+    pub const ALL: [HotkeyAction; 4] = [
+        HotkeyAction::Toggle,
+        HotkeyAction::Show,
+        HotkeyAction::Clipboard,
+        HotkeyAction::Calculator,
+    ];
+
+    /// Key used in the `[hotkeys]` section.
+    pub fn key(self) -> &'static str {
+        match self {
+            HotkeyAction::Toggle => "toggle",
+            HotkeyAction::Show => "show",
+            HotkeyAction::Clipboard => "clipboard",
+            HotkeyAction::Calculator => "calculator",
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<HotkeyAction> {
+        HotkeyAction::ALL
+            .into_iter()
+            .find(|a| a.key().eq_ignore_ascii_case(key))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct HotkeyBinding {
+    pub action: HotkeyAction,
+    pub hotkey: Hotkey,
+    /// Registered instead of `hotkey` while another application owns it.
+    pub fallback: Option<Hotkey>,
+}
+
+/// Win+R opens SwiftRun unless settings.ini has a `[hotkeys]` section.
+pub fn default_hotkeys() -> Vec<HotkeyBinding> {
+    vec![HotkeyBinding {
+        action: HotkeyAction::Toggle,
+        hotkey: Hotkey {
+            modifiers: MOD_WIN,
+            vk: b'R' as u32,
+        },
+        fallback: None,
+    }]
+}
+
+/// Parses a comma-separated list of chords, each with an optional `| fallback`, e.g.
+/// `Win+R | Ctrl+Alt+R, Ctrl+Alt+Space`. An empty value binds nothing.
+pub fn parse_hotkey_list(value: &str) -> Result<Vec<(Hotkey, Option<Hotkey>)>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(|part| match part.split_once('|') {
+            None => Ok((Hotkey::parse(part)?, None)),
+            Some((primary, fallback)) => {
+                if fallback.contains('|') {
+                    return Err(format!("'{}' has more than one fallback", part));
+                }
+                Ok((Hotkey::parse(primary)?, Some(Hotkey::parse(fallback)?)))
+            }
+        })
+        .collect()
+}
+
+// ----------------------------------------------------------------------------------
+//  REGISTRATION
+//  Which chord of each binding is live. Binding `n` always uses hotkey id `n + 1`,
+//  whether it runs on its own chord or on the fallback.
+// ----------------------------------------------------------------------------------
+
+/// The OS side of hotkey registration, faked when testing the state machine.
+pub trait Registrar {
+    fn register(&mut self, id: i32, hotkey: Hotkey) -> Result<(), String>;
+    fn unregister(&mut self, id: i32);
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum HotkeyState {
+    /// Nothing registered yet.
+    Pending,
+    Registered,
+    /// The chord is taken (`error`); the fallback is registered instead.
+    Fallback {
+        error: String,
+    },
+    /// Neither the chord nor the fallback (if any) could be registered.
+    Failed {
+        error: String,
+    },
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HotkeyStatus {
+    pub binding: HotkeyBinding,
+    pub state: HotkeyState,
+}
+
+impl HotkeyStatus {
+    /// The chord that currently triggers the action, if any.
+    pub fn active(&self) -> Option<Hotkey> {
+        match self.state {
+            HotkeyState::Registered => Some(self.binding.hotkey),
+            HotkeyState::Fallback { .. } => self.binding.fallback,
+            HotkeyState::Pending | HotkeyState::Failed { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct HotkeyRegistry {
+    statuses: Vec<HotkeyStatus>,
+}
+
+impl HotkeyRegistry {
+    pub const fn new() -> Self {
+        HotkeyRegistry {
+            statuses: Vec::new(),
+        }
+    }
+
+    pub fn statuses(&self) -> &[HotkeyStatus] {
+        &self.statuses
+    }
+
+    /// Action for a `WM_HOTKEY` id.
+    pub fn action(&self, id: usize) -> Option<HotkeyAction> {
+        let status = self.statuses.get(id.checked_sub(1)?)?;
+        status.active().map(|_| status.binding.action)
+    }
+
+    /// Unregisters everything and registers `bindings` from scratch. Returns the
+    /// statuses that ended up worse than before (newly on a fallback or failed), so
+    /// a conflict is reported once rather than on every re-registration.
+    pub fn apply(
+        &mut self,
+        registrar: &mut impl Registrar,
+        bindings: &[HotkeyBinding],
+    ) -> Vec<HotkeyStatus> {
+        let previous = std::mem::take(&mut self.statuses);
+        for (i, status) in previous.iter().enumerate() {
+            if status.active().is_some() {
+                registrar.unregister(i as i32 + 1);
+            }
+        }
+
+        self.statuses = bindings
+            .iter()
+            .map(|binding| HotkeyStatus {
+                binding: *binding,
+                state: HotkeyState::Pending,
+            })
+            .collect();
+        for i in 0..self.statuses.len() {
+            self.register(registrar, i);
+        }
+
+        self.statuses
+            .iter()
+            .filter(|status| {
+                let before = previous
+                    .iter()
+                    .find(|p| p.binding == status.binding)
+                    .map(|p| &p.state);
+                status.state != HotkeyState::Registered && before != Some(&status.state)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Tries again for every binding that is not on its own chord, e.g. after the
+    /// application holding it has exited. Returns the statuses that got worse.
+    pub fn retry(&mut self, registrar: &mut impl Registrar) -> Vec<HotkeyStatus> {
+        let mut worse = Vec::new();
+        for i in 0..self.statuses.len() {
+            let before = self.statuses[i].state.clone();
+            if before == HotkeyState::Registered {
+                continue;
+            }
+            if self.statuses[i].active().is_some() {
+                registrar.unregister(i as i32 + 1);
+            }
+            self.register(registrar, i);
+            if self.statuses[i].state != before && rank(&self.statuses[i].state) < rank(&before) {
+                worse.push(self.statuses[i].clone());
+            }
+        }
+        worse
+    }
+
+    fn register(&mut self, registrar: &mut impl Registrar, index: usize) {
+        let id = index as i32 + 1;
+        let binding = self.statuses[index].binding;
+        self.statuses[index].state = match registrar.register(id, binding.hotkey) {
+            Ok(()) => HotkeyState::Registered,
+            Err(error) => match binding.fallback {
+                Some(fallback) => match registrar.register(id, fallback) {
+                    Ok(()) => HotkeyState::Fallback { error },
+                    Err(_) => HotkeyState::Failed { error },
+                },
+                None => HotkeyState::Failed { error },
+            },
+        };
+    }
+}
+
+fn rank(state: &HotkeyState) -> u8 {
+    match state {
+        HotkeyState::Registered => 3,
+        HotkeyState::Fallback { .. } => 2,
+        HotkeyState::Pending => 1,
+        HotkeyState::Failed { .. } => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::settings::parse_settings;
+
+    fn hotkey(text: &str) -> Hotkey {
+        Hotkey::parse(text).unwrap()
+    }
+
+    #[test]
+    fn parses_chords() {
+        assert_eq!(
+            hotkey("Win+R"),
+            Hotkey {
+                modifiers: MOD_WIN,
+                vk: b'R' as u32
+            }
+        );
+        assert_eq!(
+            hotkey(" ctrl + alt + space "),
+            Hotkey {
+                modifiers: MOD_CONTROL | MOD_ALT,
+                vk: 0x20
+            }
+        );
+        assert_eq!(hotkey("F13").vk, 0x7C);
+        assert_eq!(hotkey("Alt+1").vk, b'1' as u32);
+        assert_eq!(Hotkey::parse_chord("Enter").unwrap().modifiers, 0);
+    }
+
+    #[test]
+    fn displays_modifiers_in_a_fixed_order() {
+        assert_eq!(hotkey("Win+Shift+R").to_string(), "Shift+Win+R");
+        assert_eq!(hotkey("alt+control+home").to_string(), "Ctrl+Alt+Home");
+        assert_eq!(hotkey("Ctrl+F24").to_string(), "Ctrl+F24");
+        assert_eq!(hotkey("Ctrl+Plus").to_string(), "Ctrl+Plus");
+    }
+
+    #[test]
+    fn rejects_bad_chords() {
+        for (text, error) in [
+            ("", "empty"),
+            ("Ctrl++", "Plus"),
+            ("Ctrl+Ctrl+A", "repeats"),
+            ("Ctrl+Alt", "no key"),
+            ("Ctrl+A+B", "more than one"),
+            ("Win+Foo", "'Foo' is not"),
+            ("Ctrl+F25", "not a key"),
+            ("R", "needs Ctrl"),
+            ("Shift+R", "needs Ctrl"),
+            ("Win+L", "reserved"),
+            ("Ctrl+Alt+Del", "reserved"),
+        ] {
+            let e = Hotkey::parse(text).unwrap_err();
+            assert!(e.contains(error), "{}: {}", text, e);
+        }
+    }
+
+    #[test]
+    fn parses_lists_with_fallbacks() {
+        assert_eq!(
+            parse_hotkey_list("Win+R | Ctrl+Alt+R, Ctrl+Alt+Space").unwrap(),
+            vec![
+                (hotkey("Win+R"), Some(hotkey("Ctrl+Alt+R"))),
+                (hotkey("Ctrl+Alt+Space"), None),
+            ]
+        );
+        assert!(parse_hotkey_list("").unwrap().is_empty());
+        let e = parse_hotkey_list("Win+R | Ctrl+Alt+R | Ctrl+Alt+T").unwrap_err();
+        assert!(e.contains("more than one fallback"), "{}", e);
+    }
+
+    #[test]
+    fn action_keys_round_trip() {
+        for action in HotkeyAction::ALL {
+            assert_eq!(HotkeyAction::from_key(action.key()), Some(action));
+        }
+        assert_eq!(
+            HotkeyAction::from_key("CALCULATOR"),
+            Some(HotkeyAction::Calculator)
+        );
+        assert_eq!(HotkeyAction::from_key("launch"), None);
+    }
+
+    #[test]
+    fn settings_section() {
+        assert_eq!(parse_settings("").unwrap().hotkeys, default_hotkeys());
+        let settings = parse_settings(
+            "[hotkeys]\ntoggle = Ctrl+Alt+Space\nclipboard = Win+Shift+V, Ctrl+F12\n\
+             calculator = Ctrl+Alt+C\n",
+        )
+        .unwrap();
+        let actions: Vec<HotkeyAction> = settings.hotkeys.iter().map(|b| b.action).collect();
+        assert_eq!(
+            actions,
+            vec![
+                HotkeyAction::Toggle,
+                HotkeyAction::Clipboard,
+                HotkeyAction::Clipboard,
+                HotkeyAction::Calculator,
+            ]
+        );
+        assert!(
+            parse_settings("[hotkeys]\ntoggle =\n")
+                .unwrap()
+                .hotkeys
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn settings_errors_point_at_the_line() {
+        let e = parse_settings("[hotkeys]\ntoggle = Win+R\nshow = win+r\n").unwrap_err();
+        assert_eq!(e.line, 3);
+        assert!(
+            e.message.contains("already bound to 'toggle'"),
+            "{}",
+            e.message
+        );
+        let e = parse_settings("[hotkeys]\nlaunch = Win+R\n").unwrap_err();
+        assert!(e.message.contains("unknown hotkey action"), "{}", e.message);
+        let e = parse_settings("[general]\n[hotkeys]\ntoggle = Win+X+Y\n").unwrap_err();
+        assert_eq!(e.line, 3);
+        let e = parse_settings("[hotkeys]\ntoggle = Win+R | Win+R\n").unwrap_err();
+        assert!(e.message.contains("own fallback"), "{}", e.message);
+        let e = parse_settings("[hotkeys]\ntoggle = Win+R | Ctrl+Alt+R\nshow = Ctrl+Alt+R\n")
+            .unwrap_err();
+        assert!(e.message.contains("already bound"), "{}", e.message);
+    }
+
+    /// Registers anything that is neither held by another application (`taken`) nor
+    /// already live.
+    #[derive(Default)]
+    struct FakeRegistrar {
+        taken: Vec<Hotkey>,
+        live: Vec<(i32, Hotkey)>,
+    }
+
+    impl Registrar for FakeRegistrar {
+        fn register(&mut self, id: i32, hotkey: Hotkey) -> Result<(), String> {
+            if self.taken.contains(&hotkey) || self.live.iter().any(|(_, h)| *h == hotkey) {
+                return Err("Hot key is already registered.".to_string());
+            }
+            self.live.push((id, hotkey));
+            Ok(())
+        }
+
+        fn unregister(&mut self, id: i32) {
+            self.live.retain(|(i, _)| *i != id);
+        }
+    }
+
+    fn bindings() -> Vec<HotkeyBinding> {
+        parse_settings(
+            "[hotkeys]\ntoggle = Win+R | Ctrl+Alt+R\nshow = Ctrl+Alt+Space\n\
+             clipboard = Ctrl+Alt+V\n",
+        )
+        .unwrap()
+        .hotkeys
+    }
+
+    #[test]
+    fn registers_every_free_binding() {
+        let mut registrar = FakeRegistrar::default();
+        let mut registry = HotkeyRegistry::new();
+        assert!(registry.apply(&mut registrar, &bindings()).is_empty());
+        assert!(
+            registry
+                .statuses()
+                .iter()
+                .all(|s| s.state == HotkeyState::Registered)
+        );
+        assert_eq!(registry.action(0), None);
+        assert_eq!(registry.action(1), Some(HotkeyAction::Toggle));
+        assert_eq!(registry.action(3), Some(HotkeyAction::Clipboard));
+        assert_eq!(registry.action(4), None);
+        assert_eq!(registrar.live.len(), 3);
+    }
+
+    #[test]
+    fn taken_chords_use_the_fallback_or_fail() {
+        let mut registrar = FakeRegistrar::default();
+        registrar.taken.push(hotkey("Win+R"));
+        registrar.taken.push(hotkey("Ctrl+Alt+V"));
+        let mut registry = HotkeyRegistry::new();
+        let worse = registry.apply(&mut registrar, &bindings());
+        assert_eq!(worse.len(), 2);
+        assert!(matches!(worse[0].state, HotkeyState::Fallback { .. }));
+        assert!(
+            matches!(&worse[1].state, HotkeyState::Failed { error } if error.contains("already"))
+        );
+        assert_eq!(registry.statuses()[0].active(), Some(hotkey("Ctrl+Alt+R")));
+        // The fallback runs under the binding's own id.
+        assert!(registrar.live.contains(&(1, hotkey("Ctrl+Alt+R"))));
+        assert_eq!(registry.action(1), Some(HotkeyAction::Toggle));
+        assert_eq!(registry.action(3), None);
+
+        // Applying the same bindings again reports nothing new.
+        assert!(registry.apply(&mut registrar, &bindings()).is_empty());
+        assert_eq!(registrar.live.len(), 2);
+    }
+
+    #[test]
+    fn retry_moves_back_to_the_own_chord() {
+        let mut registrar = FakeRegistrar::default();
+        registrar.taken.push(hotkey("Win+R"));
+        registrar.taken.push(hotkey("Ctrl+Alt+V"));
+        let mut registry = HotkeyRegistry::new();
+        registry.apply(&mut registrar, &bindings());
+        assert!(registry.retry(&mut registrar).is_empty());
+        assert_eq!(registrar.live.len(), 2);
+
+        registrar.taken.retain(|h| *h != hotkey("Win+R"));
+        assert!(registry.retry(&mut registrar).is_empty());
+        assert_eq!(registry.statuses()[0].state, HotkeyState::Registered);
+        assert!(registrar.live.contains(&(1, hotkey("Win+R"))));
+        assert!(
+            !registrar
+                .live
+                .iter()
+                .any(|(_, h)| *h == hotkey("Ctrl+Alt+R"))
+        );
+
+        registrar.taken.retain(|h| *h != hotkey("Ctrl+Alt+V"));
+        assert!(registry.retry(&mut registrar).is_empty());
+        assert_eq!(registry.action(3), Some(HotkeyAction::Clipboard));
+    }
+
+    #[test]
+    fn failed_binding_improves_to_its_fallback() {
+        let mut registrar = FakeRegistrar::default();
+        registrar.taken.push(hotkey("Win+R"));
+        registrar.taken.push(hotkey("Ctrl+Alt+R"));
+        let mut registry = HotkeyRegistry::new();
+        let worse = registry.apply(&mut registrar, &bindings()[..1]);
+        assert!(matches!(worse[0].state, HotkeyState::Failed { .. }));
+
+        registrar.taken.retain(|h| *h != hotkey("Ctrl+Alt+R"));
+        // Better than before, so there is nothing to report.
+        assert!(registry.retry(&mut registrar).is_empty());
+        assert!(matches!(
+            registry.statuses()[0].state,
+            HotkeyState::Fallback { .. }
+        ));
+    }
+
+    #[test]
+    fn losing_the_fallback_is_reported() {
+        let mut registrar = FakeRegistrar::default();
+        registrar.taken.push(hotkey("Win+R"));
+        let mut registry = HotkeyRegistry::new();
+        registry.apply(&mut registrar, &bindings()[..1]);
+        // Another application grabs the fallback while it is briefly released.
+        registrar.taken.push(hotkey("Ctrl+Alt+R"));
+        let worse = registry.retry(&mut registrar);
+        assert_eq!(worse.len(), 1);
+        assert!(matches!(worse[0].state, HotkeyState::Failed { .. }));
+        assert_eq!(registry.action(1), None);
+    }
+
+    #[test]
+    fn applying_nothing_unregisters_everything() {
+        let mut registrar = FakeRegistrar::default();
+        let mut registry = HotkeyRegistry::new();
+        registry.apply(&mut registrar, &bindings());
+        assert!(registry.apply(&mut registrar, &[]).is_empty());
+        assert!(registrar.live.is_empty());
+        assert!(registry.statuses().is_empty());
+    }
+}
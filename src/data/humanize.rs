@@ -0,0 +1,120 @@
+use crate::data::grapheme::graphemes;
+use crate::data::locale::{Msg, tr, tr_with};
+
+// ==================================================================================
+//  HUMANIZE
+//  Short, readable forms for the dropdown's detail line: how long ago something
+//  happened, and long text (mostly paths) shortened in the middle so both the drive
+//  and the file name stay visible.
+// ==================================================================================
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+const WEEK: u64 = 7 * DAY;
+const MONTH: u64 = 30 * DAY;
+
+/// "just now", "3 min ago", "2 h ago", "yesterday", "5 days ago", "3 wk ago",
+/// "4 mo ago", for something `seconds` in the past.
+pub fn relative_time(seconds: u64) -> String {
+    let (msg, count) = match seconds {
+        s if s < MINUTE => return tr(Msg::TimeJustNow).to_string(),
+        s if s < HOUR => (Msg::TimeMinutesAgo, s / MINUTE),
+        s if s < DAY => (Msg::TimeHoursAgo, s / HOUR),
+        s if s < 2 * DAY => return tr(Msg::TimeYesterday).to_string(),
+        s if s < 2 * WEEK => (Msg::TimeDaysAgo, s / DAY),
+        s if s < 2 * MONTH => (Msg::TimeWeeksAgo, s / WEEK),
+        s => (Msg::TimeMonthsAgo, s / MONTH),
+    };
+    tr_with(msg, &[("count", &count.to_string())])
+}
+
+/// `text`, or as much of its start and end as `fits` around an ellipsis. The end
+/// gets the extra grapheme, since it holds the file name.
+pub fn ellipsize_middle(text: &str, fits: impl Fn(&str) -> bool) -> String {
+    if fits(text) {
+        return text.to_string();
+    }
+    let starts: Vec<usize> = graphemes(text).map(|(start, _)| start).collect();
+    let shortened = |keep: usize| {
+        let head = keep / 2;
+        let tail = keep - head;
+        let head_end = starts.get(head).copied().unwrap_or(text.len());
+        let tail_start = starts[starts.len() - tail..]
+            .first()
+            .copied()
+            .unwrap_or(text.len());
+        format!("{}…{}", &text[..head_end], &text[tail_start..])
+    };
+
+    // Longest that fits; fewer graphemes are never wider.
+    let (mut low, mut high) = (0, starts.len().saturating_sub(1));
+    while low < high {
+        let mid = (low + high).div_ceil(2);
+        if fits(&shortened(mid)) {
+            low = mid;
+        } else {
+            high = mid - 1;
+        }
+    }
+    shortened(low)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn relative_times_round_down_to_the_unit() {
+        for (seconds, text) in [
+            (0, "just now"),
+            (59, "just now"),
+            (60, "1 min ago"),
+            (3 * 60 + 59, "3 min ago"),
+            (3599, "59 min ago"),
+            (3600, "1 h ago"),
+            (23 * 3600 + 3599, "23 h ago"),
+            (86400, "yesterday"),
+            (2 * 86400, "2 days ago"),
+            (13 * 86400, "13 days ago"),
+            (14 * 86400, "2 wk ago"),
+            (59 * 86400, "8 wk ago"),
+            (60 * 86400, "2 mo ago"),
+            (400 * 86400, "13 mo ago"),
+        ] {
+            assert_eq!(relative_time(seconds), text, "{}", seconds);
+        }
+    }
+
+    fn chars(max: usize) -> impl Fn(&str) -> bool {
+        move |text: &str| text.chars().count() <= max
+    }
+
+    #[test]
+    fn ellipsis_goes_in_the_middle() {
+        assert_eq!(ellipsize_middle("short", chars(10)), "short");
+        assert_eq!(ellipsize_middle("exactly10!", chars(10)), "exactly10!");
+        let path = r"C:\Program Files\Microsoft VS Code\Code.exe";
+        assert_eq!(ellipsize_middle(path, chars(20)), r"C:\Progra…e\Code.exe");
+        // With odd room the end keeps the extra character.
+        assert_eq!(ellipsize_middle("abcdefghij", chars(6)), "ab…hij");
+        // Nothing fits but the ellipsis.
+        assert_eq!(ellipsize_middle("abcdef", chars(1)), "…");
+        assert_eq!(ellipsize_middle("abcdef", chars(0)), "…");
+        assert_eq!(ellipsize_middle("", chars(0)), "");
+    }
+
+    #[test]
+    fn ellipsis_keeps_graphemes_whole() {
+        // é as e + combining accent, and a family emoji joined by ZWJs.
+        let text = "ae\u{301}bcdefgh👨‍👩‍👧";
+        let short = ellipsize_middle(text, chars(9));
+        assert!(short.starts_with('a'));
+        assert!(short.ends_with("👨‍👩‍👧"));
+        for room in 0..12 {
+            let short = ellipsize_middle(text, chars(room));
+            assert!(!short.starts_with('\u{301}'));
+            assert!(!short.contains("…\u{301}") && !short.contains("…\u{200D}"));
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use std::fmt;
+
+// Minimal INI reader shared by every user-editable file in the config directory.
+// Supports `[section]` headers, `key = value` pairs, full-line `;` / `#` comments and
+// trailing ` ; comments` after a value.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IniEntry {
+    pub key: String,
+    pub value: String,
+    pub line: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct IniSection {
+    pub name: String,
+    pub line: usize,
+    pub entries: Vec<IniEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct IniDocument {
+    // Entries before the first header land in a section with an empty name.
+    pub sections: Vec<IniSection>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ConfigError {
+    pub file: String,
+    pub line: usize,
+    pub message: String,
+}
+
+impl ConfigError {
+    pub fn new(file: &str, line: usize, message: impl Into<String>) -> Self {
+        ConfigError {
+            file: file.to_string(),
+            line,
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.line == 0 {
+            write!(f, "{}: {}", self.file, self.message)
+        } else {
+            write!(f, "{}:{}: {}", self.file, self.line, self.message)
+        }
+    }
+}
+
+pub fn parse_ini(file: &str, text: &str) -> Result<IniDocument, ConfigError> {
+    let text = text.strip_prefix('\u{FEFF}').unwrap_or(text);
+    let mut doc = IniDocument::default();
+    let mut current = IniSection::default();
+
+    for (idx, raw) in text.lines().enumerate() {
+        let line_no = idx + 1;
+        let line = raw.trim();
+        if line.is_empty() || line.starts_with(';') || line.starts_with('#') {
+            continue;
+        }
+
+        if let Some(rest) = line.strip_prefix('[') {
+            let Some(name) = rest.strip_suffix(']') else {
+                return Err(ConfigError::new(
+                    file,
+                    line_no,
+                    "missing ']' in section header",
+                ));
+            };
+            let name = name.trim();
+            if name.is_empty() {
+                return Err(ConfigError::new(file, line_no, "empty section name"));
+            }
+            if !current.name.is_empty() || !current.entries.is_empty() {
+                doc.sections.push(current);
+            }
+            current = IniSection {
+                name: name.to_string(),
+                line: line_no,
+                entries: Vec::new(),
+            };
+            continue;
+        }
+
+        let Some((key, value)) = line.split_once('=') else {
+            return Err(ConfigError::new(
+                file,
+                line_no,
+                format!("expected 'key = value', found '{}'", line),
+            ));
+        };
+        let key = key.trim();
+        if key.is_empty() {
+            return Err(ConfigError::new(file, line_no, "missing key before '='"));
+        }
+        current.entries.push(IniEntry {
+            key: key.to_string(),
+            value: unquote(strip_inline_comment(value).trim()).to_string(),
+            line: line_no,
+        });
+    }
+
+    if !current.name.is_empty() || !current.entries.is_empty() {
+        doc.sections.push(current);
+    }
+    Ok(doc)
+}
+
+fn strip_inline_comment(value: &str) -> &str {
+    // Only `;` counts here: `#` starts every colour value.
+    let bytes = value.as_bytes();
+    for i in 1..bytes.len() {
+        if bytes[i] == b';' && (bytes[i - 1] == b' ' || bytes[i - 1] == b'\t') {
+            return &value[..i];
+        }
+    }
+    value
+}
+
+fn unquote(value: &str) -> &str {
+    if value.len() >= 2 && value.starts_with('"') && value.ends_with('"') {
+        &value[1..value.len() - 1]
+    } else {
+        value
+    }
+}
+
+pub fn parse_number<T: std::str::FromStr>(file: &str, entry: &IniEntry) -> Result<T, ConfigError> {
+    entry.value.parse::<T>().map_err(|_| {
+        ConfigError::new(
+            file,
+            entry.line,
+            format!("'{}' expects a number, found '{}'", entry.key, entry.value),
+        )
+    })
+}
+
+pub fn parse_bool(file: &str, entry: &IniEntry) -> Result<bool, ConfigError> {
+    match entry.value.to_ascii_lowercase().as_str() {
+        "true" | "yes" | "on" | "1" => Ok(true),
+        "false" | "no" | "off" | "0" => Ok(false),
+        _ => Err(ConfigError::new(
+            file,
+            entry.line,
+            format!(
+                "'{}' expects true or false, found '{}'",
+                entry.key, entry.value
+            ),
+        )),
+    }
+}
@@ -0,0 +1,404 @@
+// ==================================================================================
+//  JSON
+//  A small reader for the JSON files other programs leave behind (editor state,
+//  bookmarks). Values keep object keys in file order; lookups are by exact key.
+//  `parse_jsonc` also takes the comments and trailing commas of hand-edited
+//  settings files (JSON with Comments). Only reading is needed, so there is no
+//  writer.
+// ==================================================================================
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Json {
+    Null,
+    Bool(bool),
+    Number(f64),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct JsonError {
+    /// Byte offset where parsing stopped.
+    pub offset: usize,
+    pub message: String,
+}
+
+impl Json {
+    /// The value under `key` when this is an object.
+    pub fn get(&self, key: &str) -> Option<&Json> {
+        match self {
+            Json::Object(entries) => entries.iter().find(|(k, _)| k == key).map(|(_, v)| v),
+            _ => None,
+        }
+    }
+
+    /// Follows `keys` through nested objects.
+    pub fn path(&self, keys: &[&str]) -> Option<&Json> {
+        keys.iter().try_fold(self, |value, key| value.get(key))
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Json::String(s) => Some(s),
+            _ => None,
+        }
+    }
+
+    pub fn as_bool(&self) -> Option<bool> {
+        match self {
+            Json::Bool(b) => Some(*b),
+            _ => None,
+        }
+    }
+
+    /// Items of an array; empty for anything else.
+    pub fn items(&self) -> &[Json] {
+        match self {
+            Json::Array(items) => items,
+            _ => &[],
+        }
+    }
+
+    /// Entries of an object; empty for anything else.
+    pub fn entries(&self) -> &[(String, Json)] {
+        match self {
+            Json::Object(entries) => entries,
+            _ => &[],
+        }
+    }
+}
+
+pub fn parse_json(text: &str) -> Result<Json, JsonError> {
+    parse(text, false)
+}
+
+/// JSON that may also have `//` and `/* */` comments and trailing commas.
+pub fn parse_jsonc(text: &str) -> Result<Json, JsonError> {
+    parse(text, true)
+}
+
+fn parse(text: &str, jsonc: bool) -> Result<Json, JsonError> {
+    let mut parser = Parser {
+        text,
+        bytes: text.as_bytes(),
+        pos: 0,
+        depth: 0,
+        jsonc,
+    };
+    // Files written by Windows tools often start with a byte order mark.
+    if text.starts_with('\u{FEFF}') {
+        parser.pos = '\u{FEFF}'.len_utf8();
+    }
+    let value = parser.value()?;
+    parser.skip_whitespace();
+    if parser.pos < parser.bytes.len() {
+        return Err(parser.error("unexpected text after the value"));
+    }
+    Ok(value)
+}
+
+/// Nesting beyond this is refused rather than risking the stack.
+const MAX_DEPTH: usize = 256;
+
+struct Parser<'a> {
+    text: &'a str,
+    bytes: &'a [u8],
+    pos: usize,
+    depth: usize,
+    jsonc: bool,
+}
+
+impl Parser<'_> {
+    fn error(&self, message: &str) -> JsonError {
+        JsonError {
+            offset: self.pos,
+            message: message.to_string(),
+        }
+    }
+
+    /// Skips whitespace, and comments in JSONC. An unterminated `/*` runs to the end,
+    /// where the caller then finds nothing.
+    fn skip_whitespace(&mut self) {
+        loop {
+            while self
+                .bytes
+                .get(self.pos)
+                .is_some_and(|b| matches!(b, b' ' | b'\t' | b'\n' | b'\r'))
+            {
+                self.pos += 1;
+            }
+            if !self.jsonc {
+                return;
+            }
+            let rest = &self.text[self.pos..];
+            if rest.starts_with("//") {
+                self.pos += rest.find('\n').unwrap_or(rest.len());
+            } else if let Some(comment) = rest.strip_prefix("/*") {
+                self.pos += comment.find("*/").map_or(rest.len(), |end| end + 4);
+            } else {
+                return;
+            }
+        }
+    }
+
+    /// After a `,`: whether `close` ends the list here, as JSONC allows.
+    fn trailing(&mut self, close: u8) -> bool {
+        self.jsonc && self.eat(close)
+    }
+
+    fn eat(&mut self, byte: u8) -> bool {
+        self.skip_whitespace();
+        if self.bytes.get(self.pos) == Some(&byte) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn value(&mut self) -> Result<Json, JsonError> {
+        self.skip_whitespace();
+        match self.bytes.get(self.pos) {
+            Some(b'{') => self.nested(Parser::object),
+            Some(b'[') => self.nested(Parser::array),
+            Some(b'"') => self.string().map(Json::String),
+            Some(b'-' | b'0'..=b'9') => self.number(),
+            Some(_) => {
+                for (word, value) in [
+                    ("true", Json::Bool(true)),
+                    ("false", Json::Bool(false)),
+                    ("null", Json::Null),
+                ] {
+                    if self.text[self.pos..].starts_with(word) {
+                        self.pos += word.len();
+                        return Ok(value);
+                    }
+                }
+                Err(self.error("expected a value"))
+            }
+            None => Err(self.error("unexpected end of text")),
+        }
+    }
+
+    fn nested(
+        &mut self,
+        parse: fn(&mut Self) -> Result<Json, JsonError>,
+    ) -> Result<Json, JsonError> {
+        if self.depth == MAX_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.depth += 1;
+        let value = parse(self);
+        self.depth -= 1;
+        value
+    }
+
+    fn object(&mut self) -> Result<Json, JsonError> {
+        self.pos += 1;
+        let mut entries = Vec::new();
+        if self.eat(b'}') {
+            return Ok(Json::Object(entries));
+        }
+        loop {
+            self.skip_whitespace();
+            if self.bytes.get(self.pos) != Some(&b'"') {
+                return Err(self.error("expected a key"));
+            }
+            let key = self.string()?;
+            if !self.eat(b':') {
+                return Err(self.error("expected ':'"));
+            }
+            entries.push((key, self.value()?));
+            if self.eat(b'}') {
+                return Ok(Json::Object(entries));
+            }
+            if !self.eat(b',') {
+                return Err(self.error("expected ',' or '}'"));
+            }
+            if self.trailing(b'}') {
+                return Ok(Json::Object(entries));
+            }
+        }
+    }
+
+    fn array(&mut self) -> Result<Json, JsonError> {
+        self.pos += 1;
+        let mut items = Vec::new();
+        if self.eat(b']') {
+            return Ok(Json::Array(items));
+        }
+        loop {
+            items.push(self.value()?);
+            if self.eat(b']') {
+                return Ok(Json::Array(items));
+            }
+            if !self.eat(b',') {
+                return Err(self.error("expected ',' or ']'"));
+            }
+            if self.trailing(b']') {
+                return Ok(Json::Array(items));
+            }
+        }
+    }
+
+    fn number(&mut self) -> Result<Json, JsonError> {
+        let start = self.pos;
+        while self
+            .bytes
+            .get(self.pos)
+            .is_some_and(|b| matches!(b, b'-' | b'+' | b'.' | b'e' | b'E' | b'0'..=b'9'))
+        {
+            self.pos += 1;
+        }
+        self.text[start..self.pos]
+            .parse()
+            .map(Json::Number)
+            .map_err(|_| JsonError {
+                offset: start,
+                message: "invalid number".to_string(),
+            })
+    }
+
+    fn string(&mut self) -> Result<String, JsonError> {
+        self.pos += 1;
+        let mut out = String::new();
+        loop {
+            let rest = &self.text[self.pos..];
+            let Some(special) = rest.find(['"', '\\']) else {
+                self.pos = self.bytes.len();
+                return Err(self.error("unterminated string"));
+            };
+            out.push_str(&rest[..special]);
+            self.pos += special;
+            if self.bytes[self.pos] == b'"' {
+                self.pos += 1;
+                return Ok(out);
+            }
+            self.pos += 1;
+            let escaped = match self.bytes.get(self.pos) {
+                Some(b'"') => '"',
+                Some(b'\\') => '\\',
+                Some(b'/') => '/',
+                Some(b'b') => '\u{8}',
+                Some(b'f') => '\u{C}',
+                Some(b'n') => '\n',
+                Some(b'r') => '\r',
+                Some(b't') => '\t',
+                Some(b'u') => {
+                    self.pos += 1;
+                    out.push(self.unicode_escape()?);
+                    continue;
+                }
+                _ => return Err(self.error("invalid escape")),
+            };
+            self.pos += 1;
+            out.push(escaped);
+        }
+    }
+
+    /// The character of a `\uXXXX` escape (the `\u` already read), joining a
+    /// surrogate pair; a lone surrogate becomes U+FFFD.
+    fn unicode_escape(&mut self) -> Result<char, JsonError> {
+        let high = self.hex4()?;
+        if !(0xD800..0xDC00).contains(&high) {
+            return Ok(char::from_u32(high).unwrap_or('\u{FFFD}'));
+        }
+        if !self.text[self.pos..].starts_with("\\u") {
+            return Ok('\u{FFFD}');
+        }
+        let before = self.pos;
+        self.pos += 2;
+        let low = self.hex4()?;
+        if !(0xDC00..0xE000).contains(&low) {
+            // Not a pair; leave the second escape to be read on its own.
+            self.pos = before;
+            return Ok('\u{FFFD}');
+        }
+        let code = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+        Ok(char::from_u32(code).unwrap_or('\u{FFFD}'))
+    }
+
+    fn hex4(&mut self) -> Result<u32, JsonError> {
+        let digits = self
+            .text
+            .get(self.pos..self.pos + 4)
+            .filter(|d| d.bytes().all(|b| b.is_ascii_hexdigit()))
+            .ok_or_else(|| self.error("invalid \\u escape"))?;
+        self.pos += 4;
+        Ok(u32::from_str_radix(digits, 16).unwrap_or(0))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_values() {
+        let text =
+            "\u{FEFF} {\"a\": [1, -2.5e3, true, false, null], \"b\": {\"c\": \"d\"}, \"a\": 0} ";
+        let value = parse_json(text).unwrap();
+        assert_eq!(value.get("a").unwrap().items().len(), 5);
+        assert_eq!(value.get("a").unwrap().items()[1], Json::Number(-2500.0));
+        assert_eq!(value.path(&["b", "c"]).and_then(Json::as_str), Some("d"));
+        assert_eq!(value.path(&["b", "x"]), None);
+        assert_eq!(value.entries().len(), 3);
+        assert!(Json::Null.items().is_empty() && Json::Null.get("a").is_none());
+        assert_eq!(parse_json("[]").unwrap(), Json::Array(Vec::new()));
+        assert_eq!(parse_json("{}").unwrap(), Json::Object(Vec::new()));
+    }
+
+    #[test]
+    fn decodes_string_escapes() {
+        let value = parse_json(r#""a\"b\\c\/d\n\t\u00e9\ud83d\ude00\ud800x\u0041 ü""#).unwrap();
+        assert_eq!(value.as_str(), Some("a\"b\\c/d\n\té😀\u{FFFD}xA ü"));
+        // A lone high surrogate before another escape keeps that escape.
+        let value = parse_json(r#""\ud800\u0041""#).unwrap();
+        assert_eq!(value.as_str(), Some("\u{FFFD}A"));
+    }
+
+    #[test]
+    fn rejects_malformed_text() {
+        for bad in [
+            "",
+            "{",
+            "[1,]",
+            "{\"a\" 1}",
+            "{\"a\":1,}",
+            "\"abc",
+            "\"\\x\"",
+            "\"\\u12\"",
+            "tru",
+            "1 2",
+            "{a:1}",
+            "-",
+            "[1 2]",
+        ] {
+            assert!(parse_json(bad).is_err(), "{:?}", bad);
+        }
+        assert_eq!(parse_json("[1, x]").unwrap_err().offset, 4);
+        let deep = "[".repeat(1000) + &"]".repeat(1000);
+        assert!(parse_json(&deep).unwrap_err().message.contains("deeply"));
+        let nested = "[".repeat(200) + &"]".repeat(200);
+        assert!(parse_json(&nested).is_ok());
+    }
+
+    #[test]
+    fn jsonc_allows_comments_and_trailing_commas() {
+        let text = "// leading\n{\n  /* block */ \"a\": [1, 2,], // after\n  \"url\": \"http://x//y /* not */\",\n  \"b\": {\"c\": true,},\n}\n/* tail */";
+        assert!(parse_json(text).is_err());
+        let value = parse_jsonc(text).unwrap();
+        assert_eq!(value.get("a").unwrap().items().len(), 2);
+        assert_eq!(
+            value.get("url").and_then(Json::as_str),
+            Some("http://x//y /* not */")
+        );
+        assert_eq!(value.path(&["b", "c"]).and_then(Json::as_bool), Some(true));
+        assert!(parse_jsonc("[1,,]").is_err());
+        assert!(parse_jsonc("[,]").is_err());
+        assert!(parse_jsonc("{\"a\": 1 /* open").is_err());
+        assert!(parse_jsonc("[1] // end").is_ok());
+        assert!(parse_jsonc("/").is_err());
+    }
+}
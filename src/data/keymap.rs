@@ -0,0 +1,358 @@
+use crate::data::hotkey::*;
+use crate::data::text_buffer::EditCommand;
+
+// ==================================================================================
+//  KEYMAP
+//  Keys handled while the window has focus, bound to named actions in the `[keys]`
+//  section of settings.ini. The main loop turns each key-down into a chord and asks
+//  the keymap what to do, so bindings can be checked without a message loop.
+// ==================================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyAction {
+    Run,
+    RunElevated,
+    /// Replace the input with the ghost-text prediction.
+    AcceptPrediction,
+    HistoryPrev,
+    HistoryNext,
+    ClearHistory,
+    /// Search the history backwards as you type (reverse-i-search).
+    ReverseSearch,
+    /// Open the folder containing the typed path or program in Explorer.
+    OpenLocation,
+    Close,
+    Copy,
+    Cut,
+    Paste,
+    Edit(EditCommand),
+}
+
+impl KeyAction {
+    const COMMANDS: [KeyAction; 12] = [
+        KeyAction::Run,
+        KeyAction::RunElevated,
+        KeyAction::AcceptPrediction,
+        KeyAction::HistoryPrev,
+        KeyAction::HistoryNext,
+        KeyAction::ClearHistory,
+        KeyAction::ReverseSearch,
+        KeyAction::OpenLocation,
+        KeyAction::Close,
+        KeyAction::Copy,
+        KeyAction::Cut,
+        KeyAction::Paste,
+    ];
+
+    pub fn all() -> impl Iterator<Item = KeyAction> {
+        KeyAction::COMMANDS
+            .into_iter()
+            .chain(EditCommand::ALL.into_iter().map(KeyAction::Edit))
+    }
+
+    /// Key used in the `[keys]` section.
+    pub fn key(self) -> &'static str {
+        match self {
+            KeyAction::Run => "run",
+            KeyAction::RunElevated => "run-elevated",
+            KeyAction::AcceptPrediction => "accept-prediction",
+            KeyAction::HistoryPrev => "history-prev",
+            KeyAction::HistoryNext => "history-next",
+            KeyAction::ClearHistory => "clear-history",
+            KeyAction::ReverseSearch => "reverse-search",
+            KeyAction::OpenLocation => "open-location",
+            KeyAction::Close => "close",
+            KeyAction::Copy => "copy",
+            KeyAction::Cut => "cut",
+            KeyAction::Paste => "paste",
+            KeyAction::Edit(command) => command.key(),
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<KeyAction> {
+        KeyAction::all().find(|action| action.key().eq_ignore_ascii_case(key))
+    }
+
+    fn default_chords(self) -> &'static [&'static str] {
+        match self {
+            KeyAction::Run => &["Enter", "Shift+Enter", "Ctrl+Enter"],
+            KeyAction::RunElevated => &["Ctrl+Shift+Enter"],
+            KeyAction::AcceptPrediction => &["Tab", "Right"],
+            KeyAction::HistoryPrev => &["Up"],
+            KeyAction::HistoryNext => &["Down"],
+            KeyAction::ClearHistory => &["Ctrl+Shift+Backspace"],
+            KeyAction::ReverseSearch => &["Ctrl+R"],
+            KeyAction::OpenLocation => &["Ctrl+Shift+E"],
+            KeyAction::Close => &["Esc"],
+            KeyAction::Copy => &["Ctrl+C", "Ctrl+Insert"],
+            KeyAction::Cut => &["Ctrl+X", "Shift+Delete"],
+            KeyAction::Paste => &["Ctrl+V", "Shift+Insert"],
+            KeyAction::Edit(command) => match command {
+                EditCommand::BackwardChar => &["Left"],
+                EditCommand::ForwardChar => &["Right"],
+                EditCommand::BackwardWord => &["Ctrl+Left", "Alt+B"],
+                EditCommand::ForwardWord => &["Ctrl+Right", "Alt+F"],
+                EditCommand::BeginningOfLine => &["Home"],
+                EditCommand::EndOfLine => &["End"],
+                EditCommand::SelectBackwardChar => &["Shift+Left"],
+                EditCommand::SelectForwardChar => &["Shift+Right"],
+                EditCommand::SelectBackwardWord => &["Ctrl+Shift+Left"],
+                EditCommand::SelectForwardWord => &["Ctrl+Shift+Right"],
+                EditCommand::SelectToBeginning => &["Shift+Home"],
+                EditCommand::SelectToEnd => &["Shift+End"],
+                EditCommand::SelectAll => &["Ctrl+A"],
+                EditCommand::BackwardDeleteChar => &["Backspace", "Shift+Backspace"],
+                EditCommand::DeleteChar => &["Delete"],
+                EditCommand::BackwardKillWord => &["Ctrl+Backspace"],
+                EditCommand::KillWord => &["Ctrl+Delete", "Alt+D"],
+                EditCommand::UnixWordRubout => &["Ctrl+W"],
+                EditCommand::UnixLineDiscard => &["Ctrl+U"],
+                EditCommand::KillLine => &["Ctrl+K"],
+                EditCommand::Undo => &["Ctrl+Z"],
+                EditCommand::Redo => &["Ctrl+Y", "Ctrl+Shift+Z"],
+            },
+        }
+    }
+
+    /// Only takes its key in some states, and then before any other action on it.
+    fn is_conditional(self) -> bool {
+        self == KeyAction::AcceptPrediction
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyBinding {
+    pub chord: Hotkey,
+    pub action: KeyAction,
+}
+
+/// State of the window that decides whether a binding applies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct KeyContext {
+    pub has_prediction: bool,
+    pub caret_at_end: bool,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Keymap {
+    pub bindings: Vec<KeyBinding>,
+    // Actions bound in settings rather than by default.
+    configured: Vec<KeyAction>,
+}
+
+impl Default for Keymap {
+    fn default() -> Self {
+        let bindings = KeyAction::all()
+            .flat_map(|action| {
+                action.default_chords().iter().map(move |chord| KeyBinding {
+                    chord: Hotkey::parse_chord(chord).expect("default chord"),
+                    action,
+                })
+            })
+            .collect();
+        Keymap {
+            bindings,
+            configured: Vec::new(),
+        }
+    }
+}
+
+impl Keymap {
+    /// Replaces every chord of `action`; an empty list unbinds it. A chord taken from
+    /// another action's defaults moves over, one bound in settings is a conflict.
+    /// Accepting a prediction may share its chords with one other action.
+    pub fn bind(&mut self, action: KeyAction, chords: &[Hotkey]) -> Result<(), String> {
+        self.bindings.retain(|b| b.action != action);
+        for &chord in chords {
+            let clashes = |b: &KeyBinding| {
+                b.chord == chord && b.action.is_conditional() == action.is_conditional()
+            };
+            if let Some(existing) = self.bindings.iter().find(|b| clashes(b))
+                && self.configured.contains(&existing.action)
+            {
+                return Err(format!(
+                    "{} is already bound to '{}'",
+                    chord,
+                    existing.action.key()
+                ));
+            }
+            self.bindings.retain(|b| !clashes(b));
+            self.bindings.push(KeyBinding { chord, action });
+        }
+        self.configured.push(action);
+        Ok(())
+    }
+
+    /// Action for a key-down, or `None` to let the key through as typing. Accepting a
+    /// prediction only takes its key when there is one to accept, and a key it shares
+    /// with another action (`Right`) only with the caret at the end.
+    pub fn dispatch(&self, chord: Hotkey, context: KeyContext) -> Option<KeyAction> {
+        let mut bound = self
+            .bindings
+            .iter()
+            .filter(|b| b.chord == chord)
+            .map(|b| b.action);
+        let other = bound.clone().find(|a| !a.is_conditional());
+        let accept = bound.any(|a| a == KeyAction::AcceptPrediction)
+            && context.has_prediction
+            && (context.caret_at_end || other.is_none());
+        if accept {
+            Some(KeyAction::AcceptPrediction)
+        } else {
+            other
+        }
+    }
+}
+
+/// Comma-separated chords of one `[keys]` entry; an empty value unbinds the action.
+pub fn parse_key_list(value: &str) -> Result<Vec<Hotkey>, String> {
+    value
+        .split(',')
+        .map(str::trim)
+        .filter(|part| !part.is_empty())
+        .map(Hotkey::parse_chord)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::settings::parse_settings;
+
+    const PREDICTION_AT_END: KeyContext = KeyContext {
+        has_prediction: true,
+        caret_at_end: true,
+    };
+    const PREDICTION_INSIDE: KeyContext = KeyContext {
+        has_prediction: true,
+        caret_at_end: false,
+    };
+    const NO_PREDICTION: KeyContext = KeyContext {
+        has_prediction: false,
+        caret_at_end: true,
+    };
+
+    fn chord(text: &str) -> Hotkey {
+        Hotkey::parse_chord(text).unwrap()
+    }
+
+    fn dispatch(keymap: &Keymap, text: &str) -> Option<KeyAction> {
+        keymap.dispatch(chord(text), NO_PREDICTION)
+    }
+
+    #[test]
+    fn default_bindings() {
+        let keymap = Keymap::default();
+        assert_eq!(dispatch(&keymap, "Enter"), Some(KeyAction::Run));
+        assert_eq!(
+            dispatch(&keymap, "Ctrl+Shift+Enter"),
+            Some(KeyAction::RunElevated)
+        );
+        assert_eq!(dispatch(&keymap, "Shift+Enter"), Some(KeyAction::Run));
+        assert_eq!(dispatch(&keymap, "Ctrl+Enter"), Some(KeyAction::Run));
+        assert_eq!(dispatch(&keymap, "Alt+Enter"), None);
+        assert_eq!(dispatch(&keymap, "Esc"), Some(KeyAction::Close));
+        assert_eq!(dispatch(&keymap, "Up"), Some(KeyAction::HistoryPrev));
+        assert_eq!(
+            dispatch(&keymap, "Ctrl+Shift+Backspace"),
+            Some(KeyAction::ClearHistory)
+        );
+        assert_eq!(dispatch(&keymap, "Shift+Insert"), Some(KeyAction::Paste));
+        assert_eq!(
+            dispatch(&keymap, "Ctrl+W"),
+            Some(KeyAction::Edit(EditCommand::UnixWordRubout))
+        );
+        assert_eq!(
+            dispatch(&keymap, "Backspace"),
+            Some(KeyAction::Edit(EditCommand::BackwardDeleteChar))
+        );
+        // Plain letters are typing.
+        assert_eq!(dispatch(&keymap, "A"), None);
+    }
+
+    #[test]
+    fn default_chords_parse_and_keys_round_trip() {
+        for action in KeyAction::all() {
+            assert_eq!(KeyAction::from_key(action.key()), Some(action));
+            for text in action.default_chords() {
+                assert!(Hotkey::parse_chord(text).is_ok(), "{}", text);
+            }
+        }
+    }
+
+    #[test]
+    fn prediction_takes_shared_keys_only_at_the_end() {
+        let keymap = Keymap::default();
+        let right = chord("Right");
+        let forward = Some(KeyAction::Edit(EditCommand::ForwardChar));
+        assert_eq!(keymap.dispatch(right, NO_PREDICTION), forward);
+        assert_eq!(
+            keymap.dispatch(right, PREDICTION_AT_END),
+            Some(KeyAction::AcceptPrediction)
+        );
+        assert_eq!(keymap.dispatch(right, PREDICTION_INSIDE), forward);
+        // Tab has no other action, so it accepts wherever the caret is.
+        let tab = chord("Tab");
+        assert_eq!(
+            keymap.dispatch(tab, PREDICTION_INSIDE),
+            Some(KeyAction::AcceptPrediction)
+        );
+        assert_eq!(keymap.dispatch(tab, NO_PREDICTION), None);
+    }
+
+    #[test]
+    fn settings_replace_default_chords() {
+        let settings =
+            parse_settings("[keys]\nclose = Enter\nrun = Ctrl+Enter\nhistory-next =\n").unwrap();
+        let keymap = &settings.keymap;
+        assert_eq!(dispatch(keymap, "Enter"), Some(KeyAction::Close));
+        assert_eq!(dispatch(keymap, "Esc"), None);
+        assert_eq!(dispatch(keymap, "Ctrl+Enter"), Some(KeyAction::Run));
+        assert_eq!(dispatch(keymap, "Down"), None);
+        assert_eq!(dispatch(keymap, "Up"), Some(KeyAction::HistoryPrev));
+
+        // A default chord moves to the action that names it.
+        let settings = parse_settings("[keys]\nrun-elevated = Ctrl+Enter\n").unwrap();
+        let keymap = &settings.keymap;
+        assert_eq!(dispatch(keymap, "Ctrl+Enter"), Some(KeyAction::RunElevated));
+        assert_eq!(dispatch(keymap, "Shift+Enter"), Some(KeyAction::Run));
+        assert_eq!(dispatch(keymap, "Ctrl+Shift+Enter"), None);
+    }
+
+    #[test]
+    fn configured_chords_take_over_edit_defaults() {
+        let settings = parse_settings("[keys]\nclose = Esc, Ctrl+W\nkill-line = Ctrl+Q\n").unwrap();
+        let keymap = &settings.keymap;
+        assert_eq!(dispatch(keymap, "Ctrl+W"), Some(KeyAction::Close));
+        assert_eq!(dispatch(keymap, "Ctrl+K"), None);
+        assert_eq!(
+            dispatch(keymap, "Ctrl+Q"),
+            Some(KeyAction::Edit(EditCommand::KillLine))
+        );
+    }
+
+    #[test]
+    fn conflicts_between_configured_actions_are_errors() {
+        let e = parse_settings("[keys]\nclose = Enter\nrun = Enter\n").unwrap_err();
+        assert_eq!(e.line, 3);
+        assert!(
+            e.message.contains("already bound to 'close'"),
+            "{}",
+            e.message
+        );
+        assert!(parse_settings("[keys]\nundo = Ctrl+Q\nredo = Ctrl+Q\n").is_err());
+        assert!(parse_settings("[keys]\nfly = F2\n").is_err());
+        assert!(parse_settings("[keys]\nrun = Foo\n").is_err());
+    }
+
+    #[test]
+    fn prediction_may_share_with_one_configured_action() {
+        let mut keymap = Keymap::default();
+        keymap.bind(KeyAction::Close, &[chord("Tab")]).unwrap();
+        assert_eq!(
+            keymap.dispatch(chord("Tab"), PREDICTION_AT_END),
+            Some(KeyAction::AcceptPrediction)
+        );
+        assert_eq!(dispatch(&keymap, "Tab"), Some(KeyAction::Close));
+    }
+}
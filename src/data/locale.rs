@@ -0,0 +1,613 @@
+#![allow(static_mut_refs)]
+
+use std::sync::{Arc, OnceLock};
+
+use crate::data::ini::*;
+
+// ==================================================================================
+//  LOCALISATION
+//  Every user-facing string is looked up by `Msg`. Locale files are flat INI tables
+//  (`key = text`, optionally under `[strings]`) with `{name}` placeholders. Keys a
+//  locale does not translate fall back to the bundled English table. An optional
+//  `[locale]` section sets the text direction; otherwise it follows the language.
+// ==================================================================================
+
+pub const LOCALES_DIR: &str = "locales";
+pub const DEFAULT_LANGUAGE: &str = "en";
+pub const AUTO_LANGUAGE: &str = "auto";
+
+pub const BUNDLED_LOCALES: &[(&str, &str)] = &[
+    ("en", include_str!("../../res/locales/en.ini")),
+    ("de", include_str!("../../res/locales/de.ini")),
+    ("es", include_str!("../../res/locales/es.ini")),
+    ("fr", include_str!("../../res/locales/fr.ini")),
+    ("ar", include_str!("../../res/locales/ar.ini")),
+    ("he", include_str!("../../res/locales/he.ini")),
+];
+
+/// Primary language subtags written right to left.
+pub const RTL_LANGUAGES: &[&str] = &["ar", "ckb", "dv", "fa", "he", "ps", "sd", "ug", "ur", "yi"];
+
+pub static mut LOCALE: Option<StringTable> = None;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Direction {
+    #[default]
+    Ltr,
+    Rtl,
+}
+
+impl Direction {
+    pub fn parse(value: &str) -> Option<Direction> {
+        match value.to_ascii_lowercase().as_str() {
+            "ltr" => Some(Direction::Ltr),
+            "rtl" => Some(Direction::Rtl),
+            _ => None,
+        }
+    }
+
+    /// Direction a language is written in when its locale file does not say.
+    pub fn of_language(language: &str) -> Direction {
+        let primary = language.split('-').next().unwrap_or_default();
+        if RTL_LANGUAGES
+            .iter()
+            .any(|l| l.eq_ignore_ascii_case(primary))
+        {
+            Direction::Rtl
+        } else {
+            Direction::Ltr
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Msg {
+    AppTitle,
+    Run,
+    Cancel,
+    Ok,
+    InputPlaceholder,
+    CommandNotFoundTitle,
+    CommandNotFound,
+    HistoryEmptyTitle,
+    HistoryEmpty,
+    HistoryClearedTitle,
+    HistoryCleared,
+    ConfigErrorTitle,
+    ConfigErrorMore,
+    ConfigErrorKept,
+    SetupTitle,
+    SetupErrorTitle,
+    InstallFailed,
+    Installed,
+    UninstallFailed,
+    Uninstalled,
+    HotkeyFailedTitle,
+    HotkeyFailed,
+    HotkeyFallback,
+    HotkeyActive,
+    DiagnosticsTitle,
+    NoHotkeys,
+    SearchTitle,
+    SearchFailedTitle,
+    ExpansionFailedTitle,
+    EventNotFound,
+    SubstitutionFailed,
+    SectionBestMatch,
+    SectionHistory,
+    SectionCommands,
+    SectionAliases,
+    SectionApps,
+    SectionCalculator,
+    SectionFiles,
+    SectionRepos,
+    SectionWorkspaces,
+    SectionTerminalProfiles,
+    SectionSshHosts,
+    SectionBookmarks,
+    SectionSettings,
+    SettingDisplay,
+    SettingSound,
+    SettingBluetooth,
+    SettingNetwork,
+    SettingPersonalization,
+    SettingApps,
+    SettingDefaultApps,
+    SettingWindowsUpdate,
+    SettingPower,
+    SettingStorage,
+    SettingDateTime,
+    SettingPrivacy,
+    SettingNotifications,
+    SettingLanguage,
+    TimeJustNow,
+    TimeMinutesAgo,
+    TimeHoursAgo,
+    TimeYesterday,
+    TimeDaysAgo,
+    TimeWeeksAgo,
+    TimeMonthsAgo,
+    RunOnce,
+    RunCount,
+    GitDetached,
+}
+
+impl Msg {
+    pub const ALL: [Msg; 68] = [
+        Msg::AppTitle,
+        Msg::Run,
+        Msg::Cancel,
+        Msg::Ok,
+        Msg::InputPlaceholder,
+        Msg::CommandNotFoundTitle,
+        Msg::CommandNotFound,
+        Msg::HistoryEmptyTitle,
+        Msg::HistoryEmpty,
+        Msg::HistoryClearedTitle,
+        Msg::HistoryCleared,
+        Msg::ConfigErrorTitle,
+        Msg::ConfigErrorMore,
+        Msg::ConfigErrorKept,
+        Msg::SetupTitle,
+        Msg::SetupErrorTitle,
+        Msg::InstallFailed,
+        Msg::Installed,
+        Msg::UninstallFailed,
+        Msg::Uninstalled,
+        Msg::HotkeyFailedTitle,
+        Msg::HotkeyFailed,
+        Msg::HotkeyFallback,
+        Msg::HotkeyActive,
+        Msg::DiagnosticsTitle,
+        Msg::NoHotkeys,
+        Msg::SearchTitle,
+        Msg::SearchFailedTitle,
+        Msg::ExpansionFailedTitle,
+        Msg::EventNotFound,
+        Msg::SubstitutionFailed,
+        Msg::SectionBestMatch,
+        Msg::SectionHistory,
+        Msg::SectionCommands,
+        Msg::SectionAliases,
+        Msg::SectionApps,
+        Msg::SectionCalculator,
+        Msg::SectionFiles,
+        Msg::SectionRepos,
+        Msg::SectionWorkspaces,
+        Msg::SectionTerminalProfiles,
+        Msg::SectionSshHosts,
+        Msg::SectionBookmarks,
+        Msg::SectionSettings,
+        Msg::SettingDisplay,
+        Msg::SettingSound,
+        Msg::SettingBluetooth,
+        Msg::SettingNetwork,
+        Msg::SettingPersonalization,
+        Msg::SettingApps,
+        Msg::SettingDefaultApps,
+        Msg::SettingWindowsUpdate,
+        Msg::SettingPower,
+        Msg::SettingStorage,
+        Msg::SettingDateTime,
+        Msg::SettingPrivacy,
+        Msg::SettingNotifications,
+        Msg::SettingLanguage,
+        Msg::TimeJustNow,
+        Msg::TimeMinutesAgo,
+        Msg::TimeHoursAgo,
+        Msg::TimeYesterday,
+        Msg::TimeDaysAgo,
+        Msg::TimeWeeksAgo,
+        Msg::TimeMonthsAgo,
+        Msg::RunOnce,
+        Msg::RunCount,
+        Msg::GitDetached,
+    ];
+
+    /// Key used in locale files.
+    pub fn key(self) -> &'static str {
+        match self {
+            Msg::AppTitle => "app_title",
+            Msg::Run => "run",
+            Msg::Cancel => "cancel",
+            Msg::Ok => "ok",
+            Msg::InputPlaceholder => "input_placeholder",
+            Msg::CommandNotFoundTitle => "command_not_found_title",
+            Msg::CommandNotFound => "command_not_found",
+            Msg::HistoryEmptyTitle => "history_empty_title",
+            Msg::HistoryEmpty => "history_empty",
+            Msg::HistoryClearedTitle => "history_cleared_title",
+            Msg::HistoryCleared => "history_cleared",
+            Msg::ConfigErrorTitle => "config_error_title",
+            Msg::ConfigErrorMore => "config_error_more",
+            Msg::ConfigErrorKept => "config_error_kept",
+            Msg::SetupTitle => "setup_title",
+            Msg::SetupErrorTitle => "setup_error_title",
+            Msg::InstallFailed => "install_failed",
+            Msg::Installed => "installed",
+            Msg::UninstallFailed => "uninstall_failed",
+            Msg::Uninstalled => "uninstalled",
+            Msg::HotkeyFailedTitle => "hotkey_failed_title",
+            Msg::HotkeyFailed => "hotkey_failed",
+            Msg::HotkeyFallback => "hotkey_fallback",
+            Msg::HotkeyActive => "hotkey_active",
+            Msg::DiagnosticsTitle => "diagnostics_title",
+            Msg::NoHotkeys => "no_hotkeys",
+            Msg::SearchTitle => "search_title",
+            Msg::SearchFailedTitle => "search_failed_title",
+            Msg::ExpansionFailedTitle => "expansion_failed_title",
+            Msg::EventNotFound => "event_not_found",
+            Msg::SubstitutionFailed => "substitution_failed",
+            Msg::SectionBestMatch => "section_best_match",
+            Msg::SectionHistory => "section_history",
+            Msg::SectionCommands => "section_commands",
+            Msg::SectionAliases => "section_aliases",
+            Msg::SectionApps => "section_apps",
+            Msg::SectionCalculator => "section_calculator",
+            Msg::SectionFiles => "section_files",
+            Msg::SectionRepos => "section_repos",
+            Msg::SectionWorkspaces => "section_workspaces",
+            Msg::SectionTerminalProfiles => "section_terminal_profiles",
+            Msg::SectionSshHosts => "section_ssh_hosts",
+            Msg::SectionBookmarks => "section_bookmarks",
+            Msg::SectionSettings => "section_settings",
+            Msg::SettingDisplay => "setting_display",
+            Msg::SettingSound => "setting_sound",
+            Msg::SettingBluetooth => "setting_bluetooth",
+            Msg::SettingNetwork => "setting_network",
+            Msg::SettingPersonalization => "setting_personalization",
+            Msg::SettingApps => "setting_apps",
+            Msg::SettingDefaultApps => "setting_default_apps",
+            Msg::SettingWindowsUpdate => "setting_windows_update",
+            Msg::SettingPower => "setting_power",
+            Msg::SettingStorage => "setting_storage",
+            Msg::SettingDateTime => "setting_date_time",
+            Msg::SettingPrivacy => "setting_privacy",
+            Msg::SettingNotifications => "setting_notifications",
+            Msg::SettingLanguage => "setting_language",
+            Msg::TimeJustNow => "time_just_now",
+            Msg::TimeMinutesAgo => "time_minutes_ago",
+            Msg::TimeHoursAgo => "time_hours_ago",
+            Msg::TimeYesterday => "time_yesterday",
+            Msg::TimeDaysAgo => "time_days_ago",
+            Msg::TimeWeeksAgo => "time_weeks_ago",
+            Msg::TimeMonthsAgo => "time_months_ago",
+            Msg::RunOnce => "run_once",
+            Msg::RunCount => "run_count",
+            Msg::GitDetached => "git_detached",
+        }
+    }
+
+    /// Placeholders a translation may use; anything else is rejected when parsing.
+    pub fn params(self) -> &'static [&'static str] {
+        match self {
+            Msg::CommandNotFound => &["command"],
+            Msg::ConfigErrorMore => &["count"],
+            Msg::InstallFailed | Msg::UninstallFailed => &["error"],
+            Msg::HotkeyFailed => &["hotkey", "error"],
+            Msg::HotkeyFallback => &["hotkey", "fallback", "error"],
+            Msg::HotkeyActive => &["hotkey", "action"],
+            Msg::SearchTitle | Msg::SearchFailedTitle => &["query"],
+            Msg::EventNotFound => &["event"],
+            Msg::SubstitutionFailed => &["text"],
+            Msg::TimeMinutesAgo
+            | Msg::TimeHoursAgo
+            | Msg::TimeDaysAgo
+            | Msg::TimeWeeksAgo
+            | Msg::TimeMonthsAgo
+            | Msg::RunCount => &["count"],
+            Msg::GitDetached => &["commit"],
+            _ => &[],
+        }
+    }
+
+    pub fn from_key(key: &str) -> Option<Msg> {
+        Msg::ALL
+            .into_iter()
+            .find(|m| m.key().eq_ignore_ascii_case(key))
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct StringTable {
+    pub language: String,
+    pub direction: Direction,
+    // Shared so text handed out by `tr` outlives a reload that replaces the table.
+    strings: Vec<Option<Arc<str>>>,
+    // UTF-16 copies for DirectWrite, built once per load instead of once per paint.
+    wide: Vec<Option<Arc<[u16]>>>,
+}
+
+impl StringTable {
+    pub fn get(&self, msg: Msg) -> Option<Arc<str>> {
+        self.strings.get(msg as usize)?.clone()
+    }
+
+    pub fn get_wide(&self, msg: Msg) -> Option<Arc<[u16]>> {
+        self.wide.get(msg as usize)?.clone()
+    }
+}
+
+pub fn bundled_locale(language: &str) -> Option<&'static str> {
+    BUNDLED_LOCALES
+        .iter()
+        .find(|(code, _)| code.eq_ignore_ascii_case(language))
+        .map(|(_, text)| *text)
+}
+
+/// Language codes are BCP 47 tags such as `de` or `pt-BR`, used as file stems.
+pub fn is_valid_language(code: &str) -> bool {
+    !code.is_empty() && code.chars().all(|c| c.is_ascii_alphanumeric() || c == '-')
+}
+
+/// Picks the locale to load: an explicit `language` setting wins; `auto` walks the
+/// user's UI languages (`de-AT`, then `de`) and takes the first one available.
+pub fn resolve_language(
+    setting: &str,
+    ui_languages: &[String],
+    is_available: impl Fn(&str) -> bool,
+) -> String {
+    if !setting.eq_ignore_ascii_case(AUTO_LANGUAGE) {
+        return setting.to_ascii_lowercase();
+    }
+    for language in ui_languages {
+        let language = language.to_ascii_lowercase();
+        if is_available(&language) {
+            return language;
+        }
+        if let Some((primary, _)) = language.split_once('-')
+            && is_available(primary)
+        {
+            return primary.to_string();
+        }
+    }
+    DEFAULT_LANGUAGE.to_string()
+}
+
+pub fn parse_locale(file: &str, language: &str, text: &str) -> Result<StringTable, ConfigError> {
+    let doc = parse_ini(file, text)?;
+    let mut table = StringTable {
+        language: language.to_string(),
+        direction: Direction::of_language(language),
+        strings: vec![None; Msg::ALL.len()],
+        wide: vec![None; Msg::ALL.len()],
+    };
+
+    for section in &doc.sections {
+        if section.name.eq_ignore_ascii_case("locale") {
+            for entry in &section.entries {
+                if !entry.key.eq_ignore_ascii_case("direction") {
+                    return Err(ConfigError::new(
+                        file,
+                        entry.line,
+                        format!("unknown key '{}' in [locale]", entry.key),
+                    ));
+                }
+                table.direction = Direction::parse(&entry.value).ok_or_else(|| {
+                    ConfigError::new(
+                        file,
+                        entry.line,
+                        format!("direction must be ltr or rtl, not '{}'", entry.value),
+                    )
+                })?;
+            }
+            continue;
+        }
+        if !section.name.is_empty() && !section.name.eq_ignore_ascii_case("strings") {
+            return Err(ConfigError::new(
+                file,
+                section.line,
+                format!("unknown section [{}]", section.name),
+            ));
+        }
+        for entry in &section.entries {
+            let Some(msg) = Msg::from_key(&entry.key) else {
+                return Err(ConfigError::new(
+                    file,
+                    entry.line,
+                    format!("unknown message id '{}'", entry.key),
+                ));
+            };
+            if let Some(name) = placeholders(&entry.value)
+                .into_iter()
+                .find(|name| !msg.params().contains(name))
+            {
+                return Err(ConfigError::new(
+                    file,
+                    entry.line,
+                    format!("'{}' has no placeholder {{{}}}", entry.key, name),
+                ));
+            }
+            table.wide[msg as usize] = Some(entry.value.encode_utf16().collect());
+            table.strings[msg as usize] = Some(entry.value.as_str().into());
+        }
+    }
+
+    Ok(table)
+}
+
+/// Names of the `{name}` placeholders in `template`, in order of appearance.
+pub fn placeholders(template: &str) -> Vec<&str> {
+    let mut names = Vec::new();
+    let mut rest = template;
+    while let Some(open) = rest.find('{') {
+        let after = &rest[open + 1..];
+        let Some(close) = after.find('}') else { break };
+        let name = &after[..close];
+        if !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            names.push(name);
+        }
+        rest = &after[close + 1..];
+    }
+    names
+}
+
+/// Replaces `{name}` with the matching argument; unknown placeholders are left as is.
+pub fn interpolate(template: &str, args: &[(&str, &str)]) -> String {
+    let mut out = template.to_string();
+    for (name, value) in args {
+        out = out.replace(&format!("{{{}}}", name), value);
+    }
+    out
+}
+
+fn english() -> &'static StringTable {
+    static ENGLISH: OnceLock<StringTable> = OnceLock::new();
+    ENGLISH.get_or_init(|| {
+        let text = bundled_locale(DEFAULT_LANGUAGE).unwrap_or_default();
+        parse_locale("en.ini", DEFAULT_LANGUAGE, text).unwrap_or_default()
+    })
+}
+
+/// Text of `msg` in the active locale, falling back to English.
+pub fn tr(msg: Msg) -> Arc<str> {
+    unsafe { LOCALE.as_ref() }
+        .and_then(|table| table.get(msg))
+        .or_else(|| english().get(msg))
+        .unwrap_or_else(|| msg.key().into())
+}
+
+/// `tr` as UTF-16, for text drawn with DirectWrite.
+pub fn tr_wide(msg: Msg) -> Arc<[u16]> {
+    unsafe { LOCALE.as_ref() }
+        .and_then(|table| table.get_wide(msg))
+        .or_else(|| english().get_wide(msg))
+        .unwrap_or_default()
+}
+
+/// Text direction of the active locale; the UI mirrors its layout when it is RTL.
+pub fn direction() -> Direction {
+    unsafe { LOCALE.as_ref() }.map_or(Direction::Ltr, |table| table.direction)
+}
+
+/// `tr` with `{name}` placeholders filled in.
+pub fn tr_with(msg: Msg, args: &[(&str, &str)]) -> String {
+    interpolate(&tr(msg), args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+    use std::fs;
+    use std::path::Path;
+
+    /// Message ids of a locale file, without the `[locale]` settings.
+    fn keys(file: &str, text: &str) -> BTreeSet<String> {
+        let doc = parse_ini(file, text).unwrap_or_else(|e| panic!("{}", e));
+        doc.sections
+            .iter()
+            .filter(|section| !section.name.eq_ignore_ascii_case("locale"))
+            .flat_map(|section| &section.entries)
+            .map(|entry| entry.key.to_ascii_lowercase())
+            .collect()
+    }
+
+    #[test]
+    fn english_covers_every_message() {
+        let english = keys("en.ini", bundled_locale("en").unwrap());
+        for msg in Msg::ALL {
+            assert!(
+                english.contains(msg.key()),
+                "en.ini is missing {}",
+                msg.key()
+            );
+        }
+        assert_eq!(english.len(), Msg::ALL.len());
+    }
+
+    #[test]
+    fn every_locale_file_has_the_english_keys() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("res")
+            .join(LOCALES_DIR);
+        let english = keys("en.ini", &fs::read_to_string(dir.join("en.ini")).unwrap());
+        let mut files = 0;
+        for entry in fs::read_dir(&dir).unwrap().flatten() {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let Some(language) = name.strip_suffix(".ini") else {
+                continue;
+            };
+            let text = fs::read_to_string(entry.path()).unwrap();
+            let found = keys(&name, &text);
+            let missing: Vec<_> = english.difference(&found).collect();
+            let extra: Vec<_> = found.difference(&english).collect();
+            assert!(missing.is_empty(), "{} is missing {:?}", name, missing);
+            assert!(extra.is_empty(), "{} has unknown keys {:?}", name, extra);
+            assert!(
+                bundled_locale(language).is_some(),
+                "{} is not in BUNDLED_LOCALES",
+                name
+            );
+            files += 1;
+        }
+        assert_eq!(files, BUNDLED_LOCALES.len());
+    }
+
+    #[test]
+    fn bundled_locales_parse_with_matching_placeholders() {
+        for (code, text) in BUNDLED_LOCALES {
+            let table = parse_locale(code, code, text).unwrap_or_else(|e| panic!("{}", e));
+            for msg in Msg::ALL {
+                let text = table.get(msg).unwrap();
+                let mut found = placeholders(&text);
+                found.sort();
+                let mut expected = msg.params().to_vec();
+                expected.sort();
+                assert_eq!(found, expected, "{} {}", code, msg.key());
+            }
+        }
+    }
+
+    #[test]
+    fn message_ids_match_their_index() {
+        for (i, msg) in Msg::ALL.iter().enumerate() {
+            assert_eq!(*msg as usize, i);
+            assert_eq!(Msg::from_key(msg.key()), Some(*msg));
+        }
+    }
+
+    #[test]
+    fn direction_follows_the_language() {
+        assert_eq!(Direction::of_language("he-IL"), Direction::Rtl);
+        assert_eq!(Direction::of_language("de"), Direction::Ltr);
+        let table = parse_locale("x.ini", "ar", "[locale]\ndirection = ltr\n").unwrap();
+        assert_eq!(table.direction, Direction::Ltr);
+        assert!(parse_locale("x.ini", "ar", "[locale]\ndirection = up\n").is_err());
+    }
+
+    #[test]
+    fn auto_language_walks_the_ui_languages() {
+        let available = |code: &str| bundled_locale(code).is_some();
+        let ui = ["de-AT".to_string(), "en-US".to_string()];
+        assert_eq!(resolve_language("auto", &ui, available), "de");
+        assert_eq!(
+            resolve_language("auto", &["ja-JP".to_string()], available),
+            "en"
+        );
+        assert_eq!(resolve_language("FR", &[], available), "fr");
+    }
+
+    #[test]
+    fn placeholders_are_checked_and_filled() {
+        assert_eq!(
+            interpolate("find '{command}' {x}", &[("command", "foo")]),
+            "find 'foo' {x}"
+        );
+        assert_eq!(placeholders("{a} {b c} {} {d_1}"), vec!["a", "d_1"]);
+        assert!(parse_locale("x.ini", "x", "run = {nope}").is_err());
+        assert!(parse_locale("x.ini", "x", "bogus = 1").is_err());
+    }
+
+    #[test]
+    fn text_outlives_its_table() {
+        let table = parse_locale("x.ini", "x", "run = Go").unwrap();
+        let run = table.get(Msg::Run).unwrap();
+        let wide = table.get_wide(Msg::Run).unwrap();
+        drop(table);
+        assert_eq!(&*run, "Go");
+        assert_eq!(&*wide, &['G' as u16, 'o' as u16]);
+        // Without a loaded locale, text comes from the bundled English table.
+        assert_eq!(&*tr(Msg::Run), "Run ↵");
+    }
+}
@@ -0,0 +1,314 @@
+use std::ops::Range;
+
+use crate::data::grapheme::graphemes;
+
+// ==================================================================================
+//  MATCHER
+//  Case-insensitive comparison of the input with history entries. Both sides are
+//  folded one grapheme at a time, so a match always ends on a grapheme boundary of
+//  the original entry even where folding changes the length (`ß` → `ss`,
+//  `İ` → `i̇`), and the ghost text is sliced from the entry as it was written.
+//  Accented letters are decomposed while folding, so `é` typed precomposed matches
+//  `e` followed by a combining accent and the other way round.
+// ==================================================================================
+
+/// Case-folded `text`, with accents dropped when `ignore_accents` is set.
+pub fn fold(text: &str, ignore_accents: bool) -> String {
+    let mut folded = String::with_capacity(text.len());
+    for c in text.chars() {
+        fold_char(c, ignore_accents, &mut folded);
+    }
+    folded
+}
+
+/// Byte offset in `candidate` where `input` ends when it is a prefix of it, folded.
+/// A match that stops inside a grapheme's folding (`stras` against `straße`) is none.
+pub fn prefix_match(candidate: &str, input: &str, ignore_accents: bool) -> Option<usize> {
+    let needle = fold(input, ignore_accents);
+    if needle.is_empty() {
+        return Some(0);
+    }
+    let mut folded = String::new();
+    for (start, grapheme) in graphemes(candidate) {
+        for c in grapheme.chars() {
+            fold_char(c, ignore_accents, &mut folded);
+        }
+        if folded.len() >= needle.len() {
+            return (folded == needle).then_some(start + grapheme.len());
+        }
+        if !needle.starts_with(&folded) {
+            return None;
+        }
+    }
+    None
+}
+
+/// The rest of `candidate` after the typed `input`, if it starts with it and is longer.
+pub fn completion_suffix<'a>(
+    candidate: &'a str,
+    input: &str,
+    ignore_accents: bool,
+) -> Option<&'a str> {
+    let end = prefix_match(candidate, input, ignore_accents)?;
+    (end < candidate.len()).then(|| &candidate[end..])
+}
+
+/// Whether `input` occurs anywhere in `candidate`, folded.
+pub fn contains(candidate: &str, input: &str, ignore_accents: bool) -> bool {
+    find(candidate, input, ignore_accents).is_some()
+}
+
+/// Byte range in `candidate` of the first occurrence of `input`, folded, widened to
+/// whole graphemes.
+pub fn find(candidate: &str, input: &str, ignore_accents: bool) -> Option<Range<usize>> {
+    let needle = fold(input, ignore_accents);
+    let mut folded = String::new();
+    // (start in `folded`, byte range in `candidate`) of every grapheme.
+    let mut bounds = Vec::new();
+    for (start, grapheme) in graphemes(candidate) {
+        bounds.push((folded.len(), start..start + grapheme.len()));
+        for c in grapheme.chars() {
+            fold_char(c, ignore_accents, &mut folded);
+        }
+    }
+    // An occurrence that ends before a combining mark matches a letter without its
+    // accent (`cafe` in `café`).
+    let at = folded.match_indices(&needle).map(|(at, _)| at).find(|at| {
+        !folded[at + needle.len()..]
+            .chars()
+            .next()
+            .is_some_and(is_diacritic)
+    })?;
+    let end = at + needle.len();
+    let first = bounds
+        .iter()
+        .rposition(|(folded_start, _)| *folded_start <= at)?;
+    let start = bounds[first].1.start;
+    let last = bounds
+        .iter()
+        .take_while(|(folded_start, _)| *folded_start < end)
+        .last()
+        .map_or(start, |(_, range)| range.end);
+    Some(start..last.max(start))
+}
+
+fn fold_char(c: char, ignore_accents: bool, out: &mut String) {
+    if ignore_accents && is_diacritic(c) {
+        return;
+    }
+    for lower in c.to_lowercase() {
+        // Full case folding where it differs from lowercase.
+        match lower {
+            'ß' => out.push_str("ss"),
+            'ς' => out.push('σ'),
+            'ſ' => out.push('s'),
+            _ => match decompose(lower) {
+                Some((base, mark)) => {
+                    out.push(base);
+                    if !ignore_accents {
+                        out.push(mark);
+                    }
+                }
+                // `İ` lowercases to `i` plus a combining dot.
+                None if ignore_accents && is_diacritic(lower) => {}
+                None => out.push(lower),
+            },
+        }
+    }
+}
+
+/// Combining diacritical marks.
+fn is_diacritic(c: char) -> bool {
+    matches!(c as u32,
+        0x0300..=0x036F | 0x1AB0..=0x1AFF | 0x1DC0..=0x1DFF | 0x20D0..=0x20FF | 0xFE20..=0xFE2F
+    )
+}
+
+const GRAVE: char = '\u{300}';
+const ACUTE: char = '\u{301}';
+const CIRCUMFLEX: char = '\u{302}';
+const TILDE: char = '\u{303}';
+const MACRON: char = '\u{304}';
+const BREVE: char = '\u{306}';
+const DOT_ABOVE: char = '\u{307}';
+const DIAERESIS: char = '\u{308}';
+const RING: char = '\u{30A}';
+const DOUBLE_ACUTE: char = '\u{30B}';
+const CARON: char = '\u{30C}';
+const COMMA_BELOW: char = '\u{326}';
+const CEDILLA: char = '\u{327}';
+const OGONEK: char = '\u{328}';
+
+/// Canonical decomposition of a precomposed lowercase Latin or Greek letter, so `é`
+/// folds the same as `e` followed by a combining acute accent.
+fn decompose(c: char) -> Option<(char, char)> {
+    let mark = match c {
+        'à' | 'è' | 'ì' | 'ò' | 'ù' => GRAVE,
+        'á' | 'é' | 'í' | 'ó' | 'ú' | 'ý' | 'ć' | 'ĺ' | 'ń' | 'ŕ' | 'ś' | 'ź' => ACUTE,
+        'â' | 'ê' | 'î' | 'ô' | 'û' | 'ĉ' | 'ĝ' | 'ĥ' | 'ĵ' | 'ŝ' | 'ŵ' | 'ŷ' => {
+            CIRCUMFLEX
+        }
+        'ã' | 'õ' | 'ñ' | 'ĩ' | 'ũ' => TILDE,
+        'ā' | 'ē' | 'ī' | 'ō' | 'ū' => MACRON,
+        'ă' | 'ĕ' | 'ğ' | 'ĭ' | 'ŏ' | 'ŭ' => BREVE,
+        'ċ' | 'ė' | 'ġ' | 'ż' => DOT_ABOVE,
+        'ä' | 'ë' | 'ï' | 'ö' | 'ü' | 'ÿ' | 'ϊ' | 'ϋ' => DIAERESIS,
+        'å' | 'ů' => RING,
+        'ő' | 'ű' => DOUBLE_ACUTE,
+        'č' | 'ď' | 'ě' | 'ľ' | 'ň' | 'ř' | 'š' | 'ť' | 'ž' => CARON,
+        'ș' | 'ț' => COMMA_BELOW,
+        'ç' | 'ģ' | 'ķ' | 'ļ' | 'ņ' | 'ŗ' | 'ş' | 'ţ' => CEDILLA,
+        'ą' | 'ę' | 'į' | 'ų' => OGONEK,
+        // Greek tonos is the acute accent.
+        'ά' | 'έ' | 'ή' | 'ί' | 'ό' | 'ύ' | 'ώ' => ACUTE,
+        _ => return None,
+    };
+    Some((base_letter(c), mark))
+}
+
+/// Base letter of a precomposed lowercase letter.
+fn base_letter(c: char) -> char {
+    match c {
+        'à'..='å' | 'ā' | 'ă' | 'ą' => 'a',
+        'ç' | 'ć' | 'ĉ' | 'ċ' | 'č' => 'c',
+        'ď' => 'd',
+        'è'..='ë' | 'ē' | 'ĕ' | 'ė' | 'ę' | 'ě' => 'e',
+        'ĝ' | 'ğ' | 'ġ' | 'ģ' => 'g',
+        'ĥ' => 'h',
+        'ì'..='ï' | 'ĩ' | 'ī' | 'ĭ' | 'į' => 'i',
+        'ĵ' => 'j',
+        'ķ' => 'k',
+        'ĺ' | 'ļ' | 'ľ' => 'l',
+        'ñ' | 'ń' | 'ņ' | 'ň' => 'n',
+        'ò'..='ö' | 'ō' | 'ŏ' | 'ő' => 'o',
+        'ŕ' | 'ŗ' | 'ř' => 'r',
+        'ś' | 'ŝ' | 'ş' | 'š' | 'ș' => 's',
+        'ţ' | 'ť' | 'ț' => 't',
+        'ù'..='ü' | 'ũ' | 'ū' | 'ŭ' | 'ů' | 'ű' | 'ų' => 'u',
+        'ŵ' => 'w',
+        'ý' | 'ÿ' | 'ŷ' => 'y',
+        'ź' | 'ż' | 'ž' => 'z',
+        'ά' => 'α',
+        'έ' => 'ε',
+        'ή' => 'η',
+        'ί' | 'ϊ' => 'ι',
+        'ό' => 'ο',
+        'ύ' | 'ϋ' => 'υ',
+        'ώ' => 'ω',
+        _ => c,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn ascii_prefixes_ignore_case() {
+        assert_eq!(completion_suffix("Notepad", "NOTE", false), Some("pad"));
+        assert_eq!(completion_suffix("notepad", "notepad", false), None);
+        assert_eq!(completion_suffix("notepad", "x", false), None);
+        assert_eq!(prefix_match("notepad", "", false), Some(0));
+        assert!(contains("C:\\Windows", "windows", false));
+    }
+
+    #[test]
+    fn turkish_dotted_and_dotless_i() {
+        // `İ` lowercases to `i` plus a combining dot, three bytes instead of two.
+        assert_eq!(
+            completion_suffix("İstanbul", "i\u{307}st", false),
+            Some("anbul")
+        );
+        assert_eq!(completion_suffix("İstanbul", "İST", false), Some("anbul"));
+        assert_eq!(completion_suffix("İstanbul", "ist", false), None);
+        assert_eq!(completion_suffix("İstanbul", "ist", true), Some("anbul"));
+        // Dotless `ı` is a letter of its own.
+        assert_eq!(completion_suffix("ılık", "ıl", false), Some("ık"));
+        assert_eq!(completion_suffix("ılık", "il", false), None);
+        assert_eq!(completion_suffix("ılık", "IL", false), None);
+        assert_eq!(completion_suffix("ILIK", "ıl", false), None);
+    }
+
+    #[test]
+    fn german_sharp_s() {
+        assert_eq!(completion_suffix("Straße 1", "strasse", false), Some(" 1"));
+        assert_eq!(completion_suffix("Strasse 1", "STRAẞE", false), Some(" 1"));
+        assert_eq!(completion_suffix("straße", "stra", false), Some("ße"));
+        // Stops inside the folding of `ß`.
+        assert_eq!(completion_suffix("straße", "stras", false), None);
+        assert!(contains("straße", "stras", false));
+        assert_eq!(completion_suffix("Größe", "GRÖ", false), Some("ße"));
+        assert_eq!(completion_suffix("Größe", "gro", false), None);
+        assert_eq!(completion_suffix("Größe", "gro", true), Some("ße"));
+    }
+
+    #[test]
+    fn decomposed_input_matches_precomposed_entries() {
+        assert_eq!(
+            completion_suffix("café au lait", "cafe\u{301}", false),
+            Some(" au lait")
+        );
+        assert_eq!(
+            completion_suffix("CAFÉ au lait", "cafe\u{301}", false),
+            Some(" au lait")
+        );
+        assert_eq!(
+            completion_suffix("Ångström", "a\u{30A}ngstro\u{308}", false),
+            Some("m")
+        );
+        assert_eq!(
+            completion_suffix("Dvořák", "dvor\u{30C}", false),
+            Some("ák")
+        );
+        assert!(contains("ma café", "E\u{301}", false));
+        assert_eq!(find("ma café!", "e\u{301}", false), Some(6..8));
+    }
+
+    #[test]
+    fn precomposed_input_matches_decomposed_entries() {
+        let decomposed = "cafe\u{301} au lait";
+        assert_eq!(
+            completion_suffix(decomposed, "café", false),
+            Some(" au lait")
+        );
+        assert_eq!(
+            completion_suffix(decomposed, "CAFÉ", false),
+            Some(" au lait")
+        );
+        // A match never ends between a letter and its accent.
+        assert_eq!(
+            completion_suffix(decomposed, "caf", false),
+            Some("e\u{301} au lait")
+        );
+        assert_eq!(completion_suffix(decomposed, "cafe", false), None);
+        assert_eq!(find(decomposed, "é", false), Some(3..6));
+        assert!(!contains(decomposed, "cafe", false));
+    }
+
+    #[test]
+    fn accents_can_be_ignored() {
+        let decomposed = "cafe\u{301} au lait";
+        assert_eq!(
+            completion_suffix(decomposed, "cafe", true),
+            Some(" au lait")
+        );
+        assert_eq!(
+            completion_suffix(decomposed, "café", true),
+            Some(" au lait")
+        );
+        assert_eq!(
+            completion_suffix("café au lait", "cafe", true),
+            Some(" au lait")
+        );
+        assert!(contains("ma café", "CAFE", true));
+        assert!(!contains("ma café", "CAFE", false));
+        assert_eq!(fold("Ţară ȘI Çok", true), "tara si cok");
+    }
+
+    #[test]
+    fn greek_final_sigma_and_tonos() {
+        assert_eq!(completion_suffix("ΟΔΟΣ x", "οδος", false), Some(" x"));
+        assert_eq!(completion_suffix("Αθήνα", "αθη\u{301}", false), Some("να"));
+        assert_eq!(completion_suffix("Αθήνα", "αθη", true), Some("να"));
+    }
+}
@@ -1 +1,37 @@
+pub mod aliases;
+pub mod arguments;
+pub mod bookmarks;
+pub mod calculator;
+pub mod command_line;
+pub mod completion;
+pub mod completion_spec;
+pub mod contrast;
+pub mod double_tap;
+pub mod expansion;
+pub mod file_index;
+pub mod git_repos;
+pub mod grapheme;
 pub mod history;
+pub mod history_search;
+pub mod hotkey;
+pub mod humanize;
+pub mod ini;
+pub mod json;
+pub mod keymap;
+pub mod locale;
+pub mod matcher;
+pub mod providers;
+pub mod reload;
+pub mod sections;
+pub mod settings;
+pub mod sqlite;
+pub mod ssh_config;
+pub mod suggest;
+pub mod syntax;
+#[cfg(test)]
+pub mod temp_dir;
+pub mod terminal;
+pub mod text_buffer;
+pub mod theme;
+pub mod usage;
+pub mod vscode;
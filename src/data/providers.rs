@@ -0,0 +1,1055 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::*;
+use crate::data::aliases::Alias;
+use crate::data::arguments::argument_suggestions;
+use crate::data::bookmarks::Bookmark;
+use crate::data::calculator::{evaluate, format_number};
+use crate::data::command_line::{CommandLine, program_key};
+use crate::data::completion::{DirectoryLister, complete};
+use crate::data::completion_spec::Command;
+use crate::data::file_index::FileIndex;
+use crate::data::git_repos::{Repo, open_command};
+use crate::data::locale::{Msg, tr};
+use crate::data::matcher::{self, completion_suffix};
+use crate::data::ssh_config::SshHost;
+use crate::data::suggest::*;
+use crate::data::terminal::TerminalProfile;
+use crate::data::vscode::RecentWorkspace;
+
+// ==================================================================================
+//  PROVIDERS
+//  The built-in suggestion sources. Each holds a shared snapshot (an `Arc`) of what
+//  it searches, taken on the UI thread, so nothing here touches the globals from a
+//  worker thread and a keystroke copies no lists.
+//  Scores put a calculation first, then files asked for with `f `, editor
+//  workspaces, terminal profiles and ssh hosts asked for with `code `, `term ` and
+//  `ssh `, history that continues the input, apps and settings pages named like it,
+//  aliases, repositories, completion specs, bookmarks named like it, and history,
+//  apps, repositories or bookmarks that only contain it.
+// ==================================================================================
+
+const CALCULATION_SCORE: f32 = 1.0;
+const FILE_SCORE: f32 = 0.9;
+const WORKSPACE_NAME_SCORE: f32 = 0.85;
+const PROFILE_NAME_SCORE: f32 = 0.85;
+const SSH_HOST_SCORE: f32 = 0.85;
+const HISTORY_PREFIX_SCORE: f32 = 0.8;
+const APP_NAME_SCORE: f32 = 0.75;
+const ALIAS_SCORE: f32 = 0.7;
+const SETTING_NAME_SCORE: f32 = 0.7;
+const REPO_NAME_SCORE: f32 = 0.65;
+const COMPLETION_SCORE: f32 = 0.6;
+const WORKSPACE_WORD_SCORE: f32 = 0.55;
+const PROFILE_WORD_SCORE: f32 = 0.55;
+const SSH_HOST_PART_SCORE: f32 = 0.55;
+const APP_WORD_SCORE: f32 = 0.5;
+const BOOKMARK_TITLE_SCORE: f32 = 0.45;
+const SETTING_WORD_SCORE: f32 = 0.45;
+const HISTORY_CONTAINS_SCORE: f32 = 0.4;
+const REPO_WORD_SCORE: f32 = 0.35;
+const BOOKMARK_WORD_SCORE: f32 = 0.3;
+
+/// Where names of apps, settings pages, profiles and bookmark titles split into words.
+const WORD_SEPARATORS: &[char] = &[' ', '\t'];
+/// Where folder and workspace names split into words, e.g. `my-app`.
+const NAME_SEPARATORS: &[char] = &['-', '_', '.', ' '];
+/// Where host names split, so `db.prod.example.com` is also found by `prod`.
+const HOST_SEPARATORS: &[char] = &['.', '-', '_'];
+
+/// What was typed, matched against the start of a name or of one of its words.
+#[derive(Clone, Copy)]
+struct Typed<'a> {
+    text: &'a str,
+    ignore_accents: bool,
+}
+
+impl Typed<'_> {
+    fn starts(self, text: &str) -> bool {
+        matcher::prefix_match(text, self.text, self.ignore_accents).is_some()
+    }
+
+    /// `name_score` if `name` starts with what was typed, `word_score` if one of its
+    /// words does.
+    fn score(
+        self,
+        name: &str,
+        separators: &[char],
+        name_score: f32,
+        word_score: f32,
+    ) -> Option<f32> {
+        if self.starts(name) {
+            Some(name_score)
+        } else if name.split(separators).any(|word| self.starts(word)) {
+            Some(word_score)
+        } else {
+            None
+        }
+    }
+}
+
+/// A suggestion for each item that `score` keeps; nothing once the query is stale.
+fn scored<'a, T: 'a>(
+    items: impl IntoIterator<Item = &'a T>,
+    cancel: &CancelToken,
+    score: impl Fn(&T) -> Option<f32>,
+    suggestion: impl Fn(&T, f32) -> Suggestion,
+) -> Vec<Suggestion> {
+    let mut suggestions = Vec::new();
+    for item in items {
+        if cancel.is_cancelled() {
+            return Vec::new();
+        }
+        if let Some(score) = score(item) {
+            suggestions.push(suggestion(item, score));
+        }
+    }
+    suggestions
+}
+
+/// Past commands: the program's argument lists once it is typed, otherwise lines
+/// that start with or contain the input.
+pub struct HistoryProvider {
+    pub history: Arc<Vec<String>>,
+}
+
+impl SuggestionProvider for HistoryProvider {
+    fn source(&self) -> Source {
+        Source::History
+    }
+
+    fn budget(&self) -> Duration {
+        Duration::from_millis(SUGGEST_HISTORY_BUDGET_MS)
+    }
+
+    fn suggest(&self, query: &Query, cancel: &CancelToken) -> Vec<Suggestion> {
+        let input = query.text.as_str();
+        let arguments =
+            argument_suggestions(input, &self.history, query.ignore_accents).unwrap_or_default();
+        if !arguments.is_empty() {
+            return arguments
+                .into_iter()
+                .map(|text| suggestion(text, String::new(), Source::History, HISTORY_PREFIX_SCORE))
+                .collect();
+        }
+
+        let mut suggestions = Vec::new();
+        for line in self.history.iter() {
+            if cancel.is_cancelled() {
+                return Vec::new();
+            }
+            let score = if completion_suffix(line, input, query.ignore_accents).is_some() {
+                HISTORY_PREFIX_SCORE
+            } else if matcher::contains(line, input, query.ignore_accents) {
+                HISTORY_CONTAINS_SCORE
+            } else {
+                continue;
+            };
+            suggestions.push(suggestion(
+                line.clone(),
+                String::new(),
+                Source::History,
+                score,
+            ));
+        }
+        suggestions
+    }
+}
+
+/// Subcommands, flags and argument values from the typed program's completion spec.
+pub struct CompletionProvider<L> {
+    pub spec: Option<Command>,
+    pub lister: L,
+}
+
+impl<L: DirectoryLister + Send> SuggestionProvider for CompletionProvider<L> {
+    fn source(&self) -> Source {
+        Source::Completion
+    }
+
+    fn budget(&self) -> Duration {
+        Duration::from_millis(SUGGEST_COMPLETION_BUDGET_MS)
+    }
+
+    fn suggest(&self, query: &Query, _cancel: &CancelToken) -> Vec<Suggestion> {
+        let Some(spec) = &self.spec else {
+            return Vec::new();
+        };
+        complete(spec, &query.text, &self.lister, query.ignore_accents)
+            .into_iter()
+            .map(|c| suggestion(c.text, c.description, Source::Completion, COMPLETION_SCORE))
+            .collect()
+    }
+}
+
+/// Aliases from `aliases.ini` whose name starts with the input, with their target.
+pub struct AliasProvider {
+    pub aliases: Arc<Vec<Alias>>,
+}
+
+impl SuggestionProvider for AliasProvider {
+    fn source(&self) -> Source {
+        Source::Alias
+    }
+
+    fn budget(&self) -> Duration {
+        Duration::from_millis(SUGGEST_ALIAS_BUDGET_MS)
+    }
+
+    fn suggest(&self, query: &Query, _cancel: &CancelToken) -> Vec<Suggestion> {
+        let input = query.text.as_str();
+        if input.contains(char::is_whitespace) {
+            return Vec::new();
+        }
+        self.aliases
+            .iter()
+            .filter(|alias| completion_suffix(&alias.name, input, query.ignore_accents).is_some())
+            .map(|alias| {
+                suggestion(
+                    alias.name.clone(),
+                    alias.target.clone(),
+                    Source::Alias,
+                    ALIAS_SCORE,
+                )
+            })
+            .collect()
+    }
+}
+
+/// A Start menu shortcut.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct App {
+    pub name: String,
+    /// The shortcut file, which is what gets run.
+    pub path: String,
+    /// Where the shortcut points, when that is a plain file.
+    pub target: String,
+}
+
+/// Apps whose name, or a word of it, starts with the input.
+pub struct AppsProvider {
+    pub apps: Arc<Vec<App>>,
+}
+
+impl SuggestionProvider for AppsProvider {
+    fn source(&self) -> Source {
+        Source::App
+    }
+
+    fn budget(&self) -> Duration {
+        Duration::from_millis(SUGGEST_APPS_BUDGET_MS)
+    }
+
+    fn suggest(&self, query: &Query, cancel: &CancelToken) -> Vec<Suggestion> {
+        let input = query.text.trim();
+        if input.is_empty() {
+            return Vec::new();
+        }
+        let typed = Typed {
+            text: input,
+            ignore_accents: query.ignore_accents,
+        };
+        // Typing a whole name still lists the app.
+        scored(
+            self.apps.iter(),
+            cancel,
+            |app| typed.score(&app.name, WORD_SEPARATORS, APP_NAME_SCORE, APP_WORD_SCORE),
+            |app, score| Suggestion {
+                text: app.path.clone(),
+                name: app.name.clone(),
+                detail: app.target.clone(),
+                source: Source::App,
+                score,
+            },
+        )
+    }
+}
+
+/// Windows Settings pages offered by name, with the `ms-settings:` URI that opens each.
+pub const SETTINGS_PAGES: [(Msg, &str); 14] = [
+    (Msg::SettingDisplay, "ms-settings:display"),
+    (Msg::SettingSound, "ms-settings:sound"),
+    (Msg::SettingBluetooth, "ms-settings:bluetooth"),
+    (Msg::SettingNetwork, "ms-settings:network"),
+    (Msg::SettingPersonalization, "ms-settings:personalization"),
+    (Msg::SettingApps, "ms-settings:appsfeatures"),
+    (Msg::SettingDefaultApps, "ms-settings:defaultapps"),
+    (Msg::SettingWindowsUpdate, "ms-settings:windowsupdate"),
+    (Msg::SettingPower, "ms-settings:powersleep"),
+    (Msg::SettingStorage, "ms-settings:storagesense"),
+    (Msg::SettingDateTime, "ms-settings:dateandtime"),
+    (Msg::SettingPrivacy, "ms-settings:privacy"),
+    (Msg::SettingNotifications, "ms-settings:notifications"),
+    (Msg::SettingLanguage, "ms-settings:regionlanguage"),
+];
+
+/// A Windows Settings page under its name in the current locale.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SettingsPage {
+    pub name: String,
+    pub uri: &'static str,
+}
+
+/// `SETTINGS_PAGES` named in the current locale.
+pub fn settings_pages() -> Vec<SettingsPage> {
+    SETTINGS_PAGES
+        .iter()
+        .map(|&(msg, uri)| SettingsPage {
+            name: tr(msg).to_string(),
+            uri,
+        })
+        .collect()
+}
+
+/// Settings pages whose name, or a word of it, starts with the input. Picking one
+/// runs its URI, which opens the page in Windows Settings.
+pub struct SettingsProvider {
+    pub pages: Vec<SettingsPage>,
+}
+
+impl SuggestionProvider for SettingsProvider {
+    fn source(&self) -> Source {
+        Source::Setting
+    }
+
+    fn budget(&self) -> Duration {
+        Duration::from_millis(SUGGEST_SETTINGS_BUDGET_MS)
+    }
+
+    fn suggest(&self, query: &Query, cancel: &CancelToken) -> Vec<Suggestion> {
+        let input = query.text.trim();
+        if input.is_empty() {
+            return Vec::new();
+        }
+        let typed = Typed {
+            text: input,
+            ignore_accents: query.ignore_accents,
+        };
+        scored(
+            &self.pages,
+            cancel,
+            |page| {
+                typed.score(
+                    &page.name,
+                    WORD_SEPARATORS,
+                    SETTING_NAME_SCORE,
+                    SETTING_WORD_SCORE,
+                )
+            },
+            |page, score| Suggestion {
+                text: page.uri.to_string(),
+                name: page.name.clone(),
+                detail: page.uri.to_string(),
+                source: Source::Setting,
+                score,
+            },
+        )
+    }
+}
+
+/// Git working trees whose name, or a word of it, starts with the input. Picking one
+/// runs the `[git]` open action on it.
+pub struct GitReposProvider {
+    pub repos: Arc<Vec<Repo>>,
+    pub open: String,
+    /// `git_detached` in the active locale, looked up on the UI thread.
+    pub detached: Arc<str>,
+}
+
+impl SuggestionProvider for GitReposProvider {
+    fn source(&self) -> Source {
+        Source::Repo
+    }
+
+    fn budget(&self) -> Duration {
+        Duration::from_millis(SUGGEST_REPOS_BUDGET_MS)
+    }
+
+    fn suggest(&self, query: &Query, cancel: &CancelToken) -> Vec<Suggestion> {
+        let input = query.text.trim();
+        if input.is_empty() {
+            return Vec::new();
+        }
+        let typed = Typed {
+            text: input,
+            ignore_accents: query.ignore_accents,
+        };
+
+        scored(
+            self.repos.iter(),
+            cancel,
+            |repo| {
+                typed.score(
+                    &repo.name,
+                    NAME_SEPARATORS,
+                    REPO_NAME_SCORE,
+                    REPO_WORD_SCORE,
+                )
+            },
+            |repo, score| {
+                let branch = repo.head.describe(&self.detached);
+                Suggestion {
+                    text: open_command(&self.open, &repo.path),
+                    name: repo.name.clone(),
+                    detail: if branch.is_empty() {
+                        repo.path.clone()
+                    } else {
+                        format!("{} · {}", branch, repo.path)
+                    },
+                    source: Source::Repo,
+                    score,
+                }
+            },
+        )
+    }
+}
+
+/// Recently opened editor folders and workspaces, most recent first, once the input
+/// is `code ` (or the command of another VS Code build) and part of a name.
+pub struct WorkspacesProvider {
+    pub recent: Arc<Vec<RecentWorkspace>>,
+}
+
+impl SuggestionProvider for WorkspacesProvider {
+    fn source(&self) -> Source {
+        Source::Workspace
+    }
+
+    fn budget(&self) -> Duration {
+        Duration::from_millis(SUGGEST_WORKSPACES_BUDGET_MS)
+    }
+
+    fn suggest(&self, query: &Query, cancel: &CancelToken) -> Vec<Suggestion> {
+        let Some(line) = CommandLine::parse(&query.text) else {
+            return Vec::new();
+        };
+        let ends_word = query.text.ends_with(char::is_whitespace);
+        let typed = match line.arguments.as_slice() {
+            [] if ends_word => "",
+            [word] if !ends_word => word.value.as_str(),
+            _ => return Vec::new(),
+        };
+        let command = program_key(&line.program.value);
+        let typed = Typed {
+            text: typed,
+            ignore_accents: query.ignore_accents,
+        };
+
+        scored(
+            self.recent.iter().filter(|w| w.command == command),
+            cancel,
+            |workspace| {
+                typed.score(
+                    &workspace.name,
+                    NAME_SEPARATORS,
+                    WORKSPACE_NAME_SCORE,
+                    WORKSPACE_WORD_SCORE,
+                )
+            },
+            |workspace, score| Suggestion {
+                text: workspace.open_command(),
+                name: workspace.name.clone(),
+                detail: workspace.location(),
+                source: Source::Workspace,
+                score,
+            },
+        )
+    }
+}
+
+/// Windows Terminal profiles named like what follows `term ` or `terminal `.
+/// Picking one keeps the alias, so running it goes through `profile_command`.
+pub struct TerminalProfilesProvider {
+    pub profiles: Arc<Vec<TerminalProfile>>,
+}
+
+impl SuggestionProvider for TerminalProfilesProvider {
+    fn source(&self) -> Source {
+        Source::TerminalProfile
+    }
+
+    fn budget(&self) -> Duration {
+        Duration::from_millis(SUGGEST_TERMINAL_BUDGET_MS)
+    }
+
+    fn suggest(&self, query: &Query, cancel: &CancelToken) -> Vec<Suggestion> {
+        let Some((alias, typed)) = query.text.split_once(' ') else {
+            return Vec::new();
+        };
+        if !alias.eq_ignore_ascii_case("term") && !alias.eq_ignore_ascii_case("terminal") {
+            return Vec::new();
+        }
+        let typed = typed.trim_start();
+        let typed = Typed {
+            text: typed,
+            ignore_accents: query.ignore_accents,
+        };
+
+        scored(
+            self.profiles.iter(),
+            cancel,
+            |profile| {
+                typed.score(
+                    &profile.name,
+                    WORD_SEPARATORS,
+                    PROFILE_NAME_SCORE,
+                    PROFILE_WORD_SCORE,
+                )
+            },
+            |profile, score| Suggestion {
+                text: format!("{} {}", alias, profile.name),
+                name: profile.name.clone(),
+                detail: profile.detail.clone(),
+                source: Source::TerminalProfile,
+                score,
+            },
+        )
+    }
+}
+
+/// Hosts from the ssh config and `known_hosts` named like what follows `ssh `.
+/// A `user@` typed before the host is kept.
+pub struct SshHostsProvider {
+    pub hosts: Arc<Vec<SshHost>>,
+}
+
+impl SuggestionProvider for SshHostsProvider {
+    fn source(&self) -> Source {
+        Source::SshHost
+    }
+
+    fn budget(&self) -> Duration {
+        Duration::from_millis(SUGGEST_SSH_BUDGET_MS)
+    }
+
+    fn suggest(&self, query: &Query, cancel: &CancelToken) -> Vec<Suggestion> {
+        let Some((program, typed)) = query.text.split_once(' ') else {
+            return Vec::new();
+        };
+        let typed = typed.trim_start();
+        // Only the host itself: options and remote commands are the user's own.
+        if !program.eq_ignore_ascii_case("ssh")
+            || typed.starts_with('-')
+            || typed.contains(char::is_whitespace)
+        {
+            return Vec::new();
+        }
+        let (user, typed) = match typed.rsplit_once('@') {
+            Some((user, host)) => (&typed[..user.len() + 1], host),
+            None => ("", typed),
+        };
+        let typed = Typed {
+            text: typed,
+            ignore_accents: query.ignore_accents,
+        };
+
+        scored(
+            self.hosts.iter(),
+            cancel,
+            |host| {
+                typed.score(
+                    &host.name,
+                    HOST_SEPARATORS,
+                    SSH_HOST_SCORE,
+                    SSH_HOST_PART_SCORE,
+                )
+            },
+            |host, score| Suggestion {
+                text: format!("{} {}{}", program, user, host.name),
+                name: host.name.clone(),
+                detail: host.detail.clone(),
+                source: Source::SshHost,
+                score,
+            },
+        )
+    }
+}
+
+/// Browser bookmarks whose title starts with the input, or whose site, folder or a
+/// word of the title does. Picking one puts its address in the input, which
+/// `run_command` opens in the default browser.
+pub struct BookmarksProvider {
+    pub bookmarks: Arc<Vec<Bookmark>>,
+}
+
+impl SuggestionProvider for BookmarksProvider {
+    fn source(&self) -> Source {
+        Source::Bookmark
+    }
+
+    fn budget(&self) -> Duration {
+        Duration::from_millis(SUGGEST_BOOKMARKS_BUDGET_MS)
+    }
+
+    fn suggest(&self, query: &Query, cancel: &CancelToken) -> Vec<Suggestion> {
+        let input = query.text.trim();
+        if input.is_empty() {
+            return Vec::new();
+        }
+        let typed = Typed {
+            text: input,
+            ignore_accents: query.ignore_accents,
+        };
+
+        scored(
+            self.bookmarks.iter(),
+            cancel,
+            |bookmark| {
+                typed
+                    .score(
+                        &bookmark.title,
+                        WORD_SEPARATORS,
+                        BOOKMARK_TITLE_SCORE,
+                        BOOKMARK_WORD_SCORE,
+                    )
+                    .or_else(|| {
+                        let found = typed.starts(site(&bookmark.url))
+                            || bookmark
+                                .folder
+                                .split(['/', ' '])
+                                .any(|part| typed.starts(part));
+                        found.then_some(BOOKMARK_WORD_SCORE)
+                    })
+            },
+            |bookmark, score| Suggestion {
+                text: bookmark.url.clone(),
+                name: bookmark.title.clone(),
+                detail: format!("{} · {}", bookmark.location(), bookmark.url),
+                source: Source::Bookmark,
+                score,
+            },
+        )
+    }
+}
+
+/// `https://www.github.com/rust-lang` → `github.com/rust-lang`.
+fn site(url: &str) -> &str {
+    let rest = url.split_once("://").map_or(url, |(_, rest)| rest);
+    rest.strip_prefix("www.").unwrap_or(rest)
+}
+
+/// Indexed files and folders named like what follows `f `. The index arrives once
+/// the indexer has loaded or built it.
+pub struct FileProvider {
+    pub index: Option<Arc<FileIndex>>,
+}
+
+impl SuggestionProvider for FileProvider {
+    fn source(&self) -> Source {
+        Source::File
+    }
+
+    fn budget(&self) -> Duration {
+        Duration::from_millis(SUGGEST_FILES_BUDGET_MS)
+    }
+
+    fn suggest(&self, query: &Query, cancel: &CancelToken) -> Vec<Suggestion> {
+        let (Some(index), Some(search)) =
+            (&self.index, query.text.strip_prefix(FILE_SEARCH_PREFIX))
+        else {
+            return Vec::new();
+        };
+        // Hits arrive best first; the merge keeps that order among equal scores.
+        index
+            .search(search, query.ignore_accents, FILE_SEARCH_LIMIT, || {
+                cancel.is_cancelled()
+            })
+            .into_iter()
+            .map(|hit| Suggestion {
+                text: hit.path,
+                name: hit.name,
+                detail: hit.folder,
+                source: Source::File,
+                score: FILE_SCORE,
+            })
+            .collect()
+    }
+}
+
+/// The result of arithmetic typed into the input.
+pub struct CalculatorProvider;
+
+impl SuggestionProvider for CalculatorProvider {
+    fn source(&self) -> Source {
+        Source::Calculator
+    }
+
+    fn budget(&self) -> Duration {
+        Duration::from_millis(SUGGEST_CALCULATOR_BUDGET_MS)
+    }
+
+    fn suggest(&self, query: &Query, _cancel: &CancelToken) -> Vec<Suggestion> {
+        match evaluate(&query.text) {
+            Some(value) => vec![suggestion(
+                format_number(value),
+                format!("{} =", query.text.trim()),
+                Source::Calculator,
+                CALCULATION_SCORE,
+            )],
+            None => Vec::new(),
+        }
+    }
+}
+
+fn suggestion(text: String, detail: String, source: Source, score: f32) -> Suggestion {
+    Suggestion {
+        text,
+        name: String::new(),
+        detail,
+        source,
+        score,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::git_repos::Head;
+    use std::sync::mpsc;
+
+    fn run(text: &str, providers: Vec<Box<dyn SuggestionProvider>>) -> Vec<Suggestion> {
+        let (tx, rx) = mpsc::channel();
+        let query = Query {
+            text: text.into(),
+            ignore_accents: false,
+        };
+        Orchestrator::new().query(query, providers, 20, move |r| tx.send(r).unwrap());
+        rx.recv_timeout(Duration::from_secs(2)).unwrap().items
+    }
+
+    fn texts(items: &[Suggestion]) -> Vec<&str> {
+        items.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    fn history(lines: &[&str]) -> Box<dyn SuggestionProvider> {
+        Box::new(HistoryProvider {
+            history: Arc::new(lines.iter().map(|s| s.to_string()).collect()),
+        })
+    }
+
+    fn aliases(names: &[(&str, &str)]) -> Box<dyn SuggestionProvider> {
+        Box::new(AliasProvider {
+            aliases: Arc::new(
+                names
+                    .iter()
+                    .map(|(name, target)| Alias {
+                        name: name.to_string(),
+                        target: target.to_string(),
+                    })
+                    .collect(),
+            ),
+        })
+    }
+
+    #[test]
+    fn history_continuations_rank_above_contained_matches() {
+        let items = run(
+            "notepad",
+            vec![
+                history(&["ping notepad", "notepad a.txt", "calc"]),
+                aliases(&[("notes", "C:\\notes")]),
+                Box::new(CalculatorProvider),
+            ],
+        );
+        assert_eq!(texts(&items), ["notepad a.txt", "ping notepad"]);
+    }
+
+    #[test]
+    fn aliases_show_their_target() {
+        let items = run(
+            "not",
+            vec![
+                history(&["ping not"]),
+                aliases(&[("notes", "C:\\notes"), ("gh", "x")]),
+            ],
+        );
+        assert_eq!(texts(&items), ["notes", "ping not"]);
+        assert_eq!(items[0].detail, "C:\\notes");
+        assert_eq!(items[0].source, Source::Alias);
+        assert!(run("not x", vec![aliases(&[("notes", "C:\\notes")])]).is_empty());
+    }
+
+    #[test]
+    fn apps_match_by_name_then_by_word() {
+        let apps = ["Visual Studio Code", "Code Runner", "Notepad"]
+            .iter()
+            .map(|name| App {
+                name: name.to_string(),
+                path: format!("C:\\Start\\{}.lnk", name),
+                target: format!("C:\\{}.exe", name),
+            })
+            .collect();
+        let apps = Arc::new(apps);
+        let items = run("code", vec![Box::new(AppsProvider { apps })]);
+        assert_eq!(items.len(), 2);
+        assert_eq!(items[0].title(), "Code Runner");
+        assert_eq!(items[0].text, "C:\\Start\\Code Runner.lnk");
+        assert_eq!(items[0].detail, "C:\\Code Runner.exe");
+        assert_eq!(items[1].title(), "Visual Studio Code");
+        assert!(items[0].score > items[1].score);
+    }
+
+    #[test]
+    fn settings_pages_open_their_uri() {
+        let pages = settings_pages();
+        assert_eq!(pages.len(), SETTINGS_PAGES.len());
+        assert!(pages.iter().all(|p| p.uri.starts_with("ms-settings:")));
+        let items = run("update", vec![Box::new(SettingsProvider { pages })]);
+        assert_eq!(texts(&items), ["ms-settings:windowsupdate"]);
+        assert_eq!(items[0].title(), "Windows Update");
+        assert_eq!(items[0].score, SETTING_WORD_SCORE);
+        let pages = vec![SettingsPage {
+            name: "Écran".into(),
+            uri: "ms-settings:display",
+        }];
+        let items = run("écr", vec![Box::new(SettingsProvider { pages })]);
+        assert_eq!(items[0].score, SETTING_NAME_SCORE);
+    }
+
+    #[test]
+    fn typed_text_matches_names_then_words() {
+        let typed = Typed {
+            text: "prod",
+            ignore_accents: false,
+        };
+        let score = |name| typed.score(name, HOST_SEPARATORS, 1.0, 0.5);
+        assert_eq!(score("Production"), Some(1.0));
+        assert_eq!(score("db.prod.example.com"), Some(0.5));
+        assert_eq!(score("db-production"), Some(0.5));
+        assert_eq!(score("reproduce"), None);
+        assert_eq!(typed.score("db prod", HOST_SEPARATORS, 1.0, 0.5), None);
+    }
+
+    #[test]
+    fn repos_match_by_name_and_open_with_the_action() {
+        let repo = |name: &str, head| Repo {
+            name: name.to_string(),
+            path: format!("C:\\src\\{}", name),
+            head,
+        };
+        let repos = Arc::new(vec![
+            repo("swift-run", Head::Branch("main".into())),
+            repo("api_server", Head::Unknown),
+            repo("vendored", Head::Detached("1a2b3c4".into())),
+        ]);
+        let find = |text| {
+            let provider = GitReposProvider {
+                repos: repos.clone(),
+                open: "terminal".into(),
+                detached: "losgelöst bei {commit}".into(),
+            };
+            run(text, vec![Box::new(provider)])
+        };
+        let items = find("swi");
+        assert_eq!(texts(&items), ["wt -d \"C:\\src\\swift-run\""]);
+        assert_eq!(items[0].title(), "swift-run");
+        assert_eq!(items[0].detail, "main · C:\\src\\swift-run");
+        let by_word = find("serv");
+        assert_eq!(by_word[0].detail, "C:\\src\\api_server");
+        assert!(by_word[0].score < items[0].score);
+        assert_eq!(
+            find("vend")[0].detail,
+            "losgelöst bei 1a2b3c4 · C:\\src\\vendored"
+        );
+        assert!(find("zzz").is_empty());
+        assert!(find(" ").is_empty());
+    }
+
+    #[test]
+    fn workspaces_follow_the_editor_command() {
+        let workspace = |name: &str, command: &str| RecentWorkspace {
+            name: name.to_string(),
+            uri: format!("file:///c%3A/src/{}", name),
+            kind: crate::data::vscode::WorkspaceKind::Folder,
+            path: format!("C:\\src\\{}", name),
+            remote: None,
+            command: command.to_string(),
+        };
+        let recent = Arc::new(vec![
+            workspace("swift-run", "code"),
+            workspace("api", "code"),
+            workspace("tools", "code-insiders"),
+        ]);
+        let find = |text| {
+            let provider = WorkspacesProvider {
+                recent: recent.clone(),
+            };
+            run(text, vec![Box::new(provider)])
+        };
+        let titles = |items: Vec<Suggestion>| -> Vec<String> {
+            items.iter().map(|i| i.title().to_string()).collect()
+        };
+        assert_eq!(titles(find("code sw")), ["swift-run"]);
+        assert_eq!(titles(find("Code.exe ru")), ["swift-run"]);
+        assert_eq!(find("code ").len(), 2);
+        assert_eq!(titles(find("code-insiders t")), ["tools"]);
+        assert!(find("code").is_empty());
+        assert!(find("code sw ").is_empty());
+        assert!(find("code sw x").is_empty());
+        assert!(find("notepad sw").is_empty());
+        let item = &find("code api")[0];
+        assert_eq!(item.text, "code \"C:\\src\\api\"");
+        assert_eq!(item.source, Source::Workspace);
+    }
+
+    #[test]
+    fn terminal_profiles_keep_the_alias() {
+        let profile = |name: &str, detail: &str| TerminalProfile {
+            name: name.to_string(),
+            detail: detail.to_string(),
+        };
+        let profiles = Arc::new(vec![
+            profile("Windows PowerShell", "powershell.exe"),
+            profile("Command Prompt", "cmd.exe"),
+            profile("Ubuntu 22.04", "CanonicalGroupLimited.Ubuntu22.04LTS"),
+            profile("Ubuntu", "Windows.Terminal.Wsl"),
+            profile("PowerShell", "Windows.Terminal.PowershellCore"),
+        ]);
+        let find = |text| {
+            let provider = TerminalProfilesProvider {
+                profiles: profiles.clone(),
+            };
+            run(text, vec![Box::new(provider)])
+        };
+        assert_eq!(
+            texts(&find("term ubu")),
+            ["term Ubuntu 22.04", "term Ubuntu"]
+        );
+        assert_eq!(
+            texts(&find("Terminal pow")),
+            ["Terminal PowerShell", "Terminal Windows PowerShell"]
+        );
+        assert_eq!(find("term ").len(), 5);
+        assert!(find("term").is_empty());
+        assert!(find("wt ubu").is_empty());
+        assert!(find("term ubuntu ~/src").is_empty());
+        let item = &find("term comm")[0];
+        assert_eq!(item.title(), "Command Prompt");
+        assert_eq!(item.detail, "cmd.exe");
+        assert_eq!(item.source, Source::TerminalProfile);
+    }
+
+    #[test]
+    fn ssh_hosts_keep_the_user() {
+        let host = |name: &str, detail: &str| SshHost {
+            name: name.to_string(),
+            detail: detail.to_string(),
+        };
+        let hosts = Arc::new(vec![
+            host("devbox", "me@10.0.0.5"),
+            host("db.prod.example.com", ""),
+            host("deploy", ""),
+        ]);
+        let find = |text| {
+            let provider = SshHostsProvider {
+                hosts: hosts.clone(),
+            };
+            run(text, vec![Box::new(provider)])
+        };
+        assert_eq!(texts(&find("ssh de")), ["ssh devbox", "ssh deploy"]);
+        assert_eq!(texts(&find("ssh root@dev")), ["ssh root@devbox"]);
+        assert_eq!(texts(&find("ssh prod")), ["ssh db.prod.example.com"]);
+        assert_eq!(find("ssh ").len(), 3);
+        assert!(find("ssh").is_empty());
+        assert!(find("ssh -p 22").is_empty());
+        assert!(find("ssh devbox ls").is_empty());
+        assert!(find("scp dev").is_empty());
+        let item = &find("ssh devb")[0];
+        assert_eq!(item.title(), "devbox");
+        assert_eq!(item.detail, "me@10.0.0.5");
+        assert_eq!(item.source, Source::SshHost);
+    }
+
+    #[test]
+    fn bookmarks_match_titles_sites_and_folders() {
+        let bookmark = |title: &str, url: &str, folder: &str, browser: &str| Bookmark {
+            title: title.to_string(),
+            url: url.to_string(),
+            folder: folder.to_string(),
+            browser: browser.to_string(),
+        };
+        let bookmarks = Arc::new(vec![
+            bookmark(
+                "Rust — docs",
+                "https://doc.rust-lang.org/std/",
+                "Bookmarks bar",
+                "Chrome",
+            ),
+            bookmark(
+                "https://crates.io/",
+                "https://crates.io/",
+                "Bookmarks bar / Work / Rust",
+                "Chrome",
+            ),
+            bookmark("Intranet", "HTTP://intranet/", "Other bookmarks", "Chrome"),
+            bookmark(
+                "GitHub home",
+                "https://www.github.com/",
+                "Bookmarks Toolbar",
+                "Firefox",
+            ),
+            bookmark(
+                "Rust Programming Language",
+                "https://www.rust-lang.org/",
+                "Bookmarks Toolbar / Dev",
+                "Firefox",
+            ),
+        ]);
+        let find = |text| {
+            let bookmarks = bookmarks.clone();
+            run(text, vec![Box::new(BookmarksProvider { bookmarks })])
+        };
+        assert_eq!(
+            texts(&find("rust")),
+            [
+                "https://doc.rust-lang.org/std/",
+                "https://www.rust-lang.org/",
+                "https://crates.io/",
+            ]
+        );
+        assert_eq!(texts(&find("git")), ["https://www.github.com/"]);
+        assert_eq!(texts(&find("work")), ["https://crates.io/"]);
+        assert_eq!(texts(&find("prog")), ["https://www.rust-lang.org/"]);
+        assert!(find("").is_empty());
+        assert!(find("zzz").is_empty());
+        let item = &find("intra")[0];
+        assert_eq!(item.title(), "Intranet");
+        assert_eq!(item.detail, "Chrome: Other bookmarks · HTTP://intranet/");
+        assert_eq!(item.source, Source::Bookmark);
+    }
+
+    #[test]
+    fn files_answer_the_f_prefix_only() {
+        let dir = crate::data::temp_dir::TempDir::new("providers_files");
+        dir.write("docs/Quarterly Report.pdf", "x");
+        let config = crate::data::file_index::FileIndexConfig {
+            roots: vec![dir.root()],
+            ..Default::default()
+        };
+        let index = Some(Arc::new(FileIndex::build(&config)));
+        let files = |text| {
+            let index = index.clone();
+            run(text, vec![Box::new(FileProvider { index })])
+        };
+        let items = files("f qrep");
+        assert_eq!(items.len(), 1);
+        assert_eq!(items[0].title(), "Quarterly Report.pdf");
+        assert_eq!(items[0].source, Source::File);
+        assert_eq!(std::path::PathBuf::from(&items[0].detail), dir.join("docs"));
+        assert!(files("qrep").is_empty());
+        assert!(files("f ").is_empty());
+        assert!(run("f qrep", vec![Box::new(FileProvider { index: None })]).is_empty());
+    }
+
+    #[test]
+    fn calculations_come_first() {
+        let items = run(
+            "6*7",
+            vec![Box::new(CalculatorProvider), history(&["6*7 > out.txt"])],
+        );
+        assert_eq!(texts(&items), ["42", "6*7 > out.txt"]);
+        assert_eq!(items[0].detail, "6*7 =");
+        assert_eq!(items[0].source, Source::Calculator);
+    }
+}
@@ -0,0 +1,439 @@
+#![allow(static_mut_refs)]
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::{Duration, Instant, SystemTime};
+
+use crate::data::aliases::*;
+use crate::data::ini::ConfigError;
+use crate::data::locale::*;
+use crate::data::settings::*;
+use crate::data::theme::*;
+
+// ==================================================================================
+//  CONFIG RELOAD PIPELINE
+//  The watcher thread only says "something changed". This module decides, after a
+//  debounce window, which files actually changed, re-parses them and produces a
+//  complete snapshot. A file that fails to parse keeps its last good value.
+// ==================================================================================
+
+pub static mut CONFIG_RELOADER: Option<ConfigReloader<SystemClock>> = None;
+
+pub trait Clock {
+    fn now(&self) -> Instant;
+}
+
+pub struct SystemClock;
+
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ConfigFile {
+    Settings,
+    Aliases,
+    Theme,
+    Locale,
+}
+
+impl ConfigFile {
+    // Settings come first: they decide which theme and locale files are read.
+    pub const ALL: [ConfigFile; 4] = [
+        ConfigFile::Settings,
+        ConfigFile::Aliases,
+        ConfigFile::Theme,
+        ConfigFile::Locale,
+    ];
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ConfigSnapshot {
+    pub settings: Settings,
+    pub aliases: Vec<Alias>,
+    pub theme: Theme,
+    pub locale: StringTable,
+}
+
+impl ConfigSnapshot {
+    /// The snapshot currently applied to the app.
+    pub fn current() -> Self {
+        unsafe {
+            ConfigSnapshot {
+                settings: settings().clone(),
+                aliases: ALIASES.as_deref().cloned().unwrap_or_default(),
+                theme: ACTIVE_THEME.clone().unwrap_or_default(),
+                locale: LOCALE.clone().unwrap_or_default(),
+            }
+        }
+    }
+
+    /// Swaps every global at once so the UI never observes a half-applied config.
+    pub fn apply(self) {
+        unsafe {
+            SETTINGS = Some(self.settings);
+            ALIASES = Some(Arc::new(self.aliases));
+            ACTIVE_THEME = Some(self.theme);
+            LOCALE = Some(self.locale);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+pub struct ReloadOutcome {
+    pub snapshot: ConfigSnapshot,
+    pub changed: Vec<ConfigFile>,
+    pub errors: Vec<ConfigError>,
+}
+
+type FileStamp = Option<(SystemTime, u64)>;
+
+// The theme file moves when `theme` changes in settings.ini, so the path is part of the key.
+type FileKey = (String, FileStamp);
+
+pub struct ConfigReloader<C: Clock> {
+    dir: PathBuf,
+    clock: C,
+    debounce: Duration,
+    stamps: HashMap<ConfigFile, FileKey>,
+    pending_since: Option<Instant>,
+    // Windows display languages in preference order, for `language = auto`.
+    ui_languages: Vec<String>,
+}
+
+impl<C: Clock> ConfigReloader<C> {
+    pub fn new(dir: PathBuf, clock: C, debounce: Duration, ui_languages: Vec<String>) -> Self {
+        ConfigReloader {
+            dir,
+            clock,
+            debounce,
+            stamps: HashMap::new(),
+            pending_since: None,
+            ui_languages,
+        }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Parses every file regardless of timestamps. Used once at startup.
+    pub fn load_all(&mut self) -> ReloadOutcome {
+        self.reload(&ConfigSnapshot::default(), true)
+    }
+
+    /// Records a change notification. Bursts of notifications (editors often write a
+    /// file several times per save) restart the debounce window.
+    pub fn notify(&mut self) {
+        self.pending_since = Some(self.clock.now());
+    }
+
+    pub fn is_pending(&self) -> bool {
+        self.pending_since.is_some()
+    }
+
+    /// Returns a new snapshot once the debounce window has elapsed and at least one
+    /// file changed or failed to parse; `None` otherwise.
+    pub fn poll(&mut self, current: &ConfigSnapshot) -> Option<ReloadOutcome> {
+        let since = self.pending_since?;
+        if self.clock.now().saturating_duration_since(since) < self.debounce {
+            return None;
+        }
+        self.pending_since = None;
+
+        let outcome = self.reload(current, false);
+        if outcome.changed.is_empty() && outcome.errors.is_empty() {
+            None
+        } else {
+            Some(outcome)
+        }
+    }
+
+    /// Path of `file` relative to the config directory.
+    pub fn relative_path(&self, file: ConfigFile, settings: &Settings) -> String {
+        match file {
+            ConfigFile::Settings => SETTINGS_FILE.to_string(),
+            ConfigFile::Aliases => ALIASES_FILE.to_string(),
+            ConfigFile::Theme => format!("{}/{}.ini", THEMES_DIR, settings.theme),
+            ConfigFile::Locale => {
+                let language = resolve_language(&settings.language, &self.ui_languages, |code| {
+                    bundled_locale(code).is_some() || self.dir.join(locale_path(code)).is_file()
+                });
+                locale_path(&language)
+            }
+        }
+    }
+
+    fn reload(&mut self, current: &ConfigSnapshot, force: bool) -> ReloadOutcome {
+        let mut outcome = ReloadOutcome {
+            snapshot: current.clone(),
+            ..Default::default()
+        };
+
+        for file in ConfigFile::ALL {
+            let relative = self.relative_path(file, &outcome.snapshot.settings);
+            let path = self.dir.join(&relative);
+            let stamp = file_stamp(&path);
+            let key = (relative.clone(), stamp);
+            if !force && self.stamps.get(&file) == Some(&key) {
+                continue;
+            }
+
+            // A deleted file falls back to defaults rather than keeping stale values.
+            // Bundled themes stand in for theme files that are not on disk.
+            let text = if stamp.is_none() {
+                match file {
+                    ConfigFile::Theme => match bundled_theme(&outcome.snapshot.settings.theme) {
+                        Some(text) => text.to_string(),
+                        None => {
+                            outcome.errors.push(ConfigError::new(
+                                &relative,
+                                0,
+                                format!("theme '{}' not found", outcome.snapshot.settings.theme),
+                            ));
+                            continue;
+                        }
+                    },
+                    ConfigFile::Locale => match bundled_locale(language_of(&relative)) {
+                        Some(text) => text.to_string(),
+                        None => {
+                            outcome.errors.push(ConfigError::new(
+                                &relative,
+                                0,
+                                format!("language '{}' not found", language_of(&relative)),
+                            ));
+                            continue;
+                        }
+                    },
+                    _ => String::new(),
+                }
+            } else {
+                match fs::read_to_string(&path) {
+                    Ok(text) => text,
+                    Err(e) => {
+                        outcome.errors.push(ConfigError::new(
+                            &relative,
+                            0,
+                            format!("cannot read file ({})", e),
+                        ));
+                        continue;
+                    }
+                }
+            };
+
+            let result = match file {
+                ConfigFile::Settings => {
+                    parse_settings(&text).map(|s| outcome.snapshot.settings = s)
+                }
+                ConfigFile::Aliases => parse_aliases(&text).map(|a| outcome.snapshot.aliases = a),
+                ConfigFile::Theme => {
+                    parse_theme(&relative, &text).map(|t| outcome.snapshot.theme = t)
+                }
+                ConfigFile::Locale => parse_locale(&relative, language_of(&relative), &text)
+                    .map(|l| outcome.snapshot.locale = l),
+            };
+            // Only a file that was read and parsed is stamped, so one that failed is
+            // tried again on the next reload.
+            match result {
+                Ok(()) => {
+                    self.stamps.insert(file, key);
+                    outcome.changed.push(file);
+                }
+                Err(e) => outcome.errors.push(e),
+            }
+        }
+
+        outcome
+    }
+}
+
+fn locale_path(language: &str) -> String {
+    format!("{}/{}.ini", LOCALES_DIR, language)
+}
+
+fn language_of(relative: &str) -> &str {
+    relative
+        .trim_start_matches(LOCALES_DIR)
+        .trim_start_matches('/')
+        .trim_end_matches(".ini")
+}
+
+fn file_stamp(path: &Path) -> FileStamp {
+    let meta = fs::metadata(path).ok()?;
+    Some((meta.modified().ok()?, meta.len()))
+}
+
+/// Loads the config directory into the globals and keeps the reloader around for
+/// `WM_APP_CONFIG_CHANGED`. Returns the errors of files that could not be applied.
+pub fn load_config(ui_languages: Vec<String>) -> Vec<ConfigError> {
+    let Some(dir) = get_config_dir() else {
+        return Vec::new();
+    };
+    let mut reloader = ConfigReloader::new(
+        dir,
+        SystemClock,
+        Duration::from_millis(crate::config::CONFIG_RELOAD_DEBOUNCE_MS),
+        ui_languages,
+    );
+    let outcome = reloader.load_all();
+    outcome.snapshot.apply();
+    unsafe {
+        CONFIG_RELOADER = Some(reloader);
+    }
+    outcome.errors
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::temp_dir::TempDir;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    const DEBOUNCE: Duration = Duration::from_millis(150);
+
+    struct FakeClock(Rc<Cell<Instant>>);
+
+    impl Clock for FakeClock {
+        fn now(&self) -> Instant {
+            self.0.get()
+        }
+    }
+
+    struct Fixture {
+        dir: TempDir,
+        time: Rc<Cell<Instant>>,
+        reloader: ConfigReloader<FakeClock>,
+    }
+
+    impl Fixture {
+        fn new(name: &str) -> Self {
+            let dir = TempDir::new(&format!("reload_{}", name));
+            let time = Rc::new(Cell::new(Instant::now()));
+            let reloader = ConfigReloader::new(
+                dir.path().to_path_buf(),
+                FakeClock(time.clone()),
+                DEBOUNCE,
+                vec!["de-AT".to_string()],
+            );
+            Fixture {
+                dir,
+                time,
+                reloader,
+            }
+        }
+
+        fn write(&self, file: &str, text: &str) {
+            self.dir.write(file, text);
+        }
+
+        fn advance(&self, ms: u64) {
+            self.time.set(self.time.get() + Duration::from_millis(ms));
+        }
+
+        /// Notifies a change and polls once the debounce window has passed.
+        fn reload(&mut self, current: &ConfigSnapshot) -> Option<ReloadOutcome> {
+            self.reloader.notify();
+            self.advance(200);
+            self.reloader.poll(current)
+        }
+    }
+
+    #[test]
+    fn empty_directory_loads_defaults() {
+        let mut fixture = Fixture::new("empty");
+        let outcome = fixture.reloader.load_all();
+        assert!(outcome.errors.is_empty(), "{:?}", outcome.errors);
+        assert_eq!(outcome.snapshot.settings, Settings::default());
+        assert_eq!(outcome.changed.len(), ConfigFile::ALL.len());
+    }
+
+    #[test]
+    fn poll_waits_for_the_debounce_window() {
+        let mut fixture = Fixture::new("debounce");
+        let current = fixture.reloader.load_all().snapshot;
+        fixture.write(SETTINGS_FILE, "[general]\nhistory_limit = 10\n");
+
+        fixture.reloader.notify();
+        assert!(fixture.reloader.poll(&current).is_none());
+        fixture.advance(100);
+        // Another notification restarts the window.
+        fixture.reloader.notify();
+        fixture.advance(100);
+        assert!(fixture.reloader.poll(&current).is_none());
+        assert!(fixture.reloader.is_pending());
+
+        fixture.advance(100);
+        let outcome = fixture.reloader.poll(&current).unwrap();
+        assert_eq!(outcome.changed, vec![ConfigFile::Settings]);
+        assert_eq!(outcome.snapshot.settings.history_limit, 10);
+        assert!(!fixture.reloader.is_pending());
+    }
+
+    #[test]
+    fn unchanged_files_are_not_reloaded() {
+        let mut fixture = Fixture::new("unchanged");
+        let current = fixture.reloader.load_all().snapshot;
+        assert!(fixture.reload(&current).is_none());
+    }
+
+    #[test]
+    fn bad_file_keeps_last_good_value() {
+        let mut fixture = Fixture::new("bad");
+        let current = fixture.reloader.load_all().snapshot;
+        fixture.write(SETTINGS_FILE, "[general]\nhistory_limit = 10\n");
+        let current = fixture.reload(&current).unwrap().snapshot;
+
+        fixture.write(SETTINGS_FILE, "[general]\nhistory_limit = x\n");
+        fixture.write(ALIASES_FILE, "gh = https://github.com\n");
+        let outcome = fixture.reload(&current).unwrap();
+        assert_eq!(outcome.snapshot.settings.history_limit, 10);
+        assert_eq!(outcome.changed, vec![ConfigFile::Aliases]);
+        assert_eq!(
+            outcome.errors[0].to_string(),
+            "settings.ini:2: 'history_limit' expects a number, found 'x'"
+        );
+        assert_eq!(outcome.snapshot.aliases[0].target, "https://github.com");
+    }
+
+    #[test]
+    fn failed_file_is_retried() {
+        let mut fixture = Fixture::new("retry");
+        let current = fixture.reloader.load_all().snapshot;
+        fixture.write(SETTINGS_FILE, "[general]\nhistory_limit = x\n");
+        assert_eq!(fixture.reload(&current).unwrap().errors.len(), 1);
+        // Still reported while the file stays broken.
+        assert_eq!(fixture.reload(&current).unwrap().errors.len(), 1);
+
+        fixture.write(SETTINGS_FILE, "[general]\nhistory_limit = 12\n");
+        let outcome = fixture.reload(&current).unwrap();
+        assert!(outcome.errors.is_empty(), "{:?}", outcome.errors);
+        assert_eq!(outcome.snapshot.settings.history_limit, 12);
+    }
+
+    #[test]
+    fn missing_theme_is_an_error() {
+        let mut fixture = Fixture::new("theme");
+        let current = fixture.reloader.load_all().snapshot;
+        fixture.write(SETTINGS_FILE, "[appearance]\ntheme = nope\n");
+        let outcome = fixture.reload(&current).unwrap();
+        assert_eq!(outcome.snapshot.settings.theme, "nope");
+        assert_eq!(
+            outcome.errors[0].to_string(),
+            "themes/nope.ini: theme 'nope' not found"
+        );
+    }
+
+    #[test]
+    fn auto_language_follows_windows() {
+        let fixture = Fixture::new("language");
+        let path = fixture
+            .reloader
+            .relative_path(ConfigFile::Locale, &Settings::default());
+        assert_eq!(path, "locales/de.ini");
+    }
+}
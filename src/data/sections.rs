@@ -0,0 +1,317 @@
+use crate::config::*;
+use crate::data::locale::Msg;
+use crate::data::suggest::{Source, Suggestion};
+
+// ==================================================================================
+//  SECTIONS
+//  The dropdown groups merged suggestions by source under a header each. The best
+//  ranked one leads on its own as the best match; every other section keeps the
+//  merge order and shows at most its limit, so one busy source cannot push the
+//  others out of view. Items are stored in display order, so stepping through them
+//  with Up/Down follows the rows.
+// ==================================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Section {
+    BestMatch,
+    History,
+    Commands,
+    Aliases,
+    Apps,
+    Calculator,
+    Files,
+    Repos,
+    Workspaces,
+    TerminalProfiles,
+    SshHosts,
+    Bookmarks,
+    Settings,
+}
+
+impl Section {
+    /// Sections after the best match, top to bottom.
+    /// Files, workspaces, terminal profiles and ssh hosts only answer `f <query>`,
+    /// `code <name>`, `term <profile>` and `ssh <host>`, so they lead when they show.
+    pub const ORDER: [Section; 12] = [
+        Section::Files,
+        Section::Workspaces,
+        Section::TerminalProfiles,
+        Section::SshHosts,
+        Section::History,
+        Section::Commands,
+        Section::Aliases,
+        Section::Repos,
+        Section::Apps,
+        Section::Settings,
+        Section::Bookmarks,
+        Section::Calculator,
+    ];
+
+    pub fn of(source: Source) -> Section {
+        match source {
+            Source::History => Section::History,
+            Source::Completion => Section::Commands,
+            Source::Alias => Section::Aliases,
+            Source::App => Section::Apps,
+            Source::Calculator => Section::Calculator,
+            Source::File => Section::Files,
+            Source::Repo => Section::Repos,
+            Source::Workspace => Section::Workspaces,
+            Source::TerminalProfile => Section::TerminalProfiles,
+            Source::SshHost => Section::SshHosts,
+            Source::Bookmark => Section::Bookmarks,
+            Source::Setting => Section::Settings,
+        }
+    }
+
+    pub fn title(self) -> Msg {
+        match self {
+            Section::BestMatch => Msg::SectionBestMatch,
+            Section::History => Msg::SectionHistory,
+            Section::Commands => Msg::SectionCommands,
+            Section::Aliases => Msg::SectionAliases,
+            Section::Apps => Msg::SectionApps,
+            Section::Calculator => Msg::SectionCalculator,
+            Section::Files => Msg::SectionFiles,
+            Section::Repos => Msg::SectionRepos,
+            Section::Workspaces => Msg::SectionWorkspaces,
+            Section::TerminalProfiles => Msg::SectionTerminalProfiles,
+            Section::SshHosts => Msg::SectionSshHosts,
+            Section::Bookmarks => Msg::SectionBookmarks,
+            Section::Settings => Msg::SectionSettings,
+        }
+    }
+
+    pub fn limit(self) -> usize {
+        match self {
+            Section::BestMatch => 1,
+            Section::History => SECTION_HISTORY_LIMIT,
+            Section::Commands => SECTION_COMMANDS_LIMIT,
+            Section::Aliases => SECTION_ALIASES_LIMIT,
+            Section::Apps => SECTION_APPS_LIMIT,
+            Section::Calculator => SECTION_CALCULATOR_LIMIT,
+            Section::Files => SECTION_FILES_LIMIT,
+            Section::Repos => SECTION_REPOS_LIMIT,
+            Section::Workspaces => SECTION_WORKSPACES_LIMIT,
+            Section::TerminalProfiles => SECTION_TERMINAL_LIMIT,
+            Section::SshHosts => SECTION_SSH_LIMIT,
+            Section::Bookmarks => SECTION_BOOKMARKS_LIMIT,
+            Section::Settings => SECTION_SETTINGS_LIMIT,
+        }
+    }
+}
+
+/// Segoe MDL2 Assets glyph drawn before an entry from `source`.
+pub fn source_glyph(source: Source) -> char {
+    match source {
+        Source::History => '\u{E81C}',
+        Source::Completion => '\u{E756}',
+        Source::Alias => '\u{E71B}',
+        Source::App => '\u{ECAA}',
+        Source::Calculator => '\u{E8EF}',
+        Source::File => '\u{E8A5}',
+        Source::Repo => '\u{E943}',
+        Source::Workspace => '\u{E838}',
+        Source::TerminalProfile => '\u{E8A7}',
+        Source::SshHost => '\u{E968}',
+        Source::Bookmark => '\u{E728}',
+        Source::Setting => '\u{E713}',
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Row {
+    Header(Section),
+    /// Index into `Groups::items`.
+    Item(usize),
+}
+
+/// What the dropdown shows: the entries in display order and the rows that lay
+/// them out.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Groups {
+    pub items: Vec<Suggestion>,
+    pub rows: Vec<Row>,
+}
+
+impl Groups {
+    /// Merged suggestions (best first) under section headers.
+    pub fn grouped(merged: Vec<Suggestion>) -> Groups {
+        let mut groups = Groups::default();
+        let mut rest = merged.into_iter();
+        let Some(best) = rest.next() else {
+            return groups;
+        };
+        groups.push_section(Section::BestMatch, vec![best]);
+
+        let rest: Vec<Suggestion> = rest.collect();
+        for section in Section::ORDER {
+            let items = rest
+                .iter()
+                .filter(|item| Section::of(item.source) == section)
+                .take(section.limit())
+                .cloned()
+                .collect();
+            groups.push_section(section, items);
+        }
+        groups
+    }
+
+    /// Entries as one list without headers or limits, e.g. the whole history.
+    pub fn flat(items: Vec<Suggestion>) -> Groups {
+        Groups {
+            rows: (0..items.len()).map(Row::Item).collect(),
+            items,
+        }
+    }
+
+    fn push_section(&mut self, section: Section, items: Vec<Suggestion>) {
+        if items.is_empty() {
+            return;
+        }
+        self.rows.push(Row::Header(section));
+        for item in items {
+            self.rows.push(Row::Item(self.items.len()));
+            self.items.push(item);
+        }
+    }
+
+    /// The entry row `row` shows, if it is not a header.
+    pub fn item_at(&self, row: usize) -> Option<&Suggestion> {
+        match self.rows.get(row)? {
+            Row::Item(index) => self.items.get(*index),
+            Row::Header(_) => None,
+        }
+    }
+
+    /// Row of the first entry, below its header.
+    pub fn first_item_row(&self) -> Option<usize> {
+        self.rows.iter().position(|row| matches!(row, Row::Item(_)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(text: &str, source: Source, score: f32) -> Suggestion {
+        Suggestion {
+            text: text.into(),
+            name: String::new(),
+            detail: String::new(),
+            source,
+            score,
+        }
+    }
+
+    #[test]
+    fn best_match_leads_then_sections_in_order() {
+        let groups = Groups::grouped(vec![
+            item("4", Source::Calculator, 1.0),
+            item("git status", Source::History, 0.8),
+            item("ms-settings:display", Source::Setting, 0.7),
+            item("notes", Source::Alias, 0.7),
+            item("git stash", Source::Completion, 0.6),
+            item("git show", Source::History, 0.4),
+        ]);
+        let texts: Vec<&str> = groups.items.iter().map(|i| i.text.as_str()).collect();
+        assert_eq!(
+            texts,
+            [
+                "4",
+                "git status",
+                "git show",
+                "git stash",
+                "notes",
+                "ms-settings:display"
+            ]
+        );
+        assert_eq!(
+            groups.rows,
+            [
+                Row::Header(Section::BestMatch),
+                Row::Item(0),
+                Row::Header(Section::History),
+                Row::Item(1),
+                Row::Item(2),
+                Row::Header(Section::Commands),
+                Row::Item(3),
+                Row::Header(Section::Aliases),
+                Row::Item(4),
+                Row::Header(Section::Settings),
+                Row::Item(5),
+            ]
+        );
+    }
+
+    #[test]
+    fn rows_map_back_to_items() {
+        let groups = Groups::grouped(vec![
+            item("a", Source::History, 0.8),
+            item("b", Source::History, 0.5),
+        ]);
+        assert_eq!(groups.item_at(0), None);
+        assert_eq!(groups.item_at(1).unwrap().text, "a");
+        assert_eq!(groups.item_at(3).unwrap().text, "b");
+        assert_eq!(groups.item_at(99), None);
+        assert_eq!(groups.first_item_row(), Some(1));
+    }
+
+    #[test]
+    fn sections_keep_their_limit() {
+        let merged: Vec<Suggestion> = (0..20)
+            .map(|i| item(&format!("cmd{}", i), Source::History, 0.8))
+            .chain((0..20).map(|i| item(&format!("app{}", i), Source::App, 0.5)))
+            .collect();
+        let groups = Groups::grouped(merged);
+        let count = |source| groups.items.iter().filter(|i| i.source == source).count();
+        // The best match does not count against its section.
+        assert_eq!(count(Source::History), 1 + SECTION_HISTORY_LIMIT);
+        assert_eq!(count(Source::App), SECTION_APPS_LIMIT);
+        assert_eq!(groups.items[0].text, "cmd0");
+        assert_eq!(groups.items[1].text, "cmd1");
+    }
+
+    #[test]
+    fn empty_and_flat_lists() {
+        assert_eq!(Groups::grouped(Vec::new()), Groups::default());
+        assert_eq!(Groups::default().first_item_row(), None);
+        let flat = Groups::flat(vec![
+            item("a", Source::History, 0.0),
+            item("b", Source::History, 0.0),
+        ]);
+        assert_eq!(flat.rows, [Row::Item(0), Row::Item(1)]);
+        assert_eq!(flat.first_item_row(), Some(0));
+    }
+
+    #[test]
+    fn every_source_has_a_section_and_its_own_glyph() {
+        let sources = [
+            Source::History,
+            Source::Completion,
+            Source::Alias,
+            Source::App,
+            Source::Calculator,
+            Source::File,
+            Source::Repo,
+            Source::Workspace,
+            Source::TerminalProfile,
+            Source::SshHost,
+            Source::Bookmark,
+            Source::Setting,
+        ];
+        let glyphs = sources.map(source_glyph);
+        for (i, source) in sources.iter().enumerate() {
+            assert!(
+                Section::ORDER.contains(&Section::of(*source)),
+                "{:?}",
+                source
+            );
+            assert!(('\u{E000}'..='\u{F8FF}').contains(&glyphs[i]));
+            assert!(!glyphs[..i].contains(&glyphs[i]), "{:?}", source);
+        }
+        assert!(!Section::ORDER.contains(&Section::BestMatch));
+        assert_eq!(Section::BestMatch.title(), Msg::SectionBestMatch);
+        assert_eq!(Section::Settings.title(), Msg::SectionSettings);
+    }
+}
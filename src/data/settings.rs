@@ -0,0 +1,368 @@
+#![allow(static_mut_refs)]
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::data::double_tap::TapKey;
+use crate::data::file_index::{FileIndexConfig, split_list};
+use crate::data::git_repos::{GitReposConfig, is_valid_open_action};
+use crate::data::hotkey::*;
+use crate::data::ini::*;
+use crate::data::keymap::*;
+use crate::data::locale::*;
+use crate::data::theme::*;
+
+pub const SETTINGS_FILE: &str = "settings.ini";
+
+pub static mut SETTINGS: Option<Settings> = None;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Settings {
+    pub history_limit: usize,
+    pub tooltip_timeout_ms: u32,
+    pub theme: String,
+    /// Dropdown rows get a second line with when and how often a command ran.
+    pub detailed_rows: bool,
+    pub language: String,
+    /// Suggestions match `cafe` against `café`.
+    pub ignore_accents: bool,
+    pub hotkeys: Vec<HotkeyBinding>,
+    pub double_tap: Option<TapKey>,
+    pub double_tap_ms: u32,
+    pub keymap: Keymap,
+    pub files: FileIndexConfig,
+    pub git: GitReposConfig,
+    /// Program `ssh` sessions are opened in; `none` runs ssh directly.
+    pub ssh_terminal: String,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            history_limit: 50,
+            tooltip_timeout_ms: 8000,
+            theme: DEFAULT_THEME.to_string(),
+            detailed_rows: false,
+            language: AUTO_LANGUAGE.to_string(),
+            ignore_accents: false,
+            hotkeys: default_hotkeys(),
+            double_tap: None,
+            double_tap_ms: 300,
+            keymap: Keymap::default(),
+            files: FileIndexConfig::default(),
+            git: GitReposConfig::default(),
+            ssh_terminal: "wt".to_string(),
+        }
+    }
+}
+
+/// Active settings, falling back to defaults until the config directory has been loaded.
+pub fn settings() -> &'static Settings {
+    static DEFAULT: OnceLock<Settings> = OnceLock::new();
+    unsafe { SETTINGS.as_ref() }.unwrap_or_else(|| DEFAULT.get_or_init(Settings::default))
+}
+
+/// `%LOCALAPPDATA%\SwiftRun`, home of the history file and all user-editable config files.
+pub fn get_config_dir() -> Option<PathBuf> {
+    let local_app_data = std::env::var("LOCALAPPDATA").ok()?;
+    let mut path = PathBuf::from(local_app_data);
+    path.push("SwiftRun");
+    if !path.exists() {
+        let _ = fs::create_dir(&path);
+    }
+    Some(path)
+}
+
+pub fn parse_settings(text: &str) -> Result<Settings, ConfigError> {
+    let doc = parse_ini(SETTINGS_FILE, text)?;
+    let mut settings = Settings::default();
+    let mut seen_hotkeys = false;
+
+    for section in &doc.sections {
+        match section.name.to_ascii_lowercase().as_str() {
+            "general" => {
+                for entry in &section.entries {
+                    match entry.key.to_ascii_lowercase().as_str() {
+                        "history_limit" => {
+                            settings.history_limit = parse_number(SETTINGS_FILE, entry)?;
+                            if settings.history_limit == 0 {
+                                return Err(ConfigError::new(
+                                    SETTINGS_FILE,
+                                    entry.line,
+                                    "'history_limit' must be at least 1",
+                                ));
+                            }
+                        }
+                        "tooltip_timeout_ms" => {
+                            settings.tooltip_timeout_ms = parse_number(SETTINGS_FILE, entry)?;
+                        }
+                        "language" => {
+                            if !is_valid_language(&entry.value) {
+                                return Err(ConfigError::new(
+                                    SETTINGS_FILE,
+                                    entry.line,
+                                    format!("'{}' is not a valid language code", entry.value),
+                                ));
+                            }
+                            settings.language = entry.value.to_ascii_lowercase();
+                        }
+                        "ignore_accents" => {
+                            settings.ignore_accents = parse_bool(SETTINGS_FILE, entry)?;
+                        }
+                        _ => return Err(unknown_key(entry)),
+                    }
+                }
+            }
+            "appearance" => {
+                for entry in &section.entries {
+                    match entry.key.to_ascii_lowercase().as_str() {
+                        "theme" => {
+                            if !is_valid_theme_name(&entry.value) {
+                                return Err(ConfigError::new(
+                                    SETTINGS_FILE,
+                                    entry.line,
+                                    format!("'{}' is not a valid theme name", entry.value),
+                                ));
+                            }
+                            settings.theme = entry.value.clone();
+                        }
+                        "detailed_rows" => {
+                            settings.detailed_rows = parse_bool(SETTINGS_FILE, entry)?;
+                        }
+                        _ => return Err(unknown_key(entry)),
+                    }
+                }
+            }
+            "files" => {
+                for entry in &section.entries {
+                    match entry.key.to_ascii_lowercase().as_str() {
+                        "roots" => settings.files.roots = split_list(&entry.value),
+                        "include" => settings.files.include = split_list(&entry.value),
+                        "exclude" => settings.files.exclude = split_list(&entry.value),
+                        "depth" => settings.files.depth = parse_number(SETTINGS_FILE, entry)?,
+                        "rescan_minutes" => {
+                            settings.files.rescan_minutes = parse_number(SETTINGS_FILE, entry)?;
+                        }
+                        _ => return Err(unknown_key(entry)),
+                    }
+                }
+            }
+            "git" => {
+                for entry in &section.entries {
+                    match entry.key.to_ascii_lowercase().as_str() {
+                        "roots" => settings.git.roots = split_list(&entry.value),
+                        "depth" => settings.git.depth = parse_number(SETTINGS_FILE, entry)?,
+                        "open" => {
+                            if !is_valid_open_action(&entry.value) {
+                                return Err(ConfigError::new(
+                                    SETTINGS_FILE,
+                                    entry.line,
+                                    format!(
+                                        "'{}' is not an open action (editor, terminal, explorer or a command with {{path}})",
+                                        entry.value
+                                    ),
+                                ));
+                            }
+                            settings.git.open = entry.value.clone();
+                        }
+                        _ => return Err(unknown_key(entry)),
+                    }
+                }
+            }
+            "ssh" => {
+                for entry in &section.entries {
+                    match entry.key.to_ascii_lowercase().as_str() {
+                        "terminal" => {
+                            if entry.value.is_empty() {
+                                return Err(ConfigError::new(
+                                    SETTINGS_FILE,
+                                    entry.line,
+                                    "terminal needs a program, or none".to_string(),
+                                ));
+                            }
+                            settings.ssh_terminal = entry.value.clone();
+                        }
+                        _ => return Err(unknown_key(entry)),
+                    }
+                }
+            }
+            "hotkeys" => {
+                // A [hotkeys] section replaces the default Win+R binding.
+                if !seen_hotkeys {
+                    settings.hotkeys.clear();
+                    seen_hotkeys = true;
+                }
+                for entry in &section.entries {
+                    let Some(action) = HotkeyAction::from_key(&entry.key) else {
+                        return Err(ConfigError::new(
+                            SETTINGS_FILE,
+                            entry.line,
+                            format!("unknown hotkey action '{}'", entry.key),
+                        ));
+                    };
+                    let hotkeys = parse_hotkey_list(&entry.value)
+                        .map_err(|message| ConfigError::new(SETTINGS_FILE, entry.line, message))?;
+                    for (hotkey, fallback) in hotkeys {
+                        if fallback == Some(hotkey) {
+                            return Err(ConfigError::new(
+                                SETTINGS_FILE,
+                                entry.line,
+                                format!("{} cannot be its own fallback", hotkey),
+                            ));
+                        }
+                        for chord in std::iter::once(hotkey).chain(fallback) {
+                            if let Some(existing) = settings
+                                .hotkeys
+                                .iter()
+                                .find(|b| b.hotkey == chord || b.fallback == Some(chord))
+                            {
+                                return Err(ConfigError::new(
+                                    SETTINGS_FILE,
+                                    entry.line,
+                                    format!(
+                                        "{} is already bound to '{}'",
+                                        chord,
+                                        existing.action.key()
+                                    ),
+                                ));
+                            }
+                        }
+                        settings.hotkeys.push(HotkeyBinding {
+                            action,
+                            hotkey,
+                            fallback,
+                        });
+                    }
+                }
+            }
+            "keys" => {
+                // Each entry replaces the default chords of its action only.
+                for entry in &section.entries {
+                    let Some(action) = KeyAction::from_key(&entry.key) else {
+                        return Err(ConfigError::new(
+                            SETTINGS_FILE,
+                            entry.line,
+                            format!("unknown key action '{}'", entry.key),
+                        ));
+                    };
+                    parse_key_list(&entry.value)
+                        .and_then(|chords| settings.keymap.bind(action, &chords))
+                        .map_err(|message| ConfigError::new(SETTINGS_FILE, entry.line, message))?;
+                }
+            }
+            "double_tap" => {
+                for entry in &section.entries {
+                    match entry.key.to_ascii_lowercase().as_str() {
+                        "key" => {
+                            settings.double_tap = if entry.value.eq_ignore_ascii_case("none") {
+                                None
+                            } else {
+                                Some(TapKey::parse(&entry.value).ok_or_else(|| {
+                                    ConfigError::new(
+                                        SETTINGS_FILE,
+                                        entry.line,
+                                        format!(
+                                            "'{}' is not a double-tap key (ctrl, alt, shift or none)",
+                                            entry.value
+                                        ),
+                                    )
+                                })?)
+                            };
+                        }
+                        "interval_ms" => {
+                            settings.double_tap_ms = parse_number(SETTINGS_FILE, entry)?;
+                            if !(100..=1000).contains(&settings.double_tap_ms) {
+                                return Err(ConfigError::new(
+                                    SETTINGS_FILE,
+                                    entry.line,
+                                    "'interval_ms' must be between 100 and 1000",
+                                ));
+                            }
+                        }
+                        _ => return Err(unknown_key(entry)),
+                    }
+                }
+            }
+            "" => {
+                if let Some(entry) = section.entries.first() {
+                    return Err(ConfigError::new(
+                        SETTINGS_FILE,
+                        entry.line,
+                        format!("'{}' must be inside a [section]", entry.key),
+                    ));
+                }
+            }
+            _ => {
+                return Err(ConfigError::new(
+                    SETTINGS_FILE,
+                    section.line,
+                    format!("unknown section [{}]", section.name),
+                ));
+            }
+        }
+    }
+
+    Ok(settings)
+}
+
+fn unknown_key(entry: &IniEntry) -> ConfigError {
+    ConfigError::new(
+        SETTINGS_FILE,
+        entry.line,
+        format!("unknown setting '{}'", entry.key),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detailed_rows_are_opt_in() {
+        assert!(!parse_settings("").unwrap().detailed_rows);
+        let settings = parse_settings("[appearance]\ndetailed_rows = true\n").unwrap();
+        assert!(settings.detailed_rows);
+        assert!(parse_settings("[appearance]\ndetailed_rows = maybe\n").is_err());
+    }
+
+    #[test]
+    fn files_section_lists_roots_and_globs() {
+        let text = "[files]\n\
+            roots = C:\\Docs; %USERPROFILE%\\Projects\n\
+            include = *.pdf\n\
+            exclude = bin; obj\n\
+            depth = 3\n\
+            rescan_minutes = 0\n";
+        let files = parse_settings(text).unwrap().files;
+        assert_eq!(files.roots, ["C:\\Docs", "%USERPROFILE%\\Projects"]);
+        assert_eq!(files.include, ["*.pdf"]);
+        assert_eq!(files.exclude, ["bin", "obj"]);
+        assert_eq!((files.depth, files.rescan_minutes), (3, 0));
+        assert!(files.is_enabled());
+        assert!(!parse_settings("").unwrap().files.is_enabled());
+        assert!(parse_settings("[files]\ndepth = deep\n").is_err());
+        assert!(parse_settings("[files]\nfoo = 1\n").is_err());
+    }
+
+    #[test]
+    fn git_section_checks_the_open_action() {
+        let text = "[git]\nroots = C:\\src; D:\\work\ndepth = 2\nopen = code -n \"{path}\"\n";
+        let git = parse_settings(text).unwrap().git;
+        assert_eq!(git.roots, ["C:\\src", "D:\\work"]);
+        assert_eq!(git.depth, 2);
+        assert_eq!(git.open, "code -n \"{path}\"");
+        let err = parse_settings("[git]\nopen = vim\n").unwrap_err();
+        assert!(err.message.contains("open action"));
+        assert_eq!(parse_settings("").unwrap().git.open, "editor");
+    }
+
+    #[test]
+    fn ssh_section_sets_the_terminal() {
+        assert_eq!(parse_settings("").unwrap().ssh_terminal, "wt");
+        let text = "[ssh]\nterminal = none\n";
+        assert_eq!(parse_settings(text).unwrap().ssh_terminal, "none");
+        assert!(parse_settings("[ssh]\nterminal =\n").is_err());
+        assert!(parse_settings("[ssh]\nshell = x\n").is_err());
+    }
+}
@@ -0,0 +1,722 @@
+use std::collections::{BTreeSet, HashMap};
+use std::fmt;
+use std::ops::Bound;
+
+// ==================================================================================
+//  SQLITE
+//  Just enough of the SQLite file format to read tables, or rows of them by rowid,
+//  out of another program's database (Firefox's `places.sqlite`): the header, table
+//  b-trees with overflow pages, records, and the schema in `sqlite_master`. Pages
+//  committed to the `-wal` file but not yet checkpointed replace their copies in
+//  the main file. The bytes may be read while the owner is writing, so every
+//  offset is checked and a damaged file is an error rather than a panic.
+// ==================================================================================
+
+const HEADER: &[u8] = b"SQLite format 3\0";
+const WAL_MAGIC: u32 = 0x377F_0682;
+const WAL_HEADER_SIZE: usize = 32;
+const WAL_FRAME_HEADER_SIZE: usize = 24;
+/// Table b-trees deeper than this are taken to be damaged.
+const MAX_TREE_DEPTH: usize = 64;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl Value {
+    pub fn as_i64(&self) -> Option<i64> {
+        match self {
+            Value::Integer(i) => Some(*i),
+            _ => None,
+        }
+    }
+
+    pub fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::Text(s) => Some(s),
+            _ => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SqliteError {
+    /// Page where reading stopped; 0 for the file header.
+    pub page: u32,
+    pub message: String,
+}
+
+impl fmt::Display for SqliteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "page {}: {}", self.page, self.message)
+    }
+}
+
+fn error(page: u32, message: &str) -> SqliteError {
+    SqliteError {
+        page,
+        message: message.to_string(),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Encoding {
+    Utf8,
+    Utf16Le,
+    Utf16Be,
+}
+
+pub struct Database<'a> {
+    main: &'a [u8],
+    /// Newest committed copy of each page in the write-ahead log.
+    wal: HashMap<u32, &'a [u8]>,
+    page_size: usize,
+    /// Page size less the bytes extensions reserve at the end of each page.
+    usable: usize,
+    page_count: u32,
+    encoding: Encoding,
+}
+
+impl<'a> Database<'a> {
+    /// Reads the header of `main`, and the committed frames of `wal` when given.
+    pub fn open(main: &'a [u8], wal: Option<&'a [u8]>) -> Result<Database<'a>, SqliteError> {
+        if main.len() < 100 || !main.starts_with(HEADER) {
+            return Err(error(0, "not an SQLite database"));
+        }
+        let page_size = match u16::from_be_bytes([main[16], main[17]]) {
+            1 => 65536,
+            size => size as usize,
+        };
+        if !page_size.is_power_of_two() || !(512..=65536).contains(&page_size) {
+            return Err(error(0, "invalid page size"));
+        }
+        let usable = page_size - main[20] as usize;
+        if usable < 480 {
+            return Err(error(0, "invalid reserved space"));
+        }
+        let encoding = match be_u32(main, 56) {
+            2 => Encoding::Utf16Le,
+            3 => Encoding::Utf16Be,
+            _ => Encoding::Utf8,
+        };
+        let mut page_count = (main.len() / page_size) as u32;
+        let (wal, wal_pages) = match wal {
+            Some(bytes) => read_wal(bytes, page_size),
+            None => (HashMap::new(), None),
+        };
+        if let Some(count) = wal_pages {
+            page_count = count;
+        }
+        Ok(Database {
+            main,
+            wal,
+            page_size,
+            usable,
+            page_count,
+            encoding,
+        })
+    }
+
+    fn page(&self, number: u32) -> Result<&'a [u8], SqliteError> {
+        if number == 0 || number > self.page_count {
+            return Err(error(number, "page out of range"));
+        }
+        if let Some(page) = self.wal.get(&number) {
+            return Ok(page);
+        }
+        let start = (number as usize - 1) * self.page_size;
+        self.main
+            .get(start..start + self.page_size)
+            .ok_or_else(|| error(number, "page past the end of the file"))
+    }
+
+    /// The `columns` of every row of table `name`, in rowid order. A column missing
+    /// from an older row (added later by `ALTER TABLE`) reads as `Null`. Values come
+    /// back as stored: a whole number in a REAL column is an `Integer`.
+    pub fn read_table(&self, name: &str, columns: &[&str]) -> Result<Vec<Vec<Value>>, SqliteError> {
+        self.read_selected(name, columns, None)
+    }
+
+    /// Like `read_table`, for the rows whose rowid is in `rowids` only; b-tree pages
+    /// that cannot hold any of them are not read.
+    pub fn read_table_rows(
+        &self,
+        name: &str,
+        columns: &[&str],
+        rowids: &BTreeSet<i64>,
+    ) -> Result<Vec<Vec<Value>>, SqliteError> {
+        self.read_selected(name, columns, Some(rowids))
+    }
+
+    fn read_selected(
+        &self,
+        name: &str,
+        columns: &[&str],
+        rowids: Option<&BTreeSet<i64>>,
+    ) -> Result<Vec<Vec<Value>>, SqliteError> {
+        let schema = self.read_rows(1, &[0, 1, 3, 4], None)?;
+        let table = schema.iter().find(|(_, row)| {
+            row[0].as_str() == Some("table")
+                && row[1]
+                    .as_str()
+                    .is_some_and(|n| n.eq_ignore_ascii_case(name))
+        });
+        let Some((_, row)) = table else {
+            return Err(error(1, &format!("no table '{}'", name)));
+        };
+        let root = row[2]
+            .as_i64()
+            .and_then(|r| u32::try_from(r).ok())
+            .ok_or_else(|| error(1, "invalid root page"))?;
+        let definitions = column_definitions(row[3].as_str().unwrap_or_default());
+
+        let mut indexes = Vec::new();
+        let mut rowid_column = None;
+        for (i, column) in columns.iter().enumerate() {
+            let Some(index) = definitions
+                .iter()
+                .position(|(n, _)| n.eq_ignore_ascii_case(column))
+            else {
+                return Err(error(
+                    root,
+                    &format!("no column '{}' in '{}'", column, name),
+                ));
+            };
+            // An INTEGER PRIMARY KEY column is stored as the rowid, with NULL in the
+            // record.
+            if definitions[index].1 {
+                rowid_column = Some(i);
+            }
+            indexes.push(index);
+        }
+
+        let mut rows = self.read_rows(root, &indexes, rowids)?;
+        if let Some(i) = rowid_column {
+            for (rowid, row) in &mut rows {
+                row[i] = Value::Integer(*rowid);
+            }
+        }
+        Ok(rows.into_iter().map(|(_, row)| row).collect())
+    }
+
+    /// Rowid and the values at `indexes` of each row in the table b-tree at `root`,
+    /// or of those with a rowid in `rowids`.
+    fn read_rows(
+        &self,
+        root: u32,
+        indexes: &[usize],
+        rowids: Option<&BTreeSet<i64>>,
+    ) -> Result<Vec<(i64, Vec<Value>)>, SqliteError> {
+        let mut rows = Vec::new();
+        // Pages are visited depth first; more visits than pages means a cycle.
+        let mut stack = vec![(root, 0)];
+        let mut visited = 0u32;
+        while let Some((number, depth)) = stack.pop() {
+            visited += 1;
+            if depth > MAX_TREE_DEPTH || visited > self.page_count {
+                return Err(error(number, "b-tree loops"));
+            }
+            let page = self.page(number)?;
+            let header = if number == 1 { 100 } else { 0 };
+            let kind = *page
+                .get(header)
+                .ok_or_else(|| error(number, "short page"))?;
+            let cells = be_u16(page, header + 3) as usize;
+            match kind {
+                // Interior: children left to right, then the right-most one. Each
+                // cell's child holds the rowids above the previous cell's key up to
+                // its own; the right-most child those above the last key.
+                0x05 => {
+                    let mut children = Vec::with_capacity(cells + 1);
+                    let mut low = Bound::Unbounded;
+                    for i in 0..cells {
+                        let cell = be_u16(page, header + 12 + i * 2) as usize;
+                        let Some(rowids) = rowids else {
+                            children.push(be_u32(page, cell));
+                            continue;
+                        };
+                        let (key, _) =
+                            varint(page, cell + 4).ok_or_else(|| error(number, "damaged cell"))?;
+                        let key = key as i64;
+                        let mut wanted = rowids.range((low, Bound::Unbounded));
+                        if wanted.next().is_some_and(|&rowid| rowid <= key) {
+                            children.push(be_u32(page, cell));
+                        }
+                        low = Bound::Excluded(key);
+                    }
+                    if rowids.is_none_or(|r| r.range((low, Bound::Unbounded)).next().is_some()) {
+                        children.push(be_u32(page, header + 8));
+                    }
+                    for child in children.into_iter().rev() {
+                        stack.push((child, depth + 1));
+                    }
+                }
+                0x0D => {
+                    for i in 0..cells {
+                        let cell = be_u16(page, header + 8 + i * 2) as usize;
+                        if let Some(row) = self.read_cell(number, page, cell, indexes, rowids)? {
+                            rows.push(row);
+                        }
+                    }
+                }
+                _ => return Err(error(number, "not a table b-tree page")),
+            }
+        }
+        Ok(rows)
+    }
+
+    fn read_cell(
+        &self,
+        number: u32,
+        page: &[u8],
+        cell: usize,
+        indexes: &[usize],
+        rowids: Option<&BTreeSet<i64>>,
+    ) -> Result<Option<(i64, Vec<Value>)>, SqliteError> {
+        let damaged = || error(number, "damaged cell");
+        let (size, used) = varint(page, cell).ok_or_else(damaged)?;
+        let (rowid, used2) = varint(page, cell + used).ok_or_else(damaged)?;
+        if rowids.is_some_and(|r| !r.contains(&(rowid as i64))) {
+            return Ok(None);
+        }
+        let start = cell + used + used2;
+        let size = usize::try_from(size).map_err(|_| damaged())?;
+
+        // Payload beyond what fits on the page continues in a chain of overflow pages.
+        let max_local = self.usable - 35;
+        let local = if size <= max_local {
+            size
+        } else {
+            let min_local = (self.usable - 12) * 32 / 255 - 23;
+            let local = min_local + (size - min_local) % (self.usable - 4);
+            if local <= max_local { local } else { min_local }
+        };
+        let mut payload = page.get(start..start + local).ok_or_else(damaged)?.to_vec();
+        if local < size {
+            let mut next = be_u32(page, start + local);
+            let mut hops = 0;
+            while payload.len() < size {
+                hops += 1;
+                if next == 0 || hops > self.page_count {
+                    return Err(error(number, "broken overflow chain"));
+                }
+                let overflow = self.page(next)?;
+                let take = (size - payload.len()).min(self.usable - 4);
+                payload.extend_from_slice(&overflow[4..4 + take]);
+                next = be_u32(overflow, 0);
+            }
+        }
+
+        let values = self.decode_record(number, &payload, indexes)?;
+        Ok(Some((rowid as i64, values)))
+    }
+
+    /// The values at `indexes` of a record, `Null` past its last column.
+    fn decode_record(
+        &self,
+        number: u32,
+        record: &[u8],
+        indexes: &[usize],
+    ) -> Result<Vec<Value>, SqliteError> {
+        let damaged = || error(number, "damaged record");
+        let (header_size, mut pos) = varint(record, 0).ok_or_else(damaged)?;
+        let header_size = usize::try_from(header_size).map_err(|_| damaged())?;
+        let mut types = Vec::new();
+        while pos < header_size {
+            let (serial, used) = varint(record, pos).ok_or_else(damaged)?;
+            types.push(serial);
+            pos += used;
+        }
+        // Sizes come from the file, so a damaged header must not overflow the sum.
+        let mut offsets = Vec::with_capacity(types.len());
+        let mut offset = header_size;
+        for &serial in &types {
+            offsets.push(offset);
+            offset = value_size(serial)
+                .and_then(|size| offset.checked_add(size))
+                .ok_or_else(damaged)?;
+        }
+        if offset > record.len() {
+            return Err(damaged());
+        }
+        indexes
+            .iter()
+            .map(|&i| match types.get(i) {
+                Some(&serial) => self
+                    .decode_value(serial, &record[offsets[i]..])
+                    .ok_or_else(damaged),
+                None => Ok(Value::Null),
+            })
+            .collect()
+    }
+
+    fn decode_value(&self, serial: u64, bytes: &[u8]) -> Option<Value> {
+        let int = |len: usize| {
+            let bytes = bytes.get(..len)?;
+            // Sign-extend from the first byte.
+            let mut value = if bytes[0] & 0x80 != 0 { -1i64 } else { 0 };
+            for &b in bytes {
+                value = (value << 8) | b as i64;
+            }
+            Some(Value::Integer(value))
+        };
+        match serial {
+            0 => Some(Value::Null),
+            1..=4 => int(serial as usize),
+            5 => int(6),
+            6 => int(8),
+            7 => {
+                let raw: [u8; 8] = bytes.get(..8)?.try_into().ok()?;
+                Some(Value::Real(f64::from_be_bytes(raw)))
+            }
+            8 => Some(Value::Integer(0)),
+            9 => Some(Value::Integer(1)),
+            n if n >= 12 && n % 2 == 0 => {
+                Some(Value::Blob(bytes.get(..(n as usize - 12) / 2)?.to_vec()))
+            }
+            n if n >= 13 => Some(Value::Text(
+                self.decode_text(bytes.get(..(n as usize - 13) / 2)?),
+            )),
+            _ => None,
+        }
+    }
+
+    fn decode_text(&self, bytes: &[u8]) -> String {
+        let pairs = bytes.chunks_exact(2);
+        let units: Vec<u16> = match self.encoding {
+            Encoding::Utf8 => return String::from_utf8_lossy(bytes).to_string(),
+            Encoding::Utf16Le => pairs.map(|p| u16::from_le_bytes([p[0], p[1]])).collect(),
+            Encoding::Utf16Be => pairs.map(|p| u16::from_be_bytes([p[0], p[1]])).collect(),
+        };
+        String::from_utf16_lossy(&units)
+    }
+}
+
+/// Pages of the committed transactions in a write-ahead log, and the database size
+/// in pages after the last of them. Frames from an older generation (other salts),
+/// with a wrong checksum, or after the last commit are left out.
+fn read_wal(bytes: &[u8], page_size: usize) -> (HashMap<u32, &[u8]>, Option<u32>) {
+    let mut committed = (HashMap::new(), None);
+    if bytes.len() < WAL_HEADER_SIZE
+        || be_u32(bytes, 0) & !1 != WAL_MAGIC
+        || be_u32(bytes, 8) as usize != page_size
+    {
+        return committed;
+    }
+    let big_endian = be_u32(bytes, 0) & 1 == 1;
+    let salts = &bytes[16..24];
+    let mut sum = wal_checksum(big_endian, (0, 0), &bytes[..24]);
+    if sum != (be_u32(bytes, 24), be_u32(bytes, 28)) {
+        return committed;
+    }
+
+    let mut pending = HashMap::new();
+    let frame_size = WAL_FRAME_HEADER_SIZE + page_size;
+    let mut offset = WAL_HEADER_SIZE;
+    while let Some(frame) = bytes.get(offset..offset + frame_size) {
+        let page = &frame[WAL_FRAME_HEADER_SIZE..];
+        if &frame[8..16] != salts {
+            break;
+        }
+        sum = wal_checksum(big_endian, sum, &frame[..8]);
+        sum = wal_checksum(big_endian, sum, page);
+        if sum != (be_u32(frame, 16), be_u32(frame, 20)) {
+            break;
+        }
+        pending.insert(be_u32(frame, 0), page);
+        let pages_after_commit = be_u32(frame, 4);
+        if pages_after_commit != 0 {
+            committed.0.extend(pending.drain());
+            committed.1 = Some(pages_after_commit);
+        }
+        offset += frame_size;
+    }
+    committed
+}
+
+fn wal_checksum(big_endian: bool, (mut s0, mut s1): (u32, u32), bytes: &[u8]) -> (u32, u32) {
+    let word = |b: &[u8]| {
+        let b = [b[0], b[1], b[2], b[3]];
+        if big_endian {
+            u32::from_be_bytes(b)
+        } else {
+            u32::from_le_bytes(b)
+        }
+    };
+    for pair in bytes.chunks_exact(8) {
+        s0 = s0.wrapping_add(word(&pair[..4])).wrapping_add(s1);
+        s1 = s1.wrapping_add(word(&pair[4..])).wrapping_add(s0);
+    }
+    (s0, s1)
+}
+
+/// Bytes a value of `serial` type takes in a record.
+fn value_size(serial: u64) -> Option<usize> {
+    match serial {
+        0 | 8 | 9 => Some(0),
+        1..=4 => Some(serial as usize),
+        5 => Some(6),
+        6 | 7 => Some(8),
+        10 | 11 => None,
+        n => usize::try_from((n - 12) / 2).ok(),
+    }
+}
+
+/// Name of each column in a `CREATE TABLE` statement, and whether it is an
+/// `INTEGER PRIMARY KEY` (an alias for the rowid).
+fn column_definitions(sql: &str) -> Vec<(String, bool)> {
+    let (Some(open), Some(close)) = (sql.find('('), sql.rfind(')')) else {
+        return Vec::new();
+    };
+    let Some(body) = sql.get(open + 1..close) else {
+        return Vec::new();
+    };
+    // Split on commas outside parentheses and quotes.
+    let mut parts = Vec::new();
+    let (mut depth, mut quote, mut start) = (0, None, 0);
+    for (i, c) in body.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '[') => quote = Some(']'),
+            (None, '(') => depth += 1,
+            (None, ')') => depth -= 1,
+            (None, ',') if depth == 0 => {
+                parts.push(&body[start..i]);
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(&body[start..]);
+
+    let mut columns = Vec::new();
+    for part in parts {
+        let words: Vec<&str> = part.split_whitespace().collect();
+        let Some(first) = words.first() else {
+            continue;
+        };
+        // Constraints may be written without a space: `UNIQUE(body)`.
+        let keyword = first
+            .split('(')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_uppercase();
+        if matches!(
+            keyword.as_str(),
+            "CONSTRAINT" | "PRIMARY" | "UNIQUE" | "CHECK" | "FOREIGN"
+        ) {
+            continue;
+        }
+        let name = first.trim_matches(['"', '`', '[', ']', '\'']).to_string();
+        let rest = words[1..].join(" ").to_ascii_uppercase();
+        columns.push((name, rest.starts_with("INTEGER PRIMARY KEY")));
+    }
+    columns
+}
+
+/// A SQLite varint at `pos`: its value and length in bytes.
+fn varint(bytes: &[u8], pos: usize) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for i in 0..9 {
+        let byte = *bytes.get(pos + i)?;
+        if i == 8 {
+            return Some(((value << 8) | byte as u64, 9));
+        }
+        value = (value << 7) | (byte & 0x7F) as u64;
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+/// Big-endian integers; 0 past the end of a damaged page, which then fails as a
+/// page number or reads as an empty cell list.
+fn be_u16(bytes: &[u8], pos: usize) -> u16 {
+    bytes
+        .get(pos..pos + 2)
+        .map_or(0, |b| u16::from_be_bytes([b[0], b[1]]))
+}
+
+fn be_u32(bytes: &[u8], pos: usize) -> u32 {
+    bytes
+        .get(pos..pos + 4)
+        .map_or(0, |b| u32::from_be_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const PLACES: &[u8] = include_bytes!("../../tests/fixtures/places.sqlite");
+    const ALTERED: &[u8] = include_bytes!("../../tests/fixtures/altered.sqlite");
+    const PAGE_SIZE: usize = 1024;
+
+    fn text(value: &str) -> Value {
+        Value::Text(value.to_string())
+    }
+
+    #[test]
+    fn open_checks_the_header() {
+        assert!(Database::open(&[], None).is_err());
+        let err = Database::open(b"SQLite format 3\0 but not really", None).err();
+        assert_eq!(err.unwrap().to_string(), "page 0: not an SQLite database");
+        let mut odd_size = PLACES.to_vec();
+        odd_size[16..18].copy_from_slice(&1000u16.to_be_bytes());
+        assert_eq!(
+            Database::open(&odd_size, None).err().unwrap().message,
+            "invalid page size"
+        );
+    }
+
+    #[test]
+    fn tables_read_in_rowid_order() {
+        let db = Database::open(ALTERED, None).unwrap();
+        let rows = db.read_table("NOTES", &["score", "body", "id"]).unwrap();
+        assert_eq!(
+            rows,
+            [
+                // A whole REAL is stored as an integer.
+                vec![Value::Integer(-1), text("negative"), Value::Integer(-7)],
+                // Added by ALTER TABLE after this row was written.
+                vec![Value::Null, text("café ☕"), Value::Integer(1)],
+                vec![Value::Real(2.5), text("second"), Value::Integer(2)],
+            ]
+        );
+        let err = db.read_table("notes", &["missing"]).unwrap_err();
+        assert_eq!(err.message, "no column 'missing' in 'notes'");
+        let err = db.read_table("nope", &["id"]).unwrap_err();
+        assert_eq!(err.to_string(), "page 1: no table 'nope'");
+    }
+
+    #[test]
+    fn tables_span_interior_and_overflow_pages() {
+        let db = Database::open(PLACES, None).unwrap();
+        let rows = db.read_table("moz_places", &["id", "url"]).unwrap();
+        assert_eq!(rows.len(), 607);
+        assert_eq!(
+            rows[0],
+            [Value::Integer(1), text("https://www.rust-lang.org/")]
+        );
+        let long = format!("https://example.com/{}", "x".repeat(3000));
+        assert_eq!(rows[2][1].as_str(), Some(long.as_str()));
+        assert_eq!(
+            rows[606][1].as_str(),
+            Some("https://history.example.com/page/1599")
+        );
+    }
+
+    #[test]
+    fn rows_by_rowid_skip_other_pages() {
+        let wanted = BTreeSet::from([2, 1000, 1599, 5000, -3]);
+        let db = Database::open(PLACES, None).unwrap();
+        let rows = db
+            .read_table_rows("moz_places", &["id", "title"], &wanted)
+            .unwrap();
+        let ids: Vec<i64> = rows.iter().filter_map(|row| row[0].as_i64()).collect();
+        assert_eq!(ids, [2, 1000, 1599]);
+        assert_eq!(rows[0][1], text("GitHub"));
+        assert!(
+            db.read_table_rows("moz_places", &["id"], &BTreeSet::new())
+                .unwrap()
+                .is_empty()
+        );
+
+        // A damaged page holding none of the rows is never read.
+        let at = PLACES.windows(9).position(|w| w == b"page/1300").unwrap();
+        let mut damaged = PLACES.to_vec();
+        damaged[at / PAGE_SIZE * PAGE_SIZE] = 0x42;
+        let db = Database::open(&damaged, None).unwrap();
+        assert!(db.read_table("moz_places", &["id"]).is_err());
+        let rows = db
+            .read_table_rows("moz_places", &["id"], &BTreeSet::from([1, 2]))
+            .unwrap();
+        assert_eq!(rows.len(), 2);
+    }
+
+    #[test]
+    fn record_sizes_must_not_overflow() {
+        let db = Database::open(ALTERED, None).unwrap();
+        // Two BLOBs of nearly `u64::MAX / 2` bytes each.
+        let huge = [0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFF, 0xFE];
+        let mut record = vec![19];
+        record.extend_from_slice(&huge);
+        record.extend_from_slice(&huge);
+        assert_eq!(
+            db.decode_record(3, &record, &[0, 1]),
+            Err(error(3, "damaged record"))
+        );
+        // Reserved serial types and values past the end.
+        assert!(db.decode_record(3, &[2, 10], &[0]).is_err());
+        assert!(db.decode_record(3, &[2, 6, 1], &[0]).is_err());
+        assert_eq!(
+            db.decode_record(3, &[2, 1, 0xFF], &[0, 1]),
+            Ok(vec![Value::Integer(-1), Value::Null])
+        );
+    }
+
+    #[test]
+    fn damaged_files_fail_without_panicking() {
+        let db = Database::open(&PLACES[..PLACES.len() / 2], None).unwrap();
+        assert!(db.read_table("moz_places", &["id"]).is_err());
+
+        // Whatever gets overwritten, reading fails or succeeds but never panics.
+        let mut seed = 12345u32;
+        for round in 0..1000 {
+            let mut copy = PLACES.to_vec();
+            for _ in 0..1 + round % 8 {
+                seed = seed.wrapping_mul(1103515245).wrapping_add(12345);
+                let at = (seed as usize >> 3) % copy.len();
+                copy[at] = (seed >> 16) as u8;
+            }
+            if let Ok(db) = Database::open(&copy, None) {
+                let _ = db.read_table("moz_places", &["id", "url", "title"]);
+                let _ = db.read_table_rows("moz_places", &["url"], &BTreeSet::from([1, 1300]));
+            }
+        }
+
+        // Every interior page pointing back at itself.
+        let mut looped = PLACES.to_vec();
+        for page in 1..PLACES.len() / PAGE_SIZE {
+            let at = page * PAGE_SIZE;
+            if looped[at] == 0x05 {
+                looped[at + 8..at + 12].copy_from_slice(&(page as u32 + 1).to_be_bytes());
+            }
+        }
+        let db = Database::open(&looped, None).unwrap();
+        assert_eq!(
+            db.read_table("moz_places", &["id"]).unwrap_err().message,
+            "b-tree loops"
+        );
+    }
+
+    #[test]
+    fn column_definitions_skip_constraints() {
+        let sql = "CREATE TABLE \"notes\" ([id] integer primary key, body TEXT, \
+                   CHECK (length(body) > 0), UNIQUE(body), \"a,b\" DEFAULT (1, 2))";
+        assert_eq!(
+            column_definitions(sql),
+            [
+                ("id".to_string(), true),
+                ("body".to_string(), false),
+                ("a,b".to_string(), false),
+            ]
+        );
+        assert!(column_definitions("CREATE TABLE x").is_empty());
+    }
+
+    #[test]
+    fn varints_take_up_to_nine_bytes() {
+        assert_eq!(varint(&[0x05], 0), Some((5, 1)));
+        assert_eq!(varint(&[0x81, 0x00], 0), Some((128, 2)));
+        assert_eq!(varint(&[0xFF; 9], 0), Some((u64::MAX, 9)));
+        assert_eq!(varint(&[0x81], 0), None);
+    }
+}
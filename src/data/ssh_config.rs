@@ -0,0 +1,370 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use crate::data::command_line::{CommandLine, program_key};
+use crate::data::file_index::glob_match;
+
+// ==================================================================================
+//  SSH CONFIG
+//  Host names for `ssh <host>`: the aliases in `~/.ssh/config` (following
+//  `Include`, like ssh itself up to 16 files deep), then the hosts in `known_hosts`.
+//  Patterns with `*` or `?` and negated ones (`!name`) only say where settings
+//  apply, so they are not offered. Keywords are case-insensitive and may be
+//  separated from their value by `=`.
+// ==================================================================================
+
+const MAX_INCLUDE_DEPTH: usize = 16;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SshHost {
+    pub name: String,
+    /// `user@hostname:port` from its `Host` block, as far as it is set.
+    pub detail: String,
+}
+
+/// Hosts from `config` and `known_hosts` in `ssh_dir` (`~/.ssh`), config first,
+/// each name once.
+pub fn load_ssh_hosts(ssh_dir: &Path) -> Vec<SshHost> {
+    let mut hosts = Vec::new();
+    read_config(&ssh_dir.join("config"), ssh_dir, 0, &mut hosts);
+    if let Ok(text) = fs::read_to_string(ssh_dir.join("known_hosts")) {
+        for name in parse_known_hosts(&text) {
+            add_host(&mut hosts, &name);
+        }
+    }
+    hosts
+}
+
+fn read_config(path: &Path, ssh_dir: &Path, depth: usize, hosts: &mut Vec<SshHost>) {
+    if let Ok(text) = fs::read_to_string(path) {
+        parse_config(&text, ssh_dir, depth, hosts);
+    }
+}
+
+/// The `Host` entries of one config file, reading its `Include`s in place.
+pub fn parse_config(text: &str, ssh_dir: &Path, depth: usize, hosts: &mut Vec<SshHost>) {
+    // Hosts named by the current `Host` line, and what its block says about them.
+    let mut block: Vec<usize> = Vec::new();
+    let (mut hostname, mut user, mut port) = (None, None, None);
+
+    for line in text.lines() {
+        let Some((keyword, value)) = split_line(line) else {
+            continue;
+        };
+        let keyword = keyword.to_ascii_lowercase();
+        // Each of these ends the block before it.
+        if matches!(keyword.as_str(), "host" | "match" | "include") {
+            finish_block(hosts, &block, &hostname, &user, &port);
+            block.clear();
+            (hostname, user, port) = (None, None, None);
+        }
+        match keyword.as_str() {
+            "host" => {
+                for pattern in value.split_whitespace() {
+                    if is_concrete(pattern) {
+                        block.push(add_host(hosts, pattern));
+                    }
+                }
+            }
+            "include" if depth < MAX_INCLUDE_DEPTH => {
+                for pattern in value.split_whitespace() {
+                    for path in include_paths(pattern, ssh_dir) {
+                        read_config(&path, ssh_dir, depth + 1, hosts);
+                    }
+                }
+            }
+            "hostname" => hostname = Some(value.to_string()),
+            "user" => user = Some(value.to_string()),
+            "port" => port = Some(value.to_string()),
+            _ => {}
+        }
+    }
+    finish_block(hosts, &block, &hostname, &user, &port);
+}
+
+/// Keyword and value of a config line; `None` for blanks and comments.
+fn split_line(line: &str) -> Option<(&str, &str)> {
+    let line = line.trim();
+    if line.is_empty() || line.starts_with('#') {
+        return None;
+    }
+    let end = line.find(|c: char| c.is_whitespace() || c == '=')?;
+    let value = line[end..]
+        .trim_start()
+        .strip_prefix('=')
+        .unwrap_or(&line[end..]);
+    Some((&line[..end], value.trim().trim_matches('"')))
+}
+
+fn is_concrete(pattern: &str) -> bool {
+    !pattern.is_empty() && !pattern.contains(['*', '?', '!'])
+}
+
+/// Index of `name` in `hosts`, added if new.
+fn add_host(hosts: &mut Vec<SshHost>, name: &str) -> usize {
+    if let Some(index) = hosts.iter().position(|h| h.name.eq_ignore_ascii_case(name)) {
+        return index;
+    }
+    hosts.push(SshHost {
+        name: name.to_string(),
+        detail: String::new(),
+    });
+    hosts.len() - 1
+}
+
+fn finish_block(
+    hosts: &mut [SshHost],
+    block: &[usize],
+    hostname: &Option<String>,
+    user: &Option<String>,
+    port: &Option<String>,
+) {
+    let mut detail = String::new();
+    if let Some(user) = user {
+        detail.push_str(&format!("{}@", user));
+    }
+    if let Some(hostname) = hostname {
+        detail.push_str(hostname);
+    }
+    if let Some(port) = port {
+        detail.push_str(&format!(":{}", port));
+    }
+    for &index in block {
+        // The first block that names a host wins, as for ssh's own settings.
+        if hosts[index].detail.is_empty() {
+            hosts[index].detail = detail.clone();
+        }
+    }
+}
+
+/// Files an `Include` pattern names: `~` is the home folder, relative paths are in
+/// `~/.ssh`, and the file name may hold `*` and `?`.
+fn include_paths(pattern: &str, ssh_dir: &Path) -> Vec<PathBuf> {
+    let path = match pattern.strip_prefix("~/").or(pattern.strip_prefix("~\\")) {
+        Some(rest) => ssh_dir.parent().unwrap_or(ssh_dir).join(rest),
+        None => ssh_dir.join(pattern),
+    };
+    let file_name = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    if !file_name.contains(['*', '?']) {
+        return vec![path];
+    }
+    let Some(dir) = path.parent() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    // ssh reads matches in sorted order.
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_file()))
+        .filter(|entry| glob_match(&file_name, &entry.file_name().to_string_lossy()))
+        .map(|entry| entry.path())
+        .collect();
+    paths.sort();
+    paths
+}
+
+/// Host names in `known_hosts`. Hashed entries cannot be read back and are skipped;
+/// `[host]:port` gives the host.
+pub fn parse_known_hosts(text: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    for line in text.lines() {
+        let mut fields = line.split_whitespace();
+        let Some(mut hosts) = fields.next() else {
+            continue;
+        };
+        if hosts.starts_with('#') || hosts == "@revoked" {
+            continue;
+        }
+        if hosts == "@cert-authority" {
+            let Some(next) = fields.next() else {
+                continue;
+            };
+            hosts = next;
+        }
+        for host in hosts.split(',') {
+            let host = match host.strip_prefix('[') {
+                Some(bracketed) => bracketed.split(']').next().unwrap_or_default(),
+                None => host,
+            };
+            if is_concrete(host)
+                && !host.starts_with('|')
+                && !names.iter().any(|n| n.eq_ignore_ascii_case(host))
+            {
+                names.push(host.to_string());
+            }
+        }
+    }
+    names
+}
+
+/// `command` run through `terminal` when it starts an ssh session, since ssh needs a
+/// console to ask for passwords; `None` for other commands or `terminal = none`.
+pub fn ssh_launch_command(command: &str, terminal: &str) -> Option<String> {
+    let line = CommandLine::parse(command)?;
+    if program_key(&line.program.value) != "ssh" || terminal.eq_ignore_ascii_case("none") {
+        return None;
+    }
+    Some(format!("{} {}", terminal, command))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::temp_dir::TempDir;
+
+    fn config(text: &str) -> Vec<SshHost> {
+        let mut hosts = Vec::new();
+        parse_config(text, Path::new("ssh"), 0, &mut hosts);
+        hosts
+    }
+
+    fn names(hosts: &[SshHost]) -> Vec<&str> {
+        hosts.iter().map(|h| h.name.as_str()).collect()
+    }
+
+    #[test]
+    fn host_blocks_give_names_and_details() {
+        let hosts = config(
+            "# comment\n\
+             Host devbox dev\n  HostName 10.0.0.5\n  User me\n  Port 2222\n\n\
+             Host plain\n\
+             Host devbox\n  User other\n",
+        );
+        assert_eq!(names(&hosts), ["devbox", "dev", "plain"]);
+        assert_eq!(hosts[0].detail, "me@10.0.0.5:2222");
+        assert_eq!(hosts[1].detail, "me@10.0.0.5:2222");
+        assert_eq!(hosts[2].detail, "");
+    }
+
+    #[test]
+    fn patterns_and_negations_are_not_offered() {
+        let hosts = config(
+            "Host *\n  ServerAliveInterval 30\n\
+             Host !bad *.corp web?? prod\n  HostName prod.example.com\n\
+             Host !*.lan\n",
+        );
+        assert_eq!(names(&hosts), ["prod"]);
+        assert_eq!(hosts[0].detail, "prod.example.com");
+    }
+
+    #[test]
+    fn keywords_ignore_case_and_accept_equals() {
+        let hosts = config(
+            "HOST=\"quoted\"\n  hostname=q.example\n  USER = me\n  Port=22\n\
+             host spaced\n  HostName   s.example  \n",
+        );
+        assert_eq!(names(&hosts), ["quoted", "spaced"]);
+        assert_eq!(hosts[0].detail, "me@q.example:22");
+        assert_eq!(hosts[1].detail, "s.example");
+    }
+
+    #[test]
+    fn match_blocks_end_the_host_before_them() {
+        let hosts = config("Host a\nMatch host b\n  User x\n  HostName b.example\n");
+        assert_eq!(names(&hosts), ["a"]);
+        assert_eq!(hosts[0].detail, "");
+    }
+
+    #[test]
+    fn includes_are_read_in_place() {
+        let home = TempDir::new("ssh_config_include");
+        home.write(
+            ".ssh/config",
+            "Host first\n\
+             Include config.d/*.conf ~/extra missing\n\
+             Host last\n",
+        );
+        home.write(".ssh/config.d/b.conf", "Host beta\n HostName b.example\n");
+        // Including itself stops at the depth limit rather than looping.
+        home.write(
+            ".ssh/config.d/a.conf",
+            "Host alpha\nInclude config.d/a.conf\n",
+        );
+        home.write(".ssh/config.d/skip.txt", "Host skipped\n");
+        home.write("extra", "Host extra\n");
+
+        let hosts = load_ssh_hosts(&home.join(".ssh"));
+        assert_eq!(names(&hosts), ["first", "alpha", "beta", "extra", "last"]);
+        assert_eq!(hosts[2].detail, "b.example");
+    }
+
+    #[test]
+    fn include_ends_the_block_before_it() {
+        let home = TempDir::new("ssh_config_include_block");
+        home.write(".ssh/config", "Host a\nInclude other\n  User ignored\n");
+        home.write(".ssh/other", "Host b\n");
+        let hosts = load_ssh_hosts(&home.join(".ssh"));
+        assert_eq!(names(&hosts), ["a", "b"]);
+        assert!(hosts.iter().all(|h| h.detail.is_empty()));
+    }
+
+    #[test]
+    fn known_hosts_follow_the_config() {
+        let home = TempDir::new("ssh_config_known_hosts");
+        home.write(".ssh/config", "Host devbox\n  HostName 10.0.0.5\n");
+        home.write(
+            ".ssh/known_hosts",
+            "devbox ssh-ed25519 AAA\nDEVBOX ssh-rsa AAA\ngithub.com ssh-ed25519 AAA\n",
+        );
+        let hosts = load_ssh_hosts(&home.join(".ssh"));
+        assert_eq!(names(&hosts), ["devbox", "github.com"]);
+        assert_eq!(hosts[0].detail, "10.0.0.5");
+    }
+
+    #[test]
+    fn missing_files_give_no_hosts() {
+        let home = TempDir::new("ssh_config_missing");
+        home.create_dir(".ssh");
+        assert!(load_ssh_hosts(&home.join(".ssh")).is_empty());
+        assert!(load_ssh_hosts(&home.join("nowhere")).is_empty());
+    }
+
+    #[test]
+    fn known_hosts_forms() {
+        assert_eq!(
+            parse_known_hosts("a,b x\n[c]:22,d y\n[e.example]:2222 ssh-rsa AAA\n"),
+            ["a", "b", "c", "d", "e.example"]
+        );
+        // Hashed names cannot be read back.
+        assert_eq!(
+            parse_known_hosts(
+                "|1|F1E1KeoE/eEWhi10WpGv4OdiO6Y=|3988QV0VE8wmZL7suNrYQLITLCg= ssh-rsa AAA\nplain x\n"
+            ),
+            ["plain"]
+        );
+        assert_eq!(
+            parse_known_hosts(
+                "@revoked revoked.example ssh-rsa AAA\n\
+                 @cert-authority *.corp ssh-rsa AAA\n\
+                 @cert-authority ca.example ssh-rsa AAA\n\
+                 @cert-authority\n"
+            ),
+            ["ca.example"]
+        );
+        assert_eq!(
+            parse_known_hosts("# comment\n\n  \nGitHub.com x\ngithub.com y\n!neg z\nweb? w\n"),
+            ["GitHub.com"]
+        );
+    }
+
+    #[test]
+    fn ssh_commands_run_in_the_terminal() {
+        assert_eq!(
+            ssh_launch_command("ssh devbox", "wt").as_deref(),
+            Some("wt ssh devbox")
+        );
+        assert_eq!(
+            ssh_launch_command("SSH.exe -p 22 me@h", "cmd /k").as_deref(),
+            Some("cmd /k SSH.exe -p 22 me@h")
+        );
+        assert_eq!(ssh_launch_command("ssh devbox", "None"), None);
+        assert_eq!(ssh_launch_command("sshd", "wt"), None);
+        assert_eq!(ssh_launch_command("notepad ssh", "wt"), None);
+        assert_eq!(ssh_launch_command("", "wt"), None);
+    }
+}
@@ -0,0 +1,423 @@
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::mpsc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+// ==================================================================================
+//  SUGGESTIONS
+//  Every keystroke starts a query with a new generation number. Each provider runs
+//  on its own thread; the merge waits for each one up to its budget, drops the late
+//  ones, ranks what arrived and hands it on, unless a newer query has started by
+//  then. Providers see the same generation through their `CancelToken` and should
+//  stop early once it is stale.
+// ==================================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Source {
+    History,
+    Completion,
+    Alias,
+    App,
+    Calculator,
+    File,
+    Repo,
+    Workspace,
+    TerminalProfile,
+    SshHost,
+    Bookmark,
+    Setting,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    /// Text the input takes when the suggestion is picked.
+    pub text: String,
+    /// Shown instead of `text` when set, e.g. an app's name for its shortcut path.
+    pub name: String,
+    /// Secondary text: a description, target or the calculation.
+    pub detail: String,
+    pub source: Source,
+    /// Rank across providers, higher first; roughly 0 to 1.
+    pub score: f32,
+}
+
+impl Suggestion {
+    /// What the dropdown row shows.
+    pub fn title(&self) -> &str {
+        if self.name.is_empty() {
+            &self.text
+        } else {
+            &self.name
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Query {
+    pub text: String,
+    pub ignore_accents: bool,
+}
+
+/// Tells a provider whether its query is still the newest one.
+#[derive(Debug, Clone)]
+pub struct CancelToken {
+    current: Arc<AtomicU64>,
+    generation: u64,
+}
+
+impl CancelToken {
+    pub fn is_cancelled(&self) -> bool {
+        self.current.load(Ordering::SeqCst) != self.generation
+    }
+}
+
+pub trait SuggestionProvider: Send {
+    fn source(&self) -> Source;
+    /// How long the merge waits for this provider before dropping its results.
+    fn budget(&self) -> Duration;
+    fn suggest(&self, query: &Query, cancel: &CancelToken) -> Vec<Suggestion>;
+}
+
+/// Merged results of one query.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestions {
+    pub generation: u64,
+    pub items: Vec<Suggestion>,
+    /// Providers that missed their budget.
+    pub late: Vec<Source>,
+}
+
+#[derive(Debug, Default)]
+pub struct Orchestrator {
+    generation: Arc<AtomicU64>,
+}
+
+impl Orchestrator {
+    pub fn new() -> Self {
+        Orchestrator::default()
+    }
+
+    /// Generation of the newest query; results carrying another one are stale.
+    pub fn generation(&self) -> u64 {
+        self.generation.load(Ordering::SeqCst)
+    }
+
+    /// Makes every running query stale.
+    pub fn cancel(&self) {
+        self.generation.fetch_add(1, Ordering::SeqCst);
+    }
+
+    /// Runs `providers` for `query`, cancelling the previous query, and calls `deliver`
+    /// on a worker thread with the best `limit` results. Returns the query's generation.
+    pub fn query(
+        &self,
+        query: Query,
+        providers: Vec<Box<dyn SuggestionProvider>>,
+        limit: usize,
+        deliver: impl FnOnce(Suggestions) + Send + 'static,
+    ) -> u64 {
+        let generation = self.generation.fetch_add(1, Ordering::SeqCst) + 1;
+        let token = CancelToken {
+            current: self.generation.clone(),
+            generation,
+        };
+        let query = Arc::new(query);
+
+        thread::spawn(move || {
+            let start = Instant::now();
+            let budgets: Vec<(Source, Duration)> =
+                providers.iter().map(|p| (p.source(), p.budget())).collect();
+            let (sender, receiver) = mpsc::channel();
+            for (index, provider) in providers.into_iter().enumerate() {
+                let (sender, query, token) = (sender.clone(), query.clone(), token.clone());
+                thread::spawn(move || {
+                    let items = provider.suggest(&query, &token);
+                    let _ = sender.send((index, items));
+                });
+            }
+            drop(sender);
+
+            let deadline = start + budgets.iter().map(|(_, b)| *b).max().unwrap_or_default();
+            let mut results: Vec<Option<Vec<Suggestion>>> = vec![None; budgets.len()];
+            let mut waiting = budgets.len();
+            while waiting > 0 && !token.is_cancelled() {
+                let Some(left) = deadline.checked_duration_since(Instant::now()) else {
+                    break;
+                };
+                let Ok((index, items)) = receiver.recv_timeout(left) else {
+                    break;
+                };
+                waiting -= 1;
+                if start.elapsed() <= budgets[index].1 {
+                    results[index] = Some(items);
+                }
+            }
+            if token.is_cancelled() {
+                return;
+            }
+
+            let late = budgets
+                .iter()
+                .zip(&results)
+                .filter(|(_, items)| items.is_none())
+                .map(|((source, _), _)| *source)
+                .collect();
+            deliver(Suggestions {
+                generation,
+                items: merge(results.into_iter().flatten().collect(), limit),
+                late,
+            });
+        });
+        generation
+    }
+}
+
+/// Best first. Equal scores keep provider order, then each provider's own order; a
+/// text offered twice keeps its better-ranked entry.
+pub fn merge(lists: Vec<Vec<Suggestion>>, limit: usize) -> Vec<Suggestion> {
+    let mut items: Vec<Suggestion> = lists.into_iter().flatten().collect();
+    items.sort_by(|a, b| b.score.total_cmp(&a.score));
+    let mut merged: Vec<Suggestion> = Vec::new();
+    for item in items {
+        if merged.len() == limit {
+            break;
+        }
+        if !merged.iter().any(|m| m.text == item.text) {
+            merged.push(item);
+        }
+    }
+    merged
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn item(text: &str, source: Source, score: f32) -> Suggestion {
+        Suggestion {
+            text: text.into(),
+            name: String::new(),
+            detail: String::new(),
+            source,
+            score,
+        }
+    }
+
+    fn query(text: &str) -> Query {
+        Query {
+            text: text.into(),
+            ignore_accents: false,
+        }
+    }
+
+    /// Sleeps `delay` milliseconds in 1 ms steps, stopping early once cancelled
+    /// when `honours_cancel` is set.
+    struct FakeProvider {
+        source: Source,
+        delay: u64,
+        budget: u64,
+        items: Vec<Suggestion>,
+        honours_cancel: bool,
+    }
+
+    impl SuggestionProvider for FakeProvider {
+        fn source(&self) -> Source {
+            self.source
+        }
+
+        fn budget(&self) -> Duration {
+            Duration::from_millis(self.budget)
+        }
+
+        fn suggest(&self, _query: &Query, cancel: &CancelToken) -> Vec<Suggestion> {
+            for _ in 0..self.delay {
+                if self.honours_cancel && cancel.is_cancelled() {
+                    return Vec::new();
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+            self.items.clone()
+        }
+    }
+
+    fn fake(
+        source: Source,
+        delay: u64,
+        budget: u64,
+        items: &[(&str, f32)],
+    ) -> Box<dyn SuggestionProvider> {
+        Box::new(FakeProvider {
+            source,
+            delay,
+            budget,
+            items: items
+                .iter()
+                .map(|(text, score)| item(text, source, *score))
+                .collect(),
+            honours_cancel: true,
+        })
+    }
+
+    fn texts(results: &Suggestions) -> Vec<&str> {
+        results.items.iter().map(|s| s.text.as_str()).collect()
+    }
+
+    #[test]
+    fn merge_ranks_and_deduplicates() {
+        let merged = merge(
+            vec![
+                vec![
+                    item("a", Source::History, 0.5),
+                    item("b", Source::History, 0.8),
+                ],
+                vec![item("c", Source::Alias, 0.8), item("a", Source::Alias, 0.9)],
+            ],
+            10,
+        );
+        let found: Vec<(&str, Source)> =
+            merged.iter().map(|s| (s.text.as_str(), s.source)).collect();
+        assert_eq!(
+            found,
+            [
+                ("a", Source::Alias),
+                ("b", Source::History),
+                ("c", Source::Alias)
+            ]
+        );
+        let capped = merge(
+            vec![vec![
+                item("a", Source::History, 1.0),
+                item("b", Source::History, 1.0),
+            ]],
+            1,
+        );
+        assert_eq!(capped.len(), 1);
+    }
+
+    #[test]
+    fn delivers_merged_results_for_the_generation() {
+        let orchestrator = Orchestrator::new();
+        let (tx, rx) = mpsc::channel();
+        let generation = orchestrator.query(
+            query("x"),
+            vec![
+                fake(Source::History, 0, 200, &[("hist", 0.5)]),
+                fake(Source::Calculator, 5, 200, &[("calc", 1.0)]),
+            ],
+            10,
+            move |r| tx.send(r).unwrap(),
+        );
+        let results = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(results.generation, generation);
+        assert_eq!(orchestrator.generation(), generation);
+        assert_eq!(texts(&results), ["calc", "hist"]);
+        assert!(results.late.is_empty());
+    }
+
+    #[test]
+    fn providers_over_budget_are_dropped() {
+        let orchestrator = Orchestrator::new();
+        let (tx, rx) = mpsc::channel();
+        orchestrator.query(
+            query("x"),
+            vec![
+                fake(Source::History, 0, 300, &[("fast", 0.5)]),
+                fake(Source::Completion, 150, 20, &[("slow", 1.0)]),
+            ],
+            10,
+            move |r| tx.send(r).unwrap(),
+        );
+        let results = rx.recv_timeout(Duration::from_secs(2)).unwrap();
+        assert_eq!(texts(&results), ["fast"]);
+        assert_eq!(results.late, [Source::Completion]);
+    }
+
+    #[test]
+    fn a_stuck_provider_does_not_hold_the_merge() {
+        let orchestrator = Orchestrator::new();
+        let (tx, rx) = mpsc::channel();
+        let started = Instant::now();
+        let stuck = Box::new(FakeProvider {
+            source: Source::Alias,
+            delay: 2000,
+            budget: 30,
+            items: Vec::new(),
+            honours_cancel: false,
+        });
+        orchestrator.query(
+            query("x"),
+            vec![stuck, fake(Source::History, 0, 60, &[("h", 0.1)])],
+            10,
+            move |r| tx.send(r).unwrap(),
+        );
+        let results = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert!(started.elapsed() < Duration::from_millis(900));
+        assert_eq!(results.late, [Source::Alias]);
+    }
+
+    /// Reports whether it saw its query go stale before running out of time.
+    struct Watcher(mpsc::Sender<bool>);
+
+    impl SuggestionProvider for Watcher {
+        fn source(&self) -> Source {
+            Source::History
+        }
+
+        fn budget(&self) -> Duration {
+            Duration::from_millis(500)
+        }
+
+        fn suggest(&self, _query: &Query, cancel: &CancelToken) -> Vec<Suggestion> {
+            for _ in 0..400 {
+                if cancel.is_cancelled() {
+                    self.0.send(true).unwrap();
+                    return Vec::new();
+                }
+                thread::sleep(Duration::from_millis(1));
+            }
+            self.0.send(false).unwrap();
+            Vec::new()
+        }
+    }
+
+    #[test]
+    fn a_newer_query_cancels_the_stale_one() {
+        let orchestrator = Orchestrator::new();
+        let (tx, rx) = mpsc::channel();
+        let (cancelled_tx, cancelled_rx) = mpsc::channel();
+        let first_tx = tx.clone();
+        let first = orchestrator.query(
+            query("a"),
+            vec![Box::new(Watcher(cancelled_tx))],
+            10,
+            move |r| first_tx.send(r).unwrap(),
+        );
+        thread::sleep(Duration::from_millis(20));
+        let second = orchestrator.query(
+            query("ab"),
+            vec![fake(Source::History, 0, 100, &[("ab", 0.5)])],
+            10,
+            move |r| tx.send(r).unwrap(),
+        );
+        assert!(second > first);
+        assert!(cancelled_rx.recv_timeout(Duration::from_secs(1)).unwrap());
+        let results = rx.recv_timeout(Duration::from_secs(1)).unwrap();
+        assert_eq!(results.generation, second);
+        assert!(rx.recv_timeout(Duration::from_millis(300)).is_err());
+    }
+
+    #[test]
+    fn cancel_drops_a_running_query() {
+        let orchestrator = Orchestrator::new();
+        let (tx, rx) = mpsc::channel();
+        orchestrator.query(
+            query("x"),
+            vec![fake(Source::History, 50, 200, &[("x1", 0.5)])],
+            10,
+            move |r| tx.send(r).unwrap(),
+        );
+        orchestrator.cancel();
+        assert!(rx.recv_timeout(Duration::from_millis(400)).is_err());
+    }
+}
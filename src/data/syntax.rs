@@ -0,0 +1,400 @@
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::data::command_line::tokenize;
+
+// ==================================================================================
+//  SYNTAX
+//  Splits the command line into words the way the executor does and gives every
+//  part a role (program, argument, quoted string, `%VAR%`, URL) for colouring. The
+//  filesystem is reached through `Resolver`, so classification runs without Windows.
+// ==================================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Program,
+    Argument,
+    String,
+    Variable,
+    Url,
+}
+
+/// A coloured byte range of the input. `error` marks a program that cannot be found
+/// or a path that does not exist; every part of that word carries it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Span {
+    pub range: Range<usize>,
+    pub kind: TokenKind,
+    pub error: bool,
+}
+
+/// What classification needs to know about the machine. Both take the word without
+/// quotes but with `%VAR%`s unexpanded.
+pub trait Resolver {
+    /// An alias, built-in command, existing path or program the shell can find.
+    fn is_program(&self, program: &str) -> bool;
+    fn path_exists(&self, path: &str) -> bool;
+}
+
+/// Earlier answers to `path_exists`, so retyping a word does not reach the disk again,
+/// and neither does anything typed inside a folder already known to be missing.
+#[derive(Debug, Default)]
+pub struct PathCache {
+    known: HashMap<String, bool>,
+}
+
+impl PathCache {
+    /// Whether `path` exists, asking `probe` only when no earlier answer decides it.
+    pub fn exists(&mut self, path: &str, probe: impl FnOnce(&str) -> bool) -> bool {
+        let key = path.to_lowercase();
+        if let Some(&known) = self.known.get(&key) {
+            return known;
+        }
+        let in_missing_folder = key
+            .rsplit_once(['\\', '/'])
+            .is_some_and(|(parent, _)| self.known.get(parent) == Some(&false));
+        let known = !in_missing_folder && probe(path);
+        self.known.insert(key, known);
+        known
+    }
+}
+
+/// Styled spans of `text`, in order and never overlapping. Whitespace is left out.
+/// The word at `caret` is still being typed, so it is not looked up: each
+/// keystroke would otherwise search `PATH` for a program name that is not done yet.
+pub fn highlight(text: &str, caret: Option<usize>, resolver: &impl Resolver) -> Vec<Span> {
+    let words = words(text);
+    let typing = caret.and_then(|caret| word_at(&words, caret));
+    let mut spans = Vec::new();
+
+    // The executor runs an existing path with spaces as a whole, so colour it as one.
+    let whole = text.trim();
+    if words.len() > 1 && !whole.contains('"') && !is_url(whole) && resolver.path_exists(whole) {
+        let start = text.len() - text.trim_start().len();
+        push_segments(
+            text,
+            start..start + whole.len(),
+            TokenKind::Program,
+            false,
+            &mut spans,
+        );
+        return spans;
+    }
+
+    for (index, range) in words.into_iter().enumerate() {
+        let bare = text[range.clone()].replace('"', "");
+        let (kind, error) = if is_url(&bare) {
+            (TokenKind::Url, false)
+        } else if typing.as_ref() == Some(&range) {
+            let kind = if index == 0 {
+                TokenKind::Program
+            } else {
+                TokenKind::Argument
+            };
+            (kind, false)
+        } else if index == 0 {
+            let unknown =
+                !bare.is_empty() && !is_network_path(&bare) && !resolver.is_program(&bare);
+            (TokenKind::Program, unknown)
+        } else if looks_like_path(&bare) && !is_network_path(&bare) {
+            (TokenKind::Argument, !resolver.path_exists(&bare))
+        } else {
+            (TokenKind::Argument, false)
+        };
+        push_segments(text, range, kind, error, &mut spans);
+    }
+    spans
+}
+
+/// Byte ranges of the words, split as `command_line::tokenize` does.
+pub fn words(text: &str) -> Vec<Range<usize>> {
+    tokenize(text)
+        .into_iter()
+        .map(|token| token.range)
+        .collect()
+}
+
+/// The word of `words` that `caret` is in or at either end of.
+pub fn word_at(words: &[Range<usize>], caret: usize) -> Option<Range<usize>> {
+    words
+        .iter()
+        .find(|word| word.start <= caret && caret <= word.end)
+        .cloned()
+}
+
+/// Same rule as the executor, plus `scheme:` URIs such as `ms-settings:` that
+/// `ShellExecute` opens. A drive letter is not a scheme.
+pub fn is_url(word: &str) -> bool {
+    if word.starts_with("http") || word.starts_with("www") || word.contains("://") {
+        return true;
+    }
+    match word.split_once(':') {
+        Some((scheme, _)) => {
+            scheme.len() > 1
+                && scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+                && scheme
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        }
+        None => false,
+    }
+}
+
+/// Arguments are only checked when they are plainly paths: `C:\…`, `.\…`, `%VAR%\…`.
+/// Switches like `/s` are not.
+fn looks_like_path(word: &str) -> bool {
+    let bytes = word.as_bytes();
+    let drive = bytes.len() >= 3
+        && bytes[0].is_ascii_alphabetic()
+        && bytes[1] == b':'
+        && matches!(bytes[2], b'\\' | b'/');
+    drive || word.contains('\\')
+}
+
+/// Not checked at all: reaching the server could stall typing.
+pub fn is_network_path(word: &str) -> bool {
+    word.starts_with("\\\\")
+}
+
+/// Splits one word into quoted strings, `%VAR%`s and the rest in `kind`.
+fn push_segments(
+    text: &str,
+    range: Range<usize>,
+    kind: TokenKind,
+    error: bool,
+    spans: &mut Vec<Span>,
+) {
+    let mut push = |range: Range<usize>, kind: TokenKind| match spans.last_mut() {
+        Some(last) if last.range.end == range.start && last.kind == kind => {
+            last.range.end = range.end
+        }
+        _ => spans.push(Span { range, kind, error }),
+    };
+
+    // `%20` in a URL is an escape, not a variable.
+    if kind == TokenKind::Url {
+        push(range, kind);
+        return;
+    }
+
+    let mut in_quotes = false;
+    let mut at = range.start;
+    while at < range.end {
+        let rest = &text[at..range.end];
+        let c = rest.chars().next().unwrap_or_default();
+        if c == '%'
+            && let Some(len) = variable_len(rest)
+        {
+            push(at..at + len, TokenKind::Variable);
+            at += len;
+            continue;
+        }
+        let quote = c == '"';
+        push(
+            at..at + c.len_utf8(),
+            if quote || in_quotes {
+                TokenKind::String
+            } else {
+                kind
+            },
+        );
+        in_quotes ^= quote;
+        at += c.len_utf8();
+    }
+}
+
+/// Length of the `%NAME%` at the start of `text`.
+fn variable_len(text: &str) -> Option<usize> {
+    let end = text[1..].find('%')? + 1;
+    let name = &text[1..end];
+    (!name.is_empty() && !name.contains(char::is_whitespace)).then_some(end + 1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::TokenKind::*;
+    use super::*;
+    use std::cell::Cell;
+
+    struct FakeResolver;
+
+    impl Resolver for FakeResolver {
+        fn is_program(&self, program: &str) -> bool {
+            matches!(
+                program.to_lowercase().as_str(),
+                "notepad" | "cmd" | "c:\\program files\\app.exe" | "%windir%\\notepad.exe"
+            )
+        }
+
+        fn path_exists(&self, path: &str) -> bool {
+            matches!(
+                path,
+                "C:\\Users" | "C:\\Program Files\\App.exe" | "%TEMP%\\a.txt" | "C:\\My Docs"
+            )
+        }
+    }
+
+    fn spans(text: &str) -> Vec<(&str, TokenKind, bool)> {
+        highlight(text, None, &FakeResolver)
+            .into_iter()
+            .map(|span| (&text[span.range], span.kind, span.error))
+            .collect()
+    }
+
+    #[test]
+    fn program_and_path_arguments() {
+        assert_eq!(
+            spans("notepad C:\\Users /s"),
+            vec![
+                ("notepad", Program, false),
+                ("C:\\Users", Argument, false),
+                ("/s", Argument, false),
+            ]
+        );
+        assert_eq!(
+            spans("nope C:\\Nothing"),
+            vec![("nope", Program, true), ("C:\\Nothing", Argument, true)]
+        );
+        assert!(spans("").is_empty());
+        assert!(spans("   ").is_empty());
+    }
+
+    #[test]
+    fn strings_and_variables() {
+        assert_eq!(
+            spans("cmd \"a b\" x"),
+            vec![
+                ("cmd", Program, false),
+                ("\"a b\"", String, false),
+                ("x", Argument, false),
+            ]
+        );
+        assert_eq!(
+            spans("cmd %TEMP%\\a.txt"),
+            vec![
+                ("cmd", Program, false),
+                ("%TEMP%", Variable, false),
+                ("\\a.txt", Argument, false),
+            ]
+        );
+        assert_eq!(
+            spans("%windir%\\notepad.exe"),
+            vec![
+                ("%windir%", Variable, false),
+                ("\\notepad.exe", Program, false)
+            ]
+        );
+        assert_eq!(
+            spans("cmd \"unclosed %X% y"),
+            vec![
+                ("cmd", Program, false),
+                ("\"unclosed ", String, false),
+                ("%X%", Variable, false),
+                (" y", String, false),
+            ]
+        );
+        // A lone percent sign is not a variable.
+        assert_eq!(
+            spans("cmd 50% off"),
+            vec![
+                ("cmd", Program, false),
+                ("50%", Argument, false),
+                ("off", Argument, false),
+            ]
+        );
+    }
+
+    #[test]
+    fn urls_and_network_paths_are_not_checked() {
+        assert_eq!(
+            spans("https://x.org/a%20b%20c"),
+            vec![("https://x.org/a%20b%20c", Url, false)]
+        );
+        assert_eq!(
+            spans("ms-settings:display"),
+            vec![("ms-settings:display", Url, false)]
+        );
+        assert_eq!(
+            spans("cmd \\\\server\\share"),
+            vec![
+                ("cmd", Program, false),
+                ("\\\\server\\share", Argument, false)
+            ]
+        );
+        assert!(!is_url("C:\\x"));
+        assert!(!is_url("notepad"));
+        assert!(is_url("mailto:a@b"));
+    }
+
+    #[test]
+    fn paths_with_spaces() {
+        assert_eq!(
+            spans("\"C:\\Program Files\\App.exe\" -v"),
+            vec![
+                ("\"C:\\Program Files\\App.exe\"", String, false),
+                ("-v", Argument, false),
+            ]
+        );
+        // An existing path typed without quotes runs as a whole.
+        assert_eq!(
+            spans("  C:\\My Docs "),
+            vec![("C:\\My Docs", Program, false)]
+        );
+        assert_eq!(words("a  \"b c\"d e"), vec![0..1, 3..9, 10..11]);
+    }
+
+    #[test]
+    fn the_word_being_typed_is_not_looked_up() {
+        fn typed(text: &str, caret: usize) -> Vec<(&str, bool)> {
+            highlight(text, Some(caret), &FakeResolver)
+                .into_iter()
+                .map(|span| (&text[span.range], span.error))
+                .collect()
+        }
+        assert_eq!(typed("notep", 5), [("notep", false)]);
+        assert_eq!(typed("notep ", 6), [("notep", true)]);
+        assert_eq!(
+            typed("nope C:\\Noth", 12),
+            [("nope", true), ("C:\\Noth", false)]
+        );
+        // Moving the caret back into a word leaves the others checked.
+        assert_eq!(
+            typed("nope C:\\Noth", 2),
+            [("nope", false), ("C:\\Noth", true)]
+        );
+        assert_eq!(word_at(&words("a bc"), 4), Some(2..4));
+        assert_eq!(word_at(&words("a  bc"), 2), None);
+    }
+
+    #[test]
+    fn path_cache_probes_each_path_once() {
+        let probes = Cell::new(0);
+        let probe = |path: &str| {
+            probes.set(probes.get() + 1);
+            path.eq_ignore_ascii_case("C:\\Users")
+        };
+        let mut cache = PathCache::default();
+        assert!(cache.exists("C:\\Users", probe));
+        assert!(cache.exists("c:\\users", probe));
+        assert!(!cache.exists("C:\\Nope", probe));
+        assert!(!cache.exists("C:\\Nope", probe));
+        assert_eq!(probes.get(), 2);
+    }
+
+    #[test]
+    fn path_cache_skips_missing_folders() {
+        let probes = Cell::new(0);
+        let probe = |_: &str| {
+            probes.set(probes.get() + 1);
+            false
+        };
+        let mut cache = PathCache::default();
+        assert!(!cache.exists("C:\\Nope", probe));
+        // Typing on inside the missing folder does not reach the disk.
+        for path in ["C:\\Nope\\", "C:\\Nope\\a", "C:\\Nope\\ab", "C:\\nope/x"] {
+            assert!(!cache.exists(path, probe));
+        }
+        assert_eq!(probes.get(), 1);
+        assert!(cache.exists("C:\\Users\\a", |_| true));
+    }
+}
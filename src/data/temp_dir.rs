@@ -0,0 +1,53 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+// ==================================================================================
+//  TEMP DIR
+//  A folder for tests that read the disk: named after the test and the process so
+//  parallel runs do not meet, emptied when created and removed when dropped.
+// ==================================================================================
+
+pub struct TempDir {
+    path: PathBuf,
+}
+
+impl TempDir {
+    /// An empty `<temp>/<name>_<process id>`.
+    pub fn new(name: &str) -> TempDir {
+        let path = std::env::temp_dir().join(format!("{}_{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&path);
+        fs::create_dir_all(&path).unwrap();
+        TempDir { path }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn join(&self, relative: &str) -> PathBuf {
+        self.path.join(relative)
+    }
+
+    /// Creates folder `relative` and the folders above it.
+    pub fn create_dir(&self, relative: &str) {
+        fs::create_dir_all(self.join(relative)).unwrap();
+    }
+
+    /// Writes file `relative`, creating the folders above it.
+    pub fn write(&self, relative: &str, text: &str) {
+        let path = self.join(relative);
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, text).unwrap();
+    }
+
+    /// The folder as a `[files]` or `[git]` root.
+    pub fn root(&self) -> String {
+        self.path.to_string_lossy().to_string()
+    }
+}
+
+impl Drop for TempDir {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.path);
+    }
+}
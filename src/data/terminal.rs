@@ -0,0 +1,162 @@
+use crate::data::command_line::quote;
+use crate::data::json::{Json, parse_jsonc};
+
+// ==================================================================================
+//  WINDOWS TERMINAL
+//  Profiles from Windows Terminal's `settings.json` (JSON with comments), for
+//  `term <profile> [<dir>]`. Older files keep the profiles in a plain array, newer
+//  ones under `profiles.list`; hidden ones are left out. Profile names may contain
+//  spaces, so the longest name that starts the arguments wins and the rest is the
+//  directory.
+// ==================================================================================
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TerminalProfile {
+    pub name: String,
+    /// What the profile runs, or the generator it comes from (e.g. WSL).
+    pub detail: String,
+}
+
+/// The visible profiles in `settings.json`, in file order.
+pub fn parse_terminal_settings(text: &str) -> Vec<TerminalProfile> {
+    let Ok(root) = parse_jsonc(text) else {
+        return Vec::new();
+    };
+    let list = match root.get("profiles") {
+        Some(Json::Array(items)) => &items[..],
+        Some(profiles) => profiles.get("list").map_or(&[][..], Json::items),
+        None => &[],
+    };
+
+    let mut profiles: Vec<TerminalProfile> = Vec::new();
+    for entry in list {
+        if entry.get("hidden").and_then(Json::as_bool) == Some(true) {
+            continue;
+        }
+        let Some(name) = entry.get("name").and_then(Json::as_str).map(str::trim) else {
+            continue;
+        };
+        if name.is_empty() || profiles.iter().any(|p| p.name.eq_ignore_ascii_case(name)) {
+            continue;
+        }
+        let detail = ["commandline", "source"]
+            .iter()
+            .find_map(|key| entry.get(key).and_then(Json::as_str))
+            .unwrap_or_default();
+        profiles.push(TerminalProfile {
+            name: name.to_string(),
+            detail: detail.to_string(),
+        });
+    }
+    profiles
+}
+
+/// `wt -p "<profile>" -d "<dir>"` for the arguments of `term`, or `None` when they
+/// do not start with a profile name. The name may be quoted.
+pub fn profile_command(args: &str, profiles: &[TerminalProfile]) -> Option<String> {
+    let args = args.trim();
+    let (profile, rest) = if let Some(quoted) = args.strip_prefix('"') {
+        let end = quoted.find('"')?;
+        let name = &quoted[..end];
+        let profile = profiles.iter().find(|p| same_name(&p.name, name))?;
+        (profile, &quoted[end + 1..])
+    } else {
+        profiles
+            .iter()
+            .filter(|p| {
+                args.get(..p.name.len())
+                    .is_some_and(|head| same_name(head, &p.name))
+                    && args[p.name.len()..]
+                        .chars()
+                        .next()
+                        .is_none_or(char::is_whitespace)
+            })
+            .max_by_key(|p| p.name.len())
+            .map(|p| (p, &args[p.name.len()..]))?
+    };
+
+    let dir = rest.trim().trim_matches('"');
+    let mut command = format!("wt -p {}", quote(&profile.name));
+    if !dir.is_empty() {
+        command.push_str(&format!(" -d {}", quote(dir)));
+    }
+    Some(command)
+}
+
+fn same_name(a: &str, b: &str) -> bool {
+    a.to_lowercase() == b.to_lowercase()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SETTINGS: &str = include_str!("../../tests/fixtures/terminal_settings.json");
+
+    fn names(profiles: &[TerminalProfile]) -> Vec<&str> {
+        profiles.iter().map(|p| p.name.as_str()).collect()
+    }
+
+    #[test]
+    fn reads_visible_profiles_from_jsonc() {
+        let profiles = parse_terminal_settings(SETTINGS);
+        // Hidden, blank, nameless and repeated names are left out.
+        assert_eq!(
+            names(&profiles),
+            [
+                "Windows PowerShell",
+                "Command Prompt",
+                "Ubuntu 22.04",
+                "Ubuntu",
+                "PowerShell"
+            ]
+        );
+        assert_eq!(profiles[1].detail, r"%SystemRoot%\System32\cmd.exe");
+        assert_eq!(profiles[3].detail, "Windows.Terminal.Wsl");
+    }
+
+    #[test]
+    fn reads_the_old_plain_array() {
+        let old = r#"{"profiles": [{"name": "cmd", "commandline": "cmd.exe"}]}"#;
+        assert_eq!(names(&parse_terminal_settings(old)), ["cmd"]);
+        assert!(parse_terminal_settings("{").is_empty());
+        assert!(parse_terminal_settings(r#"{"profiles": 3}"#).is_empty());
+    }
+
+    #[test]
+    fn builds_wt_command_lines() {
+        let profiles = parse_terminal_settings(SETTINGS);
+        let command = |args: &str| profile_command(args, &profiles);
+        assert_eq!(command("ubuntu").unwrap(), r#"wt -p "Ubuntu""#);
+        assert_eq!(command("Ubuntu 22.04").unwrap(), r#"wt -p "Ubuntu 22.04""#);
+        assert_eq!(
+            command("ubuntu 22.04 ~/src").unwrap(),
+            r#"wt -p "Ubuntu 22.04" -d "~/src""#
+        );
+        assert_eq!(
+            command(r"Ubuntu C:\src").unwrap(),
+            r#"wt -p "Ubuntu" -d "C:\src""#
+        );
+        assert_eq!(
+            command(r#"windows powershell "C:\My Files""#).unwrap(),
+            r#"wt -p "Windows PowerShell" -d "C:\My Files""#
+        );
+        // A drive root keeps its backslash instead of escaping the quote.
+        assert_eq!(
+            command(r#""command prompt" D:\"#).unwrap(),
+            r#"wt -p "Command Prompt" -d "D:\\""#
+        );
+        assert_eq!(command("  PowerShell  ").unwrap(), r#"wt -p "PowerShell""#);
+    }
+
+    #[test]
+    fn needs_a_known_profile() {
+        let profiles = parse_terminal_settings(SETTINGS);
+        let command = |args: &str| profile_command(args, &profiles);
+        assert_eq!(command("Ubuntux"), None);
+        assert_eq!(command(r#""Nope" x"#), None);
+        assert_eq!(command(r#""Ubuntu"#), None);
+        assert_eq!(command("nt -h"), None);
+        assert_eq!(command(""), None);
+    }
+}
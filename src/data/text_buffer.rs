@@ -0,0 +1,701 @@
+use std::ops::Range;
+
+use crate::config::UNDO_LIMIT;
+use crate::data::grapheme::*;
+
+// ==================================================================================
+//  TEXT BUFFER
+//  The single-line input: text, caret and selection, readline-style editing and
+//  undo/redo. Offsets are UTF-8 byte offsets that always sit on grapheme
+//  boundaries; the renderer converts them to UTF-16 for DirectWrite.
+// ==================================================================================
+
+/// Editing commands the keymap can bind, named after their readline equivalents.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum EditCommand {
+    BackwardChar,
+    ForwardChar,
+    BackwardWord,
+    ForwardWord,
+    BeginningOfLine,
+    EndOfLine,
+    SelectBackwardChar,
+    SelectForwardChar,
+    SelectBackwardWord,
+    SelectForwardWord,
+    SelectToBeginning,
+    SelectToEnd,
+    SelectAll,
+    BackwardDeleteChar,
+    DeleteChar,
+    /// Deletes back to the start of the word (letters, digits and `_`).
+    BackwardKillWord,
+    KillWord,
+    /// Deletes back to the previous whitespace, taking whole paths and switches.
+    UnixWordRubout,
+    UnixLineDiscard,
+    KillLine,
+    Undo,
+    Redo,
+}
+
+impl EditCommand {
+    pub const ALL: [EditCommand; 22] = [
+        EditCommand::BackwardChar,
+        EditCommand::ForwardChar,
+        EditCommand::BackwardWord,
+        EditCommand::ForwardWord,
+        EditCommand::BeginningOfLine,
+        EditCommand::EndOfLine,
+        EditCommand::SelectBackwardChar,
+        EditCommand::SelectForwardChar,
+        EditCommand::SelectBackwardWord,
+        EditCommand::SelectForwardWord,
+        EditCommand::SelectToBeginning,
+        EditCommand::SelectToEnd,
+        EditCommand::SelectAll,
+        EditCommand::BackwardDeleteChar,
+        EditCommand::DeleteChar,
+        EditCommand::BackwardKillWord,
+        EditCommand::KillWord,
+        EditCommand::UnixWordRubout,
+        EditCommand::UnixLineDiscard,
+        EditCommand::KillLine,
+        EditCommand::Undo,
+        EditCommand::Redo,
+    ];
+
+    pub fn key(self) -> &'static str {
+        match self {
+            EditCommand::BackwardChar => "backward-char",
+            EditCommand::ForwardChar => "forward-char",
+            EditCommand::BackwardWord => "backward-word",
+            EditCommand::ForwardWord => "forward-word",
+            EditCommand::BeginningOfLine => "beginning-of-line",
+            EditCommand::EndOfLine => "end-of-line",
+            EditCommand::SelectBackwardChar => "select-backward-char",
+            EditCommand::SelectForwardChar => "select-forward-char",
+            EditCommand::SelectBackwardWord => "select-backward-word",
+            EditCommand::SelectForwardWord => "select-forward-word",
+            EditCommand::SelectToBeginning => "select-to-beginning",
+            EditCommand::SelectToEnd => "select-to-end",
+            EditCommand::SelectAll => "select-all",
+            EditCommand::BackwardDeleteChar => "backward-delete-char",
+            EditCommand::DeleteChar => "delete-char",
+            EditCommand::BackwardKillWord => "backward-kill-word",
+            EditCommand::KillWord => "kill-word",
+            EditCommand::UnixWordRubout => "unix-word-rubout",
+            EditCommand::UnixLineDiscard => "unix-line-discard",
+            EditCommand::KillLine => "kill-line",
+            EditCommand::Undo => "undo",
+            EditCommand::Redo => "redo",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Motion {
+    CharLeft,
+    CharRight,
+    WordLeft,
+    WordRight,
+    WhitespaceLeft,
+    Start,
+    End,
+}
+
+/// Consecutive edits of the same kind share one undo step.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum EditKind {
+    Typing,
+    DeleteBack,
+    DeleteForward,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct Snapshot {
+    text: String,
+    caret: usize,
+    anchor: usize,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextBuffer {
+    text: String,
+    caret: usize,
+    // The other end of the selection; equal to `caret` when nothing is selected.
+    anchor: usize,
+    undo: Vec<Snapshot>,
+    redo: Vec<Snapshot>,
+    last_edit: Option<EditKind>,
+}
+
+impl Default for TextBuffer {
+    fn default() -> Self {
+        TextBuffer::new()
+    }
+}
+
+impl TextBuffer {
+    pub const fn new() -> TextBuffer {
+        TextBuffer {
+            text: String::new(),
+            caret: 0,
+            anchor: 0,
+            undo: Vec::new(),
+            redo: Vec::new(),
+            last_edit: None,
+        }
+    }
+
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    pub fn selection(&self) -> Range<usize> {
+        self.caret.min(self.anchor)..self.caret.max(self.anchor)
+    }
+
+    pub fn has_selection(&self) -> bool {
+        self.caret != self.anchor
+    }
+
+    pub fn selected_text(&self) -> &str {
+        &self.text[self.selection()]
+    }
+
+    /// Starts over with `text`, all selected so typing replaces it. Forgets undo.
+    pub fn reset(&mut self, text: &str) {
+        *self = TextBuffer::new();
+        self.text = text.to_string();
+        self.select_all();
+    }
+
+    /// Replaces the whole text as one undoable step and selects it.
+    pub fn set_text(&mut self, text: &str) {
+        if self.text != text {
+            self.push_undo(None);
+            self.text = text.to_string();
+        }
+        self.select_all();
+    }
+
+    /// Shows `text` with `selection` selected without recording an undo step, for
+    /// previews such as history search. Undo skips over it to the last real edit.
+    pub fn preview(&mut self, text: &str, selection: Range<usize>) {
+        self.text = text.to_string();
+        self.anchor = floor_boundary(&self.text, selection.start);
+        self.caret = floor_boundary(&self.text, selection.end);
+        self.last_edit = None;
+    }
+
+    pub fn select_all(&mut self) {
+        self.anchor = 0;
+        self.caret = self.text.len();
+        self.last_edit = None;
+    }
+
+    /// Moves the caret to the grapheme boundary at or before `at`, keeping the anchor
+    /// when `extend` is set (Shift+click, dragging).
+    pub fn set_caret(&mut self, at: usize, extend: bool) {
+        self.caret = floor_boundary(&self.text, at);
+        if !extend {
+            self.anchor = self.caret;
+        }
+        self.last_edit = None;
+    }
+
+    /// Selects the word or the run of spaces/punctuation around `at` (double-click).
+    pub fn select_word_at(&mut self, at: usize) {
+        if self.text.is_empty() {
+            return;
+        }
+        let mut start = floor_boundary(&self.text, at);
+        if start == self.text.len() {
+            start = prev_boundary(&self.text, start);
+        }
+        let class = char_class(&self.text[start..next_boundary(&self.text, start)]);
+        while start > 0 {
+            let prev = prev_boundary(&self.text, start);
+            if char_class(&self.text[prev..start]) != class {
+                break;
+            }
+            start = prev;
+        }
+        let mut end = start;
+        while end < self.text.len() {
+            let next = next_boundary(&self.text, end);
+            if char_class(&self.text[end..next]) != class {
+                break;
+            }
+            end = next;
+        }
+        self.anchor = start;
+        self.caret = end;
+        self.last_edit = None;
+    }
+
+    /// Types `text` over the selection. Control characters are dropped, so this
+    /// is also how pasted text goes in. Returns whether the text changed.
+    pub fn insert(&mut self, text: &str) -> bool {
+        let text: String = text.chars().filter(|c| !c.is_control()).collect();
+        if text.is_empty() && !self.has_selection() {
+            return false;
+        }
+        // A space closes the word being typed, so undo goes back word by word.
+        let kind = (!self.has_selection() && !text.starts_with(char::is_whitespace))
+            .then_some(EditKind::Typing);
+        self.push_undo(kind);
+        let range = self.selection();
+        self.text.replace_range(range.clone(), &text);
+        // Typing a letter before a combining mark puts the caret after the whole grapheme.
+        self.caret = ceil_boundary(&self.text, range.start + text.len());
+        self.anchor = self.caret;
+        true
+    }
+
+    /// Runs an editing command. Returns whether the text changed.
+    pub fn apply(&mut self, command: EditCommand) -> bool {
+        match command {
+            EditCommand::BackwardChar => self.move_caret(Motion::CharLeft, false),
+            EditCommand::ForwardChar => self.move_caret(Motion::CharRight, false),
+            EditCommand::BackwardWord => self.move_caret(Motion::WordLeft, false),
+            EditCommand::ForwardWord => self.move_caret(Motion::WordRight, false),
+            EditCommand::BeginningOfLine => self.move_caret(Motion::Start, false),
+            EditCommand::EndOfLine => self.move_caret(Motion::End, false),
+            EditCommand::SelectBackwardChar => self.move_caret(Motion::CharLeft, true),
+            EditCommand::SelectForwardChar => self.move_caret(Motion::CharRight, true),
+            EditCommand::SelectBackwardWord => self.move_caret(Motion::WordLeft, true),
+            EditCommand::SelectForwardWord => self.move_caret(Motion::WordRight, true),
+            EditCommand::SelectToBeginning => self.move_caret(Motion::Start, true),
+            EditCommand::SelectToEnd => self.move_caret(Motion::End, true),
+            EditCommand::SelectAll => {
+                self.select_all();
+                false
+            }
+            EditCommand::BackwardDeleteChar => {
+                self.delete(Motion::CharLeft, Some(EditKind::DeleteBack))
+            }
+            EditCommand::DeleteChar => {
+                self.delete(Motion::CharRight, Some(EditKind::DeleteForward))
+            }
+            EditCommand::BackwardKillWord => self.delete(Motion::WordLeft, None),
+            EditCommand::KillWord => self.delete(Motion::WordRight, None),
+            EditCommand::UnixWordRubout => self.delete(Motion::WhitespaceLeft, None),
+            EditCommand::UnixLineDiscard => self.delete(Motion::Start, None),
+            EditCommand::KillLine => self.delete(Motion::End, None),
+            EditCommand::Undo => self.undo(),
+            EditCommand::Redo => self.redo(),
+        }
+    }
+
+    pub fn undo(&mut self) -> bool {
+        let Some(snapshot) = self.undo.pop() else {
+            return false;
+        };
+        let current = self.restore(snapshot);
+        self.redo.push(current);
+        true
+    }
+
+    pub fn redo(&mut self) -> bool {
+        let Some(snapshot) = self.redo.pop() else {
+            return false;
+        };
+        let current = self.restore(snapshot);
+        self.undo.push(current);
+        true
+    }
+
+    fn restore(&mut self, snapshot: Snapshot) -> Snapshot {
+        self.last_edit = None;
+        let current = self.snapshot();
+        self.text = snapshot.text;
+        self.caret = snapshot.caret;
+        self.anchor = snapshot.anchor;
+        current
+    }
+
+    fn snapshot(&self) -> Snapshot {
+        Snapshot {
+            text: self.text.clone(),
+            caret: self.caret,
+            anchor: self.anchor,
+        }
+    }
+
+    /// Records the state before an edit, unless it continues the previous edit.
+    fn push_undo(&mut self, kind: Option<EditKind>) {
+        self.redo.clear();
+        if kind.is_none() || kind != self.last_edit {
+            self.undo.push(self.snapshot());
+            if self.undo.len() > UNDO_LIMIT {
+                self.undo.remove(0);
+            }
+        }
+        self.last_edit = kind;
+    }
+
+    fn move_caret(&mut self, motion: Motion, extend: bool) -> bool {
+        let target = if !extend && self.has_selection() {
+            // Left/Right collapse the selection to the side they point at.
+            match motion {
+                Motion::CharLeft => self.selection().start,
+                Motion::CharRight => self.selection().end,
+                _ => self.target(motion),
+            }
+        } else {
+            self.target(motion)
+        };
+        self.caret = target;
+        if !extend {
+            self.anchor = target;
+        }
+        self.last_edit = None;
+        false
+    }
+
+    fn delete(&mut self, motion: Motion, kind: Option<EditKind>) -> bool {
+        let range = if self.has_selection() {
+            self.selection()
+        } else {
+            let target = self.target(motion);
+            self.caret.min(target)..self.caret.max(target)
+        };
+        if range.is_empty() {
+            return false;
+        }
+        self.push_undo(if self.has_selection() { None } else { kind });
+        self.text.replace_range(range.clone(), "");
+        self.caret = range.start;
+        self.anchor = range.start;
+        true
+    }
+
+    fn target(&self, motion: Motion) -> usize {
+        let text = &self.text;
+        let at = self.caret;
+        match motion {
+            Motion::CharLeft => prev_boundary(text, at),
+            Motion::CharRight => next_boundary(text, at),
+            Motion::Start => 0,
+            Motion::End => text.len(),
+            Motion::WordLeft => {
+                // Skip separators, then the word itself.
+                let mut pos = at;
+                while pos > 0 && !is_word(&text[prev_boundary(text, pos)..pos]) {
+                    pos = prev_boundary(text, pos);
+                }
+                while pos > 0 && is_word(&text[prev_boundary(text, pos)..pos]) {
+                    pos = prev_boundary(text, pos);
+                }
+                pos
+            }
+            Motion::WordRight => {
+                let mut pos = at;
+                while pos < text.len() && !is_word(&text[pos..next_boundary(text, pos)]) {
+                    pos = next_boundary(text, pos);
+                }
+                while pos < text.len() && is_word(&text[pos..next_boundary(text, pos)]) {
+                    pos = next_boundary(text, pos);
+                }
+                pos
+            }
+            Motion::WhitespaceLeft => {
+                let mut pos = at;
+                while pos > 0 && is_space(&text[prev_boundary(text, pos)..pos]) {
+                    pos = prev_boundary(text, pos);
+                }
+                while pos > 0 && !is_space(&text[prev_boundary(text, pos)..pos]) {
+                    pos = prev_boundary(text, pos);
+                }
+                pos
+            }
+        }
+    }
+}
+
+fn is_word(grapheme: &str) -> bool {
+    grapheme
+        .chars()
+        .next()
+        .is_some_and(|c| c.is_alphanumeric() || c == '_')
+}
+
+fn is_space(grapheme: &str) -> bool {
+    grapheme.chars().next().is_some_and(char::is_whitespace)
+}
+
+#[derive(PartialEq, Eq)]
+enum CharClass {
+    Word,
+    Space,
+    Other,
+}
+
+fn char_class(grapheme: &str) -> CharClass {
+    if is_word(grapheme) {
+        CharClass::Word
+    } else if is_space(grapheme) {
+        CharClass::Space
+    } else {
+        CharClass::Other
+    }
+}
+
+/// UTF-16 offset of byte offset `byte`, as DirectWrite counts text positions.
+pub fn utf16_offset(text: &str, byte: usize) -> usize {
+    text[..byte.min(text.len())].encode_utf16().count()
+}
+
+/// Byte offset of UTF-16 offset `utf16`; one inside a surrogate pair rounds down.
+pub fn byte_offset(text: &str, utf16: usize) -> usize {
+    let mut units = 0;
+    for (byte, c) in text.char_indices() {
+        units += c.len_utf16();
+        if units > utf16 {
+            return byte;
+        }
+    }
+    text.len()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EditCommand::*;
+    use super::*;
+
+    fn buffer(text: &str, caret: usize) -> TextBuffer {
+        let mut buffer = TextBuffer::new();
+        buffer.reset(text);
+        buffer.set_caret(caret, false);
+        buffer
+    }
+
+    fn type_text(buffer: &mut TextBuffer, text: &str) {
+        for c in text.chars() {
+            buffer.insert(&c.to_string());
+        }
+    }
+
+    #[test]
+    fn caret_moves_by_grapheme() {
+        let mut b = buffer("ae\u{301}👍🏽", 0);
+        b.apply(ForwardChar);
+        assert_eq!(b.caret(), 1);
+        b.apply(ForwardChar);
+        assert_eq!(b.caret(), 4);
+        b.apply(ForwardChar);
+        assert_eq!(b.caret(), b.text().len());
+        assert!(!b.apply(ForwardChar));
+        b.apply(BackwardDeleteChar);
+        assert_eq!(b.text(), "ae\u{301}");
+        b.apply(BackwardChar);
+        b.apply(DeleteChar);
+        assert_eq!(b.text(), "a");
+        b.set_caret(0, false);
+        assert!(!b.apply(BackwardChar));
+    }
+
+    #[test]
+    fn word_jumps_stop_at_punctuation() {
+        let text = "git commit -m \"fix: bug\"";
+        let mut b = buffer(text, text.len());
+        b.apply(BackwardWord);
+        assert_eq!(&text[b.caret()..], "bug\"");
+        b.apply(BackwardWord);
+        assert_eq!(&text[b.caret()..], "fix: bug\"");
+        b.apply(BackwardWord);
+        assert_eq!(&text[b.caret()..], "m \"fix: bug\"");
+        b.apply(ForwardWord);
+        assert_eq!(&text[b.caret()..], " \"fix: bug\"");
+        b.set_caret(0, false);
+        b.apply(ForwardWord);
+        assert_eq!(b.caret(), 3);
+    }
+
+    #[test]
+    fn word_kills() {
+        let mut b = buffer(r"C:\Program Files\app.exe --flag", 31);
+        b.apply(UnixWordRubout);
+        assert_eq!(b.text(), r"C:\Program Files\app.exe ");
+        b.apply(UnixWordRubout);
+        assert_eq!(b.text(), r"C:\Program ");
+        b.apply(BackwardKillWord);
+        assert_eq!(b.text(), r"C:\");
+        b.apply(BackwardKillWord);
+        assert_eq!(b.text(), "");
+        assert!(!b.apply(BackwardKillWord));
+
+        // Accented words stay whole.
+        let mut b = buffer("cafe\u{301} bar", 6);
+        b.apply(BackwardKillWord);
+        assert_eq!(b.text(), " bar");
+    }
+
+    #[test]
+    fn line_kills() {
+        let mut b = buffer("one two three", 4);
+        b.apply(KillWord);
+        assert_eq!(b.text(), "one  three");
+        b.apply(KillLine);
+        assert_eq!(b.text(), "one ");
+        b.apply(EndOfLine);
+        b.insert("x");
+        b.set_caret(2, false);
+        b.apply(UnixLineDiscard);
+        assert_eq!((b.text(), b.caret()), ("e x", 0));
+
+        // With a selection, a kill deletes the selection.
+        let mut b = buffer("abc def", 0);
+        b.apply(SelectForwardChar);
+        b.apply(UnixLineDiscard);
+        assert_eq!(b.text(), "bc def");
+    }
+
+    #[test]
+    fn selection_extends_and_collapses() {
+        let mut b = buffer("hello world", 5);
+        b.apply(SelectForwardWord);
+        assert_eq!(b.selected_text(), " world");
+        b.apply(BackwardChar);
+        assert_eq!((b.caret(), b.has_selection()), (5, false));
+        b.apply(SelectToBeginning);
+        assert_eq!(b.selected_text(), "hello");
+        b.apply(ForwardChar);
+        assert_eq!(b.caret(), 5);
+        b.apply(SelectAll);
+        assert_eq!(b.selection(), 0..11);
+        assert!(b.insert("x"));
+        assert_eq!(b.text(), "x");
+        b.apply(SelectBackwardChar);
+        b.apply(SelectForwardChar);
+        assert!(!b.has_selection());
+    }
+
+    #[test]
+    fn clicks_select_words() {
+        let mut b = buffer("open C:\\my files\\x.txt", 0);
+        b.select_word_at(8);
+        assert_eq!(b.selected_text(), "my");
+        b.select_word_at(4);
+        assert_eq!(b.selected_text(), " ");
+        b.select_word_at(100);
+        assert_eq!(b.selected_text(), "txt");
+        b.set_caret(3, false);
+        b.set_caret(0, true);
+        assert_eq!(b.selected_text(), "ope");
+    }
+
+    #[test]
+    fn undo_and_redo_whole_words() {
+        let mut b = TextBuffer::new();
+        type_text(&mut b, "git status");
+        assert!(b.apply(Undo));
+        assert_eq!(b.text(), "git ");
+        assert!(b.apply(Undo));
+        assert_eq!(b.text(), "git");
+        assert!(b.apply(Undo));
+        assert_eq!(b.text(), "");
+        assert!(!b.apply(Undo));
+        assert!(b.apply(Redo));
+        assert_eq!((b.text(), b.caret()), ("git", 3));
+        b.apply(Redo);
+        b.apply(Redo);
+        assert_eq!(b.text(), "git status");
+        assert!(!b.apply(Redo));
+    }
+
+    #[test]
+    fn deletes_coalesce_and_new_edits_drop_redo() {
+        let mut b = TextBuffer::new();
+        type_text(&mut b, "git status");
+        b.apply(BackwardDeleteChar);
+        b.apply(BackwardDeleteChar);
+        b.apply(Undo);
+        assert_eq!(b.text(), "git status");
+        b.apply(BackwardKillWord);
+        assert_eq!(b.text(), "git ");
+        b.apply(Undo);
+        b.insert("!");
+        assert!(!b.apply(Redo));
+    }
+
+    #[test]
+    fn moving_the_caret_splits_typing() {
+        let mut b = TextBuffer::new();
+        b.insert("a");
+        b.insert("b");
+        b.apply(BackwardChar);
+        b.insert("c");
+        b.apply(Undo);
+        assert_eq!((b.text(), b.caret()), ("ab", 1));
+        // `set_text` is undoable, `reset` is not.
+        b.set_text("notepad");
+        assert_eq!(b.selection(), 0..7);
+        b.apply(Undo);
+        assert_eq!(b.text(), "ab");
+        b.reset("calc");
+        assert!(!b.apply(Undo));
+    }
+
+    #[test]
+    fn undo_history_is_limited() {
+        let mut b = TextBuffer::new();
+        for i in 0..150 {
+            b.set_text(&i.to_string());
+        }
+        let mut undone = 0;
+        while b.apply(Undo) {
+            undone += 1;
+        }
+        assert_eq!(undone, 100);
+        assert_eq!(b.text(), "49");
+    }
+
+    #[test]
+    fn insert_filters_controls_and_keeps_graphemes_whole() {
+        let mut b = TextBuffer::new();
+        assert!(!b.insert("\u{7}"));
+        b.insert("a\tb\r\nc");
+        assert_eq!(b.text(), "abc");
+        b.set_caret(1, false);
+        b.insert("e");
+        b.insert("\u{301}");
+        assert_eq!((b.text(), b.caret()), ("ae\u{301}bc", 4));
+
+        b.reset("e\u{301}x");
+        b.set_caret(0, false);
+        b.insert("q");
+        assert_eq!(b.caret(), 1);
+        // A base typed before a lone mark: the caret goes after the whole grapheme.
+        b.reset("\u{301}x");
+        b.set_caret(0, false);
+        b.insert("e");
+        assert_eq!(b.caret(), 3);
+        b.set_caret(2, false);
+        assert_eq!(b.caret(), 0);
+    }
+
+    #[test]
+    fn utf16_offsets() {
+        let text = "a😀é";
+        assert_eq!(utf16_offset(text, 0), 0);
+        assert_eq!(utf16_offset(text, 1), 1);
+        assert_eq!(utf16_offset(text, 5), 3);
+        assert_eq!(utf16_offset(text, text.len()), 4);
+        assert_eq!(byte_offset(text, 1), 1);
+        // Inside a surrogate pair.
+        assert_eq!(byte_offset(text, 2), 1);
+        assert_eq!(byte_offset(text, 3), 5);
+        assert_eq!(byte_offset(text, 99), text.len());
+    }
+}
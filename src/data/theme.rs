@@ -0,0 +1,508 @@
+#![allow(static_mut_refs)]
+
+use std::sync::OnceLock;
+
+use crate::config::*;
+use crate::data::contrast::HIGH_CONTRAST_THEME;
+use crate::data::ini::*;
+
+// ==================================================================================
+//  THEMES
+//  A theme overrides the design tokens of `config.rs`: light and dark palettes,
+//  accent, fonts, corner radii and the window backdrop. Missing keys keep the
+//  defaults, so a theme file only needs to list what it changes.
+// ==================================================================================
+
+pub const THEMES_DIR: &str = "themes";
+pub const DEFAULT_THEME: &str = "fluent";
+
+pub const BUNDLED_THEMES: &[(&str, &str)] = &[
+    ("fluent", include_str!("../../res/themes/fluent.ini")),
+    ("nord", include_str!("../../res/themes/nord.ini")),
+    ("solarized", include_str!("../../res/themes/solarized.ini")),
+];
+
+pub static mut ACTIVE_THEME: Option<Theme> = None;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+    pub a: f32,
+}
+
+impl Color {
+    pub const fn gray(level: f32) -> Self {
+        Color {
+            r: level,
+            g: level,
+            b: level,
+            a: 1.0,
+        }
+    }
+
+    pub const fn rgb(r: f32, g: f32, b: f32) -> Self {
+        Color { r, g, b, a: 1.0 }
+    }
+
+    pub const fn with_alpha(self, a: f32) -> Self {
+        Color { a, ..self }
+    }
+
+    /// Scales the existing alpha, so translucent theme colours stay translucent.
+    pub fn fade(self, opacity: f32) -> Self {
+        Color {
+            a: self.a * opacity,
+            ..self
+        }
+    }
+
+    /// Reads the `0x00BBGGRR` layout used by DWM accent tints.
+    pub fn from_abgr(value: u32) -> Self {
+        Color {
+            r: (value & 0xFF) as f32 / 255.0,
+            g: ((value >> 8) & 0xFF) as f32 / 255.0,
+            b: ((value >> 16) & 0xFF) as f32 / 255.0,
+            a: ((value >> 24) & 0xFF) as f32 / 255.0,
+        }
+    }
+
+    pub fn to_abgr(self) -> u32 {
+        let c = |v: f32| (v.clamp(0.0, 1.0) * 255.0).round() as u32;
+        (c(self.a) << 24) | (c(self.b) << 16) | (c(self.g) << 8) | c(self.r)
+    }
+
+    /// Parses `#RRGGBB` or `#RRGGBBAA`.
+    pub fn parse_hex(value: &str) -> Option<Self> {
+        let hex = value.strip_prefix('#')?;
+        if !(hex.len() == 6 || hex.len() == 8) || !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+            return None;
+        }
+        let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).ok();
+        let alpha = if hex.len() == 8 { channel(6)? } else { 255 };
+        Some(Color {
+            r: channel(0)? as f32 / 255.0,
+            g: channel(2)? as f32 / 255.0,
+            b: channel(4)? as f32 / 255.0,
+            a: alpha as f32 / 255.0,
+        })
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub background: Color,
+    pub text: Color,
+    pub text_secondary: Color,
+    pub button: Color,
+    pub button_hover: Color,
+    pub border: Color,
+    pub input_background: Color,
+    pub backdrop_tint: Color,
+    /// Text drawn on `button_hover` and destructive fills.
+    pub hover_text: Color,
+    /// Text drawn on accent fills and opaque selections.
+    pub accent_text: Color,
+    /// Command line highlighting; arguments use `text`, errors `destructive`.
+    pub syntax_program: Color,
+    pub syntax_string: Color,
+    pub syntax_variable: Color,
+    pub syntax_url: Color,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccentSource {
+    System,
+    Custom(Color),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Backdrop {
+    Acrylic,
+    Mica,
+    Solid,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ThemeFonts {
+    pub display: String,
+    pub text: String,
+    pub small: String,
+    pub title_size: f32,
+    pub label_size: f32,
+    pub button_size: f32,
+    pub input_size: f32,
+    pub tooltip_size: f32,
+    pub tooltip_title_size: f32,
+    pub dialog_message_size: f32,
+    pub dialog_button_size: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Theme {
+    pub name: String,
+    pub dark: Palette,
+    pub light: Palette,
+    pub accent: AccentSource,
+    pub destructive: Color,
+    pub warning: Color,
+    pub fonts: ThemeFonts,
+    pub corner_radius: f32,
+    pub tooltip_corner_radius: f32,
+    pub backdrop: Backdrop,
+    /// Derived from system colours; disables every translucent fill.
+    pub high_contrast: bool,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Theme {
+            name: "Fluent".to_string(),
+            dark: Palette {
+                background: Color::gray(COLOR_DARK_BG),
+                text: Color::gray(COLOR_DARK_TEXT),
+                text_secondary: Color::gray(COLOR_DARK_TEXT_SEC),
+                button: Color::gray(COLOR_DARK_BTN),
+                button_hover: Color::gray(COLOR_DARK_BTN_HOVER),
+                border: Color::gray(COLOR_DARK_BORDER),
+                input_background: Color::gray(COLOR_INPUT_BG_DARK)
+                    .with_alpha(COLOR_INPUT_BG_OPACITY),
+                backdrop_tint: Color::from_abgr(ACRYLIC_TINT_DARK),
+                hover_text: Color::gray(COLOR_DARK_TEXT),
+                accent_text: Color::gray(COLOR_DARK_TEXT),
+                syntax_program: Color::from_abgr(COLOR_SYNTAX_PROGRAM_DARK),
+                syntax_string: Color::from_abgr(COLOR_SYNTAX_STRING_DARK),
+                syntax_variable: Color::from_abgr(COLOR_SYNTAX_VARIABLE_DARK),
+                syntax_url: Color::from_abgr(COLOR_SYNTAX_URL_DARK),
+            },
+            light: Palette {
+                background: Color::gray(COLOR_LIGHT_BG),
+                text: Color::gray(COLOR_LIGHT_TEXT),
+                text_secondary: Color::gray(COLOR_LIGHT_TEXT_SEC),
+                button: Color::gray(COLOR_LIGHT_BTN),
+                button_hover: Color::gray(COLOR_LIGHT_BTN_HOVER),
+                border: Color::gray(COLOR_LIGHT_BORDER),
+                input_background: Color::gray(COLOR_INPUT_BG_LIGHT)
+                    .with_alpha(COLOR_INPUT_BG_OPACITY),
+                backdrop_tint: Color::from_abgr(ACRYLIC_TINT_LIGHT),
+                hover_text: Color::gray(COLOR_LIGHT_TEXT),
+                accent_text: Color::gray(COLOR_LIGHT_TEXT),
+                syntax_program: Color::from_abgr(COLOR_SYNTAX_PROGRAM_LIGHT),
+                syntax_string: Color::from_abgr(COLOR_SYNTAX_STRING_LIGHT),
+                syntax_variable: Color::from_abgr(COLOR_SYNTAX_VARIABLE_LIGHT),
+                syntax_url: Color::from_abgr(COLOR_SYNTAX_URL_LIGHT),
+            },
+            accent: AccentSource::System,
+            destructive: Color::rgb(
+                COLOR_DESTRUCTIVE_R,
+                COLOR_DESTRUCTIVE_G,
+                COLOR_DESTRUCTIVE_B,
+            ),
+            warning: Color::rgb(COLOR_WARNING_R, COLOR_WARNING_G, COLOR_WARNING_B),
+            fonts: ThemeFonts {
+                display: FONT_DISPLAY.to_string(),
+                text: FONT_TEXT.to_string(),
+                small: FONT_SMALL.to_string(),
+                title_size: FONT_SZ_TITLE,
+                label_size: FONT_SZ_LABEL,
+                button_size: FONT_SZ_BUTTON,
+                input_size: FONT_SZ_BUTTON,
+                tooltip_size: FONT_SZ_TOOLTIP,
+                tooltip_title_size: FONT_SZ_TOOLTIP_BOLD,
+                dialog_message_size: FONT_SZ_DIALOG_MSG,
+                dialog_button_size: FONT_SZ_DIALOG_BTN,
+            },
+            corner_radius: CORNER_RADIUS,
+            tooltip_corner_radius: TOOLTIP_CORNER_RADIUS,
+            backdrop: Backdrop::Acrylic,
+            high_contrast: false,
+        }
+    }
+}
+
+impl Theme {
+    pub fn palette(&self, is_dark: bool) -> &Palette {
+        if is_dark { &self.dark } else { &self.light }
+    }
+}
+
+/// Theme to paint with: the high-contrast palette while Windows high contrast is on,
+/// otherwise the user theme, falling back to the built-in Fluent look until config has
+/// been loaded.
+pub fn theme() -> &'static Theme {
+    static DEFAULT: OnceLock<Theme> = OnceLock::new();
+    unsafe { HIGH_CONTRAST_THEME.as_ref().or(ACTIVE_THEME.as_ref()) }
+        .unwrap_or_else(|| DEFAULT.get_or_init(Theme::default))
+}
+
+pub fn bundled_theme(name: &str) -> Option<&'static str> {
+    BUNDLED_THEMES
+        .iter()
+        .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        .map(|(_, text)| *text)
+}
+
+/// Theme names must be plain file stems so they cannot escape the themes folder.
+pub fn is_valid_theme_name(name: &str) -> bool {
+    !name.is_empty()
+        && name
+            .chars()
+            .all(|c| c.is_alphanumeric() || c == '-' || c == '_')
+}
+
+pub fn parse_theme(file: &str, text: &str) -> Result<Theme, ConfigError> {
+    let doc = parse_ini(file, text)?;
+    let mut theme = Theme::default();
+    // `hover_text` and `accent_text` follow `text` unless a palette sets them.
+    let mut explicit: Vec<(String, String)> = Vec::new();
+
+    for section in &doc.sections {
+        let name = section.name.to_ascii_lowercase();
+        if !matches!(name.as_str(), "" | "theme" | "fonts" | "dark" | "light") {
+            return Err(ConfigError::new(
+                file,
+                section.line,
+                format!("unknown section [{}]", section.name),
+            ));
+        }
+        for entry in &section.entries {
+            let key = entry.key.to_ascii_lowercase();
+            match (name.as_str(), key.as_str()) {
+                ("theme", "name") => theme.name = non_empty(file, entry)?,
+                ("theme", "backdrop") => {
+                    theme.backdrop = match entry.value.to_ascii_lowercase().as_str() {
+                        "acrylic" => Backdrop::Acrylic,
+                        "mica" => Backdrop::Mica,
+                        "solid" => Backdrop::Solid,
+                        _ => {
+                            return Err(ConfigError::new(
+                                file,
+                                entry.line,
+                                format!(
+                                    "backdrop must be acrylic, mica or solid, found '{}'",
+                                    entry.value
+                                ),
+                            ));
+                        }
+                    }
+                }
+                ("theme", "accent") => {
+                    theme.accent = if entry.value.eq_ignore_ascii_case("system") {
+                        AccentSource::System
+                    } else {
+                        AccentSource::Custom(parse_color(file, entry)?)
+                    }
+                }
+                ("theme", "destructive") => theme.destructive = parse_color(file, entry)?,
+                ("theme", "warning") => theme.warning = parse_color(file, entry)?,
+                ("theme", "corner_radius") => {
+                    theme.corner_radius = parse_ranged(file, entry, 0.0, 20.0)?
+                }
+                ("theme", "tooltip_corner_radius") => {
+                    theme.tooltip_corner_radius = parse_ranged(file, entry, 0.0, 20.0)?
+                }
+                ("fonts", "display") => theme.fonts.display = non_empty(file, entry)?,
+                ("fonts", "text") => theme.fonts.text = non_empty(file, entry)?,
+                ("fonts", "small") => theme.fonts.small = non_empty(file, entry)?,
+                ("fonts", k) if k.ends_with("_size") => {
+                    let size = parse_ranged(file, entry, 6.0, 48.0)?;
+                    let slot = match k {
+                        "title_size" => &mut theme.fonts.title_size,
+                        "label_size" => &mut theme.fonts.label_size,
+                        "button_size" => &mut theme.fonts.button_size,
+                        "input_size" => &mut theme.fonts.input_size,
+                        "tooltip_size" => &mut theme.fonts.tooltip_size,
+                        "tooltip_title_size" => &mut theme.fonts.tooltip_title_size,
+                        "dialog_message_size" => &mut theme.fonts.dialog_message_size,
+                        "dialog_button_size" => &mut theme.fonts.dialog_button_size,
+                        _ => return Err(unknown_key(file, entry)),
+                    };
+                    *slot = size;
+                }
+                ("dark", _) => {
+                    set_palette_color(&mut theme.dark, file, entry)?;
+                    explicit.push((name.clone(), key.clone()));
+                }
+                ("light", _) => {
+                    set_palette_color(&mut theme.light, file, entry)?;
+                    explicit.push((name.clone(), key.clone()));
+                }
+                ("", _) => {
+                    return Err(ConfigError::new(
+                        file,
+                        entry.line,
+                        format!("'{}' must be inside a [section]", entry.key),
+                    ));
+                }
+                _ => return Err(unknown_key(file, entry)),
+            }
+        }
+    }
+
+    for (name, palette) in [("dark", &mut theme.dark), ("light", &mut theme.light)] {
+        let is_explicit = |key: &str| explicit.iter().any(|(n, k)| n == name && k == key);
+        if !is_explicit("hover_text") {
+            palette.hover_text = palette.text;
+        }
+        if !is_explicit("accent_text") {
+            palette.accent_text = palette.text;
+        }
+    }
+
+    Ok(theme)
+}
+
+fn set_palette_color(
+    palette: &mut Palette,
+    file: &str,
+    entry: &IniEntry,
+) -> Result<(), ConfigError> {
+    let slot = match entry.key.to_ascii_lowercase().as_str() {
+        "background" => &mut palette.background,
+        "text" => &mut palette.text,
+        "text_secondary" => &mut palette.text_secondary,
+        "button" => &mut palette.button,
+        "button_hover" => &mut palette.button_hover,
+        "border" => &mut palette.border,
+        "input_background" => &mut palette.input_background,
+        "backdrop_tint" => &mut palette.backdrop_tint,
+        "hover_text" => &mut palette.hover_text,
+        "accent_text" => &mut palette.accent_text,
+        "syntax_program" => &mut palette.syntax_program,
+        "syntax_string" => &mut palette.syntax_string,
+        "syntax_variable" => &mut palette.syntax_variable,
+        "syntax_url" => &mut palette.syntax_url,
+        _ => return Err(unknown_key(file, entry)),
+    };
+    *slot = parse_color(file, entry)?;
+    Ok(())
+}
+
+fn parse_color(file: &str, entry: &IniEntry) -> Result<Color, ConfigError> {
+    Color::parse_hex(&entry.value).ok_or_else(|| {
+        ConfigError::new(
+            file,
+            entry.line,
+            format!(
+                "'{}' expects a colour like #RRGGBB or #RRGGBBAA, found '{}'",
+                entry.key, entry.value
+            ),
+        )
+    })
+}
+
+fn parse_ranged(file: &str, entry: &IniEntry, min: f32, max: f32) -> Result<f32, ConfigError> {
+    let value: f32 = parse_number(file, entry)?;
+    if !(min..=max).contains(&value) {
+        return Err(ConfigError::new(
+            file,
+            entry.line,
+            format!("'{}' must be between {} and {}", entry.key, min, max),
+        ));
+    }
+    Ok(value)
+}
+
+fn non_empty(file: &str, entry: &IniEntry) -> Result<String, ConfigError> {
+    if entry.value.is_empty() {
+        return Err(ConfigError::new(
+            file,
+            entry.line,
+            format!("'{}' cannot be empty", entry.key),
+        ));
+    }
+    Ok(entry.value.clone())
+}
+
+fn unknown_key(file: &str, entry: &IniEntry) -> ConfigError {
+    ConfigError::new(file, entry.line, format!("unknown key '{}'", entry.key))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_themes_parse() {
+        for (name, text) in BUNDLED_THEMES {
+            if let Err(e) = parse_theme(name, text) {
+                panic!("{}", e);
+            }
+        }
+    }
+
+    #[test]
+    fn empty_file_is_the_default_theme() {
+        assert_eq!(parse_theme("t.ini", "").unwrap(), Theme::default());
+    }
+
+    #[test]
+    fn missing_keys_keep_their_defaults() {
+        let theme = parse_theme("t.ini", "[dark]\nbackground = #102030\n").unwrap();
+        let default = Theme::default();
+        assert_eq!(theme.dark.background, Color::parse_hex("#102030").unwrap());
+        assert_eq!(theme.dark.text, default.dark.text);
+        assert_eq!(theme.light, default.light);
+        assert_eq!(theme.fonts, default.fonts);
+        assert_eq!(theme.accent, AccentSource::System);
+    }
+
+    #[test]
+    fn derived_text_colours_follow_text() {
+        let theme =
+            parse_theme("t.ini", "[dark]\ntext = #EEEEEE\naccent_text = #000000\n").unwrap();
+        assert_eq!(theme.dark.hover_text, Color::parse_hex("#EEEEEE").unwrap());
+        assert_eq!(theme.dark.accent_text, Color::parse_hex("#000000").unwrap());
+        assert_eq!(theme.light.hover_text, theme.light.text);
+    }
+
+    #[test]
+    fn bad_colour_is_an_error() {
+        let e = parse_theme("t.ini", "[dark]\ntext = #12\n").unwrap_err();
+        assert_eq!(
+            e.to_string(),
+            "t.ini:2: 'text' expects a colour like #RRGGBB or #RRGGBBAA, found '#12'"
+        );
+        assert!(parse_theme("t.ini", "[theme]\naccent = red\n").is_err());
+    }
+
+    #[test]
+    fn unknown_keys_and_sections_are_errors() {
+        let e = parse_theme("t.ini", "[dark]\nforeground = #FFFFFF\n").unwrap_err();
+        assert_eq!(e.to_string(), "t.ini:2: unknown key 'foreground'");
+        let e = parse_theme("t.ini", "[colours]\n").unwrap_err();
+        assert_eq!(e.to_string(), "t.ini:1: unknown section [colours]");
+        assert!(parse_theme("t.ini", "name = x\n").is_err());
+    }
+
+    #[test]
+    fn ranges_are_checked() {
+        assert!(parse_theme("t.ini", "[theme]\ncorner_radius = 21\n").is_err());
+        assert!(parse_theme("t.ini", "[fonts]\nlabel_size = 5\n").is_err());
+        let theme = parse_theme("t.ini", "[fonts]\nlabel_size = 16\n").unwrap();
+        assert_eq!(theme.fonts.label_size, 16.0);
+    }
+
+    #[test]
+    fn colour_hex_forms() {
+        let c = Color::parse_hex("#11223380").unwrap();
+        assert_eq!(c.to_abgr(), 0x80332211);
+        assert_eq!(Color::parse_hex("#112233").unwrap().a, 1.0);
+        assert!(Color::parse_hex("112233").is_none());
+        assert!(Color::parse_hex("#11223G").is_none());
+    }
+
+    #[test]
+    fn theme_names_stay_in_the_themes_folder() {
+        assert!(is_valid_theme_name("nord-dark_2"));
+        assert!(!is_valid_theme_name("../x"));
+        assert!(!is_valid_theme_name(""));
+    }
+
+    #[test]
+    fn bundled_theme_lookup_ignores_case() {
+        assert!(bundled_theme(&DEFAULT_THEME.to_uppercase()).is_some());
+        assert!(bundled_theme("missing").is_none());
+    }
+}
@@ -0,0 +1,172 @@
+#![allow(static_mut_refs)]
+
+use std::collections::HashMap;
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::data::humanize::relative_time;
+use crate::data::locale::{Msg, tr, tr_with};
+use crate::data::settings::get_config_dir;
+
+// ==================================================================================
+//  USAGE
+//  How often and when each history command was run, kept next to `history.txt` in
+//  `usage.txt` so the history file stays one command per line. Each line is
+//  `<runs>\t<last run, unix seconds>\t<command>`; commands that left the history are
+//  dropped on save.
+// ==================================================================================
+
+pub const USAGE_FILE: &str = "usage.txt";
+const SEPARATOR: &str = " · ";
+
+pub static mut USAGE: Option<HashMap<String, Usage>> = None;
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Usage {
+    pub runs: u32,
+    /// Unix seconds.
+    pub last_run: u64,
+}
+
+/// Reads `usage.txt`; lines that do not parse are skipped.
+pub fn parse_usage(text: &str) -> HashMap<String, Usage> {
+    text.lines()
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, '\t');
+            let runs = parts.next()?.parse().ok()?;
+            let last_run = parts.next()?.parse().ok()?;
+            let command = parts.next().filter(|c| !c.trim().is_empty())?;
+            Some((command.to_string(), Usage { runs, last_run }))
+        })
+        .collect()
+}
+
+/// `usage` for the commands in `history`, in history order.
+pub fn format_usage(usage: &HashMap<String, Usage>, history: &[String]) -> String {
+    history
+        .iter()
+        .filter_map(|command| {
+            let u = usage.get(command)?;
+            Some(format!("{}\t{}\t{}\n", u.runs, u.last_run, command))
+        })
+        .collect()
+}
+
+pub fn record_run(usage: &mut HashMap<String, Usage>, command: &str, now: u64) {
+    let entry = usage.entry(command.to_string()).or_default();
+    entry.runs = entry.runs.saturating_add(1);
+    entry.last_run = now;
+}
+
+/// "3 min ago · 12 runs · C:\Windows\notepad.exe"; parts that are unknown are left out.
+pub fn usage_line(usage: Option<Usage>, now: u64, target: &str) -> String {
+    let mut parts = Vec::new();
+    if let Some(usage) = usage.filter(|u| u.runs > 0) {
+        parts.push(relative_time(now.saturating_sub(usage.last_run)));
+        parts.push(if usage.runs == 1 {
+            tr(Msg::RunOnce).to_string()
+        } else {
+            tr_with(Msg::RunCount, &[("count", &usage.runs.to_string())])
+        });
+    }
+    if !target.is_empty() {
+        parts.push(target.to_string());
+    }
+    parts.join(SEPARATOR)
+}
+
+pub fn now_unix() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |d| d.as_secs())
+}
+
+fn get_usage_path() -> Option<PathBuf> {
+    get_config_dir().map(|dir| dir.join(USAGE_FILE))
+}
+
+pub fn load_usage() {
+    let text = get_usage_path()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .unwrap_or_default();
+    unsafe { USAGE = Some(parse_usage(&text)) };
+}
+
+/// Counts a run of `command` and saves the counts of what is still in `history`.
+pub fn save_usage(command: &str, history: &[String]) {
+    let usage = unsafe { USAGE.get_or_insert_with(HashMap::new) };
+    record_run(usage, command, now_unix());
+    usage.retain(|c, _| history.contains(c));
+    if let Some(path) = get_usage_path() {
+        let _ = fs::write(path, format_usage(usage, history));
+    }
+}
+
+pub fn clear_usage() {
+    if let Some(path) = get_usage_path() {
+        let _ = fs::remove_file(path);
+    }
+    unsafe { USAGE = Some(HashMap::new()) };
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_and_formats_in_history_order() {
+        let text = "3\t1700000000\tnotepad\nbad line\n1\t1700000100\tgit commit -m \"a\tb\"\nx\t1\tfoo\n2\t5\t  \n";
+        let usage = parse_usage(text);
+        assert_eq!(usage.len(), 2);
+        assert_eq!(
+            usage["notepad"],
+            Usage {
+                runs: 3,
+                last_run: 1_700_000_000
+            }
+        );
+        assert_eq!(usage["git commit -m \"a\tb\""].runs, 1);
+
+        let history = [
+            "git commit -m \"a\tb\"".to_string(),
+            "calc".to_string(),
+            "notepad".to_string(),
+        ];
+        let out = format_usage(&usage, &history);
+        assert_eq!(
+            out,
+            "1\t1700000100\tgit commit -m \"a\tb\"\n3\t1700000000\tnotepad\n"
+        );
+        assert_eq!(parse_usage(&out), usage);
+    }
+
+    #[test]
+    fn record_run_counts_and_stamps() {
+        let mut usage = HashMap::new();
+        record_run(&mut usage, "calc", 100);
+        record_run(&mut usage, "calc", 250);
+        assert_eq!(
+            usage["calc"],
+            Usage {
+                runs: 2,
+                last_run: 250
+            }
+        );
+    }
+
+    #[test]
+    fn detail_line() {
+        let now = 10_000;
+        let used = |runs, last_run| Some(Usage { runs, last_run });
+        assert_eq!(
+            usage_line(used(12, now - 180), now, r"C:\Windows\notepad.exe"),
+            r"3 min ago · 12 runs · C:\Windows\notepad.exe"
+        );
+        assert_eq!(usage_line(used(1, now), now, ""), "just now · 1 run");
+        assert_eq!(usage_line(None, now, r"C:\x.exe"), r"C:\x.exe");
+        assert_eq!(usage_line(Some(Usage::default()), now, ""), "");
+        // A clock set back does not show the future.
+        assert_eq!(usage_line(used(2, now + 50), now, ""), "just now · 2 runs");
+    }
+}
@@ -0,0 +1,335 @@
+use crate::data::command_line::quote;
+use crate::data::json::{Json, parse_json};
+
+// ==================================================================================
+//  VS CODE
+//  Recently opened folders and workspaces from VS Code's `storage.json`, for
+//  `code <name>`. Releases have kept them in different places (`openedPathsList`
+//  with `entries` or the older `workspaces3`/`folders2` lists, the open windows,
+//  the hot-exit backups, profile associations), so all of them are read and merged,
+//  most recent first. Anything with an unexpected shape is skipped.
+// ==================================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WorkspaceKind {
+    Folder,
+    /// A `.code-workspace` file.
+    Workspace,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecentWorkspace {
+    pub name: String,
+    pub uri: String,
+    pub kind: WorkspaceKind,
+    /// Local path, or the path on the remote machine.
+    pub path: String,
+    /// "SSH: devbox", "WSL: Ubuntu"; `None` for local ones.
+    pub remote: Option<String>,
+    /// The editor's command line name: `code`, `code-insiders`, `codium`.
+    pub command: String,
+}
+
+impl RecentWorkspace {
+    /// The command that reopens it in the editor.
+    pub fn open_command(&self) -> String {
+        match (&self.remote, self.kind) {
+            (None, _) => format!("{} {}", self.command, quote(&self.path)),
+            (Some(_), WorkspaceKind::Folder) => {
+                format!("{} --folder-uri \"{}\"", self.command, self.uri)
+            }
+            (Some(_), WorkspaceKind::Workspace) => {
+                format!("{} --file-uri \"{}\"", self.command, self.uri)
+            }
+        }
+    }
+
+    /// "C:\src\app", or "SSH: devbox · /home/me/app".
+    pub fn location(&self) -> String {
+        match &self.remote {
+            Some(remote) => format!("{} · {}", remote, self.path),
+            None => self.path.clone(),
+        }
+    }
+}
+
+/// Recent folders and workspaces in `storage.json` of the editor run as `command`.
+pub fn parse_storage(text: &str, command: &str) -> Vec<RecentWorkspace> {
+    let Ok(root) = parse_json(text) else {
+        return Vec::new();
+    };
+    let mut found: Vec<(String, WorkspaceKind)> = Vec::new();
+    let mut add = |uri: Option<&str>, kind| {
+        if let Some(uri) = uri.filter(|u| !u.is_empty()) {
+            found.push((uri.to_string(), kind));
+        }
+    };
+
+    let opened = root.get("openedPathsList");
+    for entry in opened
+        .and_then(|o| o.get("entries"))
+        .map_or(&[][..], Json::items)
+    {
+        add(folder_uri(entry), WorkspaceKind::Folder);
+        add(workspace_uri(entry), WorkspaceKind::Workspace);
+    }
+    for list in ["workspaces3", "workspaces2", "folders2"] {
+        for entry in opened
+            .and_then(|o| o.get(list))
+            .map_or(&[][..], Json::items)
+        {
+            // Plain strings are folders; objects are workspaces or folders.
+            add(entry.as_str(), WorkspaceKind::Folder);
+            add(folder_uri(entry), WorkspaceKind::Folder);
+            add(workspace_uri(entry), WorkspaceKind::Workspace);
+        }
+    }
+
+    let windows = root.get("windowsState");
+    let last = windows.and_then(|w| w.get("lastActiveWindow"));
+    let open = windows
+        .and_then(|w| w.get("openedWindows"))
+        .map_or(&[][..], Json::items);
+    for window in last.into_iter().chain(open) {
+        add(folder_uri(window), WorkspaceKind::Folder);
+        add(workspace_uri(window), WorkspaceKind::Workspace);
+    }
+
+    let backups = root.get("backupWorkspaces");
+    for entry in backups
+        .and_then(|b| b.get("folders"))
+        .map_or(&[][..], Json::items)
+    {
+        add(folder_uri(entry), WorkspaceKind::Folder);
+    }
+    for entry in backups
+        .and_then(|b| b.get("workspaces"))
+        .map_or(&[][..], Json::items)
+    {
+        add(workspace_uri(entry), WorkspaceKind::Workspace);
+    }
+
+    let associations = root.path(&["profileAssociations", "workspaces"]);
+    for (uri, _) in associations.map_or(&[][..], Json::entries) {
+        let kind = if uri.ends_with(".code-workspace") {
+            WorkspaceKind::Workspace
+        } else {
+            WorkspaceKind::Folder
+        };
+        add(Some(uri), kind);
+    }
+
+    let mut workspaces: Vec<RecentWorkspace> = Vec::new();
+    for (uri, kind) in found {
+        if workspaces.iter().any(|w| same_uri(&w.uri, &uri)) {
+            continue;
+        }
+        if let Some(workspace) = from_uri(&uri, kind, command) {
+            workspaces.push(workspace);
+        }
+    }
+    workspaces
+}
+
+fn folder_uri(entry: &Json) -> Option<&str> {
+    ["folderUri", "folder"]
+        .iter()
+        .find_map(|key| entry.get(key).and_then(Json::as_str))
+}
+
+/// `workspace.configPath` in newer files, `configURIPath` in older ones.
+fn workspace_uri(entry: &Json) -> Option<&str> {
+    let workspace = entry.get("workspace").unwrap_or(entry);
+    ["configPath", "configURIPath"]
+        .iter()
+        .find_map(|key| workspace.get(key).and_then(Json::as_str))
+}
+
+/// VS Code writes the same folder as `file:///c%3A/src` and `file:///C:/src`.
+fn same_uri(a: &str, b: &str) -> bool {
+    percent_decode(a).eq_ignore_ascii_case(&percent_decode(b))
+}
+
+fn from_uri(uri: &str, kind: WorkspaceKind, command: &str) -> Option<RecentWorkspace> {
+    let (scheme, rest) = uri.split_once("://")?;
+    let (authority, path) = match rest.find('/') {
+        Some(slash) => (&rest[..slash], &rest[slash..]),
+        None => (rest, ""),
+    };
+    let authority = percent_decode(authority);
+    let path = percent_decode(path);
+    let (path, remote) = match scheme {
+        "file" => (local_path(&authority, &path), None),
+        "vscode-remote" => (path, Some(describe_remote(&authority))),
+        _ => return None,
+    };
+    // Named after the last path segment: the labels VS Code stores are display
+    // strings such as `~/app [SSH: devbox]`.
+    let last = path
+        .trim_end_matches(['/', '\\'])
+        .rsplit(['/', '\\'])
+        .next()?;
+    let name = last
+        .strip_suffix(".code-workspace")
+        .unwrap_or(last)
+        .to_string();
+    if name.is_empty() || name.ends_with(':') {
+        return None;
+    }
+    Some(RecentWorkspace {
+        name,
+        uri: uri.to_string(),
+        kind,
+        path,
+        remote,
+        command: command.to_string(),
+    })
+}
+
+/// `/c:/src/app` → `C:\src\app`; a host makes it a UNC path.
+fn local_path(host: &str, path: &str) -> String {
+    if !host.is_empty() {
+        return format!(r"\\{}{}", host, path.replace('/', "\\"));
+    }
+    let bytes = path.as_bytes();
+    if bytes.len() >= 3 && bytes[0] == b'/' && bytes[1].is_ascii_alphabetic() && bytes[2] == b':' {
+        let drive = (bytes[1] as char).to_ascii_uppercase();
+        let rest = path[3..].replace('/', "\\");
+        return format!("{}:{}", drive, if rest.is_empty() { "\\" } else { &rest });
+    }
+    path.to_string()
+}
+
+/// `ssh-remote+devbox` → "SSH: devbox". Dev containers encode their config in the
+/// authority, which is not worth showing.
+fn describe_remote(authority: &str) -> String {
+    let (kind, target) = authority.split_once('+').unwrap_or((authority, ""));
+    let name = match kind {
+        "ssh-remote" => "SSH",
+        "wsl" => "WSL",
+        "dev-container" | "attached-container" => return "Dev Container".to_string(),
+        "codespaces" => "Codespaces",
+        "tunnel" => "Tunnel",
+        _ => return authority.to_string(),
+    };
+    if target.is_empty() {
+        name.to_string()
+    } else {
+        format!("{}: {}", name, target)
+    }
+}
+
+/// `%XX` escapes decoded; anything else, such as `%+1`, is kept as written.
+fn percent_decode(text: &str) -> String {
+    let bytes = text.as_bytes();
+    let mut out = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'%'
+            && let Some(hex) = text.get(i + 1..i + 3)
+            && hex.bytes().all(|b| b.is_ascii_hexdigit())
+            && let Ok(byte) = u8::from_str_radix(hex, 16)
+        {
+            out.push(byte);
+            i += 3;
+        } else {
+            out.push(bytes[i]);
+            i += 1;
+        }
+    }
+    String::from_utf8_lossy(&out).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const STORAGE: &str = include_str!("../../tests/fixtures/vscode_storage.json");
+
+    const LEGACY: &str = r#"{"openedPathsList": {
+        "workspaces3": ["file:///c%3A/legacy/one",
+            {"id": "1", "configURIPath": "file:///c%3A/legacy/two.code-workspace"}],
+        "folders2": ["file:///c%3A/legacy/three"],
+        "files2": ["file:///c%3A/x.txt"]}}"#;
+
+    fn names(recent: &[RecentWorkspace]) -> Vec<&str> {
+        recent.iter().map(|w| w.name.as_str()).collect()
+    }
+
+    #[test]
+    fn reads_every_list_most_recent_first() {
+        let recent = parse_storage(STORAGE, "code");
+        assert_eq!(
+            names(&recent),
+            [
+                "swift-run",
+                "monorepo",
+                "api",
+                "tools",
+                "app",
+                "team",
+                "100%+1",
+                "other",
+                "big",
+                "backup",
+                "old",
+                "assoc"
+            ]
+        );
+        assert!(recent.iter().all(|w| w.command == "code"));
+        assert_eq!(recent[1].kind, WorkspaceKind::Workspace);
+        assert_eq!(recent[3].remote.as_deref(), Some("WSL: Ubuntu"));
+        assert_eq!(recent[4].remote.as_deref(), Some("Dev Container"));
+        assert_eq!(recent[5].path, r"\\server\share\team");
+        // `%+1` is not an escape and is kept as written.
+        assert_eq!(recent[6].path, r"C:\src\100%+1");
+    }
+
+    #[test]
+    fn local_and_remote_open_commands() {
+        let recent = parse_storage(STORAGE, "code");
+        assert_eq!(recent[0].path, r"C:\src\swift-run");
+        assert_eq!(recent[0].open_command(), r#"code "C:\src\swift-run""#);
+        assert_eq!(
+            recent[1].open_command(),
+            r#"code "D:\work\monorepo.code-workspace""#
+        );
+        assert_eq!(recent[2].location(), "SSH: devbox · /home/me/api");
+        assert_eq!(
+            recent[2].open_command(),
+            r#"code --folder-uri "vscode-remote://ssh-remote%2Bdevbox/home/me/api""#
+        );
+        assert_eq!(
+            recent[8].open_command(),
+            r#"code --file-uri "vscode-remote://ssh-remote%2Bdevbox/home/me/big.code-workspace""#
+        );
+    }
+
+    #[test]
+    fn reads_the_legacy_lists() {
+        let recent = parse_storage(LEGACY, "codium");
+        assert_eq!(names(&recent), ["one", "two", "three"]);
+        assert_eq!(recent[1].kind, WorkspaceKind::Workspace);
+        assert_eq!(recent[2].open_command(), r#"codium "C:\legacy\three""#);
+    }
+
+    #[test]
+    fn skips_what_it_cannot_read() {
+        assert!(parse_storage("{not json", "code").is_empty());
+        assert!(parse_storage("[]", "code").is_empty());
+        let odd = r#"{"openedPathsList": {"entries": {"x": 1}}, "windowsState": 3}"#;
+        assert!(parse_storage(odd, "code").is_empty());
+        // A drive root has no name to show.
+        let root = r#"{"openedPathsList": {"entries": [{"folderUri": "file:///c%3A/"}]}}"#;
+        assert!(parse_storage(root, "code").is_empty());
+    }
+
+    #[test]
+    fn percent_escapes_need_two_hex_digits() {
+        assert_eq!(percent_decode("c%3A/a%20b"), "c:/a b");
+        assert_eq!(percent_decode("%+1%2"), "%+1%2");
+        assert_eq!(percent_decode("%-1%zz%"), "%-1%zz%");
+        assert_eq!(percent_decode("%C3%A9"), "é");
+        assert!(same_uri("file:///c%3A/src", "file:///C:/src"));
+    }
+}
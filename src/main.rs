@@ -1,9 +1,8 @@
-﻿#![allow(unsafe_op_in_unsafe_fn)]
+#![allow(unsafe_op_in_unsafe_fn)]
 #![allow(static_mut_refs)]
 #![allow(non_snake_case)]
 #![windows_subsystem = "windows"]
 
-use std::fs;
 use std::time::Instant;
 use windows::{
     Win32::Foundation::*, Win32::Graphics::Direct2D::*, Win32::Graphics::DirectWrite::*,
@@ -22,10 +21,17 @@ mod ui;
 use animations::*;
 use config::*;
 use data::history::*;
+use data::keymap::KeyContext;
+use data::locale::*;
+use data::reload::*;
+use data::settings::settings;
 use system::explorer::*;
+use system::file_indexer::start_file_indexer;
 use system::hotkeys::*;
+use system::language::*;
 use system::registry::*;
 use system::shortcut::*;
+use system::watcher::*;
 use ui::resources::*;
 use ui::*;
 
@@ -144,42 +150,41 @@ fn main() -> Result<()> {
             }
         }
 
+        // Loaded before setup so its dialogs are localised too.
+        let config_errors = load_config(user_ui_languages());
+        refresh_high_contrast();
+
         if args.len() > 1 {
             if args[1] == "--install" {
                 if let Err(e) = manage_registry_hooks(true) {
                     show_fluent_dialog(
-                        "Setup Error",
-                        &format!("Failed to install registry hooks: {:?}", e),
+                        &tr(Msg::SetupErrorTitle),
+                        &tr_with(Msg::InstallFailed, &[("error", &format!("{:?}", e))]),
                     );
                     return Err(e.into());
                 }
                 let _ = manage_start_menu_shortcut(true);
-                show_fluent_dialog(
-                    "SwiftRun Setup",
-                    "SwiftRun installed! Explorer will now restart to finalize the takeover.",
-                );
+                show_fluent_dialog(&tr(Msg::SetupTitle), &tr(Msg::Installed));
                 restart_explorer();
                 return Ok(());
             } else if args[1] == "--uninstall" {
                 kill_processes_by_name("swift_run.exe");
                 if let Err(e) = manage_registry_hooks(false) {
                     show_fluent_dialog(
-                        "Setup Error",
-                        &format!("Failed to uninstall registry hooks: {:?}", e),
+                        &tr(Msg::SetupErrorTitle),
+                        &tr_with(Msg::UninstallFailed, &[("error", &format!("{:?}", e))]),
                     );
                     return Err(e.into());
                 }
                 let _ = manage_start_menu_shortcut(false);
-                show_fluent_dialog(
-                    "SwiftRun Setup",
-                    "SwiftRun uninstalled! Win+R will return to default behavior after restart.",
-                );
+                show_fluent_dialog(&tr(Msg::SetupTitle), &tr(Msg::Uninstalled));
                 restart_explorer();
                 return Ok(());
             }
         }
 
         load_history();
+        start_file_indexer(&settings().files);
 
         let mut work_area = RECT::default();
         let _ = SystemParametersInfoW(
@@ -222,8 +227,11 @@ fn main() -> Result<()> {
         H_MAIN = hwnd;
         let _ = ChangeWindowMessageFilterEx(hwnd, WM_APP_SHOW_UI, MSGFLT_ALLOW, None);
 
-        register_hotkeys(hwnd);
+        let hotkey_failures = register_hotkeys(hwnd);
         let _ = WTSRegisterSessionNotification(hwnd, NOTIFY_FOR_THIS_SESSION);
+        if let Some(reloader) = CONFIG_RELOADER.as_ref() {
+            start_config_watcher(hwnd, reloader.dir());
+        }
 
         H_DROPDOWN = CreateWindowExW(
             WS_EX_TOOLWINDOW | WS_EX_TOPMOST,
@@ -254,59 +262,8 @@ fn main() -> Result<()> {
         };
         DwmExtendFrameIntoClientArea(hwnd, &m).ok();
 
-        H_EDIT = CreateWindowExW(
-            WINDOW_EX_STYLE::default(),
-            w!("EDIT"),
-            None,
-            WS_CHILD | WS_VISIBLE | WINDOW_STYLE(0x0080),
-            0,
-            0,
-            0,
-            0,
-            Some(hwnd),
-            Some(HMENU(EDIT_ID as _)),
-            Some(instance.into()),
-            None,
-        )
-        .unwrap();
-        let hfont = CreateFontW(
-            FONT_SZ_INPUT,
-            0,
-            0,
-            0,
-            400,
-            0,
-            0,
-            0,
-            ANSI_CHARSET,
-            OUT_DEFAULT_PRECIS,
-            CLIP_DEFAULT_PRECIS,
-            DEFAULT_QUALITY,
-            0,
-            FONT_STD,
-        );
-        SendMessageW(
-            H_EDIT,
-            WM_SETFONT,
-            Some(WPARAM(hfont.0 as usize)),
-            Some(LPARAM(1)),
-        );
-
-        if let Some(history) = &HISTORY {
-            if let Some(latest) = history.first() {
-                if let Ok(mut lock) = INPUT_BUFFER.lock() {
-                    *lock = latest.clone();
-                }
-                let latest_u16: Vec<u16> =
-                    latest.encode_utf16().chain(std::iter::once(0)).collect();
-                let _ = SetWindowTextW(H_EDIT, PCWSTR(latest_u16.as_ptr()));
-                SendMessageW(
-                    H_EDIT,
-                    windows::Win32::UI::Controls::EM_SETSEL,
-                    Some(WPARAM(0)),
-                    Some(LPARAM(-1)),
-                );
-            }
+        if let Some(latest) = HISTORY.as_ref().and_then(|h| h.first()) {
+            INPUT.reset(latest);
         }
 
         let blink_time = GetCaretBlinkTime();
@@ -320,13 +277,15 @@ fn main() -> Result<()> {
         if should_show {
             let _ = ShowWindow(hwnd, SW_SHOW);
             let _ = SetForegroundWindow(hwnd);
-            let _ = SetFocus(Some(H_EDIT));
+            let _ = SetFocus(Some(hwnd));
             ANIM_TYPE = AnimType::Entering;
             ANIM_START_TIME = Some(Instant::now());
             SetTimer(Some(hwnd), 3, ANIM_TIMER_MS, None);
         } else {
             let _ = ShowWindow(hwnd, SW_HIDE);
         }
+        report_config_errors(&config_errors);
+        report_hotkey_failures(&hotkey_failures);
         // Heartbeat timer to ensure hotkeys stay registered (Timer ID 4, every 30s)
         SetTimer(Some(hwnd), 4, 30000, None);
 
@@ -337,103 +296,19 @@ fn main() -> Result<()> {
                     break;
                 }
 
-                if (msg.message == WM_KEYDOWN || msg.message == WM_KEYUP) && msg.hwnd == H_EDIT {
-                    let vk = msg.wParam.0 as i32;
-                    if msg.message == WM_KEYDOWN {
-                        match vk {
-                            v if v == VK_RETURN.0 as i32 => {
-                                let is_elevated = (GetKeyState(VK_CONTROL.0 as i32) < 0
-                                    && GetKeyState(VK_SHIFT.0 as i32) < 0)
-                                    as usize;
-                                let _ = PostMessageW(
-                                    Some(hwnd),
-                                    WM_APP_RUN_COMMAND,
-                                    WPARAM(is_elevated),
-                                    LPARAM(0),
-                                );
-                                continue; // Prevent beep
-                            }
-                            v if v == VK_UP.0 as i32 => {
-                                cycle_history(-1, H_EDIT);
-                                let _ = InvalidateRect(Some(hwnd), None, false);
-                                continue;
-                            }
-                            v if v == VK_DOWN.0 as i32 => {
-                                cycle_history(1, H_EDIT);
-                                let _ = InvalidateRect(Some(hwnd), None, false);
-                                continue;
-                            }
-                            v if v == VK_TAB.0 as i32
-                                || (v == VK_RIGHT.0 as i32 && !PREDICTION.is_empty()) =>
-                            {
-                                // Prediction acceptance logic...
-                                let pred = PREDICTION.clone();
-                                if !pred.is_empty() {
-                                    if let Ok(mut lock) = INPUT_BUFFER.lock() {
-                                        *lock = pred.clone();
-                                    }
-                                    let u16_vec: Vec<u16> =
-                                        pred.encode_utf16().chain(std::iter::once(0)).collect();
-                                    IS_CYCLING = true;
-                                    let _ = SetWindowTextW(H_EDIT, PCWSTR(u16_vec.as_ptr()));
-                                    SendMessageW(
-                                        H_EDIT,
-                                        windows::Win32::UI::Controls::EM_SETSEL,
-                                        Some(WPARAM(0)),
-                                        Some(LPARAM(-1)),
-                                    );
-                                    IS_CYCLING = false;
-                                    PREDICTION = String::new();
-                                    FILTERED_HISTORY = None;
-                                    if SHOW_DROPDOWN {
-                                        SHOW_DROPDOWN = false;
-                                        let _ = ShowWindow(H_DROPDOWN, SW_HIDE);
-                                    }
-                                    let _ = InvalidateRect(Some(hwnd), None, false);
-                                }
-                                continue; // Prevent beep/focus shift
-                            }
-                            v if v == VK_BACK.0 as i32
-                                && GetKeyState(VK_CONTROL.0 as i32) < 0
-                                && GetKeyState(VK_SHIFT.0 as i32) < 0 =>
-                            {
-                                if let Some(path) = get_history_path() {
-                                    let _ = fs::remove_file(path);
-                                }
-                                HISTORY = Some(Vec::new());
-                                HISTORY_INDEX = -1;
-                                if SHOW_DROPDOWN {
-                                    SHOW_DROPDOWN = false;
-                                    let _ = ShowWindow(H_DROPDOWN, SW_HIDE);
-                                }
-                                show_tooltip(
-                                    "History Cleared",
-                                    "The command history has been successfully removed.",
-                                );
-                                let _ = InvalidateRect(Some(hwnd), None, false);
-                                continue;
-                            }
-                            _ => {}
-                        }
-                    } else if msg.message == WM_KEYUP {
-                        // Also consume KEYUP for these keys to be safe
-                        match vk {
-                            v if v == VK_RETURN.0 as i32
-                                || v == VK_UP.0 as i32
-                                || v == VK_DOWN.0 as i32
-                                || v == VK_TAB.0 as i32 =>
-                            {
-                                continue;
-                            }
-                            _ => {}
-                        }
+                let is_key_down = msg.message == WM_KEYDOWN || msg.message == WM_SYSKEYDOWN;
+                let vk = msg.wParam.0 as u32;
+                if is_key_down && msg.hwnd == hwnd {
+                    let context = KeyContext {
+                        has_prediction: !PREDICTION.is_empty(),
+                        caret_at_end: INPUT.caret() == INPUT.text().len() && !INPUT.has_selection(),
+                    };
+                    if let Some(action) = settings().keymap.dispatch(current_chord(vk), context) {
+                        run_key_action(hwnd, action);
+                        continue; // Prevent beep
                     }
                 }
 
-                if msg.message == WM_KEYDOWN && msg.wParam.0 == 0x1B {
-                    start_exit_animation(hwnd, false);
-                }
-
                 let _ = TranslateMessage(&msg);
                 DispatchMessageW(&msg);
             } else {
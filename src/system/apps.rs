@@ -0,0 +1,109 @@
+#![allow(unsafe_op_in_unsafe_fn)]
+#![allow(non_snake_case)]
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use windows::Win32::System::Com::*;
+use windows::Win32::UI::Shell::*;
+use windows::core::*;
+
+use crate::data::providers::App;
+
+// ==================================================================================
+//  APPS
+//  Start menu shortcuts of the current user and of all users. Resolving a shortcut's
+//  target loads it through COM, so the list is rebuilt on a background thread each
+//  time the window opens; queries use whatever the last scan found.
+// ==================================================================================
+
+const SHORTCUT_EXTENSIONS: &[&str] = &["lnk", "url", "appref-ms"];
+const MAX_DEPTH: usize = 4;
+
+static APPS: Mutex<Option<Arc<Vec<App>>>> = Mutex::new(None);
+
+/// Apps found by the last scan.
+pub fn installed_apps() -> Arc<Vec<App>> {
+    APPS.lock()
+        .map(|apps| apps.clone().unwrap_or_default())
+        .unwrap_or_default()
+}
+
+/// Rescans the Start menu in the background.
+pub fn refresh_apps() {
+    thread::spawn(|| unsafe {
+        let _ = CoInitializeEx(None, COINIT_APARTMENTTHREADED);
+        let mut apps = Vec::new();
+        for root in start_menu_roots() {
+            collect(&root, 0, &mut apps);
+        }
+        CoUninitialize();
+
+        apps.sort_by_key(|app| app.name.to_lowercase());
+        // The same app is often in both menus.
+        apps.dedup_by(|a, b| a.name.eq_ignore_ascii_case(&b.name));
+        if let Ok(mut current) = APPS.lock() {
+            *current = Some(Arc::new(apps));
+        }
+    });
+}
+
+fn start_menu_roots() -> Vec<PathBuf> {
+    ["APPDATA", "ProgramData"]
+        .iter()
+        .filter_map(|var| std::env::var(var).ok())
+        .map(|dir| Path::new(&dir).join(r"Microsoft\Windows\Start Menu\Programs"))
+        .collect()
+}
+
+unsafe fn collect(dir: &Path, depth: usize, apps: &mut Vec<App>) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    for entry in entries.flatten() {
+        let path = entry.path();
+        if entry.file_type().is_ok_and(|t| t.is_dir()) {
+            if depth < MAX_DEPTH {
+                collect(&path, depth + 1, apps);
+            }
+            continue;
+        }
+        let is_shortcut = path.extension().is_some_and(|ext| {
+            SHORTCUT_EXTENSIONS
+                .iter()
+                .any(|known| ext.eq_ignore_ascii_case(known))
+        });
+        let Some(name) = path.file_stem().map(|s| s.to_string_lossy().to_string()) else {
+            continue;
+        };
+        // Uninstallers and readmes share the folder with the app.
+        let lower = name.to_lowercase();
+        if !is_shortcut || lower.starts_with("uninstall") || lower.contains("readme") {
+            continue;
+        }
+        let shortcut = path.to_string_lossy().to_string();
+        apps.push(App {
+            name,
+            target: shortcut_target(&shortcut).unwrap_or_default(),
+            path: shortcut,
+        });
+    }
+}
+
+/// Where a `.lnk` points; `None` for other shortcuts and for advertised (installer)
+/// shortcuts, which have no plain target.
+unsafe fn shortcut_target(shortcut: &str) -> Option<String> {
+    if !shortcut.to_lowercase().ends_with(".lnk") {
+        return None;
+    }
+    let link: IShellLinkW = CoCreateInstance(&ShellLink, None, CLSCTX_INPROC_SERVER).ok()?;
+    let file: IPersistFile = link.cast().ok()?;
+    let wide: Vec<u16> = shortcut.encode_utf16().chain(std::iter::once(0)).collect();
+    file.Load(PCWSTR(wide.as_ptr()), STGM_READ).ok()?;
+    let mut buffer = [0u16; 260];
+    link.GetPath(&mut buffer, std::ptr::null_mut(), 0).ok()?;
+    let len = buffer.iter().position(|&c| c == 0).unwrap_or(buffer.len());
+    (len > 0).then(|| String::from_utf16_lossy(&buffer[..len]))
+}
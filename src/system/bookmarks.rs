@@ -0,0 +1,184 @@
+use std::collections::HashSet;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::SystemTime;
+
+use crate::data::bookmarks::{
+    Bookmark, chromium_profile_names, parse_chromium_bookmarks, read_places,
+};
+
+// ==================================================================================
+//  BOOKMARKS
+//  Bookmarks of the installed browsers, read again on a background thread each time
+//  the window opens. Files that have not changed since the last read are not parsed
+//  again. A file that cannot be read or parsed (Firefox may be writing to its
+//  database) keeps the bookmarks it had last time.
+// ==================================================================================
+
+/// Chromium browsers and their `User Data` folder under `%LOCALAPPDATA%`.
+const CHROMIUM_BROWSERS: &[(&str, &str)] = &[
+    ("Chrome", r"Google\Chrome\User Data"),
+    ("Edge", r"Microsoft\Edge\User Data"),
+    ("Brave", r"BraveSoftware\Brave-Browser\User Data"),
+];
+/// Firefox profiles under `%APPDATA%`.
+const FIREFOX_PROFILES: &str = r"Mozilla\Firefox\Profiles";
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Chromium,
+    Firefox,
+}
+
+/// A bookmarks file, and the modification times of it and its `-wal` file.
+type Stamp = (Option<SystemTime>, Option<SystemTime>);
+
+struct Cached {
+    path: PathBuf,
+    stamp: Stamp,
+    bookmarks: Vec<Bookmark>,
+}
+
+static BOOKMARKS: Mutex<Option<Arc<Vec<Bookmark>>>> = Mutex::new(None);
+static CACHE: Mutex<Vec<Cached>> = Mutex::new(Vec::new());
+
+/// Bookmarks found by the last read, each address once.
+pub fn bookmarks() -> Arc<Vec<Bookmark>> {
+    BOOKMARKS
+        .lock()
+        .map(|bookmarks| bookmarks.clone().unwrap_or_default())
+        .unwrap_or_default()
+}
+
+/// Reads the browsers' bookmarks again in the background.
+pub fn refresh_bookmarks() {
+    thread::spawn(|| {
+        let Ok(mut cache) = CACHE.lock() else {
+            return;
+        };
+        let mut next = Vec::new();
+        for (path, format, browser) in bookmark_files() {
+            let stamp = (modified(&path), modified(&wal_path(&path)));
+            let previous = cache.iter().position(|c| c.path == path);
+            if let Some(i) = previous
+                && cache[i].stamp == stamp
+            {
+                next.push(cache.swap_remove(i));
+                continue;
+            }
+            match read_bookmarks(&path, format, &browser) {
+                Some(bookmarks) => next.push(Cached {
+                    path,
+                    stamp,
+                    bookmarks,
+                }),
+                // Keeping the old stamp makes the next refresh try again.
+                None => next.extend(previous.map(|i| cache.swap_remove(i))),
+            }
+        }
+        *cache = next;
+
+        // The first browser to have an address keeps it.
+        let mut seen: HashSet<&str> = HashSet::new();
+        let all: Vec<Bookmark> = cache
+            .iter()
+            .flat_map(|c| &c.bookmarks)
+            .filter(|bookmark| seen.insert(&bookmark.url))
+            .cloned()
+            .collect();
+        if let Ok(mut current) = BOOKMARKS.lock() {
+            *current = Some(Arc::new(all));
+        }
+    });
+}
+
+/// Every bookmarks file of every profile, with the name its bookmarks show under.
+fn bookmark_files() -> Vec<(PathBuf, Format, String)> {
+    let mut files = Vec::new();
+    if let Ok(local_app_data) = std::env::var("LOCALAPPDATA") {
+        for (browser, user_data) in CHROMIUM_BROWSERS {
+            let user_data = Path::new(&local_app_data).join(user_data);
+            let names = fs::read_to_string(user_data.join("Local State"))
+                .map(|text| chromium_profile_names(&text))
+                .unwrap_or_default();
+            let profiles: Vec<(String, PathBuf)> = subfolders(&user_data)
+                .into_iter()
+                .map(|(dir, path)| (dir, path.join("Bookmarks")))
+                .filter(|(_, path)| path.is_file())
+                .collect();
+            for (dir, path) in &profiles {
+                let name = names.iter().find(|(d, _)| d == dir).map_or(dir, |(_, n)| n);
+                let label = profile_label(browser, name, profiles.len());
+                files.push((path.clone(), Format::Chromium, label));
+            }
+        }
+    }
+    if let Ok(app_data) = std::env::var("APPDATA") {
+        let profiles: Vec<(String, PathBuf)> =
+            subfolders(&Path::new(&app_data).join(FIREFOX_PROFILES))
+                .into_iter()
+                .map(|(dir, path)| (dir, path.join("places.sqlite")))
+                .filter(|(_, path)| path.is_file())
+                .collect();
+        for (dir, path) in &profiles {
+            // Profile folders are `<random>.<name>`.
+            let name = dir.split_once('.').map_or(dir.as_str(), |(_, name)| name);
+            let label = profile_label("Firefox", name, profiles.len());
+            files.push((path.clone(), Format::Firefox, label));
+        }
+    }
+    files
+}
+
+fn profile_label(browser: &str, profile: &str, profiles: usize) -> String {
+    if profiles > 1 {
+        format!("{} ({})", browser, profile)
+    } else {
+        browser.to_string()
+    }
+}
+
+/// Names and paths of the folders in `dir`, sorted by name.
+fn subfolders(dir: &Path) -> Vec<(String, PathBuf)> {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut folders: Vec<(String, PathBuf)> = entries
+        .flatten()
+        .filter(|entry| entry.file_type().is_ok_and(|t| t.is_dir()))
+        .map(|entry| {
+            (
+                entry.file_name().to_string_lossy().to_string(),
+                entry.path(),
+            )
+        })
+        .collect();
+    folders.sort();
+    folders
+}
+
+fn read_bookmarks(path: &Path, format: Format, browser: &str) -> Option<Vec<Bookmark>> {
+    match format {
+        Format::Chromium => {
+            let text = fs::read_to_string(path).ok()?;
+            parse_chromium_bookmarks(&text, browser).ok()
+        }
+        Format::Firefox => {
+            let main = fs::read(path).ok()?;
+            let wal = fs::read(wal_path(path)).ok();
+            read_places(&main, wal.as_deref(), browser).ok()
+        }
+    }
+}
+
+fn wal_path(path: &Path) -> PathBuf {
+    let mut wal = path.as_os_str().to_owned();
+    wal.push("-wal");
+    PathBuf::from(wal)
+}
+
+fn modified(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
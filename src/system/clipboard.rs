@@ -0,0 +1,61 @@
+#![allow(unsafe_op_in_unsafe_fn)]
+
+use windows::Win32::Foundation::*;
+use windows::Win32::System::DataExchange::*;
+use windows::Win32::System::Memory::*;
+
+const CF_UNICODETEXT: u32 = 13;
+
+/// Text on the clipboard, if any. Only the first line is kept, since the input is a
+/// single line.
+pub unsafe fn clipboard_text(owner: HWND) -> Option<String> {
+    IsClipboardFormatAvailable(CF_UNICODETEXT).ok()?;
+    OpenClipboard(Some(owner)).ok()?;
+
+    let text = GetClipboardData(CF_UNICODETEXT).ok().and_then(|handle| {
+        let hglobal = HGLOBAL(handle.0);
+        let ptr = GlobalLock(hglobal) as *const u16;
+        if ptr.is_null() {
+            return None;
+        }
+        let mut len = 0;
+        while *ptr.add(len) != 0 {
+            len += 1;
+        }
+        let text = String::from_utf16_lossy(std::slice::from_raw_parts(ptr, len));
+        let _ = GlobalUnlock(hglobal);
+        Some(text)
+    });
+    let _ = CloseClipboard();
+
+    let line = text?.lines().next()?.trim().to_string();
+    (!line.is_empty()).then_some(line)
+}
+
+/// Puts `text` on the clipboard for Copy and Cut. Returns false if it was busy.
+pub unsafe fn set_clipboard_text(owner: HWND, text: &str) -> bool {
+    let wide: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+    let Ok(hglobal) = GlobalAlloc(GMEM_MOVEABLE, wide.len() * 2) else {
+        return false;
+    };
+    let ptr = GlobalLock(hglobal) as *mut u16;
+    if ptr.is_null() {
+        let _ = GlobalFree(Some(hglobal));
+        return false;
+    }
+    std::ptr::copy_nonoverlapping(wide.as_ptr(), ptr, wide.len());
+    let _ = GlobalUnlock(hglobal);
+
+    if OpenClipboard(Some(owner)).is_err() {
+        let _ = GlobalFree(Some(hglobal));
+        return false;
+    }
+    let _ = EmptyClipboard();
+    // On success the clipboard owns the memory.
+    let placed = SetClipboardData(CF_UNICODETEXT, Some(HANDLE(hglobal.0))).is_ok();
+    if !placed {
+        let _ = GlobalFree(Some(hglobal));
+    }
+    let _ = CloseClipboard();
+    placed
+}
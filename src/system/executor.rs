@@ -2,25 +2,55 @@
 #![allow(static_mut_refs)]
 #![allow(non_snake_case)]
 
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
 use std::thread;
 use windows::Win32::Foundation::*;
 
 use windows::Win32::System::Com::*;
 use windows::Win32::System::Environment::*;
+use windows::Win32::System::Registry::*;
 use windows::Win32::UI::Shell::*;
 use windows::Win32::UI::WindowsAndMessaging::*;
 use windows::core::*;
 
+use crate::config::DIAGNOSTICS_COMMAND;
+use crate::data::aliases::lookup_alias;
+use crate::data::completion::DirectoryLister;
+use crate::data::expansion::{ExpansionError, expand_history};
 use crate::data::history::*;
+use crate::data::locale::*;
+use crate::data::settings::settings;
+use crate::data::ssh_config::ssh_launch_command;
+use crate::data::syntax::{PathCache, Resolver, is_network_path, is_url};
+use crate::data::terminal::profile_command;
+use crate::system::terminal::terminal_profiles;
+use crate::ui::main_win::show_diagnostics;
 use crate::ui::resources::*;
+use crate::ui::tooltip::show_tooltip;
 
 pub unsafe fn run_command(elevated: bool) {
-    let mut input_str = String::new();
-    if let Ok(buf) = INPUT_BUFFER.lock() {
-        if !buf.is_empty() {
-            input_str = buf.clone();
-            save_history(&input_str);
+    if INPUT
+        .text()
+        .trim()
+        .eq_ignore_ascii_case(DIAGNOSTICS_COMMAND)
+    {
+        show_diagnostics();
+        return;
+    }
+    let input_str = match expand_history(INPUT.text(), history_lines()) {
+        Ok(Some(expanded)) => {
+            INPUT.set_text(&expanded);
+            expanded
+        }
+        Ok(None) => INPUT.text().to_string(),
+        Err(error) => {
+            show_expansion_error(&error);
+            return;
         }
+    };
+    if !input_str.is_empty() {
+        save_history(&input_str);
     }
 
     if input_str.is_empty() {
@@ -34,6 +64,8 @@ pub unsafe fn run_command(elevated: bool) {
     };
 
     let input_str = unsafe { expand_aliases_and_env(input_str.trim()) };
+    // ssh asks for passwords and host keys, so it gets a terminal of its own.
+    let input_str = ssh_launch_command(&input_str, &settings().ssh_terminal).unwrap_or(input_str);
 
     let is_url =
         input_str.starts_with("http") || input_str.starts_with("www") || input_str.contains("://");
@@ -99,13 +131,207 @@ pub unsafe fn run_command(elevated: bool) {
     });
 }
 
+unsafe fn show_expansion_error(error: &ExpansionError) {
+    let message = match error {
+        ExpansionError::EventNotFound(event) => tr_with(Msg::EventNotFound, &[("event", event)]),
+        ExpansionError::SubstitutionFailed(text) => {
+            tr_with(Msg::SubstitutionFailed, &[("text", text)])
+        }
+    };
+    show_tooltip(&tr(Msg::ExpansionFailedTitle), &message);
+}
+
+/// Opens Explorer with the typed path selected, or the program it names when that is
+/// found on `PATH`.
+pub unsafe fn open_location() {
+    let input = INPUT.text().trim().to_string();
+    if input.is_empty() {
+        return;
+    }
+
+    let Some(target) = resolve_location(&expand_aliases_and_env(&input)) else {
+        let _ = PostMessageW(Some(H_MAIN), WM_APP_ERROR, WPARAM(0), LPARAM(0));
+        return;
+    };
+    let params = format!("/select,\"{}\"", target.display());
+    let params_u16: Vec<u16> = params.encode_utf16().chain(std::iter::once(0)).collect();
+    let res = ShellExecuteW(
+        None,
+        PCWSTR::null(),
+        w!("explorer.exe"),
+        PCWSTR(params_u16.as_ptr()),
+        None,
+        SW_SHOWNORMAL,
+    );
+    let message = if (res.0 as isize) > 32 {
+        WM_APP_CLOSE
+    } else {
+        WM_APP_ERROR
+    };
+    let _ = PostMessageW(Some(H_MAIN), message, WPARAM(0), LPARAM(0));
+}
+
+/// The whole input when it is an existing path, else its first word as a path or a
+/// program on `PATH`.
+fn resolve_location(input: &str) -> Option<PathBuf> {
+    let input = input.trim_matches('"');
+    let whole = Path::new(input);
+    if whole.exists() {
+        return Some(whole.to_path_buf());
+    }
+    find_program(input.split(' ').next()?.trim_matches('"'))
+}
+
+/// `program` as an existing path, or found on `PATH` (trying each `PATHEXT`
+/// extension when it has none).
+fn find_program(program: &str) -> Option<PathBuf> {
+    let path = Path::new(program);
+    if path.exists() {
+        return Some(path.to_path_buf());
+    }
+    if program.contains(['\\', '/']) {
+        return None;
+    }
+
+    let extensions: Vec<String> = if path.extension().is_some() {
+        vec![String::new()]
+    } else {
+        std::env::var("PATHEXT")
+            .unwrap_or_else(|_| ".COM;.EXE;.BAT;.CMD".to_string())
+            .split(';')
+            .map(str::to_string)
+            .collect()
+    };
+    let dirs = std::env::var_os("PATH")?;
+    std::env::split_paths(&dirs).find_map(|dir| {
+        extensions
+            .iter()
+            .map(|ext| dir.join(format!("{}{}", program, ext)))
+            .find(|candidate| candidate.is_file())
+    })
+}
+
+/// Whether `program` is registered under `App Paths`, which `ShellExecute` also
+/// searches (`chrome`, `winword`, ...).
+unsafe fn has_app_path(program: &str) -> bool {
+    let name = if Path::new(program).extension().is_some() {
+        program.to_string()
+    } else {
+        format!("{}.exe", program)
+    };
+    let key = format!(
+        "Software\\Microsoft\\Windows\\CurrentVersion\\App Paths\\{}",
+        name
+    );
+    let key_u16: Vec<u16> = key.encode_utf16().chain(std::iter::once(0)).collect();
+    [HKEY_CURRENT_USER, HKEY_LOCAL_MACHINE]
+        .into_iter()
+        .any(|root| {
+            let mut h_key = HKEY::default();
+            let found = RegOpenKeyExW(
+                root,
+                PCWSTR(key_u16.as_ptr()),
+                Some(0),
+                KEY_READ,
+                &mut h_key,
+            ) == ERROR_SUCCESS;
+            if found {
+                let _ = RegCloseKey(h_key);
+            }
+            found
+        })
+}
+
+// Program lookups made while highlighting, keyed by the word as typed. Cleared each
+// time the window opens, so newly installed programs show up.
+static mut PROGRAM_CACHE: Option<HashMap<String, bool>> = None;
+
+// Paths probed while highlighting; cleared with the program cache.
+static mut PATH_CACHE: Option<PathCache> = None;
+
+// Full paths shown in the dropdown's detail line, keyed by command; cleared with the
+// program cache.
+static mut TARGET_CACHE: Option<HashMap<String, Option<String>>> = None;
+
+pub fn clear_program_cache() {
+    unsafe {
+        PROGRAM_CACHE = None;
+        PATH_CACHE = None;
+        TARGET_CACHE = None;
+    }
+}
+
+/// The file, folder or program `command` opens, as a full path; `None` for URLs,
+/// network paths, which could stall the UI thread while the server is reached, and
+/// anything that cannot be found.
+pub unsafe fn resolve_target(command: &str) -> Option<String> {
+    let cache = TARGET_CACHE.get_or_insert_with(HashMap::new);
+    if let Some(known) = cache.get(command) {
+        return known.clone();
+    }
+    let expanded = expand_aliases_and_env(command.trim());
+    let target = if is_url(&expanded) || is_network_path(expanded.trim_start_matches('"')) {
+        None
+    } else {
+        resolve_location(&expanded).map(|path| path.to_string_lossy().to_string())
+    };
+    cache.insert(command.to_string(), target.clone());
+    target
+}
+
+/// Answers the highlighter from the filesystem, the registry and the aliases.
+pub struct SystemResolver;
+
+impl Resolver for SystemResolver {
+    fn is_program(&self, program: &str) -> bool {
+        if program.eq_ignore_ascii_case(DIAGNOSTICS_COMMAND) {
+            return true;
+        }
+        let key = program.to_lowercase();
+        unsafe {
+            let cache = PROGRAM_CACHE.get_or_insert_with(HashMap::new);
+            if let Some(&known) = cache.get(&key) {
+                return known;
+            }
+            let expanded = expand_aliases_and_env(program);
+            let known =
+                is_url(&expanded) || find_program(&expanded).is_some() || has_app_path(&expanded);
+            cache.insert(key, known);
+            known
+        }
+    }
+
+    fn path_exists(&self, path: &str) -> bool {
+        unsafe {
+            PATH_CACHE
+                .get_or_insert_with(PathCache::default)
+                .exists(path, |path| Path::new(&expand_env(path)).exists())
+        }
+    }
+}
+
+impl DirectoryLister for SystemResolver {
+    fn list(&self, dir: &str) -> Vec<(String, bool)> {
+        let Ok(entries) = std::fs::read_dir(unsafe { expand_env(dir) }) else {
+            return Vec::new();
+        };
+        entries
+            .flatten()
+            .map(|entry| {
+                let is_dir = entry.path().is_dir();
+                (entry.file_name().to_string_lossy().into_owned(), is_dir)
+            })
+            .collect()
+    }
+}
+
 unsafe fn expand_aliases_and_env(input: &str) -> String {
     let mut result = input.to_string();
 
-    // 1. Handle Quick Aliases (Command-only)
+    // 1. Handle Quick Aliases (Command-only). User aliases from aliases.ini win over built-ins.
     let parts: Vec<&str> = result.splitn(2, ' ').collect();
     let cmd = parts[0].to_lowercase();
-    let alias_path = match cmd.as_str() {
+    let alias_path = lookup_alias(&cmd).or_else(|| match cmd.as_str() {
         "docs" | "documents" => get_known_folder_path(&FOLDERID_Documents),
         "pictures" | "pics" => get_known_folder_path(&FOLDERID_Pictures),
         "videos" | "vids" => get_known_folder_path(&FOLDERID_Videos),
@@ -114,7 +340,17 @@ unsafe fn expand_aliases_and_env(input: &str) -> String {
         "desktop" => get_known_folder_path(&FOLDERID_Desktop),
         "terminal" | "term" => Some("wt".to_string()),
         _ => None,
-    };
+    });
+
+    // `term <profile> [<dir>]` opens that Windows Terminal profile.
+    if matches!(cmd.as_str(), "terminal" | "term")
+        && alias_path.as_deref() == Some("wt")
+        && let Some(command) = parts
+            .get(1)
+            .and_then(|args| profile_command(args, &terminal_profiles()))
+    {
+        return expand_env(&command);
+    }
 
     if let Some(path) = alias_path {
         if parts.len() > 1 {
@@ -125,16 +361,21 @@ unsafe fn expand_aliases_and_env(input: &str) -> String {
     }
 
     // 2. Expand Environment Variables (e.g. %appdata%)
-    if result.contains('%') {
-        let input_u16: Vec<u16> = result.encode_utf16().chain(std::iter::once(0)).collect();
-        let mut buffer = [0u16; 32768];
-        let len = ExpandEnvironmentStringsW(PCWSTR(input_u16.as_ptr()), Some(&mut buffer));
-        if len > 0 && len <= 32768 {
-            result = String::from_utf16_lossy(&buffer[..len as usize - 1]);
-        }
-    }
+    expand_env(&result)
+}
 
-    result
+pub unsafe fn expand_env(text: &str) -> String {
+    if !text.contains('%') {
+        return text.to_string();
+    }
+    let input_u16: Vec<u16> = text.encode_utf16().chain(std::iter::once(0)).collect();
+    let mut buffer = [0u16; 32768];
+    let len = ExpandEnvironmentStringsW(PCWSTR(input_u16.as_ptr()), Some(&mut buffer));
+    if len > 0 && len <= 32768 {
+        String::from_utf16_lossy(&buffer[..len as usize - 1])
+    } else {
+        text.to_string()
+    }
 }
 
 unsafe fn get_known_folder_path(folder_id: *const GUID) -> Option<String> {
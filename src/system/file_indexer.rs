@@ -0,0 +1,170 @@
+#![allow(static_mut_refs)]
+#![allow(unsafe_op_in_unsafe_fn)]
+
+use std::fs;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+
+use windows::Win32::Foundation::*;
+use windows::Win32::Storage::FileSystem::*;
+use windows::Win32::System::Threading::*;
+use windows::core::*;
+
+use crate::config::*;
+use crate::data::file_index::*;
+use crate::data::settings::get_config_dir;
+use crate::system::executor::expand_env;
+
+// ==================================================================================
+//  FILE INDEXER
+//  Keeps the file index current on a background thread while `[files]` names any
+//  roots. It starts from the saved index so `f ` works right away and rescans at
+//  once, or builds one if there is none; after that it rescans once a burst of
+//  change notifications from the roots settles, and every `rescan_minutes`. Each
+//  scan is published as a new snapshot and saved when it differs. Changing the
+//  settings stops the thread and starts a new one.
+// ==================================================================================
+
+/// `WaitForMultipleObjects` takes at most 64 handles; other roots wait for rescans.
+const MAX_WATCHED_ROOTS: usize = 64;
+
+static INDEX: Mutex<Option<Arc<FileIndex>>> = Mutex::new(None);
+/// Settings the running indexer uses, and the flag that stops it.
+static mut RUNNING: Option<(FileIndexConfig, Arc<AtomicBool>)> = None;
+
+/// The latest index, once the indexer has one.
+pub fn file_index() -> Option<Arc<FileIndex>> {
+    INDEX.lock().ok().and_then(|index| index.clone())
+}
+
+/// Starts, restarts or stops the indexer to match `config`; does nothing while it
+/// already runs with the same settings.
+pub unsafe fn start_file_indexer(config: &FileIndexConfig) {
+    if RUNNING
+        .as_ref()
+        .is_some_and(|(running, _)| running == config)
+    {
+        return;
+    }
+    if let Some((_, stop)) = RUNNING.take() {
+        stop.store(true, Ordering::SeqCst);
+    }
+    if let Ok(mut index) = INDEX.lock() {
+        *index = None;
+    }
+    if !config.is_enabled() {
+        return;
+    }
+
+    let stop = Arc::new(AtomicBool::new(false));
+    RUNNING = Some((config.clone(), stop.clone()));
+    let mut expanded = config.clone();
+    expanded.roots = config.roots.iter().map(|root| expand_env(root)).collect();
+    thread::spawn(move || unsafe { run(expanded, stop) });
+}
+
+unsafe fn run(config: FileIndexConfig, stop: Arc<AtomicBool>) {
+    let path = get_config_dir().map(|dir| dir.join(FILE_INDEX_FILE));
+    let save = |index: &FileIndex| {
+        if let Some(path) = &path {
+            let _ = fs::write(path, index.to_text(&config));
+        }
+    };
+    // Watched before the first scan, so nothing changed during it is missed.
+    let handles: Vec<HANDLE> = config
+        .roots
+        .iter()
+        .filter_map(|root| watch(root))
+        .take(MAX_WATCHED_ROOTS)
+        .collect();
+
+    let saved = path
+        .as_ref()
+        .and_then(|path| fs::read_to_string(path).ok())
+        .and_then(|text| FileIndex::parse(&text, &config));
+    let (mut index, mut due) = match saved {
+        // The saved index may be older than the files.
+        Some(saved) => (Arc::new(saved), true),
+        None => {
+            let built = FileIndex::build(&config);
+            if !stop.load(Ordering::SeqCst) {
+                save(&built);
+            }
+            (Arc::new(built), false)
+        }
+    };
+    publish(&index, &stop);
+
+    let debounce = Duration::from_millis(FILE_INDEX_DEBOUNCE_MS);
+    let rescan_every = (config.rescan_minutes > 0)
+        .then(|| Duration::from_secs(u64::from(config.rescan_minutes) * 60));
+    let mut last_scan = Instant::now();
+    let mut changed_at: Option<Instant> = None;
+
+    while !stop.load(Ordering::SeqCst) {
+        if due {
+            let fresh = index.refresh(&config);
+            if stop.load(Ordering::SeqCst) {
+                break;
+            }
+            if fresh != *index {
+                save(&fresh);
+                index = Arc::new(fresh);
+            }
+            publish(&index, &stop);
+            last_scan = Instant::now();
+            changed_at = None;
+        }
+        if wait_for_change(&handles) {
+            changed_at.get_or_insert_with(Instant::now);
+        }
+        due = changed_at.is_some_and(|at| at.elapsed() >= debounce)
+            || rescan_every.is_some_and(|every| last_scan.elapsed() >= every);
+    }
+
+    for handle in handles {
+        let _ = FindCloseChangeNotification(handle);
+    }
+}
+
+/// Swaps in `index` unless this indexer was stopped; checked under the lock so a
+/// stopped one cannot overwrite its successor's state.
+fn publish(index: &Arc<FileIndex>, stop: &AtomicBool) {
+    if let Ok(mut current) = INDEX.lock()
+        && !stop.load(Ordering::SeqCst)
+    {
+        *current = Some(index.clone());
+    }
+}
+
+/// Change notifications for names added, removed or renamed anywhere under `root`.
+unsafe fn watch(root: &str) -> Option<HANDLE> {
+    let wide: Vec<u16> = root.encode_utf16().chain(std::iter::once(0)).collect();
+    match FindFirstChangeNotificationW(
+        PCWSTR(wide.as_ptr()),
+        true,
+        FILE_NOTIFY_CHANGE_FILE_NAME | FILE_NOTIFY_CHANGE_DIR_NAME,
+    ) {
+        Ok(handle) if handle != INVALID_HANDLE_VALUE => Some(handle),
+        _ => None,
+    }
+}
+
+/// Waits up to `FILE_INDEX_POLL_MS` for a notification; true if one came.
+unsafe fn wait_for_change(handles: &[HANDLE]) -> bool {
+    if handles.is_empty() {
+        thread::sleep(Duration::from_millis(u64::from(FILE_INDEX_POLL_MS)));
+        return false;
+    }
+    let result = WaitForMultipleObjects(handles, false, FILE_INDEX_POLL_MS);
+    let signalled = result.0.wrapping_sub(WAIT_OBJECT_0.0) as usize;
+    match handles.get(signalled) {
+        Some(&handle) => {
+            let _ = FindNextChangeNotification(handle);
+            true
+        }
+        None => false,
+    }
+}
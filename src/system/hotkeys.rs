@@ -1,15 +1,91 @@
+#![allow(static_mut_refs)]
+
 use windows::Win32::Foundation::*;
+use windows::Win32::System::LibraryLoader::GetModuleHandleW;
 use windows::Win32::UI::Input::KeyboardAndMouse::*;
+use windows::Win32::UI::WindowsAndMessaging::*;
+
+use crate::data::double_tap::*;
+use crate::data::hotkey::*;
+use crate::data::settings::settings;
+use crate::ui::resources::{H_MAIN, WM_APP_DOUBLE_TAP};
+
+// Registration state of every configured hotkey, also read by the diagnostics command.
+pub static mut HOTKEYS: HotkeyRegistry = HotkeyRegistry::new();
 
-pub unsafe fn register_hotkeys(hwnd: HWND) -> bool {
-    // Win+R (ID 1)
-    let success = RegisterHotKey(Some(hwnd), 1, MOD_WIN | MOD_NOREPEAT, VK_R.0 as u32).is_ok();
-    if !success {
-        eprintln!("Failed to register Win+R hotkey! It might be in use by another application.");
+// Low-level keyboard hook feeding the double-tap detector; only installed when enabled.
+static mut KEYBOARD_HOOK: Option<HHOOK> = None;
+static mut DOUBLE_TAP: Option<DoubleTapDetector> = None;
+
+struct Win32Registrar(HWND);
+
+impl Registrar for Win32Registrar {
+    fn register(&mut self, id: i32, hotkey: Hotkey) -> Result<(), String> {
+        let modifiers = HOT_KEY_MODIFIERS(hotkey.modifiers) | MOD_NOREPEAT;
+        unsafe { RegisterHotKey(Some(self.0), id, modifiers, hotkey.vk) }
+            .map_err(|e| e.message().trim().to_string())
     }
-    success
+
+    fn unregister(&mut self, id: i32) {
+        let _ = unsafe { UnregisterHotKey(Some(self.0), id) };
+    }
+}
+
+/// (Re)registers every hotkey from the settings and reinstalls the double-tap hook.
+/// Returns the bindings that newly fell back or failed, so each conflict is reported once.
+pub unsafe fn register_hotkeys(hwnd: HWND) -> Vec<HotkeyStatus> {
+    install_double_tap_hook();
+    HOTKEYS.apply(&mut Win32Registrar(hwnd), &settings().hotkeys)
+}
+
+/// Heartbeat: tries again for hotkeys that are on their fallback or failed.
+pub unsafe fn retry_hotkeys(hwnd: HWND) -> Vec<HotkeyStatus> {
+    HOTKEYS.retry(&mut Win32Registrar(hwnd))
 }
 
 pub unsafe fn unregister_hotkeys(hwnd: HWND) {
-    let _ = UnregisterHotKey(Some(hwnd), 1);
+    remove_double_tap_hook();
+    HOTKEYS.apply(&mut Win32Registrar(hwnd), &[]);
+}
+
+/// Action bound to the `WM_HOTKEY` id.
+pub unsafe fn hotkey_action(id: usize) -> Option<HotkeyAction> {
+    HOTKEYS.action(id)
+}
+
+/// Windows silently drops low-level hooks that time out, so the hook is installed
+/// afresh on every registration rather than only when the setting changes.
+unsafe fn install_double_tap_hook() {
+    remove_double_tap_hook();
+    let Some(key) = settings().double_tap else {
+        return;
+    };
+    DOUBLE_TAP = Some(DoubleTapDetector::new(key, settings().double_tap_ms));
+    let instance = GetModuleHandleW(None).ok().map(|m| m.into());
+    KEYBOARD_HOOK = SetWindowsHookExW(WH_KEYBOARD_LL, Some(keyboard_hook_proc), instance, 0).ok();
+}
+
+unsafe fn remove_double_tap_hook() {
+    if let Some(hook) = KEYBOARD_HOOK.take() {
+        let _ = UnhookWindowsHookEx(hook);
+    }
+    DOUBLE_TAP = None;
+}
+
+unsafe extern "system" fn keyboard_hook_proc(code: i32, wp: WPARAM, lp: LPARAM) -> LRESULT {
+    if code == HC_ACTION as i32
+        && let Some(detector) = DOUBLE_TAP.as_mut()
+    {
+        let info = &*(lp.0 as *const KBDLLHOOKSTRUCT);
+        let event = KeyEvent {
+            vk: info.vkCode,
+            down: matches!(wp.0 as u32, WM_KEYDOWN | WM_SYSKEYDOWN),
+            time: info.time,
+        };
+        // Post rather than act here: the hook must return quickly.
+        if detector.feed(event) {
+            let _ = PostMessageW(Some(H_MAIN), WM_APP_DOUBLE_TAP, WPARAM(0), LPARAM(0));
+        }
+    }
+    CallNextHookEx(None, code, wp, lp)
 }
@@ -0,0 +1,30 @@
+#![allow(unsafe_op_in_unsafe_fn)]
+
+use windows::Win32::Globalization::*;
+use windows::core::*;
+
+/// The user's Windows display languages in preference order, e.g. `["de-AT", "en-US"]`.
+pub unsafe fn user_ui_languages() -> Vec<String> {
+    let mut count = 0u32;
+    let mut len = 0u32;
+    if GetUserPreferredUILanguages(MUI_LANGUAGE_NAME, &mut count, None, &mut len).is_err() {
+        return Vec::new();
+    }
+    let mut buffer = vec![0u16; len as usize];
+    if GetUserPreferredUILanguages(
+        MUI_LANGUAGE_NAME,
+        &mut count,
+        Some(PWSTR(buffer.as_mut_ptr())),
+        &mut len,
+    )
+    .is_err()
+    {
+        return Vec::new();
+    }
+    // Double-null-terminated list of names.
+    buffer
+        .split(|&c| c == 0)
+        .filter(|name| !name.is_empty())
+        .map(String::from_utf16_lossy)
+        .collect()
+}
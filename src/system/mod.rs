@@ -1,5 +1,15 @@
+pub mod apps;
+pub mod bookmarks;
+pub mod clipboard;
 pub mod executor;
 pub mod explorer;
+pub mod file_indexer;
 pub mod hotkeys;
+pub mod language;
 pub mod registry;
+pub mod repos;
 pub mod shortcut;
+pub mod ssh;
+pub mod terminal;
+pub mod vscode;
+pub mod watcher;
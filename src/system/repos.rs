@@ -0,0 +1,36 @@
+#![allow(unsafe_op_in_unsafe_fn)]
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::data::git_repos::{GitReposConfig, Repo, discover};
+use crate::system::executor::expand_env;
+
+// ==================================================================================
+//  REPOSITORIES
+//  Git working trees under the `[git]` roots. Like the Start menu apps, they are
+//  found again on a background thread each time the window opens, which also picks
+//  up branch switches; queries use whatever the last search found.
+// ==================================================================================
+
+static REPOS: Mutex<Option<Arc<Vec<Repo>>>> = Mutex::new(None);
+
+/// Repositories found by the last search.
+pub fn known_repos() -> Arc<Vec<Repo>> {
+    REPOS
+        .lock()
+        .map(|repos| repos.clone().unwrap_or_default())
+        .unwrap_or_default()
+}
+
+/// Searches the `[git]` roots again in the background.
+pub unsafe fn refresh_repos(config: &GitReposConfig) {
+    let roots: Vec<String> = config.roots.iter().map(|root| expand_env(root)).collect();
+    let depth = config.depth;
+    thread::spawn(move || {
+        let repos = discover(&roots, depth);
+        if let Ok(mut current) = REPOS.lock() {
+            *current = Some(Arc::new(repos));
+        }
+    });
+}
@@ -0,0 +1,34 @@
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::data::ssh_config::{SshHost, load_ssh_hosts};
+
+// ==================================================================================
+//  SSH
+//  Hosts from `%USERPROFILE%\.ssh`, read again on a background thread each time
+//  the window opens so new config entries and first connections show up.
+// ==================================================================================
+
+static HOSTS: Mutex<Option<Arc<Vec<SshHost>>>> = Mutex::new(None);
+
+/// Hosts found by the last read.
+pub fn ssh_hosts() -> Arc<Vec<SshHost>> {
+    HOSTS
+        .lock()
+        .map(|hosts| hosts.clone().unwrap_or_default())
+        .unwrap_or_default()
+}
+
+/// Reads the ssh config and `known_hosts` again in the background.
+pub fn refresh_ssh_hosts() {
+    thread::spawn(|| {
+        let Ok(profile) = std::env::var("USERPROFILE") else {
+            return;
+        };
+        let hosts = load_ssh_hosts(&Path::new(&profile).join(".ssh"));
+        if let Ok(mut current) = HOSTS.lock() {
+            *current = Some(Arc::new(hosts));
+        }
+    });
+}
@@ -0,0 +1,47 @@
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::data::terminal::{TerminalProfile, parse_terminal_settings};
+
+// ==================================================================================
+//  WINDOWS TERMINAL
+//  Profiles of the installed Windows Terminal, read again on a background thread
+//  each time the window opens so edits made in its settings UI show up.
+// ==================================================================================
+
+/// Where `settings.json` lives under `%LOCALAPPDATA%`: the Store build, the Preview
+/// and the unpackaged one. The first that exists is used.
+const SETTINGS_PATHS: &[&str] = &[
+    r"Packages\Microsoft.WindowsTerminal_8wekyb3d8bbwe\LocalState\settings.json",
+    r"Packages\Microsoft.WindowsTerminalPreview_8wekyb3d8bbwe\LocalState\settings.json",
+    r"Microsoft\Windows Terminal\settings.json",
+];
+
+static PROFILES: Mutex<Option<Arc<Vec<TerminalProfile>>>> = Mutex::new(None);
+
+/// Profiles found by the last read.
+pub fn terminal_profiles() -> Arc<Vec<TerminalProfile>> {
+    PROFILES
+        .lock()
+        .map(|profiles| profiles.clone().unwrap_or_default())
+        .unwrap_or_default()
+}
+
+/// Reads Windows Terminal's settings again in the background.
+pub fn refresh_terminal_profiles() {
+    thread::spawn(|| {
+        let Ok(local_app_data) = std::env::var("LOCALAPPDATA") else {
+            return;
+        };
+        let profiles = SETTINGS_PATHS
+            .iter()
+            .find_map(|path| fs::read_to_string(Path::new(&local_app_data).join(path)).ok())
+            .map(|text| parse_terminal_settings(&text))
+            .unwrap_or_default();
+        if let Ok(mut current) = PROFILES.lock() {
+            *current = Some(Arc::new(profiles));
+        }
+    });
+}
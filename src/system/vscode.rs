@@ -0,0 +1,51 @@
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use crate::data::vscode::{RecentWorkspace, parse_storage};
+
+// ==================================================================================
+//  VS CODE
+//  Recent folders and workspaces of the VS Code builds installed for this user,
+//  read again on a background thread each time the window opens, like the Start
+//  menu apps.
+// ==================================================================================
+
+/// Folder under `%APPDATA%` and command line name of each build.
+const EDITORS: &[(&str, &str)] = &[
+    ("Code", "code"),
+    ("Code - Insiders", "code-insiders"),
+    ("VSCodium", "codium"),
+];
+
+static RECENT: Mutex<Option<Arc<Vec<RecentWorkspace>>>> = Mutex::new(None);
+
+/// Workspaces found by the last read.
+pub fn recent_workspaces() -> Arc<Vec<RecentWorkspace>> {
+    RECENT
+        .lock()
+        .map(|recent| recent.clone().unwrap_or_default())
+        .unwrap_or_default()
+}
+
+/// Reads the editors' `storage.json` files again in the background.
+pub fn refresh_recent_workspaces() {
+    thread::spawn(|| {
+        let Ok(app_data) = std::env::var("APPDATA") else {
+            return;
+        };
+        let mut recent = Vec::new();
+        for (folder, command) in EDITORS {
+            let storage = Path::new(&app_data)
+                .join(folder)
+                .join(r"User\globalStorage\storage.json");
+            if let Ok(text) = fs::read_to_string(storage) {
+                recent.extend(parse_storage(&text, command));
+            }
+        }
+        if let Ok(mut current) = RECENT.lock() {
+            *current = Some(Arc::new(recent));
+        }
+    });
+}
@@ -0,0 +1,49 @@
+#![allow(unsafe_op_in_unsafe_fn)]
+
+use std::path::Path;
+use std::thread;
+use windows::Win32::Foundation::*;
+use windows::Win32::Storage::FileSystem::*;
+use windows::Win32::System::Threading::*;
+use windows::Win32::UI::WindowsAndMessaging::*;
+use windows::core::*;
+
+use crate::ui::resources::WM_APP_CONFIG_CHANGED;
+
+/// Watches the config directory (including sub-folders such as `themes`) on a background
+/// thread and posts `WM_APP_CONFIG_CHANGED` to `hwnd` for every change notification.
+/// Parsing happens on the UI thread so the watcher never touches shared state.
+pub unsafe fn start_config_watcher(hwnd: HWND, dir: &Path) {
+    let dir_u16: Vec<u16> = dir
+        .to_string_lossy()
+        .encode_utf16()
+        .chain(std::iter::once(0))
+        .collect();
+    let hwnd_val = hwnd.0 as usize;
+
+    thread::spawn(move || unsafe {
+        let hwnd = HWND(hwnd_val as *mut std::ffi::c_void);
+        let handle = match FindFirstChangeNotificationW(
+            PCWSTR(dir_u16.as_ptr()),
+            true,
+            FILE_NOTIFY_CHANGE_LAST_WRITE | FILE_NOTIFY_CHANGE_FILE_NAME,
+        ) {
+            Ok(h) if h != INVALID_HANDLE_VALUE => h,
+            _ => return,
+        };
+
+        loop {
+            if WaitForSingleObject(handle, INFINITE) != WAIT_OBJECT_0 {
+                break;
+            }
+            if PostMessageW(Some(hwnd), WM_APP_CONFIG_CHANGED, WPARAM(0), LPARAM(0)).is_err() {
+                // The main window is gone; nothing left to notify.
+                break;
+            }
+            if FindNextChangeNotification(handle).is_err() {
+                break;
+            }
+        }
+        let _ = FindCloseChangeNotification(handle);
+    });
+}
@@ -18,6 +18,9 @@ use windows::Win32::UI::Input::KeyboardAndMouse::*;
 use windows::Win32::UI::WindowsAndMessaging::*;
 
 use crate::config::*;
+use crate::data::locale::*;
+use crate::data::theme::theme;
+use crate::ui::layout::dialog_button;
 use crate::ui::resources::*;
 use crate::ui::*;
 
@@ -62,7 +65,6 @@ pub extern "system" fn dialog_wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
 
                 let padding = DIALOG_PADDING;
                 // let title_height = 15.0; // Removed title
-                let button_w = DIALOG_BTN_W;
                 let button_h = DIALOG_BTN_H;
 
                 let scale = get_dpi_scale(hwnd);
@@ -96,19 +98,13 @@ pub extern "system" fn dialog_wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
                         a: 0.0,
                     }));
 
-                    let is_dark = is_dark_mode();
-                    let text_val = if is_dark {
-                        COLOR_DARK_TEXT
-                    } else {
-                        COLOR_LIGHT_TEXT
-                    };
-                    let text_color = D2D1_COLOR_F {
-                        r: text_val,
-                        g: text_val,
-                        b: text_val,
-                        a: 1.0,
-                    };
-                    let brush = rt.CreateSolidColorBrush(&text_color, None).unwrap();
+                    let palette = current_palette();
+                    let fonts = &theme().fonts;
+                    let display = wide_family(&fonts.display);
+                    let small = wide_family(&fonts.small);
+                    let brush = rt
+                        .CreateSolidColorBrush(&d2d_color(palette.text), None)
+                        .unwrap();
 
                     /*
                     let title_format = DWRITE_FACTORY
@@ -130,12 +126,12 @@ pub extern "system" fn dialog_wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
                         .as_ref()
                         .unwrap()
                         .CreateTextFormat(
-                            FONT_DISPLAY,
+                            PCWSTR(display.as_ptr()),
                             None,
                             DWRITE_FONT_WEIGHT_REGULAR,
                             DWRITE_FONT_STYLE_NORMAL,
                             DWRITE_FONT_STRETCH_NORMAL,
-                            FONT_SZ_DIALOG_BTN,
+                            fonts.dialog_button_size,
                             w!("en-us"),
                         )
                         .unwrap();
@@ -163,15 +159,16 @@ pub extern "system" fn dialog_wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
                         .as_ref()
                         .unwrap()
                         .CreateTextFormat(
-                            FONT_SMALL,
+                            PCWSTR(small.as_ptr()),
                             None,
                             DWRITE_FONT_WEIGHT_NORMAL,
                             DWRITE_FONT_STYLE_NORMAL,
                             DWRITE_FONT_STRETCH_NORMAL,
-                            FONT_SZ_DIALOG_MSG,
+                            fonts.dialog_message_size,
                             w!("en-us"),
                         )
                         .unwrap();
+                    apply_reading_direction(&msg_format);
 
                     let msg_u16: Vec<u16> = DIALOG_MESSAGE.encode_utf16().collect();
                     rt.DrawText(
@@ -188,77 +185,41 @@ pub extern "system" fn dialog_wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
                         DWRITE_MEASURING_MODE_NATURAL,
                     );
 
-                    let btn_rect = D2D_RECT_F {
-                        left: w - padding - button_w,
-                        top: h - padding - button_h,
-                        right: w - padding,
-                        bottom: h - padding,
-                    };
+                    let btn_rect = d2d_rect(dialog_button(w, h, direction()));
                     let btn_bg = if DIALOG_HOVER_OK {
-                        let c = if is_dark {
-                            COLOR_DARK_BTN_HOVER
-                        } else {
-                            COLOR_LIGHT_BTN_HOVER
-                        };
-                        D2D1_COLOR_F {
-                            r: c,
-                            g: c,
-                            b: c,
-                            a: 0.8,
-                        }
+                        palette.button_hover.fade(0.8)
                     } else {
-                        let c = if is_dark {
-                            COLOR_DARK_BTN
-                        } else {
-                            COLOR_LIGHT_BTN
-                        };
-                        D2D1_COLOR_F {
-                            r: c,
-                            g: c,
-                            b: c,
-                            a: 0.5,
-                        }
+                        palette.button.fade(0.5)
                     };
-                    let btn_brush = rt.CreateSolidColorBrush(&btn_bg, None).unwrap();
+                    let btn_brush = rt.CreateSolidColorBrush(&d2d_color(btn_bg), None).unwrap();
                     rt.FillRoundedRectangle(
                         &D2D1_ROUNDED_RECT {
                             rect: btn_rect,
-                            radiusX: CORNER_RADIUS,
-                            radiusY: CORNER_RADIUS,
+                            radiusX: theme().corner_radius,
+                            radiusY: theme().corner_radius,
                         },
                         &btn_brush,
                     );
 
-                    let border_val = if is_dark {
-                        COLOR_DARK_BORDER
-                    } else {
-                        COLOR_LIGHT_BORDER
-                    };
                     let border_brush = rt
                         .CreateSolidColorBrush(
-                            &D2D1_COLOR_F {
-                                r: border_val,
-                                g: border_val,
-                                b: border_val,
-                                a: COLOR_BORDER_OPACITY,
-                            },
+                            &d2d_color(palette.border.fade(COLOR_BORDER_OPACITY)),
                             None,
                         )
                         .unwrap();
                     rt.DrawRoundedRectangle(
                         &D2D1_ROUNDED_RECT {
                             rect: btn_rect,
-                            radiusX: CORNER_RADIUS,
-                            radiusY: CORNER_RADIUS,
+                            radiusX: theme().corner_radius,
+                            radiusY: theme().corner_radius,
                         },
                         &border_brush,
                         1.0,
                         None,
                     );
 
-                    let ok_text = [b'O' as u16, b'K' as u16];
                     rt.DrawText(
-                        &ok_text,
+                        &tr_wide(Msg::Ok),
                         &ok_text_format,
                         &btn_rect,
                         &brush,
@@ -285,16 +246,7 @@ pub extern "system" fn dialog_wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
                 let w = (cr.right - cr.left) as f32 / scale;
                 let h = (cr.bottom - cr.top) as f32 / scale;
 
-                let padding = DIALOG_PADDING;
-                let button_w = DIALOG_BTN_W;
-                let button_h = DIALOG_BTN_H;
-
-                let btn_rect = D2D_RECT_F {
-                    left: w - padding - button_w,
-                    top: h - padding - button_h,
-                    right: w - padding,
-                    bottom: h - padding,
-                };
+                let btn_rect = d2d_rect(dialog_button(w, h, direction()));
 
                 if sx >= btn_rect.left
                     && sx <= btn_rect.right
@@ -318,16 +270,7 @@ pub extern "system" fn dialog_wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
                 let w = (cr.right - cr.left) as f32 / scale;
                 let h = (cr.bottom - cr.top) as f32 / scale;
 
-                let padding = DIALOG_PADDING;
-                let button_w = DIALOG_BTN_W;
-                let button_h = DIALOG_BTN_H;
-
-                let btn_rect = D2D_RECT_F {
-                    left: w - padding - button_w,
-                    top: h - padding - button_h,
-                    right: w - padding,
-                    bottom: h - padding,
-                };
+                let btn_rect = d2d_rect(dialog_button(w, h, direction()));
 
                 let inside = sx >= btn_rect.left
                     && sx <= btn_rect.right
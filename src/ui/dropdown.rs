@@ -2,6 +2,7 @@
 #![allow(static_mut_refs)]
 #![allow(non_snake_case)]
 
+use std::collections::HashMap;
 use std::time::Instant;
 use windows::Win32::Foundation::*;
 use windows::core::*;
@@ -23,6 +24,12 @@ use windows::Win32::UI::WindowsAndMessaging::*;
 use crate::animations::*;
 use crate::config::*;
 use crate::data::history::*;
+use crate::data::humanize::ellipsize_middle;
+use crate::data::locale::{direction, tr_wide};
+use crate::data::sections::{Row, source_glyph};
+use crate::data::settings::settings;
+use crate::data::theme::*;
+use crate::ui::layout::*;
 use crate::ui::resources::*;
 use crate::ui::*;
 
@@ -31,6 +38,40 @@ pub static mut SCROLL_OFFSET: usize = 0;
 pub static mut DROPDOWN_RENDER_TARGET: Option<ID2D1HwndRenderTarget> = None;
 pub static mut HOVER_DROPDOWN: Option<usize> = None;
 
+// Detail lines already shortened to fit, keyed by text and width, so painting does
+// not measure them again every frame.
+static mut FITTED_DETAILS: Option<HashMap<(String, u32), Vec<u16>>> = None;
+
+/// `detail` shortened in the middle to fit `width` DIPs in `format`.
+unsafe fn fitted_detail(detail: &str, format: &IDWriteTextFormat, width: f32) -> Vec<u16> {
+    let cache = FITTED_DETAILS.get_or_insert_with(HashMap::new);
+    if cache.len() > FITTED_DETAIL_CACHE_LIMIT {
+        cache.clear();
+    }
+    cache
+        .entry((detail.to_string(), width.to_bits()))
+        .or_insert_with(|| {
+            ellipsize_middle(detail, |text| text_width(text, format) <= width)
+                .encode_utf16()
+                .collect()
+        })
+        .clone()
+}
+
+/// Width of `text` on one line in `format`.
+unsafe fn text_width(text: &str, format: &IDWriteTextFormat) -> f32 {
+    let Some(dwrite) = &DWRITE_FACTORY else {
+        return 0.0;
+    };
+    let wide: Vec<u16> = text.encode_utf16().collect();
+    let Ok(layout) = dwrite.CreateTextLayout(&wide, format, f32::MAX, f32::MAX) else {
+        return 0.0;
+    };
+    let mut metrics = DWRITE_TEXT_METRICS::default();
+    let _ = layout.GetMetrics(&mut metrics);
+    metrics.widthIncludingTrailingWhitespace
+}
+
 pub unsafe fn ensure_dropdown_resources(hwnd: HWND) {
     if DROPDOWN_RENDER_TARGET.is_none() {
         let Some(factory) = &D2D_FACTORY else { return };
@@ -98,160 +139,43 @@ pub unsafe fn ensure_dropdown_resources(hwnd: HWND) {
     };
     let rt: ID2D1RenderTarget = target.cast().unwrap();
 
-    let is_dark = is_dark_mode();
-    let text_col_val = if is_dark {
-        COLOR_DARK_TEXT
-    } else {
-        COLOR_LIGHT_TEXT
-    };
-    let white = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: text_col_val,
-                g: text_col_val,
-                b: text_col_val,
-                a: 1.0,
-            },
-            None,
-        )
-        .unwrap();
-    let gray_col = if is_dark {
-        COLOR_DARK_BORDER
-    } else {
-        COLOR_LIGHT_BORDER
-    };
-    let gray = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: gray_col,
-                g: gray_col,
-                b: gray_col,
-                a: 0.5,
-            },
-            None,
-        )
-        .unwrap();
-    let input_bg = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: 0.0,
-                g: 0.0,
-                b: 0.0,
-                a: 0.1,
-            },
-            None,
-        )
-        .unwrap();
-    let btn_bg_col = if is_dark {
-        COLOR_DARK_BTN
-    } else {
-        COLOR_LIGHT_BTN
-    };
-    let btn_bg = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: btn_bg_col,
-                g: btn_bg_col,
-                b: btn_bg_col,
-                a: 0.9,
-            },
-            None,
-        )
-        .unwrap();
-    let btn_hover_col = if is_dark {
-        COLOR_DARK_BTN_HOVER
-    } else {
-        COLOR_LIGHT_BTN_HOVER
-    };
-    let btn_hover = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: btn_hover_col,
-                g: btn_hover_col,
-                b: btn_hover_col,
-                a: 0.95,
-            },
-            None,
-        )
-        .unwrap();
-    let close_hover = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: 0.769,
-                g: 0.169,
-                b: 0.11,
-                a: 1.0,
-            },
-            None,
-        )
-        .unwrap();
-
-    let (ar, ag, ab) = get_accent_color_values();
-    let accent = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: ar,
-                g: ag,
-                b: ab,
-                a: COLOR_ACCENT_OPACITY,
-            },
-            None,
-        )
-        .unwrap();
-    let accent_hover = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: (ar + COLOR_HOVER_BRIGHTEN).min(1.0),
-                g: (ag + COLOR_HOVER_BRIGHTEN).min(1.0),
-                b: (ab + COLOR_HOVER_BRIGHTEN).min(1.0),
-                a: 1.0,
-            },
-            None,
-        )
-        .unwrap();
+    let p = current_palette();
+    let (accent, accent_hover) = accent_colors();
+    let high_contrast = theme().high_contrast;
 
     DROPDOWN_BRUSHES = Some(Brushes {
-        placeholder: rt
-            .CreateSolidColorBrush(
-                &D2D1_COLOR_F {
-                    r: text_col_val,
-                    g: text_col_val,
-                    b: text_col_val,
-                    a: 0.4,
-                },
-                None,
-            )
-            .unwrap(),
-        white,
-        gray,
-        input_bg,
-        btn_bg,
-        btn_hover,
-        close_hover,
-        accent,
-        accent_hover,
-        selection: rt
-            .CreateSolidColorBrush(
-                &D2D1_COLOR_F {
-                    r: ar,
-                    g: ag,
-                    b: ab,
-                    a: 0.4,
-                },
-                None,
-            )
-            .unwrap(),
-        btn_border: rt
-            .CreateSolidColorBrush(
-                &D2D1_COLOR_F {
-                    r: gray_col,
-                    g: gray_col,
-                    b: gray_col,
-                    a: COLOR_BORDER_OPACITY,
-                },
-                None,
-            )
-            .unwrap(),
+        placeholder: solid_brush(
+            &rt,
+            if high_contrast {
+                p.text_secondary
+            } else {
+                p.text.fade(0.4)
+            },
+        ),
+        white: solid_brush(&rt, p.text),
+        gray: solid_brush(&rt, translucent(p.border, 0.5)),
+        input_bg: solid_brush(
+            &rt,
+            if high_contrast {
+                p.background
+            } else {
+                Color::gray(0.0).with_alpha(0.1)
+            },
+        ),
+        btn_bg: solid_brush(&rt, translucent(p.button, 0.9)),
+        btn_hover: solid_brush(&rt, translucent(p.button_hover, 0.95)),
+        close_hover: solid_brush(&rt, theme().destructive),
+        accent: solid_brush(&rt, translucent(accent, COLOR_ACCENT_OPACITY)),
+        accent_hover: solid_brush(&rt, accent_hover),
+        selection: solid_brush(&rt, translucent(accent, 0.4)),
+        btn_border: solid_brush(&rt, translucent(p.border, COLOR_BORDER_OPACITY)),
+        hover_text: solid_brush(&rt, p.hover_text),
+        accent_text: solid_brush(&rt, p.accent_text),
+        syntax_program: solid_brush(&rt, p.syntax_program),
+        syntax_string: solid_brush(&rt, p.syntax_string),
+        syntax_variable: solid_brush(&rt, p.syntax_variable),
+        syntax_url: solid_brush(&rt, p.syntax_url),
+        syntax_error: solid_brush(&rt, theme().destructive),
     });
 }
 
@@ -280,7 +204,8 @@ pub unsafe extern "system" fn dropdown_wndproc(
             }
             LRESULT(0)
         }
-        WM_SETTINGCHANGE => {
+        WM_SETTINGCHANGE | WM_SYSCOLORCHANGE => {
+            refresh_high_contrast();
             set_acrylic_effect(hwnd);
             DROPDOWN_BRUSHES = None;
             let _ = InvalidateRect(Some(hwnd), None, false);
@@ -355,80 +280,121 @@ pub unsafe extern "system" fn dropdown_wndproc(
                                         right: w,
                                         bottom: h,
                                     },
-                                    radiusX: CORNER_RADIUS,
-                                    radiusY: CORNER_RADIUS,
+                                    radiusX: theme().corner_radius,
+                                    radiusY: theme().corner_radius,
                                 },
                                 &b.input_bg,
                             );
 
-                            let history_to_use = if let Some(filtered) = FILTERED_HISTORY.as_ref() {
-                                filtered
-                            } else if let Some(history) = HISTORY.as_ref() {
-                                history
-                            } else {
-                                &Vec::new()
-                            };
-
-                            if !history_to_use.is_empty() {
-                                for (i, item) in history_to_use
-                                    .iter()
-                                    .skip(SCROLL_OFFSET)
-                                    .take(DROPDOWN_MAX_ITEMS)
-                                    .enumerate()
-                                {
-                                    let item_y = i as f32 * ITEM_H;
-                                    let total_items = history_to_use.len();
-                                    let scroll_width = if total_items > DROPDOWN_MAX_ITEMS {
-                                        8.0
-                                    } else {
-                                        0.0
-                                    };
-
-                                    let rect = D2D_RECT_F {
-                                        left: 0.0,
-                                        top: item_y,
-                                        right: w - scroll_width,
-                                        bottom: item_y + ITEM_H,
+                            if let Some(groups) = SUGGESTIONS.as_ref() {
+                                let dir = direction();
+                                let detailed = settings().detailed_rows;
+                                let rows = DropdownRows::new(&groups.rows, detailed);
+                                for (index, row) in rows.visible(SCROLL_OFFSET, w, h, dir) {
+                                    let item = match groups.rows[index] {
+                                        Row::Header(section) => {
+                                            rt.DrawText(
+                                                &tr_wide(section.title()),
+                                                &f.header,
+                                                &d2d_rect(dropdown_row_text(row)),
+                                                &b.placeholder,
+                                                D2D1_DRAW_TEXT_OPTIONS_CLIP,
+                                                DWRITE_MEASURING_MODE_NATURAL,
+                                            );
+                                            continue;
+                                        }
+                                        Row::Item(item) => &groups.items[item],
                                     };
 
-                                    if HOVER_DROPDOWN == Some(i) {
+                                    let hovered = HOVER_DROPDOWN == Some(index);
+                                    if hovered {
                                         rt.FillRoundedRectangle(
                                             &D2D1_ROUNDED_RECT {
-                                                rect,
-                                                radiusX: CORNER_RADIUS,
-                                                radiusY: CORNER_RADIUS,
+                                                rect: d2d_rect(row),
+                                                radiusX: theme().corner_radius,
+                                                radiusY: theme().corner_radius,
                                             },
                                             &b.btn_hover,
                                         );
                                     }
+                                    let text_brush = if hovered { &b.hover_text } else { &b.white };
 
-                                    let txt: Vec<u16> = item.encode_utf16().collect();
+                                    let (glyph_rect, text_rect) = dropdown_row_glyph(row, dir);
+                                    let glyph: Vec<u16> = source_glyph(item.source)
+                                        .encode_utf16(&mut [0; 2])
+                                        .to_vec();
                                     rt.DrawText(
-                                        &txt,
-                                        &f.label,
-                                        &D2D_RECT_F {
-                                            left: rect.left + 10.0,
-                                            top: rect.top,
-                                            right: rect.right - 10.0,
-                                            bottom: rect.bottom,
-                                        },
-                                        &b.white,
+                                        &glyph,
+                                        &f.glyph,
+                                        &d2d_rect(glyph_rect),
+                                        &b.placeholder,
                                         D2D1_DRAW_TEXT_OPTIONS_NONE,
                                         DWRITE_MEASURING_MODE_NATURAL,
                                     );
+
+                                    let txt: Vec<u16> = item.title().encode_utf16().collect();
+                                    let text_rect = dropdown_row_text(text_rect);
+                                    if detailed {
+                                        let (title_rect, detail_rect) =
+                                            dropdown_row_lines(text_rect);
+                                        rt.DrawText(
+                                            &txt,
+                                            &f.label,
+                                            &d2d_rect(title_rect),
+                                            text_brush,
+                                            D2D1_DRAW_TEXT_OPTIONS_CLIP,
+                                            DWRITE_MEASURING_MODE_NATURAL,
+                                        );
+                                        let detail = fitted_detail(
+                                            &item.detail,
+                                            &f.detail,
+                                            detail_rect.right - detail_rect.left,
+                                        );
+                                        rt.DrawText(
+                                            &detail,
+                                            &f.detail,
+                                            &d2d_rect(detail_rect),
+                                            &b.placeholder,
+                                            D2D1_DRAW_TEXT_OPTIONS_CLIP,
+                                            DWRITE_MEASURING_MODE_NATURAL,
+                                        );
+                                    } else if item.detail.is_empty() {
+                                        rt.DrawText(
+                                            &txt,
+                                            &f.label,
+                                            &d2d_rect(text_rect),
+                                            text_brush,
+                                            D2D1_DRAW_TEXT_OPTIONS_CLIP,
+                                            DWRITE_MEASURING_MODE_NATURAL,
+                                        );
+                                    } else {
+                                        let (entry_rect, detail_rect) =
+                                            dropdown_row_detail(text_rect, dir);
+                                        rt.DrawText(
+                                            &txt,
+                                            &f.label,
+                                            &d2d_rect(entry_rect),
+                                            text_brush,
+                                            D2D1_DRAW_TEXT_OPTIONS_CLIP,
+                                            DWRITE_MEASURING_MODE_NATURAL,
+                                        );
+                                        let detail: Vec<u16> = item.detail.encode_utf16().collect();
+                                        rt.DrawText(
+                                            &detail,
+                                            &f.label,
+                                            &d2d_rect(detail_rect),
+                                            &b.gray,
+                                            D2D1_DRAW_TEXT_OPTIONS_CLIP,
+                                            DWRITE_MEASURING_MODE_NATURAL,
+                                        );
+                                    }
                                 }
 
-                                let total_items = history_to_use.len();
-                                if total_items > DROPDOWN_MAX_ITEMS {
-                                    let ratio = DROPDOWN_MAX_ITEMS as f32 / total_items as f32;
-                                    let thumb_h = h * ratio;
-                                    let thumb_y = (SCROLL_OFFSET as f32 / total_items as f32) * h;
-                                    let scroll_rect = D2D_RECT_F {
-                                        left: w - 6.0,
-                                        top: thumb_y + 2.0,
-                                        right: w - 2.0,
-                                        bottom: thumb_y + thumb_h - 2.0,
-                                    };
+                                let total = rows.content_height();
+                                if total > h {
+                                    let offset = rows.height_of(SCROLL_OFFSET);
+                                    let scroll_rect =
+                                        d2d_rect(dropdown_thumb(w, h, offset, total, dir));
                                     rt.FillRoundedRectangle(
                                         &D2D1_ROUNDED_RECT {
                                             rect: scroll_rect,
@@ -461,53 +427,36 @@ pub unsafe extern "system" fn dropdown_wndproc(
             LRESULT(0)
         }
         WM_LBUTTONDOWN => {
-            if let Some(idx) = HOVER_DROPDOWN {
-                let history_to_use = if let Some(filtered) = FILTERED_HISTORY.as_ref() {
-                    filtered
-                } else if let Some(history) = HISTORY.as_ref() {
-                    history
-                } else {
-                    &Vec::new()
-                };
-
-                if let Some(cmd) = history_to_use.get(SCROLL_OFFSET + idx) {
-                    if let Ok(mut lock) = INPUT_BUFFER.lock() {
-                        *lock = cmd.clone();
-                    }
-                    let u16_vec: Vec<u16> = cmd.encode_utf16().chain(std::iter::once(0)).collect();
-                    IS_CYCLING = true;
-                    let _ = SetWindowTextW(H_EDIT, PCWSTR(u16_vec.as_ptr()));
-                    IS_CYCLING = false;
-                    FILTERED_HISTORY = None;
-                    PREDICTION = String::new();
-
-                    SHOW_DROPDOWN = false;
-                    if let Ok(main_hwnd) = FindWindowW(w!("SwiftRunClass"), w!("SwiftRun")) {
-                        DROPDOWN_ANIM_START = Some(Instant::now());
-                        DROPDOWN_ANIM_TYPE = AnimType::Exiting;
-                        SetTimer(Some(main_hwnd), 3, 16, None);
-                    }
-                    let _ = SetFocus(Some(H_EDIT));
-                    let _ = SendMessageW(
-                        H_EDIT,
-                        windows::Win32::UI::Controls::EM_SETSEL,
-                        Some(WPARAM(0)),
-                        Some(LPARAM(-1)),
-                    );
+            let picked = HOVER_DROPDOWN
+                .and_then(|row| SUGGESTIONS.as_ref()?.item_at(row))
+                .map(|item| item.text.clone());
+            if let Some(cmd) = picked {
+                INPUT.set_text(&cmd);
+                SUGGESTIONS = None;
+                PREDICTION = String::new();
+                cancel_suggestions();
+
+                SHOW_DROPDOWN = false;
+                if let Ok(main_hwnd) = FindWindowW(w!("SwiftRunClass"), w!("SwiftRun")) {
+                    DROPDOWN_ANIM_START = Some(Instant::now());
+                    DROPDOWN_ANIM_TYPE = AnimType::Exiting;
+                    SetTimer(Some(main_hwnd), 3, 16, None);
+                    let _ = SetFocus(Some(main_hwnd));
+                    let _ = InvalidateRect(Some(main_hwnd), None, false);
                 }
             }
             LRESULT(0)
         }
         WM_MOUSEMOVE => {
-            let y = (lp.0 >> 16) as i16 as f32;
-            let idx = (y / ITEM_H) as usize;
-            if idx < DROPDOWN_MAX_ITEMS {
-                if HOVER_DROPDOWN != Some(idx) {
-                    HOVER_DROPDOWN = Some(idx);
-                    let _ = InvalidateRect(Some(hwnd), None, false);
-                }
-            } else if HOVER_DROPDOWN.is_some() {
-                HOVER_DROPDOWN = None;
+            let y = (lp.0 >> 16) as i16 as f32 / get_dpi_scale(hwnd);
+            // Headers are not entries, so they never highlight.
+            let hovered = SUGGESTIONS.as_ref().and_then(|groups| {
+                let rows = DropdownRows::new(&groups.rows, settings().detailed_rows);
+                let row = rows.hit(SCROLL_OFFSET, y)?;
+                groups.item_at(row).map(|_| row)
+            });
+            if HOVER_DROPDOWN != hovered {
+                HOVER_DROPDOWN = hovered;
                 let _ = InvalidateRect(Some(hwnd), None, false);
             }
 
@@ -527,17 +476,19 @@ pub unsafe extern "system" fn dropdown_wndproc(
         }
         WM_MOUSEWHEEL => {
             let delta = (wp.0 >> 16) as i16;
-            if let Some(history) = HISTORY.as_ref() {
-                if history.len() > DROPDOWN_MAX_ITEMS {
-                    if delta > 0 {
-                        if SCROLL_OFFSET > 0 {
-                            SCROLL_OFFSET -= 1;
-                        }
-                    } else {
-                        if SCROLL_OFFSET < history.len() - DROPDOWN_MAX_ITEMS {
-                            SCROLL_OFFSET += 1;
-                        }
-                    }
+            if let Some(groups) = SUGGESTIONS.as_ref() {
+                let mut rect = RECT::default();
+                let _ = GetClientRect(hwnd, &mut rect);
+                let height = rect.bottom as f32 / get_dpi_scale(hwnd);
+                let rows = DropdownRows::new(&groups.rows, settings().detailed_rows);
+                let max_scroll = rows.max_scroll(height);
+                let scroll = if delta > 0 {
+                    SCROLL_OFFSET.saturating_sub(1)
+                } else {
+                    (SCROLL_OFFSET + 1).min(max_scroll)
+                };
+                if scroll != SCROLL_OFFSET {
+                    SCROLL_OFFSET = scroll;
                     let _ = InvalidateRect(Some(hwnd), None, false);
                 }
             }
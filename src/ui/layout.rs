@@ -0,0 +1,670 @@
+use crate::config::*;
+use crate::data::locale::Direction;
+use crate::data::sections::Row;
+
+// ==================================================================================
+//  LAYOUT
+//  Geometry of the main window, dropdown, tooltip and dialog in DIPs. Everything is
+//  laid out left to right; right-to-left locales get the same rects mirrored around
+//  the window's vertical axis, so hit testing and painting always agree.
+// ==================================================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Rect {
+    pub left: f32,
+    pub top: f32,
+    pub right: f32,
+    pub bottom: f32,
+}
+
+impl Rect {
+    pub const fn new(left: f32, top: f32, right: f32, bottom: f32) -> Rect {
+        Rect {
+            left,
+            top,
+            right,
+            bottom,
+        }
+    }
+
+    pub fn center_x(&self) -> f32 {
+        (self.left + self.right) / 2.0
+    }
+
+    pub fn center_y(&self) -> f32 {
+        (self.top + self.bottom) / 2.0
+    }
+
+    pub fn contains(&self, x: f32, y: f32) -> bool {
+        x >= self.left && x < self.right && y >= self.top && y < self.bottom
+    }
+
+    /// The same rect reflected inside a container `width` wide.
+    pub fn mirrored(&self, width: f32) -> Rect {
+        Rect::new(width - self.right, self.top, width - self.left, self.bottom)
+    }
+
+    /// `self` in LTR, mirrored inside `width` in RTL.
+    pub fn flow(&self, width: f32, direction: Direction) -> Rect {
+        match direction {
+            Direction::Ltr => *self,
+            Direction::Rtl => self.mirrored(width),
+        }
+    }
+
+    /// X coordinate `offset` DIPs in from the leading edge (left in LTR, right in RTL).
+    /// Used for glyphs that are not symmetric within their rect.
+    pub fn leading_x(&self, offset: f32, direction: Direction) -> f32 {
+        match direction {
+            Direction::Ltr => self.left + offset,
+            Direction::Rtl => self.right - offset,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MainLayout {
+    pub direction: Direction,
+    pub close: Rect,
+    pub min: Rect,
+    pub icon: Rect,
+    pub title: Rect,
+    /// The title stretched up to the caption buttons, for the history search prompt.
+    pub title_wide: Rect,
+    pub input: Rect,
+    pub search_icon: Rect,
+    pub text: Rect,
+    pub chevron: Rect,
+    pub ok: Rect,
+    pub cancel: Rect,
+}
+
+impl MainLayout {
+    pub fn new(width: f32, direction: Direction) -> MainLayout {
+        let w = width;
+        let icon_x = MARGIN - 5.0;
+        let chevron_x = w - MARGIN - CHEVRON_INSET;
+        let chevron_y = INPUT_Y + INPUT_H / 2.0;
+        let search_y = INPUT_Y + (INPUT_H - 16.0) / 2.0;
+        let ok_x = w - MARGIN - BTN_W * 2.0 - BTN_GAP;
+        let cancel_x = w - MARGIN - BTN_W;
+        let flow = |r: Rect| r.flow(w, direction);
+
+        MainLayout {
+            direction,
+            close: flow(Rect::new(w - WIN_BTN_W, 0.0, w, TITLE_BAR_H)),
+            min: flow(Rect::new(
+                w - WIN_BTN_W * 2.0,
+                0.0,
+                w - WIN_BTN_W,
+                TITLE_BAR_H,
+            )),
+            icon: flow(Rect::new(
+                icon_x,
+                TITLE_Y - 2.0,
+                icon_x + TITLE_ICON_SIZE,
+                TITLE_Y - 2.0 + TITLE_ICON_SIZE,
+            )),
+            title: flow(Rect::new(
+                icon_x + TITLE_ICON_SIZE + 8.0,
+                TITLE_Y,
+                200.0,
+                TITLE_Y + 20.0,
+            )),
+            title_wide: flow(Rect::new(
+                icon_x + TITLE_ICON_SIZE + 8.0,
+                TITLE_Y,
+                w - WIN_BTN_W * 2.0,
+                TITLE_Y + 20.0,
+            )),
+            input: flow(Rect::new(MARGIN, INPUT_Y, w - MARGIN, INPUT_Y + INPUT_H)),
+            search_icon: flow(Rect::new(
+                MARGIN + 12.0,
+                search_y,
+                MARGIN + 28.0,
+                search_y + 16.0,
+            )),
+            text: flow(Rect::new(
+                MARGIN + INPUT_TEXT_LEADING,
+                INPUT_Y + INPUT_TEXT_PAD_Y,
+                w - MARGIN - INPUT_TEXT_TRAILING,
+                INPUT_Y + INPUT_H - INPUT_TEXT_PAD_Y,
+            )),
+            chevron: flow(Rect::new(
+                chevron_x - 10.0,
+                chevron_y - 10.0,
+                chevron_x + 10.0,
+                chevron_y + 10.0,
+            )),
+            ok: flow(Rect::new(ok_x, BTN_Y, ok_x + BTN_W, BTN_Y + BTN_H)),
+            cancel: flow(Rect::new(cancel_x, BTN_Y, cancel_x + BTN_W, BTN_Y + BTN_H)),
+        }
+    }
+
+    /// What the pointer is over; the Run button only counts when there is input.
+    pub fn hit(&self, x: f32, y: f32, input_empty: bool) -> HoverId {
+        if self.close.contains(x, y) {
+            HoverId::Close
+        } else if self.min.contains(x, y) {
+            HoverId::Min
+        } else if self.ok.contains(x, y) {
+            if input_empty {
+                HoverId::None
+            } else {
+                HoverId::Ok
+            }
+        } else if self.cancel.contains(x, y) {
+            HoverId::Cancel
+        } else if self.chevron.contains(x, y) {
+            HoverId::Dropdown
+        } else if self.input.contains(x, y) {
+            HoverId::Input
+        } else {
+            HoverId::None
+        }
+    }
+}
+
+/// The dropdown's rows with their heights, shared by painting, hit testing and
+/// scrolling. Entry rows are taller when they show a detail line.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct DropdownRows<'a> {
+    pub rows: &'a [Row],
+    pub item_h: f32,
+}
+
+impl<'a> DropdownRows<'a> {
+    pub fn new(rows: &'a [Row], detailed: bool) -> DropdownRows<'a> {
+        DropdownRows {
+            rows,
+            item_h: if detailed { ITEM_H_DETAILED } else { ITEM_H },
+        }
+    }
+
+    pub fn row_height(&self, row: Row) -> f32 {
+        match row {
+            Row::Header(_) => HEADER_H,
+            Row::Item(_) => self.item_h,
+        }
+    }
+
+    /// Height of the first `count` rows.
+    pub fn height_of(&self, count: usize) -> f32 {
+        self.rows
+            .iter()
+            .take(count)
+            .map(|row| self.row_height(*row))
+            .sum()
+    }
+
+    /// Height of all rows.
+    pub fn content_height(&self) -> f32 {
+        self.height_of(self.rows.len())
+    }
+
+    /// Window height; longer lists scroll.
+    pub fn height(&self) -> f32 {
+        self.content_height().min(DROPDOWN_MAX_H)
+    }
+
+    /// Rows from `scroll` on that show in a dropdown `width` by `height`, with their
+    /// rects; the last one may be cut off. The scrollbar takes the trailing edge when
+    /// needed.
+    pub fn visible(
+        &self,
+        scroll: usize,
+        width: f32,
+        height: f32,
+        direction: Direction,
+    ) -> Vec<(usize, Rect)> {
+        let scroll_w = if self.content_height() > height {
+            DROPDOWN_SCROLLBAR_W
+        } else {
+            0.0
+        };
+        let mut top = 0.0;
+        let mut visible = Vec::new();
+        for (index, row) in self.rows.iter().enumerate().skip(scroll) {
+            if top >= height {
+                break;
+            }
+            let bottom = top + self.row_height(*row);
+            visible.push((
+                index,
+                Rect::new(0.0, top, width - scroll_w, bottom).flow(width, direction),
+            ));
+            top = bottom;
+        }
+        visible
+    }
+
+    /// Index of the row at `y` DIPs from the top of the dropdown.
+    pub fn hit(&self, scroll: usize, y: f32) -> Option<usize> {
+        if y < 0.0 {
+            return None;
+        }
+        let mut top = 0.0;
+        for (index, row) in self.rows.iter().enumerate().skip(scroll) {
+            top += self.row_height(*row);
+            if y < top {
+                return Some(index);
+            }
+        }
+        None
+    }
+
+    /// Furthest scroll (in rows) that still fills a dropdown `height` tall.
+    pub fn max_scroll(&self, height: f32) -> usize {
+        let mut tail = 0.0;
+        for (index, row) in self.rows.iter().enumerate().rev() {
+            tail += self.row_height(*row);
+            if tail > height {
+                return index + 1;
+            }
+        }
+        0
+    }
+}
+
+/// Splits an entry row into its source glyph and the text after it.
+pub fn dropdown_row_glyph(row: Rect, direction: Direction) -> (Rect, Rect) {
+    let width = row.right - row.left;
+    let glyph = Rect::new(
+        DROPDOWN_TEXT_PAD / 2.0,
+        row.top,
+        DROPDOWN_GLYPH_W + DROPDOWN_TEXT_PAD / 2.0,
+        row.bottom,
+    );
+    let text = Rect::new(glyph.right, row.top, width, row.bottom);
+    let place = |r: Rect| {
+        let r = r.flow(width, direction);
+        Rect::new(r.left + row.left, r.top, r.right + row.left, r.bottom)
+    };
+    (place(glyph), place(text))
+}
+
+/// Splits a detailed entry's text area into the title line and the detail line.
+pub fn dropdown_row_lines(text: Rect) -> (Rect, Rect) {
+    let middle = text.top + (text.bottom - text.top) * 0.55;
+    (
+        Rect::new(text.left, text.top + 2.0, text.right, middle),
+        Rect::new(text.left, middle, text.right, text.bottom - 4.0),
+    )
+}
+
+pub fn dropdown_row_text(row: Rect) -> Rect {
+    Rect::new(
+        row.left + DROPDOWN_TEXT_PAD,
+        row.top,
+        row.right - DROPDOWN_TEXT_PAD,
+        row.bottom,
+    )
+}
+
+/// Splits a row's text area into the entry and its description, which takes the
+/// trailing `DROPDOWN_DETAIL_RATIO` of it.
+pub fn dropdown_row_detail(text: Rect, direction: Direction) -> (Rect, Rect) {
+    let detail_w = (text.right - text.left) * DROPDOWN_DETAIL_RATIO;
+    let width = text.right - text.left;
+    let entry = Rect::new(
+        0.0,
+        text.top,
+        width - detail_w - DROPDOWN_TEXT_PAD,
+        text.bottom,
+    );
+    let detail = Rect::new(width - detail_w, text.top, width, text.bottom);
+    let place = |r: Rect| {
+        let r = r.flow(width, direction);
+        Rect::new(r.left + text.left, r.top, r.right + text.left, r.bottom)
+    };
+    (place(entry), place(detail))
+}
+
+/// Scrollbar thumb for a dropdown `height` tall over `total` DIPs of rows, scrolled
+/// down by `offset` DIPs.
+pub fn dropdown_thumb(
+    width: f32,
+    height: f32,
+    offset: f32,
+    total: f32,
+    direction: Direction,
+) -> Rect {
+    let thumb_h = height * (height / total);
+    let thumb_y = (offset / total) * height;
+    Rect::new(
+        width - 6.0,
+        thumb_y + 2.0,
+        width - 2.0,
+        thumb_y + thumb_h - 2.0,
+    )
+    .flow(width, direction)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TooltipLayout {
+    pub body: Rect,
+    /// Pointer towards the input: base start, base end, tip.
+    pub triangle: [(f32, f32); 3],
+    pub icon: Rect,
+    pub title: Rect,
+    pub message: Rect,
+}
+
+impl TooltipLayout {
+    pub fn new(width: f32, height: f32, direction: Direction) -> TooltipLayout {
+        let body_bottom = height - TOOLTIP_TRI_H;
+        let text_x = TOOLTIP_PADDING + TOOLTIP_ICON_SIZE + 10.0;
+        let flow = |r: Rect| r.flow(width, direction);
+        let body = Rect::new(0.0, 0.0, width, body_bottom);
+        TooltipLayout {
+            body,
+            triangle: [
+                (body.leading_x(TOOLTIP_TRI_X, direction), body_bottom),
+                (
+                    body.leading_x(TOOLTIP_TRI_X + TOOLTIP_TRI_W, direction),
+                    body_bottom,
+                ),
+                (body.leading_x(TOOLTIP_TRI_X, direction), height),
+            ],
+            icon: flow(Rect::new(
+                TOOLTIP_PADDING,
+                TOOLTIP_PADDING,
+                TOOLTIP_PADDING + TOOLTIP_ICON_SIZE,
+                TOOLTIP_PADDING + TOOLTIP_ICON_SIZE,
+            )),
+            title: flow(Rect::new(
+                text_x,
+                TOOLTIP_PADDING,
+                width - TOOLTIP_TEXT_TRAILING,
+                35.0,
+            )),
+            message: flow(Rect::new(
+                text_x,
+                38.0,
+                width - TOOLTIP_TEXT_TRAILING,
+                body_bottom - 5.0,
+            )),
+        }
+    }
+}
+
+/// Screen x of the tooltip window so that it starts at the input's leading edge.
+/// All values are physical pixels.
+pub fn tooltip_window_x(
+    window_left: i32,
+    window_right: i32,
+    tooltip_width: i32,
+    margin: i32,
+    direction: Direction,
+) -> i32 {
+    match direction {
+        Direction::Ltr => window_left + margin,
+        Direction::Rtl => window_right - margin - tooltip_width,
+    }
+}
+
+/// The dialog's single button sits in the trailing bottom corner.
+pub fn dialog_button(width: f32, height: f32, direction: Direction) -> Rect {
+    Rect::new(
+        width - DIALOG_PADDING - DIALOG_BTN_W,
+        height - DIALOG_PADDING - DIALOG_BTN_H,
+        width - DIALOG_PADDING,
+        height - DIALOG_PADDING,
+    )
+    .flow(width, direction)
+}
+
+/// Horizontal scroll of the input so the caret stays in view. `caret_x` and the
+/// content edges are text-layout x; the result is the layout x shown at the left
+/// edge of a `view_width` wide input, never scrolled past the content.
+pub fn scroll_to_caret(
+    scroll: f32,
+    caret_x: f32,
+    view_width: f32,
+    content_left: f32,
+    content_right: f32,
+) -> f32 {
+    let scroll = scroll.clamp(caret_x - view_width, caret_x);
+    let min = content_left.min(0.0);
+    let max = (content_right - view_width).max(min).max(0.0);
+    scroll.clamp(min, max)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::data::sections::Section;
+
+    const W: f32 = 450.0;
+
+    #[test]
+    fn rtl_mirrors_every_main_rect() {
+        let ltr = MainLayout::new(W, Direction::Ltr);
+        let rtl = MainLayout::new(W, Direction::Rtl);
+        for (l, r) in [
+            (ltr.close, rtl.close),
+            (ltr.min, rtl.min),
+            (ltr.icon, rtl.icon),
+            (ltr.title, rtl.title),
+            (ltr.title_wide, rtl.title_wide),
+            (ltr.input, rtl.input),
+            (ltr.search_icon, rtl.search_icon),
+            (ltr.text, rtl.text),
+            (ltr.chevron, rtl.chevron),
+            (ltr.ok, rtl.ok),
+            (ltr.cancel, rtl.cancel),
+        ] {
+            assert_eq!(l.mirrored(W), r);
+            assert!((l.center_x() + r.center_x() - W).abs() < 1e-3);
+            assert_eq!((l.top, l.bottom), (r.top, r.bottom));
+        }
+        assert_eq!(rtl.close.left, 0.0);
+        assert!(rtl.cancel.left < rtl.ok.left);
+        assert!(rtl.chevron.right < rtl.search_icon.left);
+    }
+
+    #[test]
+    fn ltr_matches_the_fixed_layout() {
+        let ltr = MainLayout::new(W, Direction::Ltr);
+        assert_eq!(ltr.close.right, W);
+        assert_eq!(ltr.ok.left, W - MARGIN - BTN_W * 2.0 - BTN_GAP);
+        assert_eq!(ltr.cancel.right, W - MARGIN);
+        assert_eq!(ltr.text.left, MARGIN + INPUT_TEXT_LEADING);
+    }
+
+    #[test]
+    fn hit_testing_follows_the_direction() {
+        for direction in [Direction::Ltr, Direction::Rtl] {
+            let layout = MainLayout::new(W, direction);
+            let center = |r: Rect| (r.center_x(), r.center_y());
+            for (rect, id) in [
+                (layout.close, HoverId::Close),
+                (layout.min, HoverId::Min),
+                (layout.ok, HoverId::Ok),
+                (layout.cancel, HoverId::Cancel),
+                (layout.chevron, HoverId::Dropdown),
+                (layout.text, HoverId::Input),
+            ] {
+                let (x, y) = center(rect);
+                assert_eq!(layout.hit(x, y, false), id, "{:?}", direction);
+            }
+            let (x, y) = center(layout.ok);
+            assert_eq!(layout.hit(x, y, true), HoverId::None);
+            assert_eq!(layout.hit(W / 2.0, BTN_Y - 1.0, false), HoverId::None);
+        }
+        let rtl = MainLayout::new(W, Direction::Rtl);
+        assert_eq!(rtl.hit(5.0, 5.0, false), HoverId::Close);
+        assert_eq!(rtl.hit(W - 5.0, 5.0, false), HoverId::None);
+    }
+
+    #[test]
+    fn leading_x_counts_from_the_leading_edge() {
+        let rect = Rect::new(10.0, 0.0, 110.0, 20.0);
+        assert_eq!(rect.leading_x(5.0, Direction::Ltr), 15.0);
+        assert_eq!(rect.leading_x(5.0, Direction::Rtl), 105.0);
+        assert_eq!(rect.flow(200.0, Direction::Ltr), rect);
+        assert_eq!(
+            rect.flow(200.0, Direction::Rtl),
+            Rect::new(90.0, 0.0, 190.0, 20.0)
+        );
+    }
+
+    #[test]
+    fn tooltip_points_from_the_leading_side() {
+        let ltr = TooltipLayout::new(400.0, 100.0, Direction::Ltr);
+        let rtl = TooltipLayout::new(400.0, 100.0, Direction::Rtl);
+        assert_eq!(ltr.triangle[0].0, TOOLTIP_TRI_X);
+        assert_eq!(rtl.triangle[0].0, 400.0 - TOOLTIP_TRI_X);
+        assert!(rtl.triangle[1].0 < rtl.triangle[0].0);
+        assert_eq!(ltr.icon.left, TOOLTIP_PADDING);
+        assert_eq!(rtl.icon.right, 400.0 - TOOLTIP_PADDING);
+        assert_eq!(ltr.message.mirrored(400.0), rtl.message);
+        assert_eq!(tooltip_window_x(100, 550, 400, 16, Direction::Ltr), 116);
+        assert_eq!(tooltip_window_x(100, 550, 400, 16, Direction::Rtl), 134);
+    }
+
+    #[test]
+    fn dialog_button_and_thumb_sit_on_the_trailing_side() {
+        let ltr = dialog_button(300.0, 150.0, Direction::Ltr);
+        let rtl = dialog_button(300.0, 150.0, Direction::Rtl);
+        assert_eq!(ltr.right, 300.0 - DIALOG_PADDING);
+        assert_eq!(rtl.left, DIALOG_PADDING);
+        assert_eq!(ltr.bottom, rtl.bottom);
+        assert_eq!(
+            dropdown_thumb(300.0, 130.0, 0.0, 260.0, Direction::Ltr).right,
+            298.0
+        );
+        let thumb = dropdown_thumb(300.0, 130.0, 130.0, 260.0, Direction::Rtl);
+        assert_eq!((thumb.left, thumb.right), (2.0, 6.0));
+        assert_eq!(thumb.top, 67.0);
+    }
+
+    #[test]
+    fn caret_scroll_keeps_the_caret_in_view() {
+        // Fits: no scroll.
+        assert_eq!(scroll_to_caret(0.0, 50.0, 100.0, 0.0, 80.0), 0.0);
+        // Caret past the right edge.
+        assert_eq!(scroll_to_caret(0.0, 150.0, 100.0, 0.0, 200.0), 50.0);
+        // Caret back left of the view.
+        assert_eq!(scroll_to_caret(50.0, 20.0, 100.0, 0.0, 200.0), 20.0);
+        // The text shrank: no empty space after it.
+        assert_eq!(scroll_to_caret(90.0, 100.0, 100.0, 0.0, 120.0), 20.0);
+        // RTL text overflows to the left.
+        assert_eq!(scroll_to_caret(0.0, -40.0, 100.0, -60.0, 100.0), -40.0);
+        assert_eq!(scroll_to_caret(-40.0, 90.0, 100.0, -60.0, 100.0), -10.0);
+    }
+
+    #[test]
+    fn dropdown_detail_sits_on_the_trailing_side() {
+        let text = Rect::new(10.0, 0.0, 210.0, 26.0);
+        let (entry, detail) = dropdown_row_detail(text, Direction::Ltr);
+        assert_eq!((entry.left, detail.right), (10.0, 210.0));
+        assert!(entry.right < detail.left);
+        let (entry_rtl, detail_rtl) = dropdown_row_detail(text, Direction::Rtl);
+        assert_eq!((detail_rtl.left, entry_rtl.right), (10.0, 210.0));
+        assert!(detail_rtl.right < entry_rtl.left);
+        assert_eq!(entry.mirrored(220.0), entry_rtl);
+    }
+
+    fn sample_rows() -> Vec<Row> {
+        vec![
+            Row::Header(Section::BestMatch),
+            Row::Item(0),
+            Row::Header(Section::History),
+            Row::Item(1),
+            Row::Item(2),
+        ]
+    }
+
+    #[test]
+    fn dropdown_rows_stack_headers_and_items() {
+        let sample = sample_rows();
+        let rows = DropdownRows::new(&sample, false);
+        assert_eq!(rows.content_height(), 2.0 * HEADER_H + 3.0 * ITEM_H);
+        assert_eq!(rows.height(), rows.content_height());
+        assert_eq!(rows.height_of(2), HEADER_H + ITEM_H);
+        let detailed = DropdownRows::new(&sample, true);
+        assert_eq!(
+            detailed.content_height(),
+            2.0 * HEADER_H + 3.0 * ITEM_H_DETAILED
+        );
+        assert_eq!(detailed.row_height(Row::Header(Section::History)), HEADER_H);
+        let long: Vec<Row> = (0..50).map(Row::Item).collect();
+        assert_eq!(DropdownRows::new(&long, false).height(), DROPDOWN_MAX_H);
+
+        let visible = rows.visible(0, 300.0, 500.0, Direction::Ltr);
+        assert_eq!(visible.len(), 5);
+        assert_eq!(
+            visible[1].1,
+            Rect::new(0.0, HEADER_H, 300.0, HEADER_H + ITEM_H)
+        );
+        assert_eq!(visible[2].1.bottom, 2.0 * HEADER_H + ITEM_H);
+        // Scrolled by two rows, the second header is at the top.
+        let scrolled = rows.visible(2, 300.0, 500.0, Direction::Ltr);
+        assert_eq!(scrolled[0], (2, Rect::new(0.0, 0.0, 300.0, HEADER_H)));
+        // A short list keeps a partly visible last row and leaves room for the scrollbar.
+        let cut = rows.visible(0, 300.0, HEADER_H + 1.0, Direction::Ltr);
+        assert_eq!(cut.len(), 2);
+        assert_eq!(cut[0].1.right, 300.0 - DROPDOWN_SCROLLBAR_W);
+    }
+
+    #[test]
+    fn dropdown_hit_testing_matches_the_painted_rows() {
+        let sample = sample_rows();
+        let rows = DropdownRows::new(&sample, false);
+        assert_eq!(rows.hit(0, 0.0), Some(0));
+        assert_eq!(rows.hit(0, HEADER_H - 0.5), Some(0));
+        assert_eq!(rows.hit(0, HEADER_H), Some(1));
+        assert_eq!(rows.hit(0, HEADER_H + ITEM_H + 1.0), Some(2));
+        assert_eq!(rows.hit(0, 2.0 * HEADER_H + ITEM_H + 1.0), Some(3));
+        assert_eq!(rows.hit(0, 1000.0), None);
+        assert_eq!(rows.hit(0, -1.0), None);
+        // Scrolled, y counts from the first visible row.
+        assert_eq!(rows.hit(3, 1.0), Some(3));
+        assert_eq!(rows.hit(3, ITEM_H + 1.0), Some(4));
+        for detailed in [false, true] {
+            let rows = DropdownRows::new(&sample, detailed);
+            for (index, rect) in rows.visible(1, 300.0, 500.0, Direction::Rtl) {
+                assert_eq!(rows.hit(1, rect.center_y()), Some(index));
+                assert_eq!(rows.hit(1, rect.top), Some(index));
+            }
+        }
+    }
+
+    #[test]
+    fn dropdown_scrolls_no_further_than_the_last_row() {
+        let sample = sample_rows();
+        let rows = DropdownRows::new(&sample, false);
+        assert_eq!(rows.max_scroll(500.0), 0);
+        // Only the last two items fit.
+        assert_eq!(rows.max_scroll(2.0 * ITEM_H), 3);
+        assert_eq!(rows.max_scroll(2.0 * ITEM_H + 1.0), 3);
+        assert_eq!(rows.max_scroll(2.0 * ITEM_H + HEADER_H), 2);
+        let long: Vec<Row> = (0..20).map(Row::Item).collect();
+        assert_eq!(DropdownRows::new(&long, false).max_scroll(5.0 * ITEM_H), 15);
+    }
+
+    #[test]
+    fn dropdown_glyph_leads_the_row() {
+        let row = Rect::new(8.0, 26.0, 308.0, 52.0);
+        let (glyph, text) = dropdown_row_glyph(row, Direction::Ltr);
+        assert_eq!(glyph.left, 8.0 + DROPDOWN_TEXT_PAD / 2.0);
+        assert_eq!(glyph.right, text.left);
+        assert_eq!(text.right, 308.0);
+        let (glyph_rtl, text_rtl) = dropdown_row_glyph(row, Direction::Rtl);
+        assert_eq!(glyph_rtl.right, 308.0 - DROPDOWN_TEXT_PAD / 2.0);
+        assert_eq!((text_rtl.left, text_rtl.right), (8.0, glyph_rtl.left));
+        assert_eq!(glyph_rtl.top, 26.0);
+    }
+
+    #[test]
+    fn detailed_rows_split_into_two_lines() {
+        let text = Rect::new(30.0, 42.0, 290.0, 84.0);
+        let (title, detail) = dropdown_row_lines(text);
+        assert_eq!((title.left, title.right), (30.0, 290.0));
+        assert_eq!((detail.left, detail.right), (30.0, 290.0));
+        assert_eq!(title.bottom, detail.top);
+        assert!(title.top >= 42.0 && detail.bottom <= 84.0);
+        assert!(detail.bottom - detail.top > 12.0);
+    }
+}
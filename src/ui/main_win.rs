@@ -2,6 +2,7 @@
 #![allow(static_mut_refs)]
 #![allow(non_snake_case)]
 
+use std::sync::{Arc, Mutex};
 use std::time::Instant;
 use windows::Win32::Foundation::*;
 use windows::core::*;
@@ -17,7 +18,6 @@ use windows::Win32::System::Com::*;
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
 use windows::Win32::System::SystemInformation::GetTickCount;
 use windows::Win32::System::Threading::*;
-use windows::Win32::UI::Controls::*;
 use windows::Win32::UI::HiDpi::GetDpiForWindow;
 use windows::Win32::UI::Input::KeyboardAndMouse::*;
 use windows::Win32::UI::WindowsAndMessaging::*;
@@ -25,136 +25,205 @@ use windows_numerics::Vector2 as D2D_POINT_2F;
 
 use crate::animations::*;
 use crate::config::*;
+use crate::data::aliases::ALIASES;
+use crate::data::calculator::CALCULATION_PREFIX;
+use crate::data::command_line::{CommandLine, program_key};
+use crate::data::completion_spec::{clear_completion_specs, completion_spec};
+use crate::data::expansion::expand_history;
 use crate::data::history::*;
-use crate::system::executor::run_command;
+use crate::data::history_search::HistorySearch;
+use crate::data::hotkey::{
+    Hotkey, HotkeyAction, HotkeyState, HotkeyStatus, MOD_ALT, MOD_CONTROL, MOD_SHIFT, MOD_WIN,
+};
+use crate::data::ini::ConfigError;
+use crate::data::keymap::KeyAction;
+use crate::data::locale::*;
+use crate::data::matcher::completion_suffix;
+use crate::data::providers::*;
+use crate::data::reload::*;
+use crate::data::sections::Groups;
+use crate::data::settings::settings;
+use crate::data::suggest::*;
+use crate::data::syntax::{TokenKind, highlight, word_at, words};
+use crate::data::text_buffer::*;
+use crate::data::theme::*;
+use crate::data::usage::*;
+use crate::system::apps::{installed_apps, refresh_apps};
+use crate::system::bookmarks::{bookmarks, refresh_bookmarks};
+use crate::system::clipboard::{clipboard_text, set_clipboard_text};
+use crate::system::executor::{
+    SystemResolver, clear_program_cache, open_location, resolve_target, run_command,
+};
+use crate::system::file_indexer::{file_index, start_file_indexer};
 use crate::system::hotkeys::*;
+use crate::system::repos::{known_repos, refresh_repos};
+use crate::system::ssh::{refresh_ssh_hosts, ssh_hosts};
+use crate::system::terminal::{refresh_terminal_profiles, terminal_profiles};
+use crate::system::vscode::{recent_workspaces, refresh_recent_workspaces};
+use crate::ui::dialog::show_fluent_dialog;
+use crate::ui::layout::*;
 use crate::ui::resources::*;
 use crate::ui::tooltip::show_tooltip;
 use crate::ui::*;
 
+// Notices (title, message) raised while the window is hidden or sliding in wait here
+// until it settles.
+pub static mut PENDING_NOTICE: Option<(String, String)> = None;
+
+// High surrogate from a WM_CHAR waiting for its low half.
+static mut PENDING_SURROGATE: Option<u16> = None;
+// Results handed over from the suggestion worker, collected on `WM_APP_SUGGESTIONS`.
+static PENDING_SUGGESTIONS: Mutex<Option<Suggestions>> = Mutex::new(None);
+
 pub unsafe fn is_input_empty() -> bool {
-    if let Ok(buf) = INPUT_BUFFER.lock() {
-        buf.is_empty()
-    } else {
-        true
-    }
+    INPUT.is_empty()
 }
 
-pub unsafe fn hit_test(x: i32, y: i32, w: f32, _h: f32, input_empty: bool) -> HoverId {
-    let fx = x as f32;
-    let fy = y as f32;
-
-    if fx >= w - WIN_BTN_W && fy < TITLE_BAR_H {
-        return HoverId::Close;
-    }
-    if fx >= w - WIN_BTN_W * 2.0 && fx < w - WIN_BTN_W && fy < TITLE_BAR_H {
-        return HoverId::Min;
-    }
+/// After the user changed the input: leave history browsing and refilter suggestions.
+unsafe fn input_changed(hwnd: HWND) {
+    HISTORY_INDEX = -1;
+    let text = INPUT.text().to_string();
+    update_suggestions(hwnd, &text);
+    let _ = InvalidateRect(Some(hwnd), None, false);
+}
 
-    let ok_x = w - MARGIN - BTN_W * 2.0 - 8.0;
-    if fx >= ok_x && fx < ok_x + BTN_W && fy >= BTN_Y && fy < BTN_Y + BTN_H {
-        return if input_empty {
-            HoverId::None
+/// Byte offset in the input nearest to window x `x` (DIPs), from the cached layout.
+unsafe fn input_offset_at(hwnd: HWND, x: f32) -> usize {
+    let Some(layout) = CACHED_TEXT_LAYOUT.as_ref() else {
+        return INPUT.caret();
+    };
+    let mut cr = RECT::default();
+    let _ = GetClientRect(hwnd, &mut cr);
+    let width = (cr.right - cr.left) as f32 / get_dpi_scale(hwnd);
+    let text = MainLayout::new(width, direction()).text;
+    let (mut trailing, mut inside, mut metrics) = (FALSE, FALSE, std::mem::zeroed());
+    let _ = layout.HitTestPoint(
+        x - text.left + INPUT_SCROLL_X,
+        0.0,
+        &mut trailing,
+        &mut inside,
+        &mut metrics,
+    );
+    let position = metrics.textPosition
+        + if trailing.as_bool() {
+            metrics.length
         } else {
-            HoverId::Ok
+            0
         };
-    }
-
-    let cancel_x = w - MARGIN - BTN_W;
-    if fx >= cancel_x && fx < cancel_x + BTN_W && fy >= BTN_Y && fy < BTN_Y + BTN_H {
-        return HoverId::Cancel;
-    }
+    byte_offset(INPUT.text(), position as usize)
+}
 
-    let chevron_x = w - MARGIN - 20.0;
-    let chevron_y = INPUT_Y + INPUT_H / 2.0;
-    if fx >= chevron_x - 10.0
-        && fx < chevron_x + 10.0
-        && fy >= chevron_y - 10.0
-        && fy < chevron_y + 10.0
-    {
-        return HoverId::Dropdown;
-    }
+pub unsafe fn hit_test(x: i32, y: i32, w: f32, _h: f32, input_empty: bool) -> HoverId {
+    MainLayout::new(w, direction()).hit(x as f32, y as f32, input_empty)
+}
 
-    if fx >= MARGIN && fx < w - MARGIN && fy >= INPUT_Y && fy < INPUT_Y + INPUT_H {
-        return HoverId::Input;
+/// Ghost text for input that `run_command` would expand, such as `sudo !!`.
+unsafe fn expansion_preview(text: &str) -> Option<String> {
+    if !text.contains('!') && !text.starts_with('^') {
+        return None;
     }
+    let expanded = expand_history(text, history_lines()).ok()??;
+    Some(format!("{}{}", EXPANSION_PREVIEW_PREFIX, expanded))
+}
 
-    HoverId::None
+/// The providers for one query, each with its own snapshot of what it searches.
+unsafe fn suggestion_providers(input: &str) -> Vec<Box<dyn SuggestionProvider>> {
+    let spec = CommandLine::parse(input)
+        .and_then(|line| completion_spec(&program_key(&line.program.value)));
+    vec![
+        Box::new(HistoryProvider {
+            history: HISTORY.clone().unwrap_or_default(),
+        }),
+        Box::new(CompletionProvider {
+            spec,
+            lister: SystemResolver,
+        }),
+        Box::new(AliasProvider {
+            aliases: ALIASES.clone().unwrap_or_default(),
+        }),
+        Box::new(AppsProvider {
+            apps: installed_apps(),
+        }),
+        Box::new(SettingsProvider {
+            pages: settings_pages(),
+        }),
+        Box::new(GitReposProvider {
+            repos: known_repos(),
+            open: settings().git.open.clone(),
+            detached: tr(Msg::GitDetached),
+        }),
+        Box::new(WorkspacesProvider {
+            recent: recent_workspaces(),
+        }),
+        Box::new(TerminalProfilesProvider {
+            profiles: terminal_profiles(),
+        }),
+        Box::new(SshHostsProvider { hosts: ssh_hosts() }),
+        Box::new(BookmarksProvider {
+            bookmarks: bookmarks(),
+        }),
+        Box::new(CalculatorProvider),
+        Box::new(FileProvider {
+            index: file_index(),
+        }),
+    ]
 }
 
+/// Starts a query for `input`; the results arrive as `WM_APP_SUGGESTIONS`.
 pub unsafe fn update_suggestions(hwnd: HWND, input: &str) {
     if input.is_empty() {
-        FILTERED_HISTORY = None;
-        PREDICTION = String::new();
-        if SHOW_DROPDOWN {
-            SHOW_DROPDOWN = false;
-            DROPDOWN_ANIM_START = Some(Instant::now());
-            DROPDOWN_ANIM_TYPE = AnimType::Exiting;
-            SetTimer(Some(hwnd), 3, ANIM_TIMER_MS, None);
-        }
+        cancel_suggestions();
+        show_suggestions(hwnd, Vec::new());
         return;
     }
 
-    let input_lower = input.to_lowercase();
-    let mut matches = Vec::new();
-    let mut best_prediction = String::new();
-
-    if let Some(history) = HISTORY.as_ref() {
-        for cmd in history {
-            let cmd_lower = cmd.to_lowercase();
-            if cmd_lower.starts_with(&input_lower) {
-                matches.push(cmd.clone());
-                if best_prediction.is_empty() && cmd_lower != input_lower {
-                    best_prediction = cmd.clone();
-                }
-            } else if cmd_lower.contains(&input_lower) {
-                matches.push(cmd.clone());
+    let query = Query {
+        text: input.to_string(),
+        ignore_accents: settings().ignore_accents,
+    };
+    let hwnd_val = hwnd.0 as usize;
+    SUGGESTER.get_or_insert_with(Orchestrator::new).query(
+        query,
+        suggestion_providers(input),
+        SUGGESTION_LIMIT,
+        move |results| {
+            if let Ok(mut pending) = PENDING_SUGGESTIONS.lock() {
+                *pending = Some(results);
             }
-        }
-    }
+            let hwnd = HWND(hwnd_val as *mut std::ffi::c_void);
+            let _ = PostMessageW(Some(hwnd), WM_APP_SUGGESTIONS, WPARAM(0), LPARAM(0));
+        },
+    );
+}
 
-    FILTERED_HISTORY = if matches.is_empty() {
+/// Fills the dropdown and the prediction from a finished query.
+unsafe fn show_suggestions(hwnd: HWND, items: Vec<Suggestion>) {
+    let input = INPUT.text();
+    let ignore_accents = settings().ignore_accents;
+    PREDICTION = items
+        .iter()
+        .find(|item| completion_suffix(&item.text, input, ignore_accents).is_some())
+        .map(|item| item.text.clone())
+        .unwrap_or_default();
+    SUGGESTIONS = if items.is_empty() {
         None
     } else {
-        Some(matches)
+        let mut groups = Groups::grouped(items);
+        add_usage_details(&mut groups);
+        Some(groups)
     };
-    PREDICTION = best_prediction;
 
-    if FILTERED_HISTORY.is_some() {
+    if SUGGESTIONS.is_some() {
         if !SHOW_DROPDOWN {
             SHOW_DROPDOWN = true;
-            HOVER_DROPDOWN = Some(0);
+            HOVER_DROPDOWN = SUGGESTIONS.as_ref().and_then(Groups::first_item_row);
             SCROLL_OFFSET = 0;
             DROPDOWN_ANIM_START = Some(Instant::now());
             DROPDOWN_ANIM_TYPE = AnimType::Entering;
             SetTimer(Some(hwnd), 3, ANIM_TIMER_MS, None);
         }
         // Update position/size if already shown
-        let mut rect = RECT::default();
-        let _ = GetWindowRect(hwnd, &mut rect);
-        let scale = get_dpi_scale(hwnd);
-        let margin_px = (MARGIN * scale) as i32;
-        let (x, y) = (
-            rect.left + margin_px,
-            rect.top + ((INPUT_Y + INPUT_H) * scale) as i32 + (DROPDOWN_GAP * scale) as i32,
-        );
-        let w = (rect.right - rect.left) - (margin_px * 2);
-        let count = FILTERED_HISTORY
-            .as_ref()
-            .unwrap()
-            .len()
-            .min(DROPDOWN_MAX_ITEMS);
-        let h = (count as f32 * ITEM_H * scale) as i32;
-
-        let _ = SetWindowPos(
-            H_DROPDOWN,
-            Some(HWND_TOPMOST),
-            x,
-            y,
-            w,
-            h,
-            SWP_SHOWWINDOW | SWP_NOACTIVATE,
-        );
+        place_dropdown(hwnd);
     } else if SHOW_DROPDOWN {
         SHOW_DROPDOWN = false;
         DROPDOWN_ANIM_START = Some(Instant::now());
@@ -165,7 +234,174 @@ pub unsafe fn update_suggestions(hwnd: HWND, input: &str) {
     let _ = InvalidateRect(Some(hwnd), None, false);
 }
 
+/// With `detailed_rows`, history entries tell when and how often they ran and what
+/// they open.
+unsafe fn add_usage_details(groups: &mut Groups) {
+    if !settings().detailed_rows {
+        return;
+    }
+    let now = now_unix();
+    for item in groups
+        .items
+        .iter_mut()
+        .filter(|item| item.source == Source::History && item.detail.is_empty())
+    {
+        let usage = USAGE.as_ref().and_then(|u| u.get(&item.text)).copied();
+        let target = resolve_target(&item.text)
+            .filter(|target| !target.eq_ignore_ascii_case(item.text.trim()))
+            .unwrap_or_default();
+        item.detail = usage_line(usage, now, &target);
+    }
+}
+
+/// Puts the dropdown under the input, as tall as its rows need.
+unsafe fn place_dropdown(hwnd: HWND) {
+    let rows = SUGGESTIONS.as_ref().map_or(&[][..], |groups| &groups.rows);
+    let rows = DropdownRows::new(rows, settings().detailed_rows);
+    let mut rect = RECT::default();
+    let _ = GetWindowRect(hwnd, &mut rect);
+    let scale = get_dpi_scale(hwnd);
+    let margin_px = (MARGIN * scale) as i32;
+    let (x, y) = (
+        rect.left + margin_px,
+        rect.top + ((INPUT_Y + INPUT_H) * scale) as i32 + (DROPDOWN_GAP * scale) as i32,
+    );
+    let w = (rect.right - rect.left) - (margin_px * 2);
+    let h = (rows.height() * scale) as i32;
+
+    let _ = SetWindowPos(
+        H_DROPDOWN,
+        Some(HWND_TOPMOST),
+        x,
+        y,
+        w,
+        h,
+        SWP_SHOWWINDOW | SWP_NOACTIVATE,
+    );
+}
+
+pub unsafe fn report_config_errors(errors: &[ConfigError]) {
+    let Some(first) = errors.first() else { return };
+    let mut message = first.to_string();
+    if errors.len() > 1 {
+        message.push(' ');
+        message.push_str(&tr_with(
+            Msg::ConfigErrorMore,
+            &[("count", &(errors.len() - 1).to_string())],
+        ));
+    }
+    message.push(' ');
+    message.push_str(&tr(Msg::ConfigErrorKept));
+    show_notice(&tr(Msg::ConfigErrorTitle), message);
+}
+
+/// One line per hotkey that fell back or could not be registered, naming the chord.
+pub unsafe fn report_hotkey_failures(statuses: &[HotkeyStatus]) {
+    if statuses.is_empty() {
+        return;
+    }
+    let message = statuses
+        .iter()
+        .map(hotkey_status_line)
+        .collect::<Vec<_>>()
+        .join("\n");
+    show_notice(&tr(Msg::HotkeyFailedTitle), message);
+}
+
+fn hotkey_status_line(status: &HotkeyStatus) -> String {
+    let binding = &status.binding;
+    let hotkey = binding.hotkey.to_string();
+    let fallback = binding.fallback.map(|f| f.to_string()).unwrap_or_default();
+    match &status.state {
+        HotkeyState::Registered => tr_with(
+            Msg::HotkeyActive,
+            &[("hotkey", &hotkey), ("action", binding.action.key())],
+        ),
+        HotkeyState::Fallback { error } => tr_with(
+            Msg::HotkeyFallback,
+            &[
+                ("hotkey", &hotkey),
+                ("fallback", &fallback),
+                ("error", error),
+            ],
+        ),
+        HotkeyState::Failed { error } => {
+            tr_with(Msg::HotkeyFailed, &[("hotkey", &hotkey), ("error", error)])
+        }
+        HotkeyState::Pending => hotkey,
+    }
+}
+
+/// Built-in `:diagnostics` command: the registration state of every hotkey.
+pub unsafe fn show_diagnostics() {
+    let statuses = HOTKEYS.statuses();
+    let message = if statuses.is_empty() {
+        tr(Msg::NoHotkeys).to_string()
+    } else {
+        statuses
+            .iter()
+            .map(hotkey_status_line)
+            .collect::<Vec<_>>()
+            .join("\n")
+    };
+    show_fluent_dialog(&tr(Msg::DiagnosticsTitle), &message);
+}
+
+/// Shows a tooltip now if the window is settled on screen, otherwise once it is.
+unsafe fn show_notice(title: &str, message: String) {
+    if IsWindowVisible(H_MAIN).as_bool() && ANIM_TYPE == AnimType::None {
+        show_tooltip(title, &message);
+    } else {
+        PENDING_NOTICE = Some((title.to_string(), message));
+    }
+}
+
+pub unsafe fn reset_render_caches() {
+    CACHED_TEXT_LAYOUT = None;
+    CACHED_TEXT.clear();
+    CACHED_GHOST_LAYOUT = None;
+    CACHED_GHOST_TEXT.clear();
+    CACHED_PLACEHOLDER_LAYOUT = None;
+    INPUT_SCROLL_X = 0.0;
+}
+
+/// Runs the reload pipeline after the debounce window and swaps the whole config at once.
+/// Brushes and fonts are rebuilt lazily on the next paint, like on `WM_SETTINGCHANGE`.
+unsafe fn apply_config_reload(hwnd: HWND) {
+    let Some(reloader) = CONFIG_RELOADER.as_mut() else {
+        let _ = KillTimer(Some(hwnd), 5);
+        return;
+    };
+    let outcome = reloader.poll(&ConfigSnapshot::current());
+    if !reloader.is_pending() {
+        let _ = KillTimer(Some(hwnd), 5);
+    }
+    let Some(outcome) = outcome else { return };
+
+    if !outcome.changed.is_empty() {
+        outcome.snapshot.apply();
+        if outcome.changed.contains(&ConfigFile::Settings) {
+            report_hotkey_failures(&register_hotkeys(hwnd));
+            start_file_indexer(&settings().files);
+        }
+        refresh_high_contrast();
+        set_acrylic_effect(hwnd);
+        if !H_DROPDOWN.0.is_null() {
+            set_acrylic_effect(H_DROPDOWN);
+        }
+        BRUSHES = None;
+        DROPDOWN_BRUSHES = None;
+        FONTS = None;
+        reset_render_caches();
+        let _ = InvalidateRect(Some(hwnd), None, false);
+        let _ = InvalidateRect(Some(H_DROPDOWN), None, false);
+    }
+    report_config_errors(&outcome.errors);
+}
+
 pub unsafe fn start_exit_animation(hwnd: HWND, kill: bool) {
+    HISTORY_SEARCH = None;
+    cancel_suggestions();
     if ANIM_TYPE == AnimType::Exiting {
         EXIT_KILL_PROCESS = kill;
         return;
@@ -208,6 +444,223 @@ pub unsafe fn start_exit_animation(hwnd: HWND, kill: bool) {
     SetTimer(Some(hwnd), 3, ANIM_TIMER_MS, None);
 }
 
+/// Keys during a history search. Returns false for keys that end the search and then
+/// do their usual job, as in a shell.
+unsafe fn search_key_action(hwnd: HWND, action: KeyAction) -> bool {
+    let history = history_lines();
+    let Some(search) = HISTORY_SEARCH.as_mut() else {
+        return false;
+    };
+    match action {
+        KeyAction::ReverseSearch => search.older(history),
+        KeyAction::Edit(EditCommand::BackwardDeleteChar) => search.pop(history),
+        KeyAction::Run => end_search(hwnd, true),
+        KeyAction::Close => end_search(hwnd, false),
+        _ => {
+            end_search(hwnd, true);
+            return false;
+        }
+    }
+    show_search(hwnd);
+    true
+}
+
+/// Shows the current match in the input with the query selected.
+unsafe fn show_search(hwnd: HWND) {
+    if let Some(search) = HISTORY_SEARCH.as_ref() {
+        let history = history_lines();
+        let text = search.current(history);
+        let selection = search
+            .match_range(history)
+            .unwrap_or(text.len()..text.len());
+        INPUT.preview(text, selection);
+    }
+    let _ = InvalidateRect(Some(hwnd), None, false);
+}
+
+/// Leaves the search with the match in the input, or with the text from before it.
+/// Either way a single undo step goes back to that text.
+unsafe fn end_search(hwnd: HWND, accept: bool) {
+    let Some(search) = HISTORY_SEARCH.take() else {
+        return;
+    };
+    let history = history_lines();
+    let original = search.original().to_string();
+    let text = if accept {
+        search.accept(history)
+    } else {
+        search.cancel()
+    };
+    INPUT.preview(&original, 0..0);
+    INPUT.set_text(&text);
+    INPUT.apply(EditCommand::EndOfLine);
+    HISTORY_INDEX = -1;
+    let _ = InvalidateRect(Some(hwnd), None, false);
+}
+
+/// Chord for a key-down of `vk` with the modifiers held right now.
+pub unsafe fn current_chord(vk: u32) -> Hotkey {
+    let held = |key: VIRTUAL_KEY| GetKeyState(key.0 as i32) < 0;
+    let mut modifiers = 0;
+    for (pressed, flag) in [
+        (held(VK_CONTROL), MOD_CONTROL),
+        (held(VK_MENU), MOD_ALT),
+        (held(VK_SHIFT), MOD_SHIFT),
+        (held(VK_LWIN) || held(VK_RWIN), MOD_WIN),
+    ] {
+        if pressed {
+            modifiers |= flag;
+        }
+    }
+    Hotkey { modifiers, vk }
+}
+
+/// Runs an in-window key binding from the keymap.
+pub unsafe fn run_key_action(hwnd: HWND, action: KeyAction) {
+    if HISTORY_SEARCH.is_some() && search_key_action(hwnd, action) {
+        return;
+    }
+    match action {
+        KeyAction::Run | KeyAction::RunElevated => {
+            let is_elevated = (action == KeyAction::RunElevated) as usize;
+            let _ = PostMessageW(
+                Some(hwnd),
+                WM_APP_RUN_COMMAND,
+                WPARAM(is_elevated),
+                LPARAM(0),
+            );
+        }
+        KeyAction::HistoryPrev | KeyAction::HistoryNext => {
+            cycle_history(if action == KeyAction::HistoryPrev {
+                -1
+            } else {
+                1
+            });
+            let _ = InvalidateRect(Some(hwnd), None, false);
+        }
+        KeyAction::AcceptPrediction => {
+            // Caret at the end, so typing carries on from the accepted command.
+            INPUT.set_text(&PREDICTION);
+            INPUT.apply(EditCommand::EndOfLine);
+            PREDICTION = String::new();
+            SUGGESTIONS = None;
+            cancel_suggestions();
+            if SHOW_DROPDOWN {
+                SHOW_DROPDOWN = false;
+                let _ = ShowWindow(H_DROPDOWN, SW_HIDE);
+            }
+            let _ = InvalidateRect(Some(hwnd), None, false);
+        }
+        KeyAction::ClearHistory => {
+            if let Some(path) = get_history_path() {
+                let _ = std::fs::remove_file(path);
+            }
+            clear_usage();
+            HISTORY = Some(Arc::default());
+            HISTORY_INDEX = -1;
+            if SHOW_DROPDOWN {
+                SHOW_DROPDOWN = false;
+                let _ = ShowWindow(H_DROPDOWN, SW_HIDE);
+            }
+            show_tooltip(&tr(Msg::HistoryClearedTitle), &tr(Msg::HistoryCleared));
+            let _ = InvalidateRect(Some(hwnd), None, false);
+        }
+        KeyAction::ReverseSearch => {
+            // Suggestions would only get in the way of the matches.
+            update_suggestions(hwnd, "");
+            HISTORY_SEARCH = Some(HistorySearch::new(INPUT.text(), settings().ignore_accents));
+            show_search(hwnd);
+        }
+        KeyAction::OpenLocation => open_location(),
+        KeyAction::Close => start_exit_animation(hwnd, false),
+        KeyAction::Copy | KeyAction::Cut => {
+            if INPUT.has_selection()
+                && set_clipboard_text(hwnd, INPUT.selected_text())
+                && action == KeyAction::Cut
+                && INPUT.insert("")
+            {
+                input_changed(hwnd);
+            }
+        }
+        KeyAction::Paste => {
+            if let Some(text) = clipboard_text(hwnd)
+                && INPUT.insert(&text)
+            {
+                input_changed(hwnd);
+            }
+        }
+        KeyAction::Edit(command) => {
+            if INPUT.apply(command) {
+                input_changed(hwnd);
+            }
+            let _ = InvalidateRect(Some(hwnd), None, false);
+        }
+    }
+}
+
+/// Runs a hotkey action; the double tap always toggles.
+unsafe fn run_hotkey_action(hwnd: HWND, action: HotkeyAction) {
+    let is_open = IsWindowVisible(hwnd).as_bool() && !IsIconic(hwnd).as_bool();
+    match action {
+        HotkeyAction::Toggle if is_open => start_exit_animation(hwnd, false),
+        HotkeyAction::Toggle => {
+            let latest = HISTORY.as_ref().and_then(|h| h.first()).cloned();
+            show_from_hotkey(hwnd, latest);
+        }
+        HotkeyAction::Show => show_from_hotkey(hwnd, Some(String::new())),
+        HotkeyAction::Clipboard => show_from_hotkey(hwnd, clipboard_text(hwnd)),
+        HotkeyAction::Calculator => {
+            show_from_hotkey(hwnd, Some(CALCULATION_PREFIX.to_string()));
+            // Typing continues after the prefix instead of replacing it.
+            INPUT.set_caret(CALCULATION_PREFIX.len(), false);
+        }
+    }
+}
+
+/// Programs may have been installed while hidden: look them up again for highlighting
+/// and rescan the Start menu apps, git repositories, recent editor workspaces,
+/// terminal profiles, ssh hosts and browser bookmarks.
+unsafe fn forget_resolved_programs() {
+    clear_program_cache();
+    clear_completion_specs();
+    refresh_apps();
+    refresh_repos(&settings().git);
+    refresh_recent_workspaces();
+    refresh_terminal_profiles();
+    refresh_ssh_hosts();
+    refresh_bookmarks();
+    CACHED_TEXT_LAYOUT = None;
+}
+
+/// Slides the window in from a global hotkey. `text` replaces the input (selected, so
+/// typing overwrites it); `None` keeps whatever is there.
+unsafe fn show_from_hotkey(hwnd: HWND, text: Option<String>) {
+    let is_minimized = IsIconic(hwnd).as_bool();
+    if is_minimized || !IsWindowVisible(hwnd).as_bool() {
+        if is_minimized {
+            let _ = ShowWindow(hwnd, SW_RESTORE);
+        }
+        update_animation_values(hwnd);
+
+        // Move to START_Y before showing to ensure slide-in starts from bottom
+        let _ = SetWindowPos(hwnd, Some(HWND_TOPMOST), FINAL_X, START_Y, 0, 0, SWP_NOSIZE);
+
+        let _ = ShowWindow(hwnd, SW_SHOW);
+        ANIM_TYPE = AnimType::Entering;
+        ANIM_START_TIME = None;
+        SetTimer(Some(hwnd), 3, 10, None);
+        forget_resolved_programs();
+    }
+    let _ = SetForegroundWindow(hwnd);
+    let _ = SetFocus(Some(hwnd));
+
+    if let Some(text) = text {
+        INPUT.reset(&text);
+        INPUT_SCROLL_X = 0.0;
+    }
+    let _ = InvalidateRect(Some(hwnd), None, false);
+}
+
 pub unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPARAM) -> LRESULT {
     match msg {
         WM_APP_RUN_COMMAND => {
@@ -220,19 +673,31 @@ pub unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
         }
         WM_APP_ERROR => {
             let _ = ShowWindow(hwnd, SW_SHOW);
-            if let Ok(buf) = INPUT_BUFFER.lock() {
-                show_tooltip(
-                    "Command not found",
-                    &format!(
-                        "SwiftRun cannot find '{}'. Make sure you typed the name correctly, and then try again.",
-                        buf
-                    ),
-                );
+            show_tooltip(
+                &tr(Msg::CommandNotFoundTitle),
+                &tr_with(Msg::CommandNotFound, &[("command", INPUT.text())]),
+            );
+            LRESULT(0)
+        }
+        WM_APP_CONFIG_CHANGED => {
+            if let Some(reloader) = CONFIG_RELOADER.as_mut() {
+                reloader.notify();
+                SetTimer(Some(hwnd), 5, CONFIG_RELOAD_DEBOUNCE_MS as u32, None);
+            }
+            LRESULT(0)
+        }
+        WM_APP_SUGGESTIONS => {
+            let results = PENDING_SUGGESTIONS.lock().ok().and_then(|mut p| p.take());
+            if let Some(results) = results
+                && SUGGESTER.as_ref().map(Orchestrator::generation) == Some(results.generation)
+            {
+                show_suggestions(hwnd, results.items);
             }
             LRESULT(0)
         }
         WM_APP_SHOW_UI => {
             update_animation_values(hwnd);
+            forget_resolved_programs();
 
             // Move to START_Y before showing to ensure slide-in starts from bottom
             let _ = SetWindowPos(
@@ -295,8 +760,8 @@ pub unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
                 SWP_NOMOVE | SWP_NOSIZE,
             );
 
-            let _ = SetFocus(Some(H_EDIT));
-            SendMessageW(H_EDIT, EM_SETSEL, Some(WPARAM(0)), Some(LPARAM(-1)));
+            let _ = SetFocus(Some(hwnd));
+            INPUT.select_all();
 
             ANIM_TYPE = AnimType::Entering;
             ANIM_START_TIME = None;
@@ -332,35 +797,10 @@ pub unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
                         CACHED_TEXT.clear();
                         CACHED_GHOST_LAYOUT = None;
                         CACHED_GHOST_TEXT.clear();
-                        CACHED_GHOST_PREDICTION_SOURCE.clear();
-                        CACHED_GHOST_INPUT_LEN = 0;
                         CACHED_PLACEHOLDER_LAYOUT = None;
-                        CACHED_SEL_START = 0;
-                        CACHED_SEL_END = 0;
-                        CACHED_SEL_X1 = 0.0;
-                        CACHED_SEL_X2 = 0.0;
                     }
                 }
             }
-            let _w = (lp.0 & 0xFFFF) as i32;
-            let _ = SetWindowPos(
-                H_EDIT,
-                None,
-                -10000,
-                -10000,
-                0,
-                0,
-                SWP_NOZORDER | SWP_NOACTIVATE,
-            );
-            let _ = SetWindowPos(
-                H_EDIT,
-                None,
-                -10000,
-                -10000,
-                0,
-                0,
-                SWP_NOZORDER | SWP_NOACTIVATE,
-            );
             CACHED_TEXT_LAYOUT = None;
             CACHED_TEXT.clear();
             let _ = InvalidateRect(Some(hwnd), None, false);
@@ -374,8 +814,8 @@ pub unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
                     let _ = InvalidateRect(Some(hwnd), None, false);
                 }
             } else {
-                let _ = SetFocus(Some(H_EDIT));
-                SendMessageW(H_EDIT, EM_SETSEL, Some(WPARAM(0)), Some(LPARAM(-1)));
+                INPUT.select_all();
+                let _ = InvalidateRect(Some(hwnd), None, false);
             }
             LRESULT(0)
         }
@@ -436,12 +876,16 @@ pub unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
             } else if wp.0 == 3 {
                 update_animations(hwnd);
             } else if wp.0 == 4 {
-                // Heartbeat: re-register hotkeys just in case
-                register_hotkeys(hwnd);
+                // Heartbeat: win back hotkeys another application has released
+                report_hotkey_failures(&retry_hotkeys(hwnd));
+            } else if wp.0 == 5 {
+                // Config reload debounce
+                apply_config_reload(hwnd);
             }
             LRESULT(0)
         }
-        WM_SETTINGCHANGE => {
+        WM_SETTINGCHANGE | WM_SYSCOLORCHANGE => {
+            refresh_high_contrast();
             set_acrylic_effect(hwnd);
             BRUSHES = None;
             let _ = InvalidateRect(Some(hwnd), None, false);
@@ -449,7 +893,7 @@ pub unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
         }
         WM_DISPLAYCHANGE => {
             // Screen resolution or monitor change might affect window environment
-            register_hotkeys(hwnd);
+            report_hotkey_failures(&register_hotkeys(hwnd));
             let _ = InvalidateRect(Some(hwnd), None, false);
             LRESULT(0)
         }
@@ -468,26 +912,31 @@ pub unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
                 HOVER = new_hover;
                 let _ = InvalidateRect(Some(hwnd), None, false);
             }
-            if wp.0 & 0x0001 != 0 {
-                let n_lp = LPARAM(
-                    (((sx - (MARGIN + 10.0)) * scale) as i32 as isize & 0xFFFF)
-                        | (((20.0 * scale) as i32 as isize & 0xFFFF) << 16),
-                );
-                SendMessageW(H_EDIT, WM_MOUSEMOVE, Some(wp), Some(n_lp));
+            // Dragging a selection in the input.
+            if wp.0 & 0x0001 != 0 && GetCapture() == hwnd {
+                INPUT.set_caret(input_offset_at(hwnd, sx), true);
                 let _ = InvalidateRect(Some(hwnd), None, false);
             }
             LRESULT(0)
         }
         WM_LBUTTONUP => {
             let _ = ReleaseCapture();
-            let n_lp = LPARAM(
-                ((((lp.0 & 0xFFFF) as i16 as f32 / get_dpi_scale(hwnd) - (MARGIN + 10.0))
-                    * get_dpi_scale(hwnd)) as i32 as isize
-                    & 0xFFFF)
-                    | (((20.0 * get_dpi_scale(hwnd)) as i32 as isize & 0xFFFF) << 16),
+            LRESULT(0)
+        }
+        WM_LBUTTONDBLCLK => {
+            let scale = get_dpi_scale(hwnd);
+            let (sx, sy) = (
+                (lp.0 & 0xFFFF) as i16 as f32 / scale,
+                ((lp.0 >> 16) & 0xFFFF) as i16 as f32 / scale,
             );
-            SendMessageW(H_EDIT, WM_LBUTTONUP, Some(wp), Some(n_lp));
-            let _ = InvalidateRect(Some(hwnd), None, false);
+            let mut cr = RECT::default();
+            let _ = GetClientRect(hwnd, &mut cr);
+            let w = (cr.right - cr.left) as f32 / scale;
+            let h = (cr.bottom - cr.top) as f32 / scale;
+            if hit_test(sx as i32, sy as i32, w, h, is_input_empty()) == HoverId::Input {
+                INPUT.select_word_at(input_offset_at(hwnd, sx));
+                let _ = InvalidateRect(Some(hwnd), None, false);
+            }
             LRESULT(0)
         }
         WM_LBUTTONDOWN => {
@@ -519,17 +968,15 @@ pub unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
                 HoverId::Ok => run_command(false),
                 HoverId::Cancel => start_exit_animation(hwnd, false),
                 HoverId::Input => {
+                    end_search(hwnd, true);
                     let _ = SetCapture(hwnd);
-                    let n_lp = LPARAM(
-                        (((sx - (MARGIN + 10.0)) * scale) as i32 as isize & 0xFFFF)
-                            | (((20.0 * scale) as i32 as isize & 0xFFFF) << 16),
-                    );
-                    SendMessageW(H_EDIT, WM_LBUTTONDOWN, Some(wp), Some(n_lp));
-                    let _ = SetFocus(Some(H_EDIT));
+                    let _ = SetFocus(Some(hwnd));
+                    // Shift+click extends the selection.
+                    INPUT.set_caret(input_offset_at(hwnd, sx), wp.0 & 0x0004 != 0);
                     let _ = InvalidateRect(Some(hwnd), None, false);
                 }
                 HoverId::None => {
-                    let _ = SetFocus(Some(H_EDIT));
+                    let _ = SetFocus(Some(hwnd));
                     if SHOW_DROPDOWN {
                         SHOW_DROPDOWN = false;
                         let _ = ShowWindow(H_DROPDOWN, SW_HIDE);
@@ -539,48 +986,21 @@ pub unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
                 HoverId::Dropdown => {
                     let is_empty = HISTORY.as_ref().map_or(true, |h| h.is_empty());
                     if is_empty {
-                        show_tooltip(
-                            "History is empty",
-                            "Your command history is currently empty.",
-                        );
+                        show_tooltip(&tr(Msg::HistoryEmptyTitle), &tr(Msg::HistoryEmpty));
                     } else {
                         if !SHOW_DROPDOWN {
-                            let mut drop_h = 0;
-                            if let Some(h) = HISTORY.as_ref() {
-                                let scale = get_dpi_scale(hwnd);
-                                drop_h = (h.len().min(DROPDOWN_MAX_ITEMS) as f32 * ITEM_H * scale)
-                                    as i32;
-                            }
-
-                            if drop_h > 0 {
-                                SHOW_DROPDOWN = true;
-                                HOVER_DROPDOWN = Some(0);
-                                SCROLL_OFFSET = 0;
-                                let mut rect = RECT::default();
-                                let _ = GetWindowRect(hwnd, &mut rect);
-                                let scale = get_dpi_scale(hwnd);
-                                let margin_px = (MARGIN * scale) as i32;
-                                let (x, y) = (
-                                    rect.left + margin_px,
-                                    rect.top
-                                        + ((INPUT_Y + INPUT_H) * scale) as i32
-                                        + (DROPDOWN_GAP * scale) as i32,
-                                );
-                                let w = (rect.right - rect.left) - (margin_px * 2);
-
-                                let _ = SetWindowPos(
-                                    H_DROPDOWN,
-                                    Some(HWND_TOPMOST),
-                                    x,
-                                    y,
-                                    w,
-                                    drop_h,
-                                    SWP_SHOWWINDOW | SWP_NOACTIVATE,
-                                );
-                                DROPDOWN_ANIM_START = Some(Instant::now());
-                                DROPDOWN_ANIM_TYPE = AnimType::Entering;
-                                SetTimer(Some(hwnd), 3, ANIM_TIMER_MS, None);
-                            }
+                            // The chevron browses the whole history, not the last query.
+                            cancel_suggestions();
+                            let mut groups = history_groups();
+                            add_usage_details(&mut groups);
+                            SUGGESTIONS = Some(groups);
+                            SHOW_DROPDOWN = true;
+                            HOVER_DROPDOWN = Some(0);
+                            SCROLL_OFFSET = 0;
+                            place_dropdown(hwnd);
+                            DROPDOWN_ANIM_START = Some(Instant::now());
+                            DROPDOWN_ANIM_TYPE = AnimType::Entering;
+                            SetTimer(Some(hwnd), 3, ANIM_TIMER_MS, None);
                         } else {
                             SHOW_DROPDOWN = false;
                             DROPDOWN_ANIM_START = Some(Instant::now());
@@ -599,23 +1019,26 @@ pub unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
             }
             LRESULT(0)
         }
-        WM_COMMAND => {
-            let (id, code) = (wp.0 & 0xFFFF, (wp.0 >> 16) & 0xFFFF);
-            if id as u32 == EDIT_ID && code == 0x0300 {
-                if !IS_CYCLING {
-                    HISTORY_INDEX = -1;
-                }
-                let len = GetWindowTextLengthW(H_EDIT);
-                let mut buf = vec![0u16; (len + 1) as usize];
-                GetWindowTextW(H_EDIT, &mut buf);
-                let current_input = String::from_utf16_lossy(&buf[..len as usize]);
-                if let Ok(mut lock) = INPUT_BUFFER.lock() {
-                    *lock = current_input.clone();
-                }
-                if !IS_CYCLING {
-                    update_suggestions(hwnd, &current_input);
+        WM_CHAR => {
+            // Characters outside the BMP arrive as two WM_CHARs, one per surrogate.
+            let unit = wp.0 as u16;
+            let text = if (0xD800..0xDC00).contains(&unit) {
+                PENDING_SURROGATE = Some(unit);
+                None
+            } else if let Some(high) = PENDING_SURROGATE.take() {
+                String::from_utf16(&[high, unit]).ok()
+            } else {
+                String::from_utf16(&[unit]).ok()
+            };
+            if let Some(text) = text {
+                if let Some(search) = HISTORY_SEARCH.as_mut() {
+                    if !text.contains(char::is_control) {
+                        search.push_str(&text, history_lines());
+                        show_search(hwnd);
+                    }
+                } else if INPUT.insert(&text) {
+                    input_changed(hwnd);
                 }
-                let _ = InvalidateRect(Some(hwnd), None, false);
             }
             LRESULT(0)
         }
@@ -627,71 +1050,21 @@ pub unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
             let _ = EndPaint(hwnd, &ps);
             LRESULT(0)
         }
-        WM_SETFOCUS => {
-            let _ = SetFocus(Some(H_EDIT));
-            LRESULT(0)
-        }
         WM_HOTKEY => {
-            if wp.0 == 1 {
-                let is_minimized = unsafe { IsIconic(hwnd).as_bool() };
-                if unsafe { IsWindowVisible(hwnd).as_bool() } && !is_minimized {
-                    unsafe {
-                        start_exit_animation(hwnd, false);
-                    }
-                } else {
-                    unsafe {
-                        if is_minimized {
-                            let _ = ShowWindow(hwnd, SW_RESTORE);
-                        }
-                        update_animation_values(hwnd);
-
-                        // Move to START_Y before showing to ensure slide-in starts from bottom
-                        let _ = SetWindowPos(
-                            hwnd,
-                            Some(HWND_TOPMOST),
-                            FINAL_X,
-                            START_Y,
-                            0,
-                            0,
-                            SWP_NOSIZE,
-                        );
-
-                        let _ = ShowWindow(hwnd, SW_SHOW);
-                        let _ = SetForegroundWindow(hwnd);
-                        let _ = SetFocus(Some(H_EDIT));
-                        ANIM_TYPE = AnimType::Entering;
-                        ANIM_START_TIME = None;
-                        SetTimer(Some(hwnd), 3, 10, None);
-                        if let Some(history) = &HISTORY {
-                            if let Some(latest) = history.first() {
-                                if let Ok(mut lock) = INPUT_BUFFER.lock() {
-                                    *lock = latest.clone();
-                                }
-                                let latest_u16: Vec<u16> =
-                                    latest.encode_utf16().chain(std::iter::once(0)).collect();
-
-                                // Suppress expensive prediction updates during restore
-                                IS_CYCLING = true;
-                                let _ = SetWindowTextW(H_EDIT, PCWSTR(latest_u16.as_ptr()));
-                                IS_CYCLING = false;
-                                let _ = SendMessageW(
-                                    H_EDIT,
-                                    EM_SETSEL,
-                                    Some(WPARAM(0)),
-                                    Some(LPARAM(-1)),
-                                );
-                            }
-                        }
-                    }
-                }
+            if let Some(action) = hotkey_action(wp.0) {
+                run_hotkey_action(hwnd, action);
             }
             LRESULT(0)
         }
+        WM_APP_DOUBLE_TAP => {
+            run_hotkey_action(hwnd, HotkeyAction::Toggle);
+            LRESULT(0)
+        }
         WM_WTSSESSION_CHANGE => {
             // Re-register hotkeys on session unlock to ensure they still work
             if wp.0 == 0x7 || wp.0 == 0x8 {
                 // WTS_SESSION_UNLOCK || WTS_SESSION_LOGON
-                register_hotkeys(hwnd);
+                report_hotkey_failures(&register_hotkeys(hwnd));
             }
             LRESULT(0)
         }
@@ -699,7 +1072,7 @@ pub unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
             // Re-register on resume from sleep
             if wp.0 == 0x7 || wp.0 == 0x12 {
                 // PBT_APMRESUMESUSPEND || PBT_APMRESUMEAUTOMATIC
-                register_hotkeys(hwnd);
+                report_hotkey_failures(&register_hotkeys(hwnd));
             }
             LRESULT(0)
         }
@@ -737,11 +1110,7 @@ pub unsafe extern "system" fn wndproc(hwnd: HWND, msg: u32, wp: WPARAM, lp: LPAR
             CACHED_TEXT.clear();
             CACHED_GHOST_LAYOUT = None;
             CACHED_GHOST_TEXT.clear();
-            CACHED_GHOST_PREDICTION_SOURCE.clear();
-            CACHED_GHOST_INPUT_LEN = 0;
             CACHED_PLACEHOLDER_LAYOUT = None;
-            CACHED_SEL_START = 0;
-            CACHED_SEL_END = 0;
             LRESULT(0)
         }
         _ => DefWindowProcW(hwnd, msg, wp, lp),
@@ -785,177 +1154,36 @@ pub unsafe fn ensure_resources(hwnd: HWND) {
     let Some(target) = &RENDER_TARGET else { return };
     let rt: ID2D1RenderTarget = target.cast().unwrap();
 
-    let is_dark = is_dark_mode();
-    let text_col_val = if is_dark {
-        COLOR_DARK_TEXT
-    } else {
-        COLOR_LIGHT_TEXT
-    };
-    let white = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: text_col_val,
-                g: text_col_val,
-                b: text_col_val,
-                a: 1.0,
-            },
-            None,
-        )
-        .unwrap();
-    let gray_col = if is_dark {
-        COLOR_DARK_TEXT_SEC
-    } else {
-        COLOR_LIGHT_TEXT_SEC
-    };
-    let gray = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: gray_col,
-                g: gray_col,
-                b: gray_col,
-                a: 1.0,
-            },
-            None,
-        )
-        .unwrap();
-    let input_bg_col = if is_dark {
-        COLOR_INPUT_BG_DARK
-    } else {
-        COLOR_INPUT_BG_LIGHT
-    };
-    let input_bg = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: input_bg_col,
-                g: input_bg_col,
-                b: input_bg_col,
-                a: COLOR_INPUT_BG_OPACITY,
-            },
-            None,
-        )
-        .unwrap();
-    let btn_bg_col = if is_dark {
-        COLOR_DARK_BTN
-    } else {
-        COLOR_LIGHT_BTN
-    };
-    let btn_bg = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: btn_bg_col,
-                g: btn_bg_col,
-                b: btn_bg_col,
-                a: 0.9,
-            },
-            None,
-        )
-        .unwrap();
-    let btn_hover_col = if is_dark {
-        COLOR_DARK_BTN_HOVER
-    } else {
-        COLOR_LIGHT_BTN_HOVER
-    };
-    let btn_hover = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: btn_hover_col,
-                g: btn_hover_col,
-                b: btn_hover_col,
-                a: 0.95,
-            },
-            None,
-        )
-        .unwrap();
-    let close_hover = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: COLOR_DESTRUCTIVE_R,
-                g: COLOR_DESTRUCTIVE_G,
-                b: COLOR_DESTRUCTIVE_B,
-                a: 1.0,
-            },
-            None,
-        )
-        .unwrap();
-
-    let (ar, ag, ab) = get_accent_color_values();
-    let accent = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: ar,
-                g: ag,
-                b: ab,
-                a: COLOR_ACCENT_OPACITY,
-            },
-            None,
-        )
-        .unwrap();
-    let accent_hover = rt
-        .CreateSolidColorBrush(
-            &D2D1_COLOR_F {
-                r: (ar + COLOR_HOVER_BRIGHTEN).min(1.0),
-                g: (ag + COLOR_HOVER_BRIGHTEN).min(1.0),
-                b: (ab + COLOR_HOVER_BRIGHTEN).min(1.0),
-                a: 1.0,
-            },
-            None,
-        )
-        .unwrap();
+    let p = current_palette();
+    let (accent, accent_hover) = accent_colors();
+    let high_contrast = theme().high_contrast;
 
     BRUSHES = Some(Brushes {
-        placeholder: rt
-            .CreateSolidColorBrush(
-                &D2D1_COLOR_F {
-                    r: text_col_val,
-                    g: text_col_val,
-                    b: text_col_val,
-                    a: COLOR_DISABLED_OPACITY,
-                },
-                None,
-            )
-            .unwrap(),
-        white,
-        gray,
-        input_bg,
-        btn_bg,
-        btn_hover,
-        close_hover,
-        accent,
-        accent_hover,
-        selection: rt
-            .CreateSolidColorBrush(
-                &D2D1_COLOR_F {
-                    r: ar,
-                    g: ag,
-                    b: ab,
-                    a: 0.4,
-                },
-                None,
-            )
-            .unwrap(),
-        btn_border: rt
-            .CreateSolidColorBrush(
-                &D2D1_COLOR_F {
-                    r: if is_dark {
-                        COLOR_DARK_BORDER
-                    } else {
-                        COLOR_LIGHT_BORDER
-                    },
-                    g: if is_dark {
-                        COLOR_DARK_BORDER
-                    } else {
-                        COLOR_LIGHT_BORDER
-                    },
-                    b: if is_dark {
-                        COLOR_DARK_BORDER
-                    } else {
-                        COLOR_LIGHT_BORDER
-                    },
-                    a: COLOR_BORDER_OPACITY,
-                },
-                None,
-            )
-            .unwrap(),
+        placeholder: solid_brush(
+            &rt,
+            if high_contrast {
+                p.text_secondary
+            } else {
+                p.text.fade(COLOR_DISABLED_OPACITY)
+            },
+        ),
+        white: solid_brush(&rt, p.text),
+        gray: solid_brush(&rt, p.text_secondary),
+        input_bg: solid_brush(&rt, p.input_background),
+        btn_bg: solid_brush(&rt, translucent(p.button, 0.9)),
+        btn_hover: solid_brush(&rt, translucent(p.button_hover, 0.95)),
+        close_hover: solid_brush(&rt, theme().destructive),
+        accent: solid_brush(&rt, translucent(accent, COLOR_ACCENT_OPACITY)),
+        accent_hover: solid_brush(&rt, accent_hover),
+        selection: solid_brush(&rt, translucent(accent, 0.4)),
+        btn_border: solid_brush(&rt, translucent(p.border, COLOR_BORDER_OPACITY)),
+        hover_text: solid_brush(&rt, p.hover_text),
+        accent_text: solid_brush(&rt, p.accent_text),
+        syntax_program: solid_brush(&rt, p.syntax_program),
+        syntax_string: solid_brush(&rt, p.syntax_string),
+        syntax_variable: solid_brush(&rt, p.syntax_variable),
+        syntax_url: solid_brush(&rt, p.syntax_url),
+        syntax_error: solid_brush(&rt, theme().destructive),
     });
 
     if WIC_FACTORY.is_none() {
@@ -1001,15 +1229,18 @@ pub unsafe fn ensure_fonts() {
     let Some(dwrite) = &DWRITE_FACTORY else {
         return;
     };
+    let fonts = &theme().fonts;
+    let display = wide_family(&fonts.display);
+    let text = wide_family(&fonts.text);
 
     let title = dwrite
         .CreateTextFormat(
-            FONT_DISPLAY,
+            PCWSTR(display.as_ptr()),
             None,
             DWRITE_FONT_WEIGHT_REGULAR,
             DWRITE_FONT_STYLE_NORMAL,
             DWRITE_FONT_STRETCH_NORMAL,
-            FONT_SZ_TITLE,
+            fonts.title_size,
             w!(""),
         )
         .unwrap();
@@ -1018,12 +1249,12 @@ pub unsafe fn ensure_fonts() {
 
     let label = dwrite
         .CreateTextFormat(
-            FONT_TEXT,
+            PCWSTR(text.as_ptr()),
             None,
             DWRITE_FONT_WEIGHT_NORMAL,
             DWRITE_FONT_STYLE_NORMAL,
             DWRITE_FONT_STRETCH_NORMAL,
-            FONT_SZ_LABEL,
+            fonts.label_size,
             w!(""),
         )
         .unwrap();
@@ -1032,12 +1263,12 @@ pub unsafe fn ensure_fonts() {
 
     let button = dwrite
         .CreateTextFormat(
-            FONT_TEXT,
+            PCWSTR(text.as_ptr()),
             None,
             DWRITE_FONT_WEIGHT_SEMI_BOLD,
             DWRITE_FONT_STYLE_NORMAL,
             DWRITE_FONT_STRETCH_NORMAL,
-            FONT_SZ_BUTTON,
+            fonts.button_size,
             w!(""),
         )
         .unwrap();
@@ -1046,12 +1277,12 @@ pub unsafe fn ensure_fonts() {
 
     let tooltip = dwrite
         .CreateTextFormat(
-            FONT_TEXT,
+            PCWSTR(text.as_ptr()),
             None,
             DWRITE_FONT_WEIGHT_REGULAR,
             DWRITE_FONT_STYLE_NORMAL,
             DWRITE_FONT_STRETCH_NORMAL,
-            FONT_SZ_TOOLTIP,
+            fonts.tooltip_size,
             w!(""),
         )
         .unwrap();
@@ -1061,12 +1292,12 @@ pub unsafe fn ensure_fonts() {
 
     let tooltip_bold = dwrite
         .CreateTextFormat(
-            FONT_TEXT,
+            PCWSTR(text.as_ptr()),
             None,
             DWRITE_FONT_WEIGHT_BOLD,
             DWRITE_FONT_STYLE_NORMAL,
             DWRITE_FONT_STRETCH_NORMAL,
-            FONT_SZ_TOOLTIP_BOLD,
+            fonts.tooltip_title_size,
             w!(""),
         )
         .unwrap();
@@ -1076,7 +1307,7 @@ pub unsafe fn ensure_fonts() {
 
     let icon = dwrite
         .CreateTextFormat(
-            FONT_TEXT,
+            PCWSTR(text.as_ptr()),
             None,
             DWRITE_FONT_WEIGHT_BOLD,
             DWRITE_FONT_STYLE_NORMAL,
@@ -1090,18 +1321,74 @@ pub unsafe fn ensure_fonts() {
 
     let input = dwrite
         .CreateTextFormat(
-            FONT_TEXT,
+            PCWSTR(text.as_ptr()),
             None,
             DWRITE_FONT_WEIGHT_SEMI_BOLD,
             DWRITE_FONT_STYLE_NORMAL,
             DWRITE_FONT_STRETCH_NORMAL,
-            FONT_SZ_BUTTON,
+            fonts.input_size,
             w!(""),
         )
         .unwrap();
     let _ = input.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_LEADING);
     let _ = input.SetParagraphAlignment(DWRITE_PARAGRAPH_ALIGNMENT_CENTER);
 
+    let header = dwrite
+        .CreateTextFormat(
+            PCWSTR(text.as_ptr()),
+            None,
+            DWRITE_FONT_WEIGHT_SEMI_BOLD,
+            DWRITE_FONT_STYLE_NORMAL,
+            DWRITE_FONT_STRETCH_NORMAL,
+            HEADER_FONT_SIZE,
+            w!(""),
+        )
+        .unwrap();
+    let _ = header.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_LEADING);
+    let _ = header.SetParagraphAlignment(DWRITE_PARAGRAPH_ALIGNMENT_CENTER);
+
+    let detail = dwrite
+        .CreateTextFormat(
+            PCWSTR(text.as_ptr()),
+            None,
+            DWRITE_FONT_WEIGHT_NORMAL,
+            DWRITE_FONT_STYLE_NORMAL,
+            DWRITE_FONT_STRETCH_NORMAL,
+            DETAIL_FONT_SIZE,
+            w!(""),
+        )
+        .unwrap();
+    let _ = detail.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_LEADING);
+    let _ = detail.SetParagraphAlignment(DWRITE_PARAGRAPH_ALIGNMENT_CENTER);
+    let _ = detail.SetWordWrapping(DWRITE_WORD_WRAPPING_NO_WRAP);
+
+    let glyph_family = wide_family(GLYPH_FONT);
+    let glyph = dwrite
+        .CreateTextFormat(
+            PCWSTR(glyph_family.as_ptr()),
+            None,
+            DWRITE_FONT_WEIGHT_NORMAL,
+            DWRITE_FONT_STYLE_NORMAL,
+            DWRITE_FONT_STRETCH_NORMAL,
+            GLYPH_SIZE,
+            w!(""),
+        )
+        .unwrap();
+    let _ = glyph.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_CENTER);
+    let _ = glyph.SetParagraphAlignment(DWRITE_PARAGRAPH_ALIGNMENT_CENTER);
+
+    for format in [
+        &title,
+        &label,
+        &button,
+        &tooltip,
+        &tooltip_bold,
+        &input,
+        &header,
+    ] {
+        apply_reading_direction(format);
+    }
+
     FONTS = Some(Fonts {
         title,
         label,
@@ -1110,6 +1397,9 @@ pub unsafe fn ensure_fonts() {
         tooltip_bold,
         icon,
         input,
+        header,
+        detail,
+        glyph,
     });
 }
 
@@ -1129,48 +1419,40 @@ pub unsafe fn paint() {
     }));
 
     let size = target.GetSize();
-    let (w, _h) = (size.width, size.height);
+    let geometry = MainLayout::new(size.width, direction());
+    let dir = geometry.direction;
 
-    let min_x = w - WIN_BTN_W * 2.0;
     if HOVER == HoverId::Min {
-        rt.FillRectangle(
-            &D2D_RECT_F {
-                left: min_x,
-                top: 0.0,
-                right: min_x + WIN_BTN_W,
-                bottom: TITLE_BAR_H,
-            },
-            &b.btn_hover,
-        );
+        rt.FillRectangle(&d2d_rect(geometry.min), &b.btn_hover);
     }
-    let cy = TITLE_BAR_H / 2.0;
+    let cy = geometry.min.center_y();
     rt.DrawLine(
         D2D_POINT_2F {
-            X: min_x + 18.0,
+            X: geometry.min.leading_x(18.0, dir),
             Y: cy,
         },
         D2D_POINT_2F {
-            X: min_x + 28.0,
+            X: geometry.min.leading_x(28.0, dir),
             Y: cy,
         },
-        &b.white,
+        if HOVER == HoverId::Min {
+            &b.hover_text
+        } else {
+            &b.white
+        },
         1.0,
         None,
     );
 
-    let close_x = w - WIN_BTN_W;
     if HOVER == HoverId::Close {
-        rt.FillRectangle(
-            &D2D_RECT_F {
-                left: close_x,
-                top: 0.0,
-                right: w,
-                bottom: TITLE_BAR_H,
-            },
-            &b.close_hover,
-        );
+        rt.FillRectangle(&d2d_rect(geometry.close), &b.close_hover);
     }
-    let cx = close_x + WIN_BTN_W / 2.2;
+    let cx = geometry.close.leading_x(WIN_BTN_W / 2.2, dir);
+    let close_brush = if HOVER == HoverId::Close {
+        &b.hover_text
+    } else {
+        &b.white
+    };
     rt.DrawLine(
         D2D_POINT_2F {
             X: cx - 5.0,
@@ -1180,7 +1462,7 @@ pub unsafe fn paint() {
             X: cx + 5.0,
             Y: cy + 5.0,
         },
-        &b.white,
+        close_brush,
         0.8,
         None,
     );
@@ -1193,76 +1475,79 @@ pub unsafe fn paint() {
             X: cx - 5.0,
             Y: cy + 5.0,
         },
-        &b.white,
+        close_brush,
         0.8,
         None,
     );
 
-    let icon_size = 24.0;
     if let Some(bitmap) = &APP_ICON_BITMAP {
         rt.DrawBitmap(
             bitmap,
-            Some(&D2D_RECT_F {
-                left: MARGIN - 5.0,
-                top: TITLE_Y - 2.0,
-                right: MARGIN - 5.0 + icon_size,
-                bottom: TITLE_Y - 2.0 + icon_size,
-            }),
+            Some(&d2d_rect(geometry.icon)),
             1.0,
             D2D1_BITMAP_INTERPOLATION_MODE_LINEAR,
             None,
         );
     }
 
+    // The title doubles as the prompt of a history search.
+    let app_title = tr_wide(Msg::AppTitle);
+    let search_title: Option<Vec<u16>> = HISTORY_SEARCH.as_ref().map(|search| {
+        let msg = if search.failed() {
+            Msg::SearchFailedTitle
+        } else {
+            Msg::SearchTitle
+        };
+        tr_with(msg, &[("query", search.query())])
+            .encode_utf16()
+            .collect()
+    });
     rt.DrawText(
-        get_str_title(),
+        search_title.as_deref().unwrap_or(&app_title),
         &f.title,
-        &D2D_RECT_F {
-            left: MARGIN - 5.0 + icon_size + 8.0,
-            top: TITLE_Y,
-            right: 200.0,
-            bottom: TITLE_Y + 20.0,
-        },
+        &d2d_rect(if search_title.is_some() {
+            geometry.title_wide
+        } else {
+            geometry.title
+        }),
         &b.white,
-        D2D1_DRAW_TEXT_OPTIONS_NONE,
+        D2D1_DRAW_TEXT_OPTIONS_CLIP,
         DWRITE_MEASURING_MODE_NATURAL,
     );
 
-    let input_rect = D2D_RECT_F {
-        left: MARGIN,
-        top: INPUT_Y,
-        right: w - MARGIN,
-        bottom: INPUT_Y + INPUT_H,
-    };
+    let input_rect = d2d_rect(geometry.input);
     rt.FillRoundedRectangle(
         &D2D1_ROUNDED_RECT {
             rect: input_rect,
-            radiusX: CORNER_RADIUS,
-            radiusY: CORNER_RADIUS,
+            radiusX: theme().corner_radius,
+            radiusY: theme().corner_radius,
         },
         &b.input_bg,
     );
     rt.DrawRoundedRectangle(
         &D2D1_ROUNDED_RECT {
             rect: input_rect,
-            radiusX: CORNER_RADIUS,
-            radiusY: CORNER_RADIUS,
+            radiusX: theme().corner_radius,
+            radiusY: theme().corner_radius,
         },
         &b.accent,
-        1.0,
+        if theme().high_contrast {
+            HC_FOCUS_STROKE
+        } else {
+            1.0
+        },
         None,
     );
 
-    // Search Icon (Magnifying Glass)
-    let search_icon_x = MARGIN + 12.0;
-    let search_icon_y = INPUT_Y + (INPUT_H - 16.0) / 2.0;
+    // Search Icon (Magnifying Glass), handle towards the text
+    let search = geometry.search_icon;
     let search_brush = &b.accent;
 
     rt.DrawEllipse(
         &D2D1_ELLIPSE {
             point: D2D_POINT_2F {
-                X: search_icon_x + 6.0,
-                Y: search_icon_y + 6.0,
+                X: search.leading_x(6.0, dir),
+                Y: search.top + 6.0,
             },
             radiusX: 5.0,
             radiusY: 5.0,
@@ -1273,280 +1558,228 @@ pub unsafe fn paint() {
     );
     rt.DrawLine(
         D2D_POINT_2F {
-            X: search_icon_x + 10.0,
-            Y: search_icon_y + 10.0,
+            X: search.leading_x(10.0, dir),
+            Y: search.top + 10.0,
         },
         D2D_POINT_2F {
-            X: search_icon_x + 14.0,
-            Y: search_icon_y + 14.0,
+            X: search.leading_x(14.0, dir),
+            Y: search.top + 14.0,
         },
         search_brush,
         1.5,
         None,
     );
 
-    if let Ok(buf) = INPUT_BUFFER.lock() {
-        let text_rect = D2D_RECT_F {
-            left: MARGIN + 35.0,
-            top: INPUT_Y + 8.0,
-            right: w - MARGIN - 30.0,
-            bottom: INPUT_Y + INPUT_H - 8.0,
-        };
-
-        // Get Selection ONCE (moved up)
-        let (mut start, mut end) = (0, 0);
-        SendMessageW(
-            H_EDIT,
-            EM_GETSEL,
-            Some(WPARAM(&mut start as *mut _ as usize)),
-            Some(LPARAM(&mut end as *mut _ as isize)),
-        );
-
-        if buf.is_empty() {
-            // Placeholder Cache Logic
-            if let Some(dwrite) = &DWRITE_FACTORY {
-                if CACHED_PLACEHOLDER_LAYOUT.is_some() {
-                    let layout = CACHED_PLACEHOLDER_LAYOUT.as_ref().unwrap();
-                    rt.DrawTextLayout(
-                        D2D_POINT_2F {
-                            X: text_rect.left,
-                            Y: text_rect.top,
-                        },
-                        layout,
-                        &b.placeholder,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
-                    );
-                } else {
-                    let hint_u16: Vec<u16> = "Search or run a command...".encode_utf16().collect();
-                    if let Ok(layout) = dwrite.CreateTextLayout(
-                        &hint_u16,
-                        &f.input,
-                        text_rect.right - text_rect.left,
-                        text_rect.bottom - text_rect.top,
-                    ) {
-                        let _ = layout.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_LEADING);
-                        rt.DrawTextLayout(
-                            D2D_POINT_2F {
-                                X: text_rect.left,
-                                Y: text_rect.top,
-                            },
-                            &layout,
-                            &b.placeholder,
-                            D2D1_DRAW_TEXT_OPTIONS_NONE,
-                        );
-                        CACHED_PLACEHOLDER_LAYOUT = Some(layout);
-                    }
+    let text_rect = d2d_rect(geometry.text);
+    let text = INPUT.text();
+    let mut caret_x = geometry.text.leading_x(0.0, dir);
+
+    if text.is_empty() {
+        INPUT_SCROLL_X = 0.0;
+        // Placeholder Cache Logic
+        if let Some(dwrite) = &DWRITE_FACTORY {
+            if CACHED_PLACEHOLDER_LAYOUT.is_none() {
+                let hint_u16 = tr_wide(Msg::InputPlaceholder);
+                if let Ok(layout) = dwrite.CreateTextLayout(
+                    &hint_u16,
+                    &f.input,
+                    text_rect.right - text_rect.left,
+                    text_rect.bottom - text_rect.top,
+                ) {
+                    let _ = layout.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_LEADING);
+                    CACHED_PLACEHOLDER_LAYOUT = Some(layout);
                 }
             }
-        } else {
-            // Main Text Layout Logic
-            if let Some(dwrite) = &DWRITE_FACTORY {
-                // Ensure Cache matches
-                if CACHED_TEXT != *buf || CACHED_TEXT_LAYOUT.is_none() {
-                    let text_u16: Vec<u16> = buf.encode_utf16().collect();
-                    if let Ok(new_layout) = dwrite.CreateTextLayout(
-                        &text_u16,
-                        &f.input,
-                        text_rect.right - text_rect.left,
-                        text_rect.bottom - text_rect.top,
-                    ) {
-                        let _ = new_layout.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_LEADING);
-                        CACHED_TEXT = buf.clone();
-                        CACHED_TEXT_LAYOUT = Some(new_layout);
-                    }
+            if let Some(layout) = CACHED_PLACEHOLDER_LAYOUT.as_ref() {
+                rt.DrawTextLayout(
+                    D2D_POINT_2F {
+                        X: text_rect.left,
+                        Y: text_rect.top,
+                    },
+                    layout,
+                    &b.placeholder,
+                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                );
+            }
+        }
+    } else if let Some(dwrite) = &DWRITE_FACTORY {
+        // Main Text Layout Logic: one unwrapped line, scrolled to keep the caret visible
+        // The word being typed is checked once the caret leaves it.
+        let typed_word = word_at(&words(text), INPUT.caret());
+        if CACHED_TEXT != text || CACHED_TYPED_WORD != typed_word || CACHED_TEXT_LAYOUT.is_none() {
+            let text_u16: Vec<u16> = text.encode_utf16().collect();
+            if let Ok(new_layout) = dwrite.CreateTextLayout(
+                &text_u16,
+                &f.input,
+                text_rect.right - text_rect.left,
+                text_rect.bottom - text_rect.top,
+            ) {
+                let _ = new_layout.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_LEADING);
+                let _ = new_layout.SetWordWrapping(DWRITE_WORD_WRAPPING_NO_WRAP);
+                CACHED_SYNTAX = highlight(text, Some(INPUT.caret()), &SystemResolver);
+                for span in CACHED_SYNTAX.iter().filter(|span| span.error) {
+                    let _ = new_layout.SetUnderline(true, text_range(text, &span.range));
                 }
+                CACHED_TEXT = text.to_string();
+                CACHED_TYPED_WORD = typed_word;
+                CACHED_TEXT_LAYOUT = Some(new_layout);
+            }
+        }
 
-                if let Some(layout) = CACHED_TEXT_LAYOUT.as_ref() {
-                    // Draw Selection Background
-                    // Draw Selection Background
-                    if start != end {
-                        // Check Selection Cache
-                        let (x1, x2) = if CACHED_SEL_START == start as u32
-                            && CACHED_SEL_END == end as u32
-                        {
-                            (CACHED_SEL_X1, CACHED_SEL_X2)
-                        } else {
-                            let (mut x1, mut y1, mut m1, mut x2, mut y2, mut m2) =
-                                (0.0, 0.0, std::mem::zeroed(), 0.0, 0.0, std::mem::zeroed());
-                            let _ = layout.HitTestTextPosition(
-                                start as u32,
-                                false,
-                                &mut x1,
-                                &mut y1,
-                                &mut m1,
-                            );
-                            let _ = layout
-                                .HitTestTextPosition(end as u32, false, &mut x2, &mut y2, &mut m2);
-                            CACHED_SEL_START = start as u32;
-                            CACHED_SEL_END = end as u32;
-                            CACHED_SEL_X1 = x1;
-                            CACHED_SEL_X2 = x2;
-                            (x1, x2)
-                        };
-                        rt.FillRectangle(
-                            &D2D_RECT_F {
-                                left: text_rect.left + x1,
-                                top: text_rect.top,
-                                right: text_rect.left + x2,
-                                bottom: text_rect.bottom,
-                            },
-                            &b.selection,
-                        );
-                    } else {
-                        // Check Selection Cache (Empty selection, cursor position)
-                        if CACHED_SEL_START != start as u32 || CACHED_SEL_END != end as u32 {
-                            let (mut x1, mut y1, mut m1) = (0.0, 0.0, std::mem::zeroed());
-                            let _ = layout.HitTestTextPosition(
-                                start as u32,
-                                false,
-                                &mut x1,
-                                &mut y1,
-                                &mut m1,
-                            );
-                            CACHED_SEL_START = start as u32;
-                            CACHED_SEL_END = end as u32;
-                            CACHED_SEL_X1 = x1;
-                            CACHED_SEL_X2 = x1; // Same point
-                        }
-                    }
+        if let Some(layout) = CACHED_TEXT_LAYOUT.as_ref() {
+            let x_at = |byte: usize| {
+                let (mut x, mut y, mut m) = (0.0, 0.0, std::mem::zeroed());
+                let _ = layout.HitTestTextPosition(
+                    utf16_offset(text, byte) as u32,
+                    false,
+                    &mut x,
+                    &mut y,
+                    &mut m,
+                );
+                x
+            };
+            let selection = INPUT.selection();
+            let (sel_start, sel_end) = (
+                utf16_offset(text, selection.start),
+                utf16_offset(text, selection.end),
+            );
+            let caret = x_at(INPUT.caret());
+            let mut metrics = DWRITE_TEXT_METRICS::default();
+            let _ = layout.GetMetrics(&mut metrics);
+            INPUT_SCROLL_X = scroll_to_caret(
+                INPUT_SCROLL_X,
+                caret,
+                text_rect.right - text_rect.left,
+                metrics.left,
+                metrics.left + metrics.widthIncludingTrailingWhitespace,
+            );
+            let origin_x = text_rect.left - INPUT_SCROLL_X;
+            caret_x = origin_x + caret;
 
-                    // Draw Text
-                    // Draw Text
-                    rt.DrawTextLayout(
-                        D2D_POINT_2F {
-                            X: text_rect.left,
-                            Y: text_rect.top,
+            rt.PushAxisAlignedClip(&text_rect, D2D1_ANTIALIAS_MODE_ALIASED);
+
+            // Colours are set every frame since the brushes change with the theme.
+            let _ = layout.SetDrawingEffect(
+                None::<&IUnknown>,
+                DWRITE_TEXT_RANGE {
+                    startPosition: 0,
+                    length: u32::MAX,
+                },
+            );
+            if theme().high_contrast {
+                // High contrast selections are opaque, so the selected text takes
+                // the highlight text colour instead; errors keep their underline.
+                if sel_start != sel_end {
+                    let _ = layout.SetDrawingEffect(
+                        &b.accent_text,
+                        DWRITE_TEXT_RANGE {
+                            startPosition: sel_start as u32,
+                            length: (sel_end - sel_start) as u32,
                         },
-                        layout,
-                        &b.white,
-                        D2D1_DRAW_TEXT_OPTIONS_NONE,
                     );
-
-                    // Ghost Text (Prediction) - Optimized Cache
-                    if !PREDICTION.is_empty()
-                        && CACHED_GHOST_PREDICTION_SOURCE == PREDICTION
-                        && CACHED_GHOST_INPUT_LEN == buf.len()
-                        && CACHED_GHOST_LAYOUT.is_some()
-                    {
-                        // FAST PATH: Prediction unchanged
-                        if PREDICTION.len() > buf.len() {
-                            if let Some(g_layout) = CACHED_GHOST_LAYOUT.as_ref() {
-                                // For ghost x pos, we need end of text position.
-                                // We can reuse cached sel end X if sel end == buf len
-                                let cursor_x = if CACHED_SEL_END as usize == buf.len() {
-                                    CACHED_SEL_X2
-                                } else {
-                                    let (mut x, mut y, mut m) = (0.0, 0.0, std::mem::zeroed());
-                                    let _ = layout.HitTestTextPosition(
-                                        buf.len() as u32,
-                                        false,
-                                        &mut x,
-                                        &mut y,
-                                        &mut m,
-                                    );
-                                    x
-                                };
-                                rt.DrawTextLayout(
-                                    D2D_POINT_2F {
-                                        X: text_rect.left + cursor_x,
-                                        Y: text_rect.top,
-                                    },
-                                    g_layout,
-                                    &b.gray,
-                                    D2D1_DRAW_TEXT_OPTIONS_CLIP,
-                                );
-                            }
-                        }
-                    } else if !PREDICTION.is_empty()
-                        && PREDICTION.len() > buf.len()
-                        && PREDICTION.to_lowercase().starts_with(&buf.to_lowercase())
-                    {
-                        // COLD PATH: Recalculate Ghost Layout
-                        let pred_suffix = if PREDICTION.is_char_boundary(buf.len()) {
-                            PREDICTION[buf.len()..].to_string()
-                        } else {
-                            String::new()
-                        };
-                        let suffix_u16: Vec<u16> = pred_suffix.encode_utf16().collect();
-                        if let Ok(g_layout) = dwrite.CreateTextLayout(
-                            &suffix_u16,
-                            &f.input,
-                            text_rect.right - text_rect.left,
-                            text_rect.bottom - text_rect.top,
-                        ) {
-                            let _ = g_layout.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_LEADING);
-                            CACHED_GHOST_TEXT = pred_suffix;
-                            CACHED_GHOST_PREDICTION_SOURCE = PREDICTION.clone();
-                            CACHED_GHOST_INPUT_LEN = buf.len();
-                            CACHED_GHOST_LAYOUT = Some(g_layout);
-
-                            if let Some(g_layout) = CACHED_GHOST_LAYOUT.as_ref() {
-                                let (mut x, mut y, mut m) = (0.0, 0.0, std::mem::zeroed());
-                                let _ = layout.HitTestTextPosition(
-                                    buf.len() as u32,
-                                    false,
-                                    &mut x,
-                                    &mut y,
-                                    &mut m,
-                                );
-                                rt.DrawTextLayout(
-                                    D2D_POINT_2F {
-                                        X: text_rect.left + x,
-                                        Y: text_rect.top,
-                                    },
-                                    g_layout,
-                                    &b.gray,
-                                    D2D1_DRAW_TEXT_OPTIONS_CLIP,
-                                );
-                            }
-                        }
-                    } else {
-                        CACHED_GHOST_LAYOUT = None;
-                        CACHED_GHOST_PREDICTION_SOURCE.clear();
-                    }
                 }
-            }
-        }
-
-        // UNCONDITIONAL CARET DRAW
-        let blink_time = GetCaretBlinkTime();
-        let blink_time = if blink_time == 0 { 500 } else { blink_time };
-        if (GetTickCount() / blink_time) % 2 == 0 {
-            let cursor_x = if buf.is_empty() {
-                text_rect.left
             } else {
-                if CACHED_SEL_START == start as u32 && CACHED_SEL_END == end as u32 {
-                    text_rect.left + CACHED_SEL_X2
-                } else {
-                    if let Some(layout) = CACHED_TEXT_LAYOUT.as_ref() {
-                        // Recalculate
-                        let (mut x, mut y, mut m) = (0.0, 0.0, std::mem::zeroed());
-                        let _ =
-                            layout.HitTestTextPosition(end as u32, false, &mut x, &mut y, &mut m);
-                        text_rect.left + x
-                    } else {
-                        text_rect.left
-                    }
+                for span in CACHED_SYNTAX.iter() {
+                    let brush = match span.kind {
+                        _ if span.error => &b.syntax_error,
+                        TokenKind::Program => &b.syntax_program,
+                        TokenKind::Argument => continue,
+                        TokenKind::String => &b.syntax_string,
+                        TokenKind::Variable => &b.syntax_variable,
+                        TokenKind::Url => &b.syntax_url,
+                    };
+                    let _ = layout.SetDrawingEffect(brush, text_range(text, &span.range));
                 }
-            };
+            }
+
+            // Draw Selection Background
+            if sel_start != sel_end {
+                let (x1, x2) = (x_at(selection.start), x_at(selection.end));
+                // In RTL text the end of the selection can lie left of its start.
+                rt.FillRectangle(
+                    &D2D_RECT_F {
+                        left: origin_x + x1.min(x2),
+                        top: text_rect.top,
+                        right: origin_x + x1.max(x2),
+                        bottom: text_rect.bottom,
+                    },
+                    &b.selection,
+                );
+            }
 
-            rt.DrawLine(
+            // Draw Text
+            rt.DrawTextLayout(
                 D2D_POINT_2F {
-                    X: cursor_x,
+                    X: origin_x,
                     Y: text_rect.top,
                 },
-                D2D_POINT_2F {
-                    X: cursor_x,
-                    Y: text_rect.bottom,
-                },
+                layout,
                 &b.white,
-                1.5,
-                None,
+                D2D1_DRAW_TEXT_OPTIONS_NONE,
             );
+
+            // Ghost Text (Prediction, else the history expansion), cached on what it shows
+            let preview;
+            let suffix = match completion_suffix(&PREDICTION, text, settings().ignore_accents) {
+                Some(suffix) => suffix,
+                None => {
+                    preview = expansion_preview(text);
+                    preview.as_deref().unwrap_or_default()
+                }
+            };
+            if suffix.is_empty() {
+                CACHED_GHOST_LAYOUT = None;
+                CACHED_GHOST_TEXT.clear();
+            } else if CACHED_GHOST_TEXT != suffix || CACHED_GHOST_LAYOUT.is_none() {
+                let suffix_u16: Vec<u16> = suffix.encode_utf16().collect();
+                if let Ok(g_layout) = dwrite.CreateTextLayout(
+                    &suffix_u16,
+                    &f.input,
+                    text_rect.right - text_rect.left,
+                    text_rect.bottom - text_rect.top,
+                ) {
+                    let _ = g_layout.SetTextAlignment(DWRITE_TEXT_ALIGNMENT_LEADING);
+                    let _ = g_layout.SetWordWrapping(DWRITE_WORD_WRAPPING_NO_WRAP);
+                    CACHED_GHOST_TEXT = suffix.to_string();
+                    CACHED_GHOST_LAYOUT = Some(g_layout);
+                }
+            }
+            if let Some(g_layout) = CACHED_GHOST_LAYOUT.as_ref() {
+                rt.DrawTextLayout(
+                    D2D_POINT_2F {
+                        X: ghost_origin_x(&text_rect, x_at(text.len()) - INPUT_SCROLL_X, dir),
+                        Y: text_rect.top,
+                    },
+                    g_layout,
+                    &b.gray,
+                    D2D1_DRAW_TEXT_OPTIONS_NONE,
+                );
+            }
+
+            rt.PopAxisAlignedClip();
         }
     }
 
-    let (cx, cy) = (w - MARGIN - 20.0, INPUT_Y + INPUT_H / 2.0);
+    // UNCONDITIONAL CARET DRAW
+    let blink_time = GetCaretBlinkTime();
+    let blink_time = if blink_time == 0 { 500 } else { blink_time };
+    if (GetTickCount() / blink_time).is_multiple_of(2) {
+        rt.DrawLine(
+            D2D_POINT_2F {
+                X: caret_x,
+                Y: text_rect.top,
+            },
+            D2D_POINT_2F {
+                X: caret_x,
+                Y: text_rect.bottom,
+            },
+            &b.white,
+            1.5,
+            None,
+        );
+    }
+
+    let (cx, cy) = (geometry.chevron.center_x(), geometry.chevron.center_y());
     rt.DrawLine(
         D2D_POINT_2F {
             X: cx - 4.0,
@@ -1568,13 +1801,12 @@ pub unsafe fn paint() {
         None,
     );
 
-    let ok_x = w - MARGIN - BTN_W * 2.0 - 8.0;
     draw_button(
         &rt,
         b,
         f,
-        ok_x,
-        get_str_run(),
+        geometry.ok,
+        &tr_wide(Msg::Run),
         HoverId::Ok,
         is_input_empty(),
     );
@@ -1582,8 +1814,8 @@ pub unsafe fn paint() {
         &rt,
         b,
         f,
-        w - MARGIN - BTN_W,
-        get_str_cancel(),
+        geometry.cancel,
+        &tr_wide(Msg::Cancel),
         HoverId::Cancel,
         false,
     );
@@ -1591,21 +1823,35 @@ pub unsafe fn paint() {
     let _ = target.EndDraw(None, None);
 }
 
+/// DirectWrite range of a byte range of `text`.
+fn text_range(text: &str, range: &std::ops::Range<usize>) -> DWRITE_TEXT_RANGE {
+    let start = utf16_offset(text, range.start);
+    DWRITE_TEXT_RANGE {
+        startPosition: start as u32,
+        length: (utf16_offset(text, range.end) - start) as u32,
+    }
+}
+
+/// Origin of the ghost layout so its text continues from the end of the input. The
+/// ghost layout is as wide as the text box and leading-aligned, so in RTL its text
+/// sits at the right edge and the origin moves left by a full box width.
+fn ghost_origin_x(text_rect: &D2D_RECT_F, cursor_x: f32, dir: Direction) -> f32 {
+    match dir {
+        Direction::Ltr => text_rect.left + cursor_x,
+        Direction::Rtl => text_rect.left + cursor_x - (text_rect.right - text_rect.left),
+    }
+}
+
 pub unsafe fn draw_button(
     rt: &ID2D1RenderTarget,
     b: &Brushes,
     f: &Fonts,
-    x: f32,
+    rect: Rect,
     text: &[u16],
     id: HoverId,
     disabled: bool,
 ) {
-    let rect = D2D_RECT_F {
-        left: x,
-        top: BTN_Y,
-        right: x + BTN_W,
-        bottom: BTN_Y + BTN_H,
-    };
+    let rect = d2d_rect(rect);
     let bg = if disabled {
         &b.input_bg
     } else if id == HoverId::Ok {
@@ -1622,22 +1868,30 @@ pub unsafe fn draw_button(
     rt.FillRoundedRectangle(
         &D2D1_ROUNDED_RECT {
             rect,
-            radiusX: CORNER_RADIUS,
-            radiusY: CORNER_RADIUS,
+            radiusX: theme().corner_radius,
+            radiusY: theme().corner_radius,
         },
         bg,
     );
     rt.DrawRoundedRectangle(
         &D2D1_ROUNDED_RECT {
             rect,
-            radiusX: CORNER_RADIUS,
-            radiusY: CORNER_RADIUS,
+            radiusX: theme().corner_radius,
+            radiusY: theme().corner_radius,
         },
         &b.btn_border,
         1.0,
         None,
     );
-    let brush = if disabled { &b.gray } else { &b.white };
+    let brush = if disabled {
+        &b.gray
+    } else if id == HoverId::Ok {
+        &b.accent_text
+    } else if HOVER == id {
+        &b.hover_text
+    } else {
+        &b.white
+    };
     rt.DrawText(
         text,
         &f.button,
@@ -1711,6 +1965,9 @@ pub unsafe fn update_animations(hwnd: HWND) {
                         SWP_NOSIZE | SWP_NOZORDER,
                     );
                     ANIM_START_TIME = None;
+                    if let Some((title, message)) = PENDING_NOTICE.take() {
+                        show_tooltip(&title, &message);
+                    }
                 }
             }
             AnimType::Exiting => {
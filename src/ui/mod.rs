@@ -3,17 +3,25 @@
 #![allow(non_snake_case)]
 
 use windows::Win32::Foundation::*;
+use windows::Win32::Graphics::Direct2D::Common::{D2D_RECT_F, D2D1_COLOR_F};
 use windows::Win32::Graphics::Direct2D::*;
 use windows::Win32::Graphics::DirectWrite::*;
 use windows::Win32::Graphics::Dwm::*;
+use windows::Win32::Graphics::Gdi::*;
 use windows::Win32::System::Registry::*;
+use windows::Win32::UI::Accessibility::*;
 use windows::Win32::UI::Controls::*;
+use windows::Win32::UI::WindowsAndMessaging::*;
 use windows::core::*;
 
 use crate::config::*;
+use crate::data::contrast::*;
+use crate::data::locale::{Direction, direction};
+use crate::data::theme::*;
 
 pub mod dialog;
 pub mod dropdown;
+pub mod layout;
 pub mod main_win;
 pub mod resources;
 pub mod tooltip;
@@ -30,6 +38,13 @@ pub struct Brushes {
     pub accent_hover: ID2D1SolidColorBrush,
     pub selection: ID2D1SolidColorBrush,
     pub btn_border: ID2D1SolidColorBrush,
+    pub hover_text: ID2D1SolidColorBrush,
+    pub accent_text: ID2D1SolidColorBrush,
+    pub syntax_program: ID2D1SolidColorBrush,
+    pub syntax_string: ID2D1SolidColorBrush,
+    pub syntax_variable: ID2D1SolidColorBrush,
+    pub syntax_url: ID2D1SolidColorBrush,
+    pub syntax_error: ID2D1SolidColorBrush,
 }
 
 pub struct Fonts {
@@ -40,6 +55,12 @@ pub struct Fonts {
     pub tooltip_bold: IDWriteTextFormat,
     pub icon: IDWriteTextFormat,
     pub input: IDWriteTextFormat,
+    /// Section headers in the dropdown.
+    pub header: IDWriteTextFormat,
+    /// Second line of detailed dropdown rows.
+    pub detail: IDWriteTextFormat,
+    /// Source glyphs in the dropdown, from `GLYPH_FONT`.
+    pub glyph: IDWriteTextFormat,
 }
 
 #[repr(C)]
@@ -83,7 +104,114 @@ pub unsafe fn is_dark_mode() -> bool {
     buffer[0] == 0
 }
 
+pub fn d2d_color(c: Color) -> D2D1_COLOR_F {
+    D2D1_COLOR_F {
+        r: c.r,
+        g: c.g,
+        b: c.b,
+        a: c.a,
+    }
+}
+
+pub fn d2d_rect(r: layout::Rect) -> D2D_RECT_F {
+    D2D_RECT_F {
+        left: r.left,
+        top: r.top,
+        right: r.right,
+        bottom: r.bottom,
+    }
+}
+
+/// Sets the DirectWrite reading direction of `format` to the active locale's, so
+/// leading alignment means right-aligned for RTL languages.
+pub unsafe fn apply_reading_direction(format: &IDWriteTextFormat) {
+    let reading = match direction() {
+        Direction::Ltr => DWRITE_READING_DIRECTION_LEFT_TO_RIGHT,
+        Direction::Rtl => DWRITE_READING_DIRECTION_RIGHT_TO_LEFT,
+    };
+    let _ = format.SetReadingDirection(reading);
+}
+
+pub unsafe fn solid_brush(rt: &ID2D1RenderTarget, c: Color) -> ID2D1SolidColorBrush {
+    rt.CreateSolidColorBrush(&d2d_color(c), None).unwrap()
+}
+
+/// `c` at `opacity`, or fully opaque while high contrast is on.
+pub fn translucent(c: Color, opacity: f32) -> Color {
+    if theme().high_contrast {
+        c.with_alpha(1.0)
+    } else {
+        c.fade(opacity)
+    }
+}
+
+pub unsafe fn is_high_contrast() -> bool {
+    let mut hc = HIGHCONTRASTW {
+        cbSize: std::mem::size_of::<HIGHCONTRASTW>() as u32,
+        ..Default::default()
+    };
+    SystemParametersInfoW(
+        SPI_GETHIGHCONTRAST,
+        hc.cbSize,
+        Some(&mut hc as *mut _ as _),
+        SYSTEM_PARAMETERS_INFO_UPDATE_FLAGS(0),
+    )
+    .is_ok()
+        && hc.dwFlags.contains(HCF_HIGHCONTRASTON)
+}
+
+pub unsafe fn system_colors() -> SystemColors {
+    let sys = |index| Color::from_abgr(GetSysColor(index)).with_alpha(1.0);
+    SystemColors {
+        window: sys(COLOR_WINDOW),
+        window_text: sys(COLOR_WINDOWTEXT),
+        button_face: sys(COLOR_BTNFACE),
+        highlight: sys(COLOR_HIGHLIGHT),
+        highlight_text: sys(COLOR_HIGHLIGHTTEXT),
+        gray_text: sys(COLOR_GRAYTEXT),
+    }
+}
+
+/// Re-derives the high-contrast theme from the current system colours, or clears it
+/// when high contrast is off. Call before rebuilding brushes.
+pub unsafe fn refresh_high_contrast() {
+    HIGH_CONTRAST_THEME = if is_high_contrast() {
+        let base = ACTIVE_THEME.clone().unwrap_or_default();
+        Some(high_contrast_theme(&base, &system_colors()))
+    } else {
+        None
+    };
+}
+
+/// Accent fill and its hover shade. High contrast keeps the system highlight as is.
+pub unsafe fn accent_colors() -> (Color, Color) {
+    let (ar, ag, ab) = get_accent_color_values();
+    let accent = Color::rgb(ar, ag, ab);
+    if theme().high_contrast {
+        return (accent, accent);
+    }
+    let hover = Color::rgb(
+        (ar + COLOR_HOVER_BRIGHTEN).min(1.0),
+        (ag + COLOR_HOVER_BRIGHTEN).min(1.0),
+        (ab + COLOR_HOVER_BRIGHTEN).min(1.0),
+    );
+    (accent, hover)
+}
+
+/// Light or dark palette of the active theme, following the system app mode.
+pub unsafe fn current_palette() -> &'static Palette {
+    theme().palette(is_dark_mode())
+}
+
+/// Null-terminated UTF-16 copy of a theme font family for DirectWrite.
+pub fn wide_family(family: &str) -> Vec<u16> {
+    family.encode_utf16().chain(std::iter::once(0)).collect()
+}
+
 pub unsafe fn get_accent_color_values() -> (f32, f32, f32) {
+    if let AccentSource::Custom(c) = theme().accent {
+        return (c.r, c.g, c.b);
+    }
     let mut color: u32 = 0;
     let mut cb_data = 4u32;
     let mut h_key = HKEY::default();
@@ -120,15 +248,19 @@ pub unsafe fn get_accent_color_values() -> (f32, f32, f32) {
 }
 
 pub unsafe fn set_acrylic_effect(hwnd: HWND) {
-    let is_dark = is_dark_mode();
-    let gradient_color = if is_dark {
-        ACRYLIC_TINT_DARK
-    } else {
-        ACRYLIC_TINT_LIGHT
+    let t = theme();
+    let palette = t.palette(is_dark_mode());
+    let (accent_state, gradient_color, system_backdrop): (u32, u32, u32) = match t.backdrop {
+        // ACCENT_ENABLE_ACRYLICBLURBEHIND, DWMSBT_NONE
+        Backdrop::Acrylic => (4, palette.backdrop_tint.to_abgr(), 1),
+        // ACCENT_DISABLED, DWMSBT_MAINWINDOW
+        Backdrop::Mica => (0, 0, 2),
+        // ACCENT_ENABLE_GRADIENT, DWMSBT_NONE
+        Backdrop::Solid => (1, palette.background.with_alpha(1.0).to_abgr(), 1),
     };
 
     let policy = AccentPolicy {
-        accent_state: 4, // ACCENT_ENABLE_ACRYLICBLURBEHIND
+        accent_state,
         accent_flags: 2,
         gradient_color,
         animation_id: 0,
@@ -153,6 +285,14 @@ pub unsafe fn set_acrylic_effect(hwnd: HWND) {
         }
     }
 
+    // DWMWA_SYSTEMBACKDROP_TYPE (Windows 11 22H2+); reset explicitly when leaving Mica.
+    let _ = DwmSetWindowAttribute(
+        hwnd,
+        DWMWINDOWATTRIBUTE(38),
+        &system_backdrop as *const _ as _,
+        4,
+    );
+
     let margins = MARGINS {
         cxLeftWidth: -1,
         cxRightWidth: -1,
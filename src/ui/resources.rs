@@ -1,6 +1,9 @@
+use std::ops::Range;
+
 use crate::config::HoverId;
+use crate::data::syntax::Span;
+use crate::data::text_buffer::TextBuffer;
 use crate::ui::{Brushes, Fonts};
-use std::sync::Mutex;
 use windows::Win32::Foundation::*;
 use windows::Win32::Graphics::Direct2D::*;
 use windows::Win32::Graphics::DirectWrite::*;
@@ -19,29 +22,30 @@ pub static mut HOVER: HoverId = HoverId::None;
 pub static mut APP_ICON_BITMAP: Option<ID2D1Bitmap> = None;
 
 pub static mut H_MAIN: HWND = HWND(std::ptr::null_mut());
-pub static mut H_EDIT: HWND = HWND(std::ptr::null_mut());
 pub static mut H_DROPDOWN: HWND = HWND(std::ptr::null_mut());
 pub static mut H_TOOLTIP: HWND = HWND(std::ptr::null_mut());
 
-pub static INPUT_BUFFER: Mutex<String> = Mutex::new(String::new());
+pub static mut INPUT: TextBuffer = TextBuffer::new();
+// Text-layout x at the left edge of the input, so the caret stays in view.
+pub static mut INPUT_SCROLL_X: f32 = 0.0;
 
 pub static mut CACHED_TEXT_LAYOUT: Option<IDWriteTextLayout> = None;
 pub static mut CACHED_TEXT: String = String::new();
+// Highlighting of CACHED_TEXT.
+pub static mut CACHED_SYNTAX: Vec<Span> = Vec::new();
+// Word the caret was in, left unchecked by CACHED_SYNTAX.
+pub static mut CACHED_TYPED_WORD: Option<Range<usize>> = None;
 
 pub static mut CACHED_GHOST_LAYOUT: Option<IDWriteTextLayout> = None;
 pub static mut CACHED_GHOST_TEXT: String = String::new();
-pub static mut CACHED_GHOST_PREDICTION_SOURCE: String = String::new();
-pub static mut CACHED_GHOST_INPUT_LEN: usize = 0;
 
 pub static mut CACHED_PLACEHOLDER_LAYOUT: Option<IDWriteTextLayout> = None;
 
-pub static mut CACHED_SEL_START: u32 = 0;
-pub static mut CACHED_SEL_END: u32 = 0;
-pub static mut CACHED_SEL_X1: f32 = 0.0;
-pub static mut CACHED_SEL_X2: f32 = 0.0;
-
 // Message constants
 pub const WM_APP_RUN_COMMAND: u32 = 1025;
 pub const WM_APP_CLOSE: u32 = 1026;
 pub const WM_APP_ERROR: u32 = 1027;
 pub const WM_APP_SHOW_UI: u32 = 1028;
+pub const WM_APP_CONFIG_CHANGED: u32 = 1029;
+pub const WM_APP_DOUBLE_TAP: u32 = 1030;
+pub const WM_APP_SUGGESTIONS: u32 = 1031;
@@ -18,11 +18,15 @@ use windows::Win32::Graphics::Gdi::*;
 use windows::Win32::System::LibraryLoader::GetModuleHandleW;
 use windows::Win32::UI::Controls::MARGINS;
 use windows::Win32::UI::HiDpi::*;
-use windows::Win32::UI::Input::KeyboardAndMouse::SetFocus;
 use windows::Win32::UI::WindowsAndMessaging::*;
 
 use crate::animations::*;
 use crate::config::*;
+use crate::data::contrast::readable_on;
+use crate::data::locale::direction;
+use crate::data::settings::settings;
+use crate::data::theme::*;
+use crate::ui::layout::*;
 use crate::ui::resources::*;
 use crate::ui::*;
 
@@ -47,7 +51,13 @@ pub unsafe fn show_tooltip(title: &str, msg: &str) {
     let width = (TOOLTIP_W * dpi_scale) as i32;
     let height = (TOOLTIP_H * dpi_scale) as i32;
 
-    let x = main_rect.left + (MARGIN * dpi_scale) as i32;
+    let x = tooltip_window_x(
+        main_rect.left,
+        main_rect.right,
+        width,
+        (MARGIN * dpi_scale) as i32,
+        direction(),
+    );
     let input_y_screen = main_rect.top + ((INPUT_Y * dpi_scale) as i32);
     let y = input_y_screen - height - (TOOLTIP_GAP * dpi_scale) as i32;
 
@@ -86,17 +96,11 @@ pub unsafe fn show_tooltip(title: &str, msg: &str) {
     TOOLTIP_ANIM_TYPE = AnimType::Entering;
     SetTimer(Some(H_MAIN), 3, ANIM_TIMER_MS, None);
 
-    SetTimer(Some(H_TOOLTIP), 2, 8000, None);
+    SetTimer(Some(H_TOOLTIP), 2, settings().tooltip_timeout_ms, None);
 
-    if !H_EDIT.0.is_null() {
-        let _ = SetFocus(Some(H_EDIT));
-        SendMessageW(
-            H_EDIT,
-            windows::Win32::UI::Controls::EM_SETSEL,
-            Some(WPARAM(0)),
-            Some(LPARAM(-1)),
-        );
-    }
+    // Select the input so the next keystroke replaces what failed.
+    INPUT.select_all();
+    let _ = InvalidateRect(Some(H_MAIN), None, false);
     let _ = InvalidateRect(Some(H_TOOLTIP), None, false);
 }
 
@@ -231,12 +235,14 @@ pub unsafe extern "system" fn tooltip_wndproc(
                         }
 
                         let is_dark = is_dark_mode();
-                        let bg_col = if is_dark {
-                            COLOR_DARK_BG
+                        let palette = current_palette();
+                        let bg_alpha = if theme().high_contrast {
+                            1.0
+                        } else if is_dark {
+                            0.95
                         } else {
-                            COLOR_LIGHT_BG
+                            0.98
                         };
-                        let bg_alpha = if is_dark { 0.95 } else { 0.98 };
 
                         let (ar, ag, ab) = get_accent_color_values();
                         let accent_col = D2D1_COLOR_F {
@@ -246,59 +252,36 @@ pub unsafe extern "system" fn tooltip_wndproc(
                             a: 1.0,
                         };
                         let accent_brush = rt.CreateSolidColorBrush(&accent_col, None).unwrap();
+                        let icon_text =
+                            readable_on(theme().warning, Color::gray(1.0), HC_MIN_TEXT_CONTRAST);
                         let white_brush = rt
-                            .CreateSolidColorBrush(
-                                &D2D1_COLOR_F {
-                                    r: 1.0,
-                                    g: 1.0,
-                                    b: 1.0,
-                                    a: 1.0,
-                                },
-                                None,
-                            )
+                            .CreateSolidColorBrush(&d2d_color(icon_text), None)
                             .unwrap();
 
-                        let tri_h = TOOLTIP_TRI_H;
-                        let main_rect = D2D_RECT_F {
-                            left: 0.0,
-                            top: 0.0,
-                            right: w_dip,
-                            bottom: h_dip - tri_h,
-                        };
+                        let layout = TooltipLayout::new(w_dip, h_dip, direction());
+                        let main_rect = d2d_rect(layout.body);
 
                         if let Ok(bg_brush) = rt.CreateSolidColorBrush(
-                            &D2D1_COLOR_F {
-                                r: bg_col,
-                                g: bg_col,
-                                b: bg_col,
-                                a: bg_alpha,
-                            },
+                            &d2d_color(palette.background.fade(bg_alpha)),
                             None,
                         ) {
                             rt.FillRoundedRectangle(
                                 &D2D1_ROUNDED_RECT {
                                     rect: main_rect,
-                                    radiusX: TOOLTIP_CORNER_RADIUS,
-                                    radiusY: TOOLTIP_CORNER_RADIUS,
+                                    radiusX: theme().tooltip_corner_radius,
+                                    radiusY: theme().tooltip_corner_radius,
                                 },
                                 &bg_brush,
                             );
 
-                            // Triangle at bottom left
+                            // Triangle at the bottom leading corner, pointing at the input
                             if let Ok(path) = factory.CreatePathGeometry() {
                                 if let Ok(sink) = path.Open() {
-                                    sink.BeginFigure(
-                                        D2D_POINT_2F {
-                                            X: 20.0,
-                                            Y: h_dip - tri_h,
-                                        },
-                                        D2D1_FIGURE_BEGIN_FILLED,
-                                    );
-                                    sink.AddLine(D2D_POINT_2F {
-                                        X: 20.0 + TOOLTIP_TRI_W,
-                                        Y: h_dip - tri_h,
-                                    });
-                                    sink.AddLine(D2D_POINT_2F { X: 20.0, Y: h_dip });
+                                    let [start, end, tip] =
+                                        layout.triangle.map(|(x, y)| D2D_POINT_2F { X: x, Y: y });
+                                    sink.BeginFigure(start, D2D1_FIGURE_BEGIN_FILLED);
+                                    sink.AddLine(end);
+                                    sink.AddLine(tip);
                                     sink.EndFigure(D2D1_FIGURE_END_CLOSED);
                                     let _ = sink.Close();
                                     rt.FillGeometry(&path, &bg_brush, None);
@@ -310,8 +293,8 @@ pub unsafe extern "system" fn tooltip_wndproc(
                         rt.DrawRoundedRectangle(
                             &D2D1_ROUNDED_RECT {
                                 rect: main_rect,
-                                radiusX: TOOLTIP_CORNER_RADIUS,
-                                radiusY: TOOLTIP_CORNER_RADIUS,
+                                radiusX: theme().tooltip_corner_radius,
+                                radiusY: theme().tooltip_corner_radius,
                             },
                             &accent_brush,
                             1.5,
@@ -320,18 +303,12 @@ pub unsafe extern "system" fn tooltip_wndproc(
 
                         // Icon: Red Circle with '!'
                         let icon_center = D2D_POINT_2F {
-                            X: TOOLTIP_PADDING + TOOLTIP_ICON_SIZE / 2.0,
-                            Y: TOOLTIP_PADDING + TOOLTIP_ICON_SIZE / 2.0,
+                            X: layout.icon.center_x(),
+                            Y: layout.icon.center_y(),
                         };
-                        if let Ok(red_brush) = rt.CreateSolidColorBrush(
-                            &D2D1_COLOR_F {
-                                r: COLOR_WARNING_R,
-                                g: COLOR_WARNING_G,
-                                b: COLOR_WARNING_B,
-                                a: 1.0,
-                            },
-                            None,
-                        ) {
+                        if let Ok(red_brush) =
+                            rt.CreateSolidColorBrush(&d2d_color(theme().warning), None)
+                        {
                             rt.FillEllipse(
                                 &D2D1_ELLIPSE {
                                     point: icon_center,
@@ -344,12 +321,7 @@ pub unsafe extern "system" fn tooltip_wndproc(
 
                         if let Some(f) = &FONTS {
                             let excl = [b'!' as u16];
-                            let excl_rect = D2D_RECT_F {
-                                left: TOOLTIP_PADDING,
-                                top: TOOLTIP_PADDING,
-                                right: TOOLTIP_PADDING + TOOLTIP_ICON_SIZE,
-                                bottom: TOOLTIP_PADDING + TOOLTIP_ICON_SIZE,
-                            };
+                            let excl_rect = d2d_rect(layout.icon);
                             rt.DrawText(
                                 &excl,
                                 &f.icon,
@@ -360,53 +332,31 @@ pub unsafe extern "system" fn tooltip_wndproc(
                             );
                         }
 
-                        let text_col = if is_dark {
-                            COLOR_DARK_TEXT
-                        } else {
-                            COLOR_LIGHT_TEXT
-                        };
-                        if let Ok(text_brush) = rt.CreateSolidColorBrush(
-                            &D2D1_COLOR_F {
-                                r: text_col,
-                                g: text_col,
-                                b: text_col,
-                                a: 1.0,
-                            },
-                            None,
-                        ) {
-                            if let Some(f) = &FONTS {
-                                let title_u16 = TOOLTIP_TITLE.encode_utf16().collect::<Vec<u16>>();
-                                let title_rect = D2D_RECT_F {
-                                    left: TOOLTIP_PADDING + TOOLTIP_ICON_SIZE + 10.0,
-                                    top: TOOLTIP_PADDING,
-                                    right: w_dip - 25.0, // Keeping extra right padding for safety
-                                    bottom: 35.0,
-                                };
-                                rt.DrawText(
-                                    &title_u16,
-                                    &f.tooltip_bold,
-                                    &title_rect,
-                                    &text_brush,
-                                    D2D1_DRAW_TEXT_OPTIONS_CLIP,
-                                    DWRITE_MEASURING_MODE_NATURAL,
-                                );
-
-                                let msg_u16 = TOOLTIP_MESSAGE.encode_utf16().collect::<Vec<u16>>();
-                                let msg_rect = D2D_RECT_F {
-                                    left: TOOLTIP_PADDING + TOOLTIP_ICON_SIZE + 10.0,
-                                    top: 38.0,
-                                    right: w_dip - 25.0,
-                                    bottom: h_dip - tri_h - 5.0,
-                                };
-                                rt.DrawText(
-                                    &msg_u16,
-                                    &f.tooltip,
-                                    &msg_rect,
-                                    &text_brush,
-                                    D2D1_DRAW_TEXT_OPTIONS_CLIP,
-                                    DWRITE_MEASURING_MODE_NATURAL,
-                                );
-                            }
+                        if let Ok(text_brush) =
+                            rt.CreateSolidColorBrush(&d2d_color(palette.text), None)
+                            && let Some(f) = &FONTS
+                        {
+                            let title_u16 = TOOLTIP_TITLE.encode_utf16().collect::<Vec<u16>>();
+                            let title_rect = d2d_rect(layout.title);
+                            rt.DrawText(
+                                &title_u16,
+                                &f.tooltip_bold,
+                                &title_rect,
+                                &text_brush,
+                                D2D1_DRAW_TEXT_OPTIONS_CLIP,
+                                DWRITE_MEASURING_MODE_NATURAL,
+                            );
+
+                            let msg_u16 = TOOLTIP_MESSAGE.encode_utf16().collect::<Vec<u16>>();
+                            let msg_rect = d2d_rect(layout.message);
+                            rt.DrawText(
+                                &msg_u16,
+                                &f.tooltip,
+                                &msg_rect,
+                                &text_brush,
+                                D2D1_DRAW_TEXT_OPTIONS_CLIP,
+                                DWRITE_MEASURING_MODE_NATURAL,
+                            );
                         }
 
                         if pushed_layer {
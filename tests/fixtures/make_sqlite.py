@@ -0,0 +1,76 @@
+"""Rebuilds the SQLite fixtures for the bookmarks and sqlite tests.
+
+Run from tests/fixtures: python make_sqlite.py
+"""
+import sqlite3, os, shutil
+
+for f in os.listdir('.'):
+    if f.endswith(('.sqlite', '-wal', '-shm')):
+        os.remove(f)
+
+def places(path, page_size=1024, encoding=None):
+    db = sqlite3.connect(path, isolation_level=None)
+    db.execute(f"PRAGMA page_size={page_size}")
+    if encoding: db.execute(f"PRAGMA encoding='{encoding}'")
+    db.executescript("""
+    CREATE TABLE moz_places (   id INTEGER PRIMARY KEY, url LONGVARCHAR, title LONGVARCHAR, rev_host LONGVARCHAR, visit_count INTEGER DEFAULT 0, hidden INTEGER DEFAULT 0 NOT NULL, typed INTEGER DEFAULT 0 NOT NULL, frecency INTEGER DEFAULT -1 NOT NULL, last_visit_date INTEGER , guid TEXT, foreign_count INTEGER DEFAULT 0 NOT NULL, url_hash INTEGER DEFAULT 0 NOT NULL , description TEXT, preview_image_url TEXT, site_name TEXT, origin_id INTEGER REFERENCES moz_origins(id), recalc_frecency INTEGER NOT NULL DEFAULT 0, alt_frecency INTEGER, recalc_alt_frecency INTEGER NOT NULL DEFAULT 0);
+    CREATE TABLE moz_bookmarks (  id INTEGER PRIMARY KEY, type INTEGER, fk INTEGER DEFAULT NULL, parent INTEGER, position INTEGER, title LONGVARCHAR, keyword_id INTEGER, folder_type TEXT, dateAdded INTEGER, lastModified INTEGER, guid TEXT, syncStatus INTEGER NOT NULL DEFAULT 0, syncChangeCounter INTEGER NOT NULL DEFAULT 1);
+    CREATE INDEX moz_places_url_hashindex ON moz_places (url_hash);
+    """)
+    b = lambda id,t,fk,parent,pos,title,guid: db.execute("INSERT INTO moz_bookmarks(id,type,fk,parent,position,title,guid) VALUES (?,?,?,?,?,?,?)",(id,t,fk,parent,pos,title,guid))
+    p = lambda id,url,title: db.execute("INSERT INTO moz_places(id,url,title,rev_host,guid) VALUES (?,?,?,?,?)",(id,url,title,'moc.elpmaxe.','g%d'%id))
+    db.execute("BEGIN")
+    # Lots of history so moz_places spans interior pages.
+    for i in range(1000, 1600):
+        p(i, "https://history.example.com/page/%d" % i, "Visited page %d" % i)
+    p(1, "https://www.rust-lang.org/", "Rust Programming Language")
+    p(2, "https://github.com/", "GitHub")
+    p(3, "https://example.com/" + "x" * 3000, "Long")
+    p(4, "place:tag=work", "Saved search")
+    p(5, "javascript:alert(1)", "Bookmarklet")
+    p(6, "https://docs.example.com/", None)
+    p(7, "https://tagged.example.com/", "Tagged")
+    b(1,2,None,0,0,"",'root________')
+    b(2,2,None,1,0,"menu",'menu________')
+    b(3,2,None,1,1,"toolbar",'toolbar_____')
+    b(4,2,None,1,2,"tags",'tags________')
+    b(5,2,None,1,3,"unfiled",'unfiled_____')
+    b(6,2,None,1,4,"mobile",'mobile______')
+    b(10,2,None,3,1,"Dev",'dev_________')
+    b(11,2,None,10,0,"Rust",'rust________')
+    b(20,1,2,3,0,"GitHub home",'bm1_________')
+    b(21,1,1,11,0,None,'bm2_________')
+    b(22,1,3,5,0,"Very long",'bm3_________')
+    b(23,1,4,2,0,"Work search",'bm4_________')
+    b(24,1,5,2,1,"Bookmarklet",'bm5_________')
+    b(25,1,6,10,1,"",'bm6_________')
+    b(30,2,None,4,0,"work",'tagwork_____')
+    b(31,1,7,30,0,None,'bm7_________')
+    b(26,1,999,2,2,"Missing place",'bm8_________')
+    b(27,3,None,2,3,None,'sep_________')
+    db.execute("COMMIT")
+    return db
+
+places('places.sqlite').close()
+
+# WAL: one more bookmark committed to the log, one transaction left open.
+db = places('wal_base.sqlite')
+db.execute("PRAGMA journal_mode=WAL")
+db.execute("PRAGMA wal_autocheckpoint=0")
+db.execute("INSERT INTO moz_places(id,url,title) VALUES (8,'https://wal.example.com/','From the log')")
+db.execute("INSERT INTO moz_bookmarks(id,type,fk,parent,position,title,guid) VALUES (40,1,8,5,1,NULL,'bm9_________')")
+db.execute("UPDATE moz_bookmarks SET title='GitHub (renamed)' WHERE id=20")
+shutil.copy('wal_base.sqlite', 'wal.sqlite')
+shutil.copy('wal_base.sqlite-wal', 'wal.sqlite-wal')
+db.close()
+os.remove('wal_base.sqlite')
+
+# Older rows lack a column added later; text stored as UTF-16.
+db = sqlite3.connect('altered.sqlite', isolation_level=None)
+db.execute("PRAGMA encoding='UTF-16be'")
+db.execute('CREATE TABLE "notes" ([id] integer primary key, body TEXT, CHECK (length(body) > 0), UNIQUE(body))')
+db.execute("INSERT INTO notes(body) VALUES ('café ☕')")
+db.execute("ALTER TABLE notes ADD COLUMN score REAL")
+db.execute("INSERT INTO notes(body, score) VALUES ('second', 2.5)")
+db.execute("INSERT INTO notes(id, body, score) VALUES (-7, 'negative', -1)")
+db.close()